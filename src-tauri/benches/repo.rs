@@ -0,0 +1,144 @@
+//! 对比旧的 JSON 值查询方式与类型化仓储在大范围查询上的耗时
+//!
+//! 运行：cargo bench --bench repo
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use daily_report_helper_lib::models::DoneTask;
+use daily_report_helper_lib::repo::{SortDirection, TaskRepo};
+use sqlx::sqlite::SqlitePoolOptions;
+use sqlx::{Column, Pool, Row, Sqlite};
+
+const DAY_SECS: i64 = 24 * 60 * 60;
+const START: i64 = 1_704_067_200; // 2024-01-01 00:00:00 UTC
+const TASKS_PER_DAY: i64 = 20;
+const DAYS: i64 = 365;
+
+fn date_of(timestamp: i64) -> String {
+    chrono::DateTime::from_timestamp(timestamp, 0)
+        .unwrap()
+        .format("%Y-%m-%d")
+        .to_string()
+}
+
+async fn setup() -> Pool<Sqlite> {
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .unwrap();
+
+    // 与 database.rs 中的 done_tasks 表结构一致
+    sqlx::query(
+        r#"
+        CREATE TABLE done_tasks (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            content TEXT NOT NULL,
+            start_time INTEGER NOT NULL,
+            end_time INTEGER NOT NULL,
+            attachments TEXT NOT NULL,
+            created_at INTEGER NOT NULL,
+            date TEXT NOT NULL,
+            tags TEXT NOT NULL DEFAULT '[]'
+        );
+        "#,
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+    sqlx::query("CREATE INDEX idx_tasks_date ON done_tasks(date)")
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let mut tx = pool.begin().await.unwrap();
+    for day in 0..DAYS {
+        for n in 0..TASKS_PER_DAY {
+            let start_time = START + day * DAY_SECS + n * 1800;
+            sqlx::query(
+                "INSERT INTO done_tasks (content, start_time, end_time, attachments, created_at, date, tags) VALUES (?, ?, ?, ?, ?, ?, ?)",
+            )
+            .bind(format!("事项 {}-{}", day, n))
+            .bind(start_time)
+            .bind(start_time + 1500)
+            .bind(r#"["/tmp/a.png"]"#)
+            .bind(start_time)
+            .bind(date_of(start_time))
+            .bind(r#"["工作","会议"]"#)
+            .execute(&mut *tx)
+            .await
+            .unwrap();
+        }
+    }
+    tx.commit().await.unwrap();
+    pool
+}
+
+/// 旧实现：所有参数按字符串绑定，每列依次尝试 i64、f64、bool、String 转为 JSON 值，再逐字段取回
+async fn legacy_range(pool: &Pool<Sqlite>, start_date: &str, end_date: &str) -> Vec<DoneTask> {
+    let rows = sqlx::query("SELECT * FROM done_tasks WHERE date >= ? AND date <= ? ORDER BY date DESC, start_time DESC")
+        .bind(start_date)
+        .bind(end_date)
+        .fetch_all(pool)
+        .await
+        .unwrap();
+
+    let values: Vec<serde_json::Value> = rows
+        .iter()
+        .map(|row| {
+            let mut map = serde_json::Map::new();
+            for (i, column) in row.columns().iter().enumerate() {
+                let value: serde_json::Value = if let Ok(v) = row.try_get::<i64, _>(i) {
+                    serde_json::Value::from(v)
+                } else if let Ok(v) = row.try_get::<f64, _>(i) {
+                    serde_json::Value::from(v)
+                } else if let Ok(v) = row.try_get::<bool, _>(i) {
+                    serde_json::Value::from(v)
+                } else if let Ok(v) = row.try_get::<String, _>(i) {
+                    serde_json::Value::from(v)
+                } else {
+                    serde_json::Value::Null
+                };
+                map.insert(column.name().to_string(), value);
+            }
+            serde_json::Value::Object(map)
+        })
+        .collect();
+
+    values
+        .iter()
+        .map(|row| DoneTask {
+            id: row["id"].as_i64().unwrap_or(0),
+            content: row["content"].as_str().unwrap_or("").to_string(),
+            start_time: row["start_time"].as_i64().unwrap_or(0),
+            end_time: row["end_time"].as_i64().unwrap_or(0),
+            attachments: serde_json::from_str(row["attachments"].as_str().unwrap_or("[]")).unwrap_or_default(),
+            created_at: row["created_at"].as_i64().unwrap_or(0),
+            date: row["date"].as_str().unwrap_or("").to_string(),
+            tags: serde_json::from_str(row["tags"].as_str().unwrap_or("[]")).unwrap_or_default(),
+        })
+        .collect()
+}
+
+fn bench_range(c: &mut Criterion) {
+    let rt = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+    let pool = rt.block_on(setup());
+    let start_date = date_of(START);
+
+    let mut group = c.benchmark_group("done_tasks_range");
+    for days in [7, 30, 365] {
+        let end_date = date_of(START + (days - 1) * DAY_SECS);
+        group.bench_with_input(BenchmarkId::new("json_value", days), &end_date, |b, end_date| {
+            b.iter(|| rt.block_on(legacy_range(&pool, &start_date, end_date)))
+        });
+        group.bench_with_input(BenchmarkId::new("typed_repo", days), &end_date, |b, end_date| {
+            b.iter(|| {
+                rt.block_on(TaskRepo::new(&pool).list_in_range(&start_date, end_date, SortDirection::Desc))
+                    .unwrap()
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_range);
+criterion_main!(benches);
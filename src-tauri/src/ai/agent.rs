@@ -0,0 +1,386 @@
+use std::time::Instant;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::ai::client::{AiClient, ChatRequest, Message};
+use crate::ai::error::{AiError, AiErrorKind};
+use crate::ai::tools::{ToolContext, ToolRegistry};
+use crate::models::{AuditOrigin, TokenUsage};
+use crate::reports;
+use crate::webhooks;
+
+/// 默认的最大迭代次数（每次迭代为一次模型请求）
+pub const DEFAULT_MAX_ITERATIONS: usize = 5;
+
+/// 生成日报时的默认系统提示词
+pub const DEFAULT_REPORT_PROMPT: &str = "你是一个专业的日报分析助手。请根据用户的需求，使用工具获取相关数据，然后生成一份详细的日报总结。
+
+总结应该包括：
+1. 工作成果和进展
+2. 重要想法和思考
+3. 时间管理和效率分析
+4. 后续改进建议
+
+请用中文回复，语言要专业、简洁、有条理。当获取到数据后，请认真分析并生成有价值的总结。";
+
+/// 生成日报时的用户消息
+pub fn report_request(range: Option<(&str, &str)>) -> String {
+    match range {
+        Some((start_date, end_date)) => format!(
+            "请帮我分析从 {} 到 {} 这段时间的工作情况，生成一份日报总结。请使用工具获取这段时间的所有想法和已完成事项，然后进行深度分析。",
+            start_date, end_date
+        ),
+        None => "请帮我分析今天的工作情况，生成一份日报总结。请使用工具获取今天的所有想法和已完成事项，然后进行深度分析。".to_string(),
+    }
+}
+
+/// 工具调用循环的参数
+#[derive(Debug, Clone)]
+pub struct AgentOptions {
+    pub max_iterations: usize,
+    pub temperature: Option<f32>,
+    pub max_tokens: Option<u32>,
+    pub stream: bool, // 是否以流式方式输出模型回复
+}
+
+impl Default for AgentOptions {
+    fn default() -> Self {
+        Self {
+            max_iterations: DEFAULT_MAX_ITERATIONS,
+            temperature: Some(0.7),
+            max_tokens: Some(2000),
+            stream: false,
+        }
+    }
+}
+
+/// 工具调用循环中产生的事件
+pub enum AgentEvent<'a> {
+    ToolCall(&'a ToolCallEvent),
+    Delta { iteration: usize, text: &'a str }, // 流式输出的增量文本
+}
+
+/// 流式输出事件的内容（发送给前端）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StreamDeltaEvent {
+    pub iteration: usize,
+    pub delta: String,
+}
+
+/// 单次工具调用的记录（同时作为事件发送给前端）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolCallEvent {
+    pub iteration: usize,
+    pub tool_call_id: String,
+    pub name: String,
+    pub arguments: Value,
+    pub result: Option<Value>,
+    pub error: Option<String>,
+    pub duration_ms: u64,
+}
+
+/// 工具调用循环的最终结果
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AgentOutcome {
+    pub content: String,
+    pub model: String,
+    pub iterations: usize,
+    pub tool_calls: Vec<ToolCallEvent>,
+    pub usage: TokenUsage,
+    pub messages: Vec<Message>, // 包含工具调用在内的完整对话
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub report_id: Option<i64>, // 生成日报时保存的报告 id
+}
+
+fn add_usage(total: &mut TokenUsage, usage: Option<&TokenUsage>) {
+    if let Some(usage) = usage {
+        total.prompt_tokens += usage.prompt_tokens;
+        total.completion_tokens += usage.completion_tokens;
+        total.total_tokens += usage.total_tokens;
+    }
+}
+
+/// 运行工具调用循环，直到模型不再调用工具或达到最大迭代次数
+///
+/// 每次工具调用完成以及流式输出时都会调用 `on_event`，便于前端展示模型查询了什么。
+pub async fn run_agent<F>(
+    client: &AiClient,
+    registry: &ToolRegistry,
+    ctx: &ToolContext,
+    mut messages: Vec<Message>,
+    options: &AgentOptions,
+    on_event: F,
+) -> Result<AgentOutcome, AiError>
+where
+    F: Fn(AgentEvent<'_>) + Send + Sync,
+{
+    let tools = if registry.is_empty() { None } else { Some(registry.definitions()) };
+    let mut usage = TokenUsage::default();
+    let mut tool_calls = Vec::new();
+
+    for iteration in 1..=options.max_iterations {
+        let request = ChatRequest {
+            model: client.model().to_string(),
+            messages: messages.clone(),
+            tool_choice: tools.as_ref().map(|_| "auto".to_string()),
+            tools: tools.clone(),
+            temperature: options.temperature,
+            max_tokens: options.max_tokens,
+            stream: None,
+        };
+
+        let response = if options.stream {
+            let on_delta = |text: &str| on_event(AgentEvent::Delta { iteration, text });
+            client.chat_completion_stream(&request, &on_delta).await?
+        } else {
+            client.chat_completion(&request).await?
+        };
+        add_usage(&mut usage, response.usage.as_ref());
+
+        let message = response
+            .choices
+            .into_iter()
+            .next()
+            .map(|choice| choice.message)
+            .ok_or_else(|| AiError::new(AiErrorKind::InvalidResponse, "AI 响应中没有结果"))?;
+        messages.push(message.clone());
+
+        let calls = message.tool_calls.clone().unwrap_or_default();
+        if calls.is_empty() {
+            return Ok(AgentOutcome {
+                content: message.content.unwrap_or_default(),
+                model: client.model().to_string(),
+                iterations: iteration,
+                tool_calls,
+                usage,
+                messages,
+                report_id: None,
+            });
+        }
+
+        for call in calls {
+            let started = Instant::now();
+            let arguments: Value = if call.function.arguments.trim().is_empty() {
+                Value::Object(Default::default())
+            } else {
+                serde_json::from_str(&call.function.arguments).unwrap_or(Value::String(call.function.arguments.clone()))
+            };
+
+            let outcome = match registry.get(&call.function.name) {
+                Some(tool) if arguments.is_object() => tool.call(ctx, arguments.clone()).await,
+                Some(_) => Err("工具参数不是合法的 JSON 对象".to_string()),
+                None => Err(format!("未知工具: {}", call.function.name)),
+            };
+
+            let content = match &outcome {
+                Ok(result) => result.to_string(),
+                Err(error) => serde_json::json!({ "error": error }).to_string(),
+            };
+            messages.push(Message::tool(call.id.clone(), content));
+
+            let (result, error) = match outcome {
+                Ok(result) => (Some(result), None),
+                Err(error) => (None, Some(error)),
+            };
+            let event = ToolCallEvent {
+                iteration,
+                tool_call_id: call.id,
+                name: call.function.name,
+                arguments,
+                result,
+                error,
+                duration_ms: started.elapsed().as_millis() as u64,
+            };
+            on_event(AgentEvent::ToolCall(&event));
+            tool_calls.push(event);
+        }
+    }
+
+    Err(AiError::new(AiErrorKind::Other, "工具调用次数过多，可能存在循环"))
+}
+
+/// 日报的日期范围，未指定时为今天
+#[derive(Debug, Clone, Default)]
+pub struct ReportRange {
+    pub start_date: Option<String>,
+    pub end_date: Option<String>,
+}
+
+/// 生成日报：运行工具调用循环，回复不为空时保存为报告并通知 Webhook
+pub async fn generate_daily_report<F>(
+    client: &AiClient,
+    registry: &ToolRegistry,
+    ctx: &ToolContext,
+    system_prompt: Option<String>,
+    range: ReportRange,
+    options: &AgentOptions,
+    on_event: F,
+) -> Result<AgentOutcome, AiError>
+where
+    F: Fn(AgentEvent<'_>) + Send + Sync,
+{
+    let system_prompt = system_prompt
+        .filter(|p| !p.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_REPORT_PROMPT.to_string());
+    let dates = match (&range.start_date, &range.end_date) {
+        (Some(start), Some(end)) => Some((start.as_str(), end.as_str())),
+        _ => None,
+    };
+    let messages = vec![Message::system(system_prompt), Message::user(report_request(dates))];
+
+    let mut outcome = run_agent(client, registry, ctx, messages, options, on_event).await?;
+
+    // 保存生成的日报，便于之后推送
+    if !outcome.content.trim().is_empty() {
+        let today = chrono::Local::now().format("%Y-%m-%d").to_string();
+        let start_date = range.start_date.unwrap_or_else(|| today.clone());
+        let end_date = range.end_date.unwrap_or(today);
+        let report_id = reports::save_report(
+            &ctx.pool,
+            &reports::default_title(&start_date, &end_date),
+            &outcome.content,
+            &start_date,
+            &end_date,
+            Some(&outcome.model),
+            // 报告由 AI 生成后自动保存，不是用户手动修改
+            AuditOrigin::Scheduler,
+        )
+        .await?;
+        outcome.report_id = Some(report_id);
+
+        if let Ok(report) = reports::get_report(&ctx.pool, report_id).await {
+            webhooks::emit(&ctx.pool, "report.generated", serde_json::json!(report)).await;
+        }
+    }
+
+    Ok(outcome)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use chrono::TimeZone;
+    use serde_json::json;
+
+    use super::*;
+    use crate::ai::context::TokenEstimator;
+    use crate::models::ProviderKind;
+    use crate::repo::IdeaRepo;
+    use crate::test_support::{client_for, temp_pool, Reply, ScriptedServer};
+
+    fn tool_call_reply(calls: Value) -> Reply {
+        Reply::json(json!({
+            "choices": [{ "message": { "role": "assistant", "content": null, "tool_calls": calls } }],
+            "usage": { "prompt_tokens": 100, "completion_tokens": 10, "total_tokens": 110 }
+        }))
+    }
+
+    fn text_reply(content: &str) -> Reply {
+        Reply::json(json!({
+            "choices": [{ "message": { "role": "assistant", "content": content } }],
+            "usage": { "prompt_tokens": 200, "completion_tokens": 20, "total_tokens": 220 }
+        }))
+    }
+
+    fn history_call(id: &str) -> Value {
+        json!({
+            "id": id,
+            "type": "function",
+            "function": {
+                "name": "get_history_data",
+                "arguments": json!({ "start_date": "2024-05-06", "end_date": "2024-05-06" }).to_string()
+            }
+        })
+    }
+
+    async fn context() -> (ToolContext, crate::test_support::TempDir) {
+        let (pool, dir) = temp_pool().await;
+        let created_at = chrono::Local.with_ymd_and_hms(2024, 5, 6, 12, 0, 0).unwrap().timestamp();
+        IdeaRepo::new(&pool)
+            .insert("梳理周报模板".to_string(), Vec::new(), created_at, AuditOrigin::Ui)
+            .await
+            .unwrap();
+        let ctx = ToolContext { pool, estimator: TokenEstimator::for_model("gpt-4o"), data_budget: 4000 };
+        (ctx, dir)
+    }
+
+    #[tokio::test]
+    async fn replays_a_tool_round_and_returns_the_final_answer() {
+        let server = ScriptedServer::start(vec![
+            tool_call_reply(json!([
+                history_call("call_1"),
+                { "id": "call_2", "type": "function", "function": { "name": "delete_everything", "arguments": "{}" } }
+            ])),
+            text_reply("5 月 6 日：梳理了周报模板。"),
+        ])
+        .await;
+        let client = client_for(ProviderKind::OpenAi, &server.url);
+        let (ctx, _dir) = context().await;
+        let registry = ToolRegistry::with_builtin_tools();
+
+        let events = Mutex::new(Vec::new());
+        let outcome = run_agent(
+            &client,
+            &registry,
+            &ctx,
+            vec![Message::system(DEFAULT_REPORT_PROMPT), Message::user(report_request(Some(("2024-05-06", "2024-05-06"))))],
+            &AgentOptions::default(),
+            |event| {
+                if let AgentEvent::ToolCall(call) = event {
+                    events.lock().unwrap().push(call.name.clone());
+                }
+            },
+        )
+        .await
+        .expect("工具调用循环失败");
+
+        assert_eq!(outcome.content, "5 月 6 日：梳理了周报模板。");
+        assert_eq!(outcome.iterations, 2);
+        assert_eq!(outcome.usage.total_tokens, 330);
+        assert_eq!(*events.lock().unwrap(), vec!["get_history_data", "delete_everything"]);
+
+        // 工具确实读取了数据库，未知工具返回错误而不是中断循环
+        let history = outcome.tool_calls[0].result.as_ref().expect("工具没有结果").to_string();
+        assert!(history.contains("梳理周报模板"), "{}", history);
+        assert!(outcome.tool_calls[1].error.as_deref().unwrap_or_default().contains("未知工具"));
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        let first = requests[0].json();
+        assert_eq!(first["tool_choice"], "auto");
+        assert!(first["tools"].as_array().is_some_and(|tools| !tools.is_empty()));
+
+        // 第二次请求带上了工具调用和两个工具结果
+        let second = requests[1].json();
+        let messages = second["messages"].as_array().unwrap();
+        assert_eq!(messages.len(), 5);
+        assert_eq!(messages[3]["tool_call_id"], "call_1");
+        assert!(messages[3]["content"].as_str().unwrap().contains("梳理周报模板"));
+        assert_eq!(messages[4]["tool_call_id"], "call_2");
+        assert!(messages[4]["content"].as_str().unwrap().contains("error"));
+    }
+
+    #[tokio::test]
+    async fn stops_at_max_iterations() {
+        let server = ScriptedServer::start(vec![
+            tool_call_reply(json!([history_call("call_1")])),
+            tool_call_reply(json!([history_call("call_2")])),
+            tool_call_reply(json!([history_call("call_3")])),
+        ])
+        .await;
+        let client = client_for(ProviderKind::OpenAi, &server.url);
+        let (ctx, _dir) = context().await;
+        let registry = ToolRegistry::with_builtin_tools();
+        let options = AgentOptions { max_iterations: 2, ..Default::default() };
+
+        let error = run_agent(&client, &registry, &ctx, vec![Message::user("总结")], &options, |_| {})
+            .await
+            .expect_err("应当因迭代次数过多而失败");
+
+        assert_eq!(error.kind, AiErrorKind::Other);
+        assert!(error.message.contains("工具调用次数过多"), "{}", error.message);
+        assert_eq!(server.requests().len(), 2);
+    }
+}
@@ -0,0 +1,450 @@
+use std::collections::BTreeMap;
+
+use async_trait::async_trait;
+use serde_json::{json, Value};
+
+use crate::ai::client::{self, AiClient, AttemptFailure, ChatRequest, DeltaFn, FunctionCall, Message, ToolCall};
+use crate::ai::error::{AiError, AiErrorKind};
+use crate::ai::provider::{self, Provider};
+
+/// 请求头中的 API 版本
+const ANTHROPIC_VERSION: &str = "2023-06-01";
+
+/// 接口要求必须填写 max_tokens，未指定时使用该值
+const DEFAULT_MAX_TOKENS: u32 = 4096;
+
+/// Anthropic Messages API（/v1/messages）
+pub(crate) struct AnthropicProvider;
+
+#[async_trait]
+impl Provider for AnthropicProvider {
+    async fn send(
+        &self,
+        client: &AiClient,
+        request: &ChatRequest,
+        on_delta: Option<DeltaFn<'_>>,
+    ) -> Result<String, AttemptFailure> {
+        let config = client.config();
+        let base = config.api_url.trim_end_matches('/');
+        let url = if base.ends_with("/v1") {
+            format!("{}/messages", base)
+        } else {
+            format!("{}/v1/messages", base)
+        };
+
+        let builder = client
+            .http()
+            .post(&url)
+            .header("x-api-key", &config.api_key)
+            .header("anthropic-version", ANTHROPIC_VERSION)
+            .json(&request_body(request, on_delta.is_some()));
+        let mut response = client.execute(builder).await?;
+
+        let mut reply = AnthropicReply::default();
+        match on_delta {
+            Some(on_delta) => {
+                client::read_sse_data(&mut response, client.read_timeout(), |data| {
+                    let Ok(event) = serde_json::from_str::<Value>(data) else {
+                        return (false, false);
+                    };
+                    let text = reply.apply_event(&event);
+                    if let Some(text) = &text {
+                        on_delta(text);
+                    }
+                    (reply.stopped || reply.error.is_some(), text.is_some())
+                })
+                .await?;
+            }
+            None => {
+                let text = client::read_body(&mut response, client.read_timeout()).await?;
+                let message = serde_json::from_str::<Value>(&text)
+                    .map_err(|e| AttemptFailure::invalid_response(format!("解析 Anthropic 响应失败: {}", e)))?;
+                reply.apply_message(&message);
+            }
+        }
+
+        if let Some((kind, message)) = reply.error.take() {
+            // 过载或限流时可以重试，但已经输出过内容就不再重试
+            let (error_kind, retryable) = error_kind(&kind);
+            return Err(AttemptFailure {
+                error: AiError::new(error_kind, format!("Anthropic 返回错误 ({}): {}", kind, message)),
+                retryable: retryable && !reply.emitted,
+                retry_after: None,
+            });
+        }
+        // 流式输出在 message_stop 之前断开时回复不完整
+        if on_delta.is_some() && !reply.stopped {
+            let mut failure = AttemptFailure::network("Anthropic 流式响应提前结束".to_string());
+            failure.retryable = !reply.emitted;
+            return Err(failure);
+        }
+
+        Ok(reply.into_body(&request.model))
+    }
+}
+
+/// 根据流式 `error` 事件的错误类型判断错误种类，并返回是否可以重试
+fn error_kind(kind: &str) -> (AiErrorKind, bool) {
+    match kind {
+        "overloaded_error" | "api_error" => (AiErrorKind::Server, true),
+        "rate_limit_error" => (AiErrorKind::Quota, true),
+        "authentication_error" | "permission_error" => (AiErrorKind::Auth, false),
+        "not_found_error" => (AiErrorKind::InvalidModel, false),
+        "invalid_request_error" => (AiErrorKind::BadRequest, false),
+        _ => (AiErrorKind::Other, false),
+    }
+}
+
+/// 将 OpenAI 格式的请求转换为 Messages API 格式
+///
+/// 系统消息放在顶层 `system` 字段；工具结果作为 user 消息中的 `tool_result` 块，
+/// 相邻的同角色消息合并，以满足 user / assistant 交替出现的要求。
+fn request_body(request: &ChatRequest, stream: bool) -> Value {
+    let mut turns: Vec<Value> = Vec::new();
+
+    for message in &request.messages {
+        let text = message.content.clone().unwrap_or_default();
+        match message.role.as_str() {
+            "system" => {}
+            "assistant" => {
+                let mut blocks = Vec::new();
+                if !text.is_empty() {
+                    blocks.push(json!({ "type": "text", "text": text }));
+                }
+                for call in message.tool_calls.iter().flatten() {
+                    blocks.push(json!({
+                        "type": "tool_use",
+                        "id": call.id,
+                        "name": call.function.name,
+                        "input": provider::arguments_object(&call.function.arguments),
+                    }));
+                }
+                provider::push_turn(&mut turns, "assistant", "content", blocks);
+            }
+            "tool" => {
+                let block = json!({
+                    "type": "tool_result",
+                    "tool_use_id": message.tool_call_id.clone().unwrap_or_default(),
+                    "content": text,
+                });
+                provider::push_turn(&mut turns, "user", "content", vec![block]);
+            }
+            _ if !text.is_empty() => {
+                provider::push_turn(&mut turns, "user", "content", vec![json!({ "type": "text", "text": text })]);
+            }
+            _ => {}
+        }
+    }
+
+    let mut body = json!({
+        "model": request.model,
+        "max_tokens": request.max_tokens.unwrap_or(DEFAULT_MAX_TOKENS),
+        "messages": turns,
+        "stream": stream,
+    });
+    if let Some(system) = provider::system_prompt(&request.messages) {
+        body["system"] = json!(system);
+    }
+    if let Some(temperature) = request.temperature {
+        // Anthropic 的 temperature 范围为 0~1
+        body["temperature"] = json!(temperature.clamp(0.0, 1.0));
+    }
+
+    let tools = provider::tool_functions(request);
+    if !tools.is_empty() {
+        body["tools"] = tools
+            .into_iter()
+            .map(|(name, description, parameters)| {
+                json!({ "name": name, "description": description, "input_schema": parameters })
+            })
+            .collect();
+        body["tool_choice"] = match request.tool_choice.as_deref() {
+            Some("none") => json!({ "type": "none" }),
+            Some("required") => json!({ "type": "any" }),
+            _ => json!({ "type": "auto" }),
+        };
+    }
+    body
+}
+
+/// 逐步拼装 Messages API 的回复
+#[derive(Default)]
+struct AnthropicReply {
+    content: String,
+    tool_calls: BTreeMap<u64, ToolCall>, // 按内容块序号排列
+    input_tokens: i64,
+    output_tokens: i64,
+    stopped: bool,
+    emitted: bool,
+    error: Option<(String, String)>, // (错误类型, 错误信息)
+}
+
+impl AnthropicReply {
+    fn push_text(&mut self, text: &str) -> Option<String> {
+        if text.is_empty() {
+            return None;
+        }
+        self.content.push_str(text);
+        self.emitted = true;
+        Some(text.to_string())
+    }
+
+    fn start_tool(&mut self, index: u64, block: &Value, arguments: String) {
+        self.tool_calls.insert(
+            index,
+            ToolCall {
+                id: block["id"].as_str().unwrap_or_default().to_string(),
+                kind: "function".to_string(),
+                function: FunctionCall {
+                    name: block["name"].as_str().unwrap_or_default().to_string(),
+                    arguments,
+                },
+            },
+        );
+    }
+
+    /// 合并非流式响应
+    fn apply_message(&mut self, message: &Value) {
+        for (index, block) in message["content"].as_array().into_iter().flatten().enumerate() {
+            match block["type"].as_str() {
+                Some("text") => {
+                    self.push_text(block["text"].as_str().unwrap_or_default());
+                }
+                Some("tool_use") => {
+                    let arguments = block.get("input").map(Value::to_string).unwrap_or_else(|| "{}".to_string());
+                    self.start_tool(index as u64, block, arguments);
+                }
+                _ => {}
+            }
+        }
+        self.input_tokens = message["usage"]["input_tokens"].as_i64().unwrap_or(0);
+        self.output_tokens = message["usage"]["output_tokens"].as_i64().unwrap_or(0);
+    }
+
+    /// 合并一个流式事件，返回其中新增的文本
+    fn apply_event(&mut self, event: &Value) -> Option<String> {
+        let index = event["index"].as_u64().unwrap_or(0);
+        match event["type"].as_str()? {
+            "message_start" => {
+                let usage = &event["message"]["usage"];
+                self.input_tokens = usage["input_tokens"].as_i64().unwrap_or(0);
+                self.output_tokens = usage["output_tokens"].as_i64().unwrap_or(0);
+                None
+            }
+            "content_block_start" => {
+                let block = &event["content_block"];
+                match block["type"].as_str() {
+                    // 参数随后通过 input_json_delta 分段返回
+                    Some("tool_use") => {
+                        self.start_tool(index, block, String::new());
+                        None
+                    }
+                    Some("text") => self.push_text(block["text"].as_str().unwrap_or_default()),
+                    _ => None,
+                }
+            }
+            "content_block_delta" => {
+                let delta = &event["delta"];
+                match delta["type"].as_str() {
+                    Some("text_delta") => self.push_text(delta["text"].as_str().unwrap_or_default()),
+                    Some("input_json_delta") => {
+                        if let Some(call) = self.tool_calls.get_mut(&index) {
+                            call.function
+                                .arguments
+                                .push_str(delta["partial_json"].as_str().unwrap_or_default());
+                        }
+                        None
+                    }
+                    _ => None,
+                }
+            }
+            "message_delta" => {
+                if let Some(output_tokens) = event["usage"]["output_tokens"].as_i64() {
+                    self.output_tokens = output_tokens;
+                }
+                None
+            }
+            "message_stop" => {
+                self.stopped = true;
+                None
+            }
+            "error" => {
+                self.error = Some((
+                    event["error"]["type"].as_str().unwrap_or("error").to_string(),
+                    event["error"]["message"].as_str().unwrap_or_default().to_string(),
+                ));
+                None
+            }
+            _ => None,
+        }
+    }
+
+    fn into_body(self, model: &str) -> String {
+        let tool_calls: Vec<ToolCall> = self
+            .tool_calls
+            .into_values()
+            .map(|mut call| {
+                if call.function.arguments.trim().is_empty() {
+                    call.function.arguments = "{}".to_string();
+                }
+                call
+            })
+            .collect();
+        let message = Message {
+            role: "assistant".to_string(),
+            content: Some(self.content),
+            tool_calls: if tool_calls.is_empty() { None } else { Some(tool_calls) },
+            tool_call_id: None,
+        };
+        json!({
+            "object": "chat.completion",
+            "model": model,
+            "choices": [{ "index": 0, "message": message }],
+            "usage": {
+                "prompt_tokens": self.input_tokens,
+                "completion_tokens": self.output_tokens,
+                "total_tokens": self.input_tokens + self.output_tokens,
+            }
+        })
+        .to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::models::ProviderKind;
+    use crate::test_support::{client_for, sse_events, Reply, ScriptedServer};
+
+    const TEXT: &str = include_str!("../../tests/fixtures/anthropic/text.sse");
+    const TOOL_USE: &str = include_str!("../../tests/fixtures/anthropic/tool_use.sse");
+    const ERROR: &str = include_str!("../../tests/fixtures/anthropic/error.sse");
+
+    /// 依次合并录制的事件，返回输出的文本片段
+    fn replay(reply: &mut AnthropicReply, fixture: &str) -> Vec<String> {
+        sse_events(fixture).iter().filter_map(|event| reply.apply_event(event)).collect()
+    }
+
+    fn body(reply: AnthropicReply) -> Value {
+        serde_json::from_str(&reply.into_body("claude-test")).unwrap()
+    }
+
+    #[test]
+    fn text_stream_is_normalized() {
+        let mut reply = AnthropicReply::default();
+        assert_eq!(replay(&mut reply, TEXT), vec!["今天完成了", "三项工作。"]);
+        assert!(reply.stopped);
+
+        let body = body(reply);
+        assert_eq!(body["choices"][0]["message"]["role"], "assistant");
+        assert_eq!(body["choices"][0]["message"]["content"], "今天完成了三项工作。");
+        assert!(body["choices"][0]["message"].get("tool_calls").is_none());
+        assert_eq!(body["usage"], json!({ "prompt_tokens": 25, "completion_tokens": 12, "total_tokens": 37 }));
+    }
+
+    #[test]
+    fn tool_use_stream_becomes_tool_calls() {
+        let mut reply = AnthropicReply::default();
+        replay(&mut reply, TOOL_USE);
+
+        let body = body(reply);
+        let message = &body["choices"][0]["message"];
+        assert_eq!(message["content"], "我先查一下相关记录。");
+        assert_eq!(
+            message["tool_calls"],
+            json!([
+                {
+                    "id": "toolu_01T1x1fJ34qAmk2tNTrN7Up6",
+                    "type": "function",
+                    "function": { "name": "search_records", "arguments": "{\"keyword\": \"周报\"}" }
+                },
+                {
+                    "id": "toolu_01Kp3V4x8bZ5nQ2mR7sT9wYc",
+                    "type": "function",
+                    "function": { "name": "get_current_time", "arguments": "{}" }
+                }
+            ])
+        );
+        assert_eq!(body["usage"]["total_tokens"], 472 + 89);
+    }
+
+    #[test]
+    fn error_event_is_recorded() {
+        let mut reply = AnthropicReply::default();
+        assert_eq!(replay(&mut reply, ERROR), vec!["部分"]);
+        assert_eq!(reply.error, Some(("overloaded_error".to_string(), "Overloaded".to_string())));
+        assert!(reply.emitted);
+    }
+
+    #[test]
+    fn error_types_are_classified() {
+        let cases = [
+            ("overloaded_error", AiErrorKind::Server, true),
+            ("rate_limit_error", AiErrorKind::Quota, true),
+            ("authentication_error", AiErrorKind::Auth, false),
+            ("permission_error", AiErrorKind::Auth, false),
+            ("not_found_error", AiErrorKind::InvalidModel, false),
+            ("invalid_request_error", AiErrorKind::BadRequest, false),
+            ("something_new", AiErrorKind::Other, false),
+        ];
+        for (kind, expected, retryable) in cases {
+            assert_eq!(error_kind(kind), (expected, retryable), "{}", kind);
+        }
+    }
+
+    #[tokio::test]
+    async fn stream_without_message_stop_is_an_error() {
+        let (truncated, _) = TEXT.split_once("event: message_stop").unwrap();
+        let server = ScriptedServer::start(vec![Reply::sse(truncated)]).await;
+        let client = client_for(ProviderKind::Anthropic, &server.url);
+        let request = ChatRequest {
+            model: "claude-test".to_string(),
+            messages: vec![Message::user("总结本周")],
+            tools: None,
+            tool_choice: None,
+            temperature: None,
+            max_tokens: None,
+            stream: None,
+        };
+        let on_delta = |_: &str| {};
+
+        let error = client.chat_completion_stream(&request, &on_delta).await.expect_err("应当返回错误");
+        assert_eq!(error.kind, AiErrorKind::Network);
+        assert_eq!(server.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn streams_recorded_fixture_from_server() {
+        let server = ScriptedServer::start(vec![Reply::sse(TOOL_USE), Reply::sse(ERROR)]).await;
+        let client = client_for(ProviderKind::Anthropic, &server.url);
+        let request = ChatRequest {
+            model: "claude-test".to_string(),
+            messages: vec![Message::system("你是日报助手"), Message::user("总结本周")],
+            tools: None,
+            tool_choice: None,
+            temperature: Some(1.5),
+            max_tokens: None,
+            stream: None,
+        };
+        let on_delta = |_: &str| {};
+
+        let response = client.chat_completion_stream(&request, &on_delta).await.expect("请求失败");
+        assert_eq!(response.choices[0].message.tool_calls.as_ref().map(Vec::len), Some(2));
+
+        let sent = &server.requests()[0];
+        assert_eq!(sent.path, "/v1/messages");
+        assert_eq!(sent.headers.get("x-api-key").map(String::as_str), Some("test-key"));
+        assert_eq!(sent.headers.get("anthropic-version").map(String::as_str), Some(ANTHROPIC_VERSION));
+        let sent = sent.json();
+        assert_eq!(sent["system"], "你是日报助手");
+        assert_eq!(sent["stream"], true);
+        assert_eq!(sent["temperature"], 1.0);
+        assert_eq!(sent["max_tokens"], DEFAULT_MAX_TOKENS);
+
+        let error = client.chat_completion_stream(&request, &on_delta).await.expect_err("应当返回错误");
+        assert_eq!(error.kind, AiErrorKind::Server);
+        assert!(error.message.contains("overloaded_error"), "{}", error.message);
+    }
+}
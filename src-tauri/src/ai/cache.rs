@@ -0,0 +1,222 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::{Pool, Sqlite};
+
+use crate::ai::client::ChatRequest;
+use crate::models::ProviderKind;
+
+/// 进程内的缓存命中计数（作为应用状态管理）
+#[derive(Clone, Default)]
+pub struct CacheCounters {
+    hits: Arc<AtomicU64>,
+    misses: Arc<AtomicU64>,
+}
+
+impl CacheCounters {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn hit(&self) {
+        self.hits.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn miss(&self) {
+        self.misses.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// 缓存统计（用于返回给前端）
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub entries: i64,
+    pub total_bytes: i64,
+}
+
+/// 基于 SQLite 的 AI 响应缓存
+#[derive(Clone)]
+pub struct ResponseCache {
+    pool: Pool<Sqlite>,
+    ttl_secs: i64,
+    max_bytes: i64,
+    counters: CacheCounters,
+}
+
+/// 计算请求的缓存键
+///
+/// 键由服务类型、接口地址、模型、完整消息（包括工具返回的记录数据）和采样参数共同决定，
+/// 因此底层记录发生变化或切换到其他服务时自然会得到新的键。
+pub fn cache_key(provider: ProviderKind, api_url: &str, request: &ChatRequest) -> String {
+    let payload = serde_json::json!({
+        "provider": provider,
+        "api_url": api_url.trim_end_matches('/'),
+        "request": request,
+    });
+    hex::encode(Sha256::digest(payload.to_string().as_bytes()))
+}
+
+impl ResponseCache {
+    pub fn new(pool: Pool<Sqlite>, ttl_secs: i64, max_bytes: i64, counters: CacheCounters) -> Self {
+        Self {
+            pool,
+            ttl_secs,
+            max_bytes,
+            counters,
+        }
+    }
+
+    /// 读取缓存的原始响应，过期条目视为未命中并删除
+    pub async fn get(&self, key: &str) -> Result<Option<String>, String> {
+        let now = chrono::Local::now().timestamp();
+
+        let row: Option<(String, i64)> = sqlx::query_as(
+            "SELECT response, created_at FROM ai_response_cache WHERE cache_key = ?",
+        )
+        .bind(key)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| format!("读取 AI 缓存失败: {}", e))?;
+
+        match row {
+            Some((response, created_at)) if now - created_at <= self.ttl_secs => {
+                sqlx::query(
+                    "UPDATE ai_response_cache SET last_accessed_at = ?, hit_count = hit_count + 1 WHERE cache_key = ?",
+                )
+                .bind(now)
+                .bind(key)
+                .execute(&self.pool)
+                .await
+                .map_err(|e| format!("更新 AI 缓存失败: {}", e))?;
+
+                self.counters.hit();
+                Ok(Some(response))
+            }
+            Some(_) => {
+                sqlx::query("DELETE FROM ai_response_cache WHERE cache_key = ?")
+                    .bind(key)
+                    .execute(&self.pool)
+                    .await
+                    .map_err(|e| format!("删除过期 AI 缓存失败: {}", e))?;
+
+                self.counters.miss();
+                Ok(None)
+            }
+            None => {
+                self.counters.miss();
+                Ok(None)
+            }
+        }
+    }
+
+    /// 写入响应，并按容量上限淘汰最近最少使用的条目
+    pub async fn put(&self, key: &str, model: &str, response: &str) -> Result<(), String> {
+        let now = chrono::Local::now().timestamp();
+
+        sqlx::query(
+            r#"
+            INSERT OR REPLACE INTO ai_response_cache
+                (cache_key, model, response, size_bytes, created_at, last_accessed_at, hit_count)
+            VALUES (?, ?, ?, ?, ?, ?, 0)
+            "#,
+        )
+        .bind(key)
+        .bind(model)
+        .bind(response)
+        .bind(response.len() as i64)
+        .bind(now)
+        .bind(now)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| format!("写入 AI 缓存失败: {}", e))?;
+
+        self.evict(now).await
+    }
+
+    async fn evict(&self, now: i64) -> Result<(), String> {
+        sqlx::query("DELETE FROM ai_response_cache WHERE created_at < ?")
+            .bind(now - self.ttl_secs)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| format!("清理过期 AI 缓存失败: {}", e))?;
+
+        // 从最久未访问的条目开始删除，直到总大小不超过上限
+        sqlx::query(
+            r#"
+            DELETE FROM ai_response_cache WHERE cache_key IN (
+                SELECT cache_key FROM (
+                    SELECT cache_key,
+                           SUM(size_bytes) OVER (
+                               ORDER BY last_accessed_at DESC, created_at DESC, cache_key
+                           ) AS running_bytes
+                    FROM ai_response_cache
+                ) WHERE running_bytes > ?
+            )
+            "#,
+        )
+        .bind(self.max_bytes)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| format!("淘汰 AI 缓存失败: {}", e))?;
+
+        Ok(())
+    }
+}
+
+/// 读取缓存统计
+pub async fn stats(pool: &Pool<Sqlite>, counters: &CacheCounters) -> Result<CacheStats, String> {
+    let (entries, total_bytes): (i64, i64) = sqlx::query_as(
+        "SELECT COUNT(*), COALESCE(SUM(size_bytes), 0) FROM ai_response_cache",
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(|e| format!("读取 AI 缓存统计失败: {}", e))?;
+
+    Ok(CacheStats {
+        hits: counters.hits.load(Ordering::Relaxed),
+        misses: counters.misses.load(Ordering::Relaxed),
+        entries,
+        total_bytes,
+    })
+}
+
+/// 清空缓存
+pub async fn clear(pool: &Pool<Sqlite>) -> Result<(), String> {
+    sqlx::query("DELETE FROM ai_response_cache")
+        .execute(pool)
+        .await
+        .map_err(|e| format!("清空 AI 缓存失败: {}", e))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::client::Message;
+
+    fn request() -> ChatRequest {
+        ChatRequest {
+            model: "test-model".to_string(),
+            messages: vec![Message::user("总结本周")],
+            tools: None,
+            tool_choice: None,
+            temperature: None,
+            max_tokens: None,
+            stream: None,
+        }
+    }
+
+    #[test]
+    fn cache_key_depends_on_provider_and_endpoint() {
+        let request = request();
+        let base = cache_key(ProviderKind::OpenAi, "https://api.example.com/v1", &request);
+
+        assert_eq!(base, cache_key(ProviderKind::OpenAi, "https://api.example.com/v1/", &request));
+        assert_ne!(base, cache_key(ProviderKind::LlamaCpp, "https://api.example.com/v1", &request));
+        assert_ne!(base, cache_key(ProviderKind::OpenAi, "https://other.example.com/v1", &request));
+    }
+}
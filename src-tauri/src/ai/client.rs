@@ -0,0 +1,419 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::ai::cache::{self, ResponseCache};
+use crate::ai::error::{AiError, AiErrorKind};
+use crate::ai::provider::{self, Provider};
+use crate::ai::retry::{self, RetryPolicy};
+use crate::models::{ApiConfig, TokenUsage};
+
+/// 模型发起的函数调用
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FunctionCall {
+    pub name: String,
+    #[serde(default)]
+    pub arguments: String, // JSON 字符串
+}
+
+/// 模型发起的工具调用
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolCall {
+    #[serde(default)]
+    pub id: String,
+    #[serde(rename = "type", default = "default_tool_type")]
+    pub kind: String,
+    pub function: FunctionCall,
+}
+
+pub(crate) fn default_tool_type() -> String {
+    "function".to_string()
+}
+
+/// 对话消息（OpenAI chat completions 格式）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Message {
+    pub role: String,
+    #[serde(default)]
+    pub content: Option<String>,
+    #[serde(default, alias = "function_calls", skip_serializing_if = "Option::is_none")]
+    pub tool_calls: Option<Vec<ToolCall>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_call_id: Option<String>,
+}
+
+impl Message {
+    pub fn system(content: impl Into<String>) -> Self {
+        Self::text("system", content)
+    }
+
+    pub fn user(content: impl Into<String>) -> Self {
+        Self::text("user", content)
+    }
+
+    pub fn tool(tool_call_id: impl Into<String>, content: impl Into<String>) -> Self {
+        Self {
+            role: "tool".to_string(),
+            content: Some(content.into()),
+            tool_calls: None,
+            tool_call_id: Some(tool_call_id.into()),
+        }
+    }
+
+    fn text(role: &str, content: impl Into<String>) -> Self {
+        Self {
+            role: role.to_string(),
+            content: Some(content.into()),
+            tool_calls: None,
+            tool_call_id: None,
+        }
+    }
+}
+
+/// chat completions 请求体
+#[derive(Debug, Clone, Serialize)]
+pub struct ChatRequest {
+    pub model: String,
+    pub messages: Vec<Message>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tools: Option<Vec<serde_json::Value>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_choice: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stream: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Choice {
+    pub message: Message,
+}
+
+/// chat completions 响应体
+#[derive(Debug, Deserialize)]
+pub struct ChatResponse {
+    pub choices: Vec<Choice>,
+    #[serde(default)]
+    pub usage: Option<TokenUsage>,
+}
+
+/// 流式输出时接收增量文本的回调
+pub type DeltaFn<'a> = &'a (dyn Fn(&str) + Send + Sync);
+
+/// 单次请求失败的结果
+pub(crate) struct AttemptFailure {
+    pub(crate) error: AiError,
+    pub(crate) retryable: bool,
+    pub(crate) retry_after: Option<Duration>,
+}
+
+impl AttemptFailure {
+    pub(crate) fn network(message: String) -> Self {
+        Self {
+            error: AiError::network(message),
+            retryable: true,
+            retry_after: None,
+        }
+    }
+
+    /// 响应无法解析，重试也不会得到不同结果
+    pub(crate) fn invalid_response(message: String) -> Self {
+        Self {
+            error: AiError::new(AiErrorKind::InvalidResponse, message),
+            retryable: false,
+            retry_after: None,
+        }
+    }
+
+    /// 根据 HTTP 状态码构造失败结果，额度耗尽时不重试
+    pub(crate) fn from_status(status: u16, body: &str, retry_after: Option<Duration>) -> Self {
+        Self {
+            error: AiError::from_status(status, body),
+            retryable: retry::is_retryable_status(status) && !body.contains("insufficient_quota"),
+            retry_after,
+        }
+    }
+}
+
+/// AI 客户端：对外统一使用 OpenAI chat completions 格式，按配置转换为各服务商的接口
+pub struct AiClient {
+    http: reqwest::Client,
+    config: ApiConfig,
+    policy: RetryPolicy,
+    cache: Option<ResponseCache>,
+    bypass_cache: bool,
+    provider: Box<dyn Provider>,
+}
+
+fn build_http_client(policy: &RetryPolicy) -> reqwest::Client {
+    reqwest::Client::builder()
+        .connect_timeout(policy.connect_timeout)
+        .build()
+        .unwrap_or_else(|_| reqwest::Client::new())
+}
+
+impl AiClient {
+    pub fn new(config: ApiConfig) -> Self {
+        let policy = RetryPolicy::default();
+        let provider = provider::for_config(&config);
+        Self {
+            http: build_http_client(&policy),
+            config,
+            policy,
+            cache: None,
+            bypass_cache: false,
+            provider,
+        }
+    }
+
+    /// 设置超时与重试策略
+    pub fn with_policy(mut self, policy: RetryPolicy) -> Self {
+        self.http = build_http_client(&policy);
+        self.policy = policy;
+        self
+    }
+
+    /// 启用响应缓存；`bypass` 为 true 时跳过读取但仍写入最新响应
+    pub fn with_cache(mut self, cache: ResponseCache, bypass: bool) -> Self {
+        self.cache = Some(cache);
+        self.bypass_cache = bypass;
+        self
+    }
+
+    pub fn model(&self) -> &str {
+        &self.config.model
+    }
+
+    /// 调用 chat completions 接口（启用缓存时优先读取缓存）
+    pub async fn chat_completion(&self, request: &ChatRequest) -> Result<ChatResponse, AiError> {
+        self.complete(request, None).await
+    }
+
+    /// 以流式方式调用接口，每收到一段文本就调用 `on_delta`
+    pub async fn chat_completion_stream(
+        &self,
+        request: &ChatRequest,
+        on_delta: DeltaFn<'_>,
+    ) -> Result<ChatResponse, AiError> {
+        self.complete(request, Some(on_delta)).await
+    }
+
+    async fn complete(&self, request: &ChatRequest, on_delta: Option<DeltaFn<'_>>) -> Result<ChatResponse, AiError> {
+        // 是否流式不影响结果，缓存键中不包含该字段
+        let mut request = request.clone();
+        request.stream = None;
+        let key = self
+            .cache
+            .as_ref()
+            .map(|_| cache::cache_key(self.config.provider, &self.config.api_url, &request));
+
+        if let (Some(cache), Some(key), false) = (&self.cache, &key, self.bypass_cache) {
+            // 缓存异常不影响正常请求
+            match cache.get(key).await {
+                Ok(Some(body)) => {
+                    if let Ok(response) = serde_json::from_str::<ChatResponse>(&body) {
+                        if let (Some(on_delta), Some(content)) = (
+                            on_delta,
+                            response.choices.first().and_then(|c| c.message.content.as_deref()),
+                        ) {
+                            on_delta(content);
+                        }
+                        return Ok(response);
+                    }
+                }
+                Ok(None) => {}
+                Err(e) => eprintln!("{}", e),
+            }
+        }
+
+        let body = self.send(&request, on_delta).await?;
+        let response = serde_json::from_str::<ChatResponse>(&body).map_err(|e| {
+            AiError::new(AiErrorKind::InvalidResponse, format!("解析 AI 响应失败: {}", e))
+        })?;
+
+        if let (Some(cache), Some(key)) = (&self.cache, &key) {
+            if let Err(e) = cache.put(key, &request.model, &body).await {
+                eprintln!("{}", e);
+            }
+        }
+
+        Ok(response)
+    }
+
+    /// 按重试策略发送请求，返回非流式格式的响应体
+    async fn send(&self, request: &ChatRequest, on_delta: Option<DeltaFn<'_>>) -> Result<String, AiError> {
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            let failure = match self.provider.send(self, request, on_delta).await {
+                Ok(body) => return Ok(body),
+                Err(failure) => failure,
+            };
+
+            if !failure.retryable || attempt >= self.policy.max_attempts {
+                let mut error = failure.error;
+                error.attempts = attempt;
+                return Err(error);
+            }
+
+            let delay = failure
+                .retry_after
+                .unwrap_or_else(|| self.policy.backoff(attempt));
+            eprintln!(
+                "AI 请求第 {} 次失败，{} 毫秒后重试: {}",
+                attempt,
+                delay.as_millis(),
+                failure.error
+            );
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    /// 发送请求，等待响应头受读取超时限制，非 2xx 状态转换为失败结果
+    pub(crate) async fn execute(&self, builder: reqwest::RequestBuilder) -> Result<reqwest::Response, AttemptFailure> {
+        let read_timeout = self.policy.read_timeout;
+        let mut response = match tokio::time::timeout(read_timeout, builder.send()).await {
+            Err(_) => {
+                return Err(AttemptFailure::network(format!(
+                    "等待响应超过 {} 秒",
+                    read_timeout.as_secs()
+                )))
+            }
+            Ok(Err(e)) if e.is_builder() => {
+                return Err(AttemptFailure {
+                    error: AiError::new(AiErrorKind::BadRequest, format!("无效的请求: {}", e)),
+                    retryable: false,
+                    retry_after: None,
+                })
+            }
+            Ok(Err(e)) => return Err(AttemptFailure::network(format!("请求 AI 接口失败: {}", e))),
+            Ok(Ok(response)) => response,
+        };
+
+        let status = response.status().as_u16();
+        if (200..300).contains(&status) {
+            return Ok(response);
+        }
+
+        let retry_after = self.policy.retry_after(
+            response
+                .headers()
+                .get(reqwest::header::RETRY_AFTER)
+                .and_then(|v| v.to_str().ok()),
+        );
+        let body = read_body(&mut response, read_timeout).await?;
+        Err(AttemptFailure::from_status(status, &body, retry_after))
+    }
+
+    pub(crate) fn http(&self) -> &reqwest::Client {
+        &self.http
+    }
+
+    pub(crate) fn config(&self) -> &ApiConfig {
+        &self.config
+    }
+
+    pub(crate) fn read_timeout(&self) -> Duration {
+        self.policy.read_timeout
+    }
+}
+
+/// 读取下一块响应数据，超时或连接中断视为可重试的网络错误
+async fn next_chunk(
+    response: &mut reqwest::Response,
+    read_timeout: Duration,
+) -> Result<Option<Vec<u8>>, AttemptFailure> {
+    match tokio::time::timeout(read_timeout, response.chunk()).await {
+        Err(_) => Err(AttemptFailure::network(format!(
+            "读取响应超过 {} 秒没有数据",
+            read_timeout.as_secs()
+        ))),
+        Ok(Err(e)) => Err(AttemptFailure::network(format!("读取 AI 响应失败: {}", e))),
+        Ok(Ok(chunk)) => Ok(chunk.map(|bytes| bytes.to_vec())),
+    }
+}
+
+/// 逐块读取完整响应体
+pub(crate) async fn read_body(
+    response: &mut reqwest::Response,
+    read_timeout: Duration,
+) -> Result<String, AttemptFailure> {
+    let mut body = Vec::new();
+    while let Some(chunk) = next_chunk(response, read_timeout).await? {
+        body.extend_from_slice(&chunk);
+    }
+    Ok(String::from_utf8_lossy(&body).into_owned())
+}
+
+/// 按行读取流式响应，`on_line` 返回 true 时提前结束
+///
+/// 已经输出过内容（`emitted` 为 true）后出现的网络错误不再重试，避免重复输出。
+pub(crate) async fn read_lines<F>(
+    response: &mut reqwest::Response,
+    read_timeout: Duration,
+    mut on_line: F,
+) -> Result<(), AttemptFailure>
+where
+    F: FnMut(&str) -> (bool, bool), // (是否结束, 是否已输出内容)
+{
+    let mut buffer: Vec<u8> = Vec::new();
+    let mut emitted = false;
+
+    loop {
+        let chunk = match next_chunk(response, read_timeout).await {
+            Ok(Some(chunk)) => chunk,
+            Ok(None) => break,
+            Err(mut failure) => {
+                failure.retryable = !emitted;
+                return Err(failure);
+            }
+        };
+        buffer.extend_from_slice(&chunk);
+
+        while let Some(pos) = buffer.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = buffer.drain(..=pos).collect();
+            let line = String::from_utf8_lossy(&line);
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (done, has_output) = on_line(line);
+            emitted |= has_output;
+            if done {
+                return Ok(());
+            }
+        }
+    }
+
+    // 最后一行可能没有换行符
+    let rest = String::from_utf8_lossy(&buffer);
+    if !rest.trim().is_empty() {
+        on_line(rest.trim());
+    }
+    Ok(())
+}
+
+/// 读取 SSE 流中的 `data:` 行，遇到 `data: [DONE]` 时结束
+pub(crate) async fn read_sse_data<F>(
+    response: &mut reqwest::Response,
+    read_timeout: Duration,
+    mut on_data: F,
+) -> Result<(), AttemptFailure>
+where
+    F: FnMut(&str) -> (bool, bool), // (是否结束, 是否已输出内容)
+{
+    read_lines(response, read_timeout, |line| {
+        let Some(data) = line.strip_prefix("data:") else {
+            return (false, false);
+        };
+        let data = data.trim();
+        if data == "[DONE]" {
+            return (true, false);
+        }
+        on_data(data)
+    })
+    .await
+}
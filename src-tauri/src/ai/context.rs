@@ -0,0 +1,550 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tiktoken_rs::tokenizer::{get_tokenizer, Tokenizer};
+use tiktoken_rs::CoreBPE;
+
+use crate::models::{DoneTask, Idea, IdeaLink, PlanCarry, PlanStatus, PlannedTask};
+
+/// 未知模型使用 cl100k 近似估算时的放大系数
+const UNKNOWN_MODEL_FACTOR: f64 = 1.2;
+
+/// 截断长内容时保留的字符数
+const TRUNCATE_CHARS: usize = 200;
+
+/// 摘要化的天中保留的事项标题数
+const SUMMARY_SAMPLE_COUNT: usize = 3;
+
+/// 摘要中每个标题保留的字符数
+const SUMMARY_TITLE_CHARS: usize = 40;
+
+/// 未配置上下文预算且模型未知时的默认上下文窗口
+const DEFAULT_CONTEXT_WINDOW: usize = 32_000;
+
+/// 记录数据最多占用上下文窗口的比例，其余留给提示词、对话和回复
+const DATA_SHARE: f64 = 0.6;
+
+/// 基于 BPE 分词器的 token 估算器
+pub struct TokenEstimator {
+    bpe: &'static CoreBPE,
+    factor: f64,
+    exact: bool,
+}
+
+impl TokenEstimator {
+    /// 根据模型名称选择分词器；未知模型用 cl100k 估算并放大
+    pub fn for_model(model: &str) -> Self {
+        let name = model.rsplit('/').next().unwrap_or(model).to_lowercase();
+        match get_tokenizer(&name) {
+            Some(Tokenizer::O200kBase) => Self {
+                bpe: tiktoken_rs::o200k_base_singleton(),
+                factor: 1.0,
+                exact: true,
+            },
+            Some(Tokenizer::Cl100kBase) => Self {
+                bpe: tiktoken_rs::cl100k_base_singleton(),
+                factor: 1.0,
+                exact: true,
+            },
+            Some(Tokenizer::P50kBase) | Some(Tokenizer::P50kEdit) => Self {
+                bpe: tiktoken_rs::p50k_base_singleton(),
+                factor: 1.0,
+                exact: true,
+            },
+            Some(Tokenizer::R50kBase) | Some(Tokenizer::Gpt2) => Self {
+                bpe: tiktoken_rs::r50k_base_singleton(),
+                factor: 1.0,
+                exact: true,
+            },
+            _ => Self {
+                bpe: tiktoken_rs::cl100k_base_singleton(),
+                factor: UNKNOWN_MODEL_FACTOR,
+                exact: false,
+            },
+        }
+    }
+
+    /// 估算文本的 token 数
+    pub fn count(&self, text: &str) -> usize {
+        let tokens = self.bpe.encode_with_special_tokens(text).len();
+        (tokens as f64 * self.factor).ceil() as usize
+    }
+
+    /// 是否为该模型实际使用的分词器
+    pub fn is_exact(&self) -> bool {
+        self.exact
+    }
+}
+
+/// 模型的默认上下文窗口
+pub fn default_context_window(model: &str) -> usize {
+    let name = model.rsplit('/').next().unwrap_or(model).to_lowercase();
+    if name.starts_with("gpt-4o")
+        || name.starts_with("gpt-4.1")
+        || name.starts_with("gpt-4-turbo")
+        || name.starts_with("chatgpt-4o")
+        || name.starts_with("o1")
+        || name.starts_with("o3")
+        || name.starts_with("o4")
+    {
+        128_000
+    } else if name.starts_with("claude") {
+        200_000
+    } else if name.starts_with("gemini-1.5") || name.starts_with("gemini-2") {
+        1_048_576
+    } else if name.starts_with("gpt-3.5") {
+        16_385
+    } else if name.starts_with("gpt-4-32k") {
+        32_768
+    } else if name.starts_with("gpt-4") {
+        8_192
+    } else {
+        DEFAULT_CONTEXT_WINDOW
+    }
+}
+
+/// 记录数据可用的 token 预算
+pub fn data_budget(model: &str, context_budget: Option<usize>) -> usize {
+    let window = context_budget.unwrap_or_else(|| default_context_window(model));
+    (window as f64 * DATA_SHARE) as usize
+}
+
+/// 上下文裁剪报告
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ContextReport {
+    pub budget_tokens: usize,
+    pub original_tokens: usize,
+    pub final_tokens: usize,
+    pub exact_tokenizer: bool,
+    pub attachments_dropped: usize,
+    pub truncated_items: usize,
+    pub summarized_days: Vec<String>,
+    pub omitted_days: Vec<String>,
+    pub over_budget: bool, // 所有降级手段用尽后仍超出预算
+}
+
+/// 构建完成的上下文
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BuiltContext {
+    pub data: Value,
+    pub report: ContextReport,
+}
+
+/// 单天的数据
+struct DayRecords {
+    ideas: Vec<Idea>,
+    tasks: Vec<DoneTask>,
+    plans: Vec<PlannedTask>,
+    carried: Vec<PlanCarry>, // 当天未完成、已顺延到之后的计划
+    summarized: bool,
+}
+
+fn truncate_chars(text: &str, max_chars: usize) -> (String, bool) {
+    if text.chars().count() <= max_chars {
+        return (text.to_string(), false);
+    }
+    let truncated: String = text.chars().take(max_chars).collect();
+    (format!("{}…", truncated), true)
+}
+
+/// 按天组织的记录上下文构建器
+///
+/// 超出预算时依次降级：去掉附件、截断长内容、从最早的一天开始摘要化，
+/// 仍然超出时再从最早的一天开始省略。
+pub struct ContextBuilder<'a> {
+    estimator: &'a TokenEstimator,
+    budget: usize,
+    start_date: String,
+    end_date: String,
+    days: BTreeMap<String, DayRecords>,
+    next_plans: Vec<PlannedTask>,
+    links: Vec<IdeaLink>,
+    include_attachments: bool,
+}
+
+impl<'a> ContextBuilder<'a> {
+    pub fn new(estimator: &'a TokenEstimator, budget: usize, start_date: &str, end_date: &str) -> Self {
+        Self {
+            estimator,
+            budget,
+            start_date: start_date.to_string(),
+            end_date: end_date.to_string(),
+            days: BTreeMap::new(),
+            next_plans: Vec::new(),
+            links: Vec::new(),
+            include_attachments: true,
+        }
+    }
+
+    pub fn records(mut self, ideas: Vec<Idea>, tasks: Vec<DoneTask>) -> Self {
+        for idea in ideas {
+            self.day(&idea.date.clone()).ideas.push(idea);
+        }
+        for task in tasks {
+            self.day(&task.date.clone()).tasks.push(task);
+        }
+        self
+    }
+
+    /// 范围内的计划及从范围内顺延出去的记录
+    pub fn plans(mut self, plans: Vec<PlannedTask>, carries: Vec<PlanCarry>) -> Self {
+        for plan in plans {
+            self.day(&plan.target_date.clone()).plans.push(plan);
+        }
+        for carry in carries {
+            self.day(&carry.from_date.clone()).carried.push(carry);
+        }
+        self
+    }
+
+    /// 范围之后最近一天的未完成计划
+    pub fn next_plans(mut self, plans: Vec<PlannedTask>) -> Self {
+        self.next_plans = plans;
+        self
+    }
+
+    /// 想法与由其转化的计划、已完成事项之间的关联
+    pub fn idea_links(mut self, links: Vec<IdeaLink>) -> Self {
+        self.links = links;
+        self
+    }
+
+    /// 事项来源的想法，`date` 为事项或计划的日期
+    fn idea_origin(&self, link: &IdeaLink, date: &str) -> Value {
+        let mut value = json!({
+            "id": link.idea_id,
+            "content": truncate_chars(&link.idea_content, SUMMARY_TITLE_CHARS).0,
+            "date": link.idea_date,
+        });
+        let parse = |d: &str| chrono::NaiveDate::parse_from_str(d, "%Y-%m-%d").ok();
+        if let (Some(idea_date), Some(date)) = (parse(&link.idea_date), parse(date)) {
+            value["days_before"] = json!((date - idea_date).num_days());
+        }
+        value
+    }
+
+    fn day(&mut self, date: &str) -> &mut DayRecords {
+        self.days.entry(date.to_string()).or_insert_with(|| DayRecords {
+            ideas: Vec::new(),
+            tasks: Vec::new(),
+            plans: Vec::new(),
+            carried: Vec::new(),
+            summarized: false,
+        })
+    }
+
+    fn render_plan(&self, plan: &PlannedTask) -> Value {
+        let mut value = json!({
+            "id": plan.id,
+            "content": plan.content,
+            "priority": plan.priority,
+            "status": plan.status,
+        });
+        if let Some(estimate) = plan.estimate_minutes {
+            value["estimate_minutes"] = json!(estimate);
+        }
+        if let Some(task_id) = plan.done_task_id {
+            value["done_task_id"] = json!(task_id);
+            // 完成事项在范围内时附上实际耗时，便于对比预估
+            if let Some(task) = self.days.values().flat_map(|d| d.tasks.iter()).find(|t| t.id == task_id) {
+                value["actual_minutes"] = json!((task.end_time - task.start_time).max(0) / 60);
+            }
+        }
+        if plan.carry_count > 0 {
+            value["carried_from"] = json!(plan.original_date);
+        }
+        if let Some(link) = self.links.iter().find(|l| l.planned_task_id == Some(plan.id)) {
+            value["from_idea"] = self.idea_origin(link, &plan.target_date);
+        }
+        value
+    }
+
+    fn render_day(&self, date: &str, day: &DayRecords) -> Value {
+        if day.summarized {
+            let tracked: i64 = day.tasks.iter().map(|t| (t.end_time - t.start_time).max(0)).sum();
+            let samples: Vec<String> = day
+                .tasks
+                .iter()
+                .take(SUMMARY_SAMPLE_COUNT)
+                .map(|t| truncate_chars(&t.content, SUMMARY_TITLE_CHARS).0)
+                .collect();
+            return json!({
+                "date": date,
+                "summarized": true,
+                "idea_count": day.ideas.len(),
+                "task_count": day.tasks.len(),
+                "plan_count": day.plans.len() + day.carried.len(),
+                "plan_done_count": day.plans.iter().filter(|p| p.status == PlanStatus::Done).count(),
+                "tracked_minutes": tracked / 60,
+                "sample_tasks": samples,
+            });
+        }
+
+        json!({
+            "date": date,
+            "ideas": day.ideas.iter().map(|idea| {
+                let mut value = json!({
+                    "id": idea.id,
+                    "content": idea.content,
+                    "created_at": idea.created_at,
+                });
+                if self.include_attachments && !idea.attachments.is_empty() {
+                    value["attachments"] = json!(idea.attachments);
+                }
+                let promoted: Vec<Value> = self.links.iter().filter(|l| l.idea_id == idea.id).map(|l| json!({
+                    "planned_task_id": l.planned_task_id,
+                    "done_task_id": l.done_task_id,
+                })).collect();
+                if !promoted.is_empty() {
+                    value["promoted_to"] = json!(promoted);
+                }
+                value
+            }).collect::<Vec<_>>(),
+            "tasks": day.tasks.iter().map(|task| {
+                let mut value = json!({
+                    "id": task.id,
+                    "content": task.content,
+                    "start_time": task.start_time,
+                    "end_time": task.end_time,
+                    "duration": task.end_time - task.start_time,
+                });
+                if !task.tags.is_empty() {
+                    value["tags"] = json!(task.tags);
+                }
+                if let Some(link) = self.links.iter().find(|l| l.done_task_id == Some(task.id)) {
+                    value["from_idea"] = self.idea_origin(link, &task.date);
+                }
+                if self.include_attachments && !task.attachments.is_empty() {
+                    value["attachments"] = json!(task.attachments);
+                }
+                value
+            }).collect::<Vec<_>>(),
+            "plans": day.plans.iter().map(|plan| self.render_plan(plan)).collect::<Vec<_>>(),
+            "carried_over": day.carried.iter().map(|carry| json!({
+                "id": carry.planned_task_id,
+                "content": carry.content,
+                "carried_to": carry.to_date,
+            })).collect::<Vec<_>>(),
+        })
+    }
+
+    fn render(&self, report: &ContextReport) -> Value {
+        let total_ideas: usize = self.days.values().map(|d| d.ideas.len()).sum();
+        let total_tasks: usize = self.days.values().map(|d| d.tasks.len()).sum();
+        let plans = || self.days.values().flat_map(|d| d.plans.iter());
+        let count_status = |status: PlanStatus| plans().filter(|p| p.status == status).count();
+        let carried: usize = self.days.values().map(|d| d.carried.len()).sum();
+        json!({
+            "days": self.days.iter().map(|(date, day)| self.render_day(date, day)).collect::<Vec<_>>(),
+            "summary": {
+                "total_ideas": total_ideas,
+                "total_tasks": total_tasks,
+                "date_range": format!("{} 至 {}", self.start_date, self.end_date),
+                "plans": {
+                    "planned": plans().count() + carried,
+                    "completed": count_status(PlanStatus::Done),
+                    "pending": count_status(PlanStatus::Pending),
+                    "cancelled": count_status(PlanStatus::Cancelled),
+                    "carried_over": carried,
+                    "estimate_minutes": plans().filter_map(|p| p.estimate_minutes).sum::<i64>(),
+                },
+            },
+            "next_plans": self.next_plans.iter().map(|plan| {
+                let mut value = self.render_plan(plan);
+                value["date"] = json!(plan.target_date);
+                value
+            }).collect::<Vec<_>>(),
+            "context_report": report,
+        })
+    }
+
+    fn measure(&self, report: &ContextReport) -> usize {
+        self.estimator.count(&self.render(report).to_string())
+    }
+
+    fn measure_day(&self, date: &str) -> usize {
+        self.days
+            .get(date)
+            .map(|day| self.estimator.count(&self.render_day(date, day).to_string()))
+            .unwrap_or(0)
+    }
+
+    /// 日期加入报告中摘要化或省略列表后增加的 token 数
+    fn measure_listed(&self, date: &str) -> usize {
+        self.estimator.count(&format!("\"{}\",", date))
+    }
+
+    /// 在预算内构建上下文
+    pub fn build(mut self) -> BuiltContext {
+        let mut report = ContextReport {
+            budget_tokens: self.budget,
+            exact_tokenizer: self.estimator.is_exact(),
+            ..Default::default()
+        };
+        report.original_tokens = self.measure(&report);
+        let mut tokens = report.original_tokens;
+
+        // 第一步：去掉附件
+        if tokens > self.budget {
+            self.include_attachments = false;
+            report.attachments_dropped = self
+                .days
+                .values()
+                .map(|d| {
+                    d.ideas.iter().map(|i| i.attachments.len()).sum::<usize>()
+                        + d.tasks.iter().map(|t| t.attachments.len()).sum::<usize>()
+                })
+                .sum();
+            tokens = self.measure(&report);
+        }
+
+        // 第二步：截断长内容
+        if tokens > self.budget {
+            let mut truncated_items = 0;
+            for day in self.days.values_mut() {
+                for idea in day.ideas.iter_mut() {
+                    let (content, truncated) = truncate_chars(&idea.content, TRUNCATE_CHARS);
+                    if truncated {
+                        idea.content = content;
+                        truncated_items += 1;
+                    }
+                }
+                for task in day.tasks.iter_mut() {
+                    let (content, truncated) = truncate_chars(&task.content, TRUNCATE_CHARS);
+                    if truncated {
+                        task.content = content;
+                        truncated_items += 1;
+                    }
+                }
+                for plan in day.plans.iter_mut() {
+                    let (content, truncated) = truncate_chars(&plan.content, TRUNCATE_CHARS);
+                    if truncated {
+                        plan.content = content;
+                        truncated_items += 1;
+                    }
+                }
+            }
+            report.truncated_items = truncated_items;
+            tokens = self.measure(&report);
+        }
+
+        // 第三步和最后一步逐天降级，只重新计算被降级那一天的 token 数；
+        // 按天累加与整体测量略有出入，每轮结束后整体测量一次校正
+        let dates: Vec<String> = self.days.keys().cloned().collect();
+        let mut day_tokens: BTreeMap<String, usize> =
+            dates.iter().map(|date| (date.clone(), self.measure_day(date))).collect();
+        let mut next_summarized = 0;
+        let mut next_omitted = 0;
+        while tokens > self.budget {
+            let progress = (next_summarized, next_omitted);
+
+            // 第三步：从最早的一天开始摘要化，最近一天始终保留明细
+            while tokens > self.budget && next_summarized + 1 < dates.len() {
+                let date = &dates[next_summarized];
+                next_summarized += 1;
+                let Some(day) = self.days.get_mut(date) else {
+                    continue;
+                };
+                day.summarized = true;
+                let summarized = self.measure_day(date);
+                let detailed = day_tokens.insert(date.clone(), summarized).unwrap_or(0);
+                report.summarized_days.push(date.clone());
+                tokens = (tokens + summarized + self.measure_listed(date)).saturating_sub(detailed);
+            }
+
+            // 最后：从最早的一天开始省略
+            while tokens > self.budget && self.days.len() > 1 && next_omitted < dates.len() {
+                let date = &dates[next_omitted];
+                next_omitted += 1;
+                if self.days.remove(date).is_none() {
+                    continue;
+                }
+                if report.summarized_days.contains(date) {
+                    // 从摘要化列表移到省略列表，报告长度不变
+                    report.summarized_days.retain(|d| d != date);
+                } else {
+                    tokens += self.measure_listed(date);
+                }
+                report.omitted_days.push(date.clone());
+                tokens = tokens.saturating_sub(day_tokens.remove(date).unwrap_or(0));
+            }
+
+            tokens = self.measure(&report);
+            if (next_summarized, next_omitted) == progress {
+                break;
+            }
+        }
+
+        report.over_budget = tokens > self.budget;
+        report.final_tokens = tokens;
+        let data = self.render(&report);
+        BuiltContext { data, report }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 30 天、每天 6 条事项
+    fn month_of_tasks() -> Vec<DoneTask> {
+        (1..=30)
+            .flat_map(|day| {
+                (0..6).map(move |i| DoneTask {
+                    id: day * 10 + i,
+                    content: format!("第 {} 天的第 {} 项工作：整理接口文档并和前端对齐字段命名", day, i),
+                    start_time: 1_714_500_000 + day * 86_400 + i * 3_600,
+                    end_time: 1_714_500_000 + day * 86_400 + i * 3_600 + 1_800,
+                    attachments: vec![format!("/tmp/attachments/{}-{}.png", day, i)],
+                    created_at: 1_714_500_000 + day * 86_400,
+                    date: format!("2024-05-{:02}", day),
+                    tags: Vec::new(),
+                })
+            })
+            .collect()
+    }
+
+    fn build(budget: usize) -> BuiltContext {
+        let estimator = TokenEstimator::for_model("gpt-4o");
+        ContextBuilder::new(&estimator, budget, "2024-05-01", "2024-05-30")
+            .records(Vec::new(), month_of_tasks())
+            .build()
+    }
+
+    #[test]
+    fn fits_without_downgrading_when_budget_allows() {
+        let built = build(1_000_000);
+        assert_eq!(built.report.original_tokens, built.report.final_tokens);
+        assert!(built.report.summarized_days.is_empty());
+        assert_eq!(built.report.attachments_dropped, 0);
+    }
+
+    #[test]
+    fn summarizes_then_omits_oldest_days_within_budget() {
+        let estimator = TokenEstimator::for_model("gpt-4o");
+        let full = build(1_000_000).report.original_tokens;
+
+        let summarized = build(full / 2);
+        assert_eq!(summarized.report.attachments_dropped, 180);
+        assert!(!summarized.report.summarized_days.is_empty());
+        assert_eq!(summarized.report.summarized_days[0], "2024-05-01");
+        assert!(summarized.report.omitted_days.is_empty());
+        assert!(!summarized.report.over_budget);
+        // 按天增量估算后，最终数值与整体测量一致
+        let data = summarized.data.to_string();
+        let measured = estimator.count(&data);
+        assert!(summarized.report.final_tokens <= full / 2);
+        assert!(measured.abs_diff(summarized.report.final_tokens) <= 8, "{} vs {}", measured, summarized.report.final_tokens);
+
+        let omitted = build(600);
+        assert!(!omitted.report.omitted_days.is_empty());
+        assert_eq!(omitted.report.omitted_days[0], "2024-05-01");
+        let days = omitted.data["days"].as_array().unwrap();
+        assert_eq!(days.last().unwrap()["date"], "2024-05-30");
+        assert!(days.last().unwrap().get("summarized").is_none());
+        for date in &omitted.report.omitted_days {
+            assert!(!omitted.report.summarized_days.contains(date));
+        }
+    }
+}
@@ -0,0 +1,133 @@
+use serde::{Deserialize, Serialize};
+
+/// AI 请求错误类型，前端据此给出对应的提示
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AiErrorKind {
+    Auth, // API Key 无效或无权限
+    Quota, // 额度不足或持续限流
+    InvalidModel, // 模型不存在或不可用
+    Network, // 连接失败、连接重置或超时
+    Server, // 服务端 5xx
+    BadRequest, // 其他 4xx
+    InvalidResponse, // 响应无法解析
+    NotConfigured, // 未配置 AI 服务
+    Other,
+}
+
+impl AiErrorKind {
+    fn label(&self) -> &'static str {
+        match self {
+            AiErrorKind::Auth => "认证失败",
+            AiErrorKind::Quota => "额度不足或请求过于频繁",
+            AiErrorKind::InvalidModel => "模型无效",
+            AiErrorKind::Network => "网络错误",
+            AiErrorKind::Server => "AI 服务异常",
+            AiErrorKind::BadRequest => "请求无效",
+            AiErrorKind::InvalidResponse => "响应无法解析",
+            AiErrorKind::NotConfigured => "AI 服务未配置",
+            AiErrorKind::Other => "AI 请求失败",
+        }
+    }
+}
+
+/// AI 请求错误（序列化后返回给前端）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AiError {
+    pub kind: AiErrorKind,
+    pub message: String,
+    pub status: Option<u16>, // HTTP 状态码
+    pub attempts: u32, // 已尝试的次数
+}
+
+impl AiError {
+    pub fn new(kind: AiErrorKind, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            message: message.into(),
+            status: None,
+            attempts: 0,
+        }
+    }
+
+    /// 根据 HTTP 状态码和响应体判断错误类型
+    pub fn from_status(status: u16, body: &str) -> Self {
+        let lower = body.to_lowercase();
+        let mentions_model = lower.contains("model_not_found")
+            || lower.contains("model not found")
+            || lower.contains("does not exist")
+            || lower.contains("invalid model")
+            || lower.contains("unknown model")
+            || (lower.contains("model") && (lower.contains("not found") || lower.contains("not_found")));
+        // Gemini 的 API Key 无效时返回 400
+        let invalid_key = lower.contains("api_key_invalid") || lower.contains("api key not valid");
+
+        let kind = match status {
+            401 | 403 => AiErrorKind::Auth,
+            400 if invalid_key => AiErrorKind::Auth,
+            402 => AiErrorKind::Quota,
+            429 => AiErrorKind::Quota,
+            404 if mentions_model => AiErrorKind::InvalidModel,
+            400 if mentions_model => AiErrorKind::InvalidModel,
+            500..=599 => AiErrorKind::Server,
+            _ if lower.contains("insufficient_quota") => AiErrorKind::Quota,
+            _ => AiErrorKind::BadRequest,
+        };
+
+        Self {
+            kind,
+            message: format!("HTTP {}: {}", status, body),
+            status: Some(status),
+            attempts: 0,
+        }
+    }
+
+    pub fn network(message: impl Into<String>) -> Self {
+        Self::new(AiErrorKind::Network, message)
+    }
+}
+
+impl std::fmt::Display for AiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.kind.label(), self.message)
+    }
+}
+
+impl std::error::Error for AiError {}
+
+impl From<String> for AiError {
+    fn from(message: String) -> Self {
+        Self::new(AiErrorKind::Other, message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn status_codes_are_classified() {
+        let cases = [
+            (401, r#"{"error":{"message":"Incorrect API key provided"}}"#, AiErrorKind::Auth),
+            (403, "forbidden", AiErrorKind::Auth),
+            (400, r#"{"error":{"status":"INVALID_ARGUMENT","message":"API key not valid"}}"#, AiErrorKind::Auth),
+            (402, "payment required", AiErrorKind::Quota),
+            (429, "rate limit exceeded", AiErrorKind::Quota),
+            (404, r#"{"error":{"code":"model_not_found"}}"#, AiErrorKind::InvalidModel),
+            (404, "The model `gpt-9` does not exist", AiErrorKind::InvalidModel),
+            (400, "invalid model: foo", AiErrorKind::InvalidModel),
+            (404, "page not found", AiErrorKind::BadRequest),
+            (500, "internal error", AiErrorKind::Server),
+            (502, "bad gateway", AiErrorKind::Server),
+            (503, "model is loading", AiErrorKind::Server),
+            (400, r#"{"error":{"code":"insufficient_quota"}}"#, AiErrorKind::Quota),
+            (422, "unprocessable", AiErrorKind::BadRequest),
+        ];
+        for (status, body, kind) in cases {
+            let error = AiError::from_status(status, body);
+            assert_eq!(error.kind, kind, "HTTP {}: {}", status, body);
+            assert_eq!(error.status, Some(status));
+            assert!(error.message.contains(body));
+        }
+    }
+}
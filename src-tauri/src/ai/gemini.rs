@@ -0,0 +1,458 @@
+use async_trait::async_trait;
+use serde_json::{json, Map, Value};
+
+use crate::ai::client::{self, AiClient, AttemptFailure, ChatRequest, DeltaFn, FunctionCall, Message, ToolCall};
+use crate::ai::error::{AiError, AiErrorKind};
+use crate::ai::provider::{self, Provider};
+
+/// Gemini 不支持的 JSON Schema 字段，发送前从工具参数中去掉
+const UNSUPPORTED_SCHEMA_KEYS: &[&str] = &["$schema", "additionalProperties", "default", "examples"];
+
+/// Gemini generateContent API
+pub(crate) struct GeminiProvider;
+
+#[async_trait]
+impl Provider for GeminiProvider {
+    async fn send(
+        &self,
+        client: &AiClient,
+        request: &ChatRequest,
+        on_delta: Option<DeltaFn<'_>>,
+    ) -> Result<String, AttemptFailure> {
+        let config = client.config();
+        let base = config.api_url.trim_end_matches('/');
+        let root = if base.ends_with("/v1beta") || base.ends_with("/v1") {
+            base.to_string()
+        } else {
+            format!("{}/v1beta", base)
+        };
+        let model = request.model.trim_start_matches("models/");
+        let url = match on_delta {
+            Some(_) => format!("{}/models/{}:streamGenerateContent?alt=sse", root, model),
+            None => format!("{}/models/{}:generateContent", root, model),
+        };
+
+        let builder = client
+            .http()
+            .post(&url)
+            .header("x-goog-api-key", &config.api_key)
+            .json(&request_body(request));
+        let mut response = client.execute(builder).await?;
+
+        let mut reply = GeminiReply::new(provider::tool_call_count(&request.messages));
+        match on_delta {
+            Some(on_delta) => {
+                // 每个 SSE 数据块都是一个完整的 GenerateContentResponse，最后一块带有 finishReason
+                client::read_sse_data(&mut response, client.read_timeout(), |data| {
+                    let Ok(chunk) = serde_json::from_str::<Value>(data) else {
+                        return (false, false);
+                    };
+                    let text = reply.apply(&chunk);
+                    if let Some(text) = &text {
+                        on_delta(text);
+                    }
+                    (reply.error.is_some(), text.is_some())
+                })
+                .await?;
+            }
+            None => {
+                let text = client::read_body(&mut response, client.read_timeout()).await?;
+                let chunk = serde_json::from_str::<Value>(&text)
+                    .map_err(|e| AttemptFailure::invalid_response(format!("解析 Gemini 响应失败: {}", e)))?;
+                reply.apply(&chunk);
+            }
+        }
+
+        if let Some((code, message)) = reply.error.take() {
+            // 带状态码的错误按 HTTP 状态分类（如 429 限流可以重试），已经输出过内容就不再重试
+            let mut failure = match code {
+                Some(code) => AttemptFailure::from_status(code, &message, None),
+                None => AttemptFailure {
+                    error: AiError::new(AiErrorKind::BadRequest, message),
+                    retryable: false,
+                    retry_after: None,
+                },
+            };
+            failure.retryable &= !reply.emitted;
+            return Err(failure);
+        }
+        // 流式输出在 finishReason 之前断开时回复不完整
+        if on_delta.is_some() && !reply.finished {
+            let mut failure = AttemptFailure::network("Gemini 流式响应提前结束".to_string());
+            failure.retryable = !reply.emitted;
+            return Err(failure);
+        }
+
+        Ok(reply.into_body(&request.model))
+    }
+}
+
+/// 将 OpenAI 格式的请求转换为 generateContent 格式
+///
+/// 系统消息放在 `systemInstruction`；assistant 对应 model 角色；
+/// 工具结果作为 user 消息中的 `functionResponse`，按函数名关联。
+fn request_body(request: &ChatRequest) -> Value {
+    let names = provider::tool_names(&request.messages);
+    let mut contents: Vec<Value> = Vec::new();
+
+    for message in &request.messages {
+        let text = message.content.clone().unwrap_or_default();
+        match message.role.as_str() {
+            "system" => {}
+            "assistant" => {
+                let mut parts = Vec::new();
+                if !text.is_empty() {
+                    parts.push(json!({ "text": text }));
+                }
+                for call in message.tool_calls.iter().flatten() {
+                    parts.push(json!({
+                        "functionCall": {
+                            "name": call.function.name,
+                            "args": provider::arguments_object(&call.function.arguments),
+                        }
+                    }));
+                }
+                provider::push_turn(&mut contents, "model", "parts", parts);
+            }
+            "tool" => {
+                let name = message
+                    .tool_call_id
+                    .as_deref()
+                    .and_then(|id| names.get(id))
+                    .copied()
+                    .unwrap_or_default();
+                // response 必须是 JSON 对象
+                let response = match serde_json::from_str::<Value>(&text) {
+                    Ok(value) if value.is_object() => value,
+                    Ok(value) => json!({ "result": value }),
+                    Err(_) => json!({ "result": text }),
+                };
+                let part = json!({ "functionResponse": { "name": name, "response": response } });
+                provider::push_turn(&mut contents, "user", "parts", vec![part]);
+            }
+            _ if !text.is_empty() => {
+                provider::push_turn(&mut contents, "user", "parts", vec![json!({ "text": text })]);
+            }
+            _ => {}
+        }
+    }
+
+    let mut generation = Map::new();
+    if let Some(temperature) = request.temperature {
+        generation.insert("temperature".to_string(), json!(temperature));
+    }
+    if let Some(max_tokens) = request.max_tokens {
+        generation.insert("maxOutputTokens".to_string(), json!(max_tokens));
+    }
+
+    let mut body = json!({
+        "contents": contents,
+        "generationConfig": generation,
+    });
+    if let Some(system) = provider::system_prompt(&request.messages) {
+        body["systemInstruction"] = json!({ "parts": [{ "text": system }] });
+    }
+
+    let tools = provider::tool_functions(request);
+    if !tools.is_empty() {
+        let declarations: Vec<Value> = tools
+            .into_iter()
+            .map(|(name, description, mut parameters)| {
+                strip_unsupported(&mut parameters);
+                json!({ "name": name, "description": description, "parameters": parameters })
+            })
+            .collect();
+        body["tools"] = json!([{ "functionDeclarations": declarations }]);
+        let mode = match request.tool_choice.as_deref() {
+            Some("none") => "NONE",
+            Some("required") => "ANY",
+            _ => "AUTO",
+        };
+        body["toolConfig"] = json!({ "functionCallingConfig": { "mode": mode } });
+    }
+    body
+}
+
+/// 递归去掉 Gemini 不支持的 schema 字段
+fn strip_unsupported(schema: &mut Value) {
+    match schema {
+        Value::Object(map) => {
+            for key in UNSUPPORTED_SCHEMA_KEYS {
+                map.remove(*key);
+            }
+            for value in map.values_mut() {
+                strip_unsupported(value);
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(strip_unsupported),
+        _ => {}
+    }
+}
+
+/// 逐步拼装 Gemini 的回复（非流式响应视为只有一个数据块）
+#[derive(Default)]
+struct GeminiReply {
+    call_offset: usize, // 对话中已有的工具调用数
+    content: String,
+    tool_calls: Vec<ToolCall>,
+    prompt_tokens: i64,
+    completion_tokens: i64,
+    total_tokens: i64,
+    finished: bool, // 已收到 finishReason
+    emitted: bool,
+    error: Option<(Option<u16>, String)>, // (状态码, 错误信息)
+}
+
+impl GeminiReply {
+    fn new(call_offset: usize) -> Self {
+        Self { call_offset, ..Default::default() }
+    }
+
+    /// 合并一个数据块，返回其中新增的文本
+    fn apply(&mut self, chunk: &Value) -> Option<String> {
+        if let Some(error) = chunk.get("error") {
+            let code = error["code"].as_u64().and_then(|code| u16::try_from(code).ok());
+            self.error = Some((
+                code,
+                format!("Gemini 返回错误: {}", error["message"].as_str().unwrap_or_default()),
+            ));
+            return None;
+        }
+
+        let usage = &chunk["usageMetadata"];
+        if usage.is_object() {
+            self.prompt_tokens = usage["promptTokenCount"].as_i64().unwrap_or(0);
+            self.completion_tokens = usage["candidatesTokenCount"].as_i64().unwrap_or(0);
+            self.total_tokens = usage["totalTokenCount"]
+                .as_i64()
+                .unwrap_or(self.prompt_tokens + self.completion_tokens);
+        }
+
+        let Some(candidate) = chunk["candidates"].get(0) else {
+            // 没有候选结果通常是提示词被安全策略拦截
+            if let Some(reason) = chunk["promptFeedback"]["blockReason"].as_str() {
+                self.error = Some((None, format!("Gemini 拒绝了请求: {}", reason)));
+            }
+            return None;
+        };
+
+        if candidate["finishReason"].is_string() {
+            self.finished = true;
+        }
+
+        let mut text = String::new();
+        for part in candidate["content"]["parts"].as_array().into_iter().flatten() {
+            if let Some(call) = part.get("functionCall") {
+                // 较新的模型会返回调用 id，没有时接着对话中已有的调用编号生成，保证整个对话内唯一
+                let id = call["id"]
+                    .as_str()
+                    .map(str::to_string)
+                    .unwrap_or_else(|| format!("gemini_call_{}", self.call_offset + self.tool_calls.len() + 1));
+                self.tool_calls.push(ToolCall {
+                    id,
+                    kind: "function".to_string(),
+                    function: FunctionCall {
+                        name: call["name"].as_str().unwrap_or_default().to_string(),
+                        arguments: call.get("args").map(Value::to_string).unwrap_or_else(|| "{}".to_string()),
+                    },
+                });
+            } else if !part["thought"].as_bool().unwrap_or(false) {
+                // 思考过程不计入回复
+                text.push_str(part["text"].as_str().unwrap_or_default());
+            }
+        }
+
+        if text.is_empty() {
+            return None;
+        }
+        self.content.push_str(&text);
+        self.emitted = true;
+        Some(text)
+    }
+
+    fn into_body(self, model: &str) -> String {
+        let message = Message {
+            role: "assistant".to_string(),
+            content: Some(self.content),
+            tool_calls: if self.tool_calls.is_empty() { None } else { Some(self.tool_calls) },
+            tool_call_id: None,
+        };
+        json!({
+            "object": "chat.completion",
+            "model": model,
+            "choices": [{ "index": 0, "message": message }],
+            "usage": {
+                "prompt_tokens": self.prompt_tokens,
+                "completion_tokens": self.completion_tokens,
+                "total_tokens": self.total_tokens,
+            }
+        })
+        .to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::models::ProviderKind;
+    use crate::test_support::{client_for, sse_events, Reply, ScriptedServer};
+
+    const TEXT: &str = include_str!("../../tests/fixtures/gemini/text.sse");
+    const FUNCTION_CALL: &str = include_str!("../../tests/fixtures/gemini/function_call.sse");
+    const ERROR: &str = include_str!("../../tests/fixtures/gemini/error.sse");
+
+    /// 依次合并录制的数据块，返回输出的文本片段
+    fn replay(reply: &mut GeminiReply, fixture: &str) -> Vec<String> {
+        sse_events(fixture).iter().filter_map(|chunk| reply.apply(chunk)).collect()
+    }
+
+    fn body(reply: GeminiReply) -> Value {
+        serde_json::from_str(&reply.into_body("gemini-test")).unwrap()
+    }
+
+    #[test]
+    fn text_stream_is_normalized_without_thoughts() {
+        let mut reply = GeminiReply::new(0);
+        assert_eq!(replay(&mut reply, TEXT), vec!["今天完成了", "三项工作。"]);
+
+        let body = body(reply);
+        assert_eq!(body["choices"][0]["message"]["content"], "今天完成了三项工作。");
+        assert!(body["choices"][0]["message"].get("tool_calls").is_none());
+        assert_eq!(body["usage"], json!({ "prompt_tokens": 31, "completion_tokens": 9, "total_tokens": 44 }));
+    }
+
+    #[test]
+    fn function_calls_get_conversation_unique_ids() {
+        // 对话中已有两次工具调用，新生成的 id 从 3 开始
+        let mut reply = GeminiReply::new(2);
+        replay(&mut reply, FUNCTION_CALL);
+
+        let body = body(reply);
+        assert_eq!(
+            body["choices"][0]["message"]["tool_calls"],
+            json!([
+                {
+                    "id": "gemini_call_3",
+                    "type": "function",
+                    "function": {
+                        "name": "get_history_data",
+                        "arguments": json!({ "start_date": "2024-05-06", "end_date": "2024-05-10" }).to_string()
+                    }
+                },
+                {
+                    "id": "gemini_call_4",
+                    "type": "function",
+                    "function": { "name": "list_tags", "arguments": "{}" }
+                }
+            ])
+        );
+        assert_eq!(body["usage"]["total_tokens"], 234);
+    }
+
+    #[test]
+    fn error_chunk_is_recorded() {
+        let mut reply = GeminiReply::new(0);
+        assert!(replay(&mut reply, ERROR).is_empty());
+        let (code, message) = reply.error.expect("应当记录错误");
+        assert_eq!(code, Some(429));
+        assert!(message.contains("Resource has been exhausted"), "{}", message);
+    }
+
+    #[test]
+    fn tool_results_are_named_after_their_own_call() {
+        let call = |id: &str, name: &str| ToolCall {
+            id: id.to_string(),
+            kind: "function".to_string(),
+            function: FunctionCall { name: name.to_string(), arguments: "{}".to_string() },
+        };
+        let assistant = |calls: Vec<ToolCall>| Message {
+            role: "assistant".to_string(),
+            content: None,
+            tool_calls: Some(calls),
+            tool_call_id: None,
+        };
+        let request = ChatRequest {
+            model: "gemini-test".to_string(),
+            messages: vec![
+                Message::user("总结本周"),
+                assistant(vec![call("gemini_call_1", "get_current_time")]),
+                Message::tool("gemini_call_1", "{\"now\": \"2024-05-10\"}"),
+                assistant(vec![call("gemini_call_2", "list_tags")]),
+                Message::tool("gemini_call_2", "[\"开发\"]"),
+            ],
+            tools: None,
+            tool_choice: None,
+            temperature: None,
+            max_tokens: None,
+            stream: None,
+        };
+
+        let body = request_body(&request);
+        let responses: Vec<&Value> = body["contents"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .flat_map(|content| content["parts"].as_array().unwrap())
+            .filter_map(|part| part.get("functionResponse"))
+            .collect();
+        assert_eq!(responses[0]["name"], "get_current_time");
+        assert_eq!(responses[1]["name"], "list_tags");
+        assert_eq!(responses[1]["response"], json!({ "result": ["开发"] }));
+    }
+
+    #[tokio::test]
+    async fn streams_recorded_fixture_from_server() {
+        let server = ScriptedServer::start(vec![Reply::sse(TEXT), Reply::sse(ERROR)]).await;
+        let client = client_for(ProviderKind::Gemini, &server.url);
+        let request = ChatRequest {
+            model: "models/gemini-test".to_string(),
+            messages: vec![Message::system("你是日报助手"), Message::user("总结本周")],
+            tools: None,
+            tool_choice: None,
+            temperature: None,
+            max_tokens: Some(256),
+            stream: None,
+        };
+        let on_delta = |_: &str| {};
+
+        let response = client.chat_completion_stream(&request, &on_delta).await.expect("请求失败");
+        assert_eq!(response.choices[0].message.content.as_deref(), Some("今天完成了三项工作。"));
+
+        let sent = &server.requests()[0];
+        assert_eq!(sent.path, "/v1beta/models/gemini-test:streamGenerateContent?alt=sse");
+        assert_eq!(sent.headers.get("x-goog-api-key").map(String::as_str), Some("test-key"));
+        let sent = sent.json();
+        assert_eq!(sent["systemInstruction"]["parts"][0]["text"], "你是日报助手");
+        assert_eq!(sent["generationConfig"]["maxOutputTokens"], 256);
+
+        let error = client.chat_completion_stream(&request, &on_delta).await.expect_err("应当返回错误");
+        assert_eq!(error.kind, AiErrorKind::Quota);
+        assert_eq!(error.status, Some(429));
+    }
+
+    #[tokio::test]
+    async fn stream_without_finish_reason_is_an_error() {
+        let truncated: String = TEXT
+            .lines()
+            .filter(|line| !line.contains("finishReason"))
+            .map(|line| format!("{}\n", line))
+            .collect();
+        let server = ScriptedServer::start(vec![Reply::sse(truncated)]).await;
+        let client = client_for(ProviderKind::Gemini, &server.url);
+        let request = ChatRequest {
+            model: "gemini-test".to_string(),
+            messages: vec![Message::user("总结本周")],
+            tools: None,
+            tool_choice: None,
+            temperature: None,
+            max_tokens: None,
+            stream: None,
+        };
+        let on_delta = |_: &str| {};
+
+        let error = client.chat_completion_stream(&request, &on_delta).await.expect_err("应当返回错误");
+        assert_eq!(error.kind, AiErrorKind::Network);
+    }
+}
@@ -0,0 +1,468 @@
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::TimeZone;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::ai::client::{AiClient, AttemptFailure, ChatRequest, DeltaFn, FunctionCall, Message, ToolCall};
+use crate::ai::provider::Provider;
+
+/// 模拟的单步回复（按顺序对应每次模型请求）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MockStep {
+    pub content: Option<String>,
+    pub tool_calls: Vec<MockToolCall>,
+}
+
+/// 模拟的工具调用
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MockToolCall {
+    pub name: String,
+    #[serde(default)]
+    pub arguments: Value,
+}
+
+/// 注入的错误
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MockFailure {
+    #[serde(default)]
+    pub status: u16, // 为 0 时模拟连接重置
+    #[serde(default)]
+    pub body: String,
+    #[serde(default = "default_failure_times")]
+    pub times: u32, // 前几次请求失败
+    #[serde(default)]
+    pub retry_after_secs: Option<u64>,
+}
+
+fn default_failure_times() -> u32 {
+    1
+}
+
+/// 模拟服务的配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MockOptions {
+    pub chunk_chars: usize, // 流式输出时每段的字符数
+    pub chunk_delay_ms: u64, // 流式输出时每段之间的延迟
+    pub script: Vec<MockStep>, // 为空时使用内置流程：先查询记录再生成日报
+    pub fail_with: Option<MockFailure>,
+}
+
+impl Default for MockOptions {
+    fn default() -> Self {
+        Self {
+            chunk_chars: 8,
+            chunk_delay_ms: 30,
+            script: Vec::new(),
+            fail_with: None,
+        }
+    }
+}
+
+/// 内置的离线模拟服务，回复完全由请求内容决定
+pub struct MockProvider {
+    options: MockOptions,
+    failures: AtomicU32,
+}
+
+impl MockProvider {
+    pub fn new(options: MockOptions) -> Self {
+        Self {
+            options,
+            failures: AtomicU32::new(0),
+        }
+    }
+
+    /// 生成一次回复，返回 OpenAI 非流式格式的响应体
+    async fn respond(
+        &self,
+        request: &ChatRequest,
+        on_delta: Option<DeltaFn<'_>>,
+    ) -> Result<String, AttemptFailure> {
+        if let Some(failure) = &self.options.fail_with {
+            if self.failures.fetch_add(1, Ordering::SeqCst) < failure.times {
+                return Err(inject_failure(failure));
+            }
+        }
+
+        let message = self.reply(request);
+
+        if let (Some(on_delta), Some(content)) = (on_delta, message.content.as_deref()) {
+            let chars: Vec<char> = content.chars().collect();
+            for piece in chars.chunks(self.options.chunk_chars.max(1)) {
+                if self.options.chunk_delay_ms > 0 {
+                    tokio::time::sleep(Duration::from_millis(self.options.chunk_delay_ms)).await;
+                }
+                on_delta(&piece.iter().collect::<String>());
+            }
+        }
+
+        let prompt_chars: usize = request
+            .messages
+            .iter()
+            .map(|m| m.content.as_deref().unwrap_or("").chars().count())
+            .sum();
+        let completion_chars = message.content.as_deref().unwrap_or("").chars().count();
+        let prompt_tokens = (prompt_chars / 4) as i64;
+        let completion_tokens = (completion_chars / 4) as i64;
+
+        Ok(json!({
+            "id": format!("mock-{}", assistant_turns(request) + 1),
+            "object": "chat.completion",
+            "model": request.model,
+            "choices": [{ "index": 0, "message": message }],
+            "usage": {
+                "prompt_tokens": prompt_tokens,
+                "completion_tokens": completion_tokens,
+                "total_tokens": prompt_tokens + completion_tokens,
+            }
+        })
+        .to_string())
+    }
+
+    fn reply(&self, request: &ChatRequest) -> Message {
+        let turn = assistant_turns(request);
+
+        if !self.options.script.is_empty() {
+            return match self.options.script.get(turn) {
+                Some(step) => step_message(step, turn),
+                None => assistant_text("（模拟回复）脚本已结束".to_string()),
+            };
+        }
+
+        let last = request.messages.last();
+        if last.map(|m| m.role == "tool").unwrap_or(false) {
+            return assistant_text(render_report(request));
+        }
+
+        let has_history_tool = request
+            .tools
+            .as_ref()
+            .map(|tools| {
+                tools
+                    .iter()
+                    .any(|t| t["function"]["name"].as_str() == Some("get_history_data"))
+            })
+            .unwrap_or(false);
+
+        let user_text = request
+            .messages
+            .iter()
+            .rev()
+            .find(|m| m.role == "user")
+            .and_then(|m| m.content.clone())
+            .unwrap_or_default();
+
+        if has_history_tool {
+            let (start_date, end_date) = date_range_in(&user_text);
+            return step_message(
+                &MockStep {
+                    content: None,
+                    tool_calls: vec![MockToolCall {
+                        name: "get_history_data".to_string(),
+                        arguments: json!({ "start_date": start_date, "end_date": end_date }),
+                    }],
+                },
+                turn,
+            );
+        }
+
+        assistant_text(format!("（模拟回复）收到：{}", user_text))
+    }
+}
+
+#[async_trait]
+impl Provider for MockProvider {
+    async fn send(
+        &self,
+        _client: &AiClient,
+        request: &ChatRequest,
+        on_delta: Option<DeltaFn<'_>>,
+    ) -> Result<String, AttemptFailure> {
+        self.respond(request, on_delta).await
+    }
+}
+
+fn inject_failure(failure: &MockFailure) -> AttemptFailure {
+    if failure.status == 0 {
+        return AttemptFailure::network("模拟连接重置".to_string());
+    }
+    let body = if failure.body.is_empty() {
+        json!({ "error": { "message": "模拟错误" } }).to_string()
+    } else {
+        failure.body.clone()
+    };
+    AttemptFailure::from_status(
+        failure.status,
+        &body,
+        failure.retry_after_secs.map(Duration::from_secs),
+    )
+}
+
+fn assistant_turns(request: &ChatRequest) -> usize {
+    request.messages.iter().filter(|m| m.role == "assistant").count()
+}
+
+fn assistant_text(content: String) -> Message {
+    Message {
+        role: "assistant".to_string(),
+        content: Some(content),
+        tool_calls: None,
+        tool_call_id: None,
+    }
+}
+
+fn step_message(step: &MockStep, turn: usize) -> Message {
+    let tool_calls: Vec<ToolCall> = step
+        .tool_calls
+        .iter()
+        .enumerate()
+        .map(|(i, call)| ToolCall {
+            id: format!("mock_call_{}_{}", turn + 1, i + 1),
+            kind: "function".to_string(),
+            function: FunctionCall {
+                name: call.name.clone(),
+                arguments: if call.arguments.is_null() {
+                    "{}".to_string()
+                } else {
+                    call.arguments.to_string()
+                },
+            },
+        })
+        .collect();
+
+    Message {
+        role: "assistant".to_string(),
+        content: step.content.clone(),
+        tool_calls: if tool_calls.is_empty() { None } else { Some(tool_calls) },
+        tool_call_id: None,
+    }
+}
+
+/// 从文本中找出 YYYY-MM-DD 格式的日期，没有时使用今天
+fn date_range_in(text: &str) -> (String, String) {
+    let chars: Vec<char> = text.chars().collect();
+    let mut dates = Vec::new();
+    for start in 0..chars.len() {
+        if start + 10 > chars.len() {
+            break;
+        }
+        let candidate: String = chars[start..start + 10].iter().collect();
+        if chrono::NaiveDate::parse_from_str(&candidate, "%Y-%m-%d").is_ok() {
+            dates.push(candidate);
+        }
+    }
+
+    let today = chrono::Local::now().format("%Y-%m-%d").to_string();
+    match dates.as_slice() {
+        [] => (today.clone(), today),
+        [only] => (only.clone(), only.clone()),
+        [first, .., last] => (first.clone(), last.clone()),
+    }
+}
+
+fn format_clock(timestamp: i64) -> String {
+    chrono::Local
+        .timestamp_opt(timestamp, 0)
+        .single()
+        .map(|dt| dt.format("%H:%M").to_string())
+        .unwrap_or_else(|| "--:--".to_string())
+}
+
+/// 根据工具返回的记录数据生成模板化的日报
+fn render_report(request: &ChatRequest) -> String {
+    let mut tasks: Vec<Value> = Vec::new();
+    let mut ideas: Vec<Value> = Vec::new();
+    let mut date_range = String::new();
+
+    for message in request.messages.iter().filter(|m| m.role == "tool") {
+        let Some(data) = message
+            .content
+            .as_deref()
+            .and_then(|c| serde_json::from_str::<Value>(c).ok())
+        else {
+            continue;
+        };
+        if let Some(range) = data["summary"]["date_range"].as_str() {
+            date_range = range.to_string();
+        }
+        // 兼容按天组织和平铺两种格式
+        let days = data["days"].as_array().cloned().unwrap_or_else(|| vec![data.clone()]);
+        for day in days {
+            tasks.extend(day["tasks"].as_array().cloned().unwrap_or_default());
+            ideas.extend(day["ideas"].as_array().cloned().unwrap_or_default());
+        }
+    }
+
+    let total_seconds: i64 = tasks
+        .iter()
+        .map(|t| t["duration"].as_i64().unwrap_or(0).max(0))
+        .sum();
+
+    let mut report = String::new();
+    if date_range.is_empty() {
+        report.push_str("# 日报（模拟）\n\n");
+    } else {
+        report.push_str(&format!("# 日报（模拟）{}\n\n", date_range));
+    }
+
+    report.push_str("## 工作成果和进展\n\n");
+    if tasks.is_empty() {
+        report.push_str("- 暂无已完成事项\n");
+    }
+    for task in &tasks {
+        report.push_str(&format!(
+            "- [{}-{}] {}（{} 分钟）\n",
+            format_clock(task["start_time"].as_i64().unwrap_or(0)),
+            format_clock(task["end_time"].as_i64().unwrap_or(0)),
+            task["content"].as_str().unwrap_or(""),
+            task["duration"].as_i64().unwrap_or(0) / 60
+        ));
+    }
+
+    report.push_str("\n## 重要想法和思考\n\n");
+    if ideas.is_empty() {
+        report.push_str("- 暂无记录的想法\n");
+    }
+    for idea in &ideas {
+        report.push_str(&format!("- {}\n", idea["content"].as_str().unwrap_or("")));
+    }
+
+    report.push_str("\n## 时间管理和效率分析\n\n");
+    report.push_str(&format!(
+        "共完成 {} 项事项，记录 {} 条想法，累计 {:.1} 小时。\n",
+        tasks.len(),
+        ideas.len(),
+        total_seconds as f64 / 3600.0
+    ));
+
+    report.push_str("\n## 后续改进建议\n\n- 继续保持及时记录的习惯。\n");
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::ai::agent::{self, AgentEvent, AgentOptions, ReportRange};
+    use crate::ai::context::TokenEstimator;
+    use crate::ai::tools::{ToolContext, ToolRegistry};
+    use crate::audit::{self, AuditQuery};
+    use crate::models::{ApiConfig, AuditEntity, AuditOrigin, NewDoneTask, ProviderKind};
+    use crate::reports;
+    use crate::repo::{IdeaRepo, TaskRepo};
+    use crate::test_support::{temp_pool, TempDir};
+
+    fn mock_client() -> AiClient {
+        AiClient::new(ApiConfig {
+            api_key: String::new(),
+            api_url: String::new(),
+            model: "mock-model".to_string(),
+            context_budget: None,
+            provider: ProviderKind::Mock,
+            mock: Some(MockOptions { chunk_chars: 8, chunk_delay_ms: 0, ..Default::default() }),
+            local: None,
+        })
+    }
+
+    /// 准备 2024-05-06 的一个事项和一条想法
+    async fn context() -> (ToolContext, TempDir) {
+        let (pool, dir) = temp_pool().await;
+        // 日期列按 UTC 计算，取中午前后的时间，避免东西时区落到前后一天
+        let at = |hour: u32| chrono::Local.with_ymd_and_hms(2024, 5, 6, hour, 0, 0).unwrap().timestamp();
+        TaskRepo::new(&pool)
+            .insert(
+                NewDoneTask {
+                    content: "评审接口设计".to_string(),
+                    start_time: at(12),
+                    end_time: at(14),
+                    attachments: Vec::new(),
+                    created_at: at(14),
+                    tags: vec!["评审".to_string()],
+                },
+                AuditOrigin::Ui,
+            )
+            .await
+            .unwrap();
+        IdeaRepo::new(&pool)
+            .insert("缓存可以按工作区隔离".to_string(), Vec::new(), at(13), AuditOrigin::Ui)
+            .await
+            .unwrap();
+        let ctx = ToolContext { pool, estimator: TokenEstimator::for_model("mock-model"), data_budget: 4000 };
+        (ctx, dir)
+    }
+
+    fn range() -> ReportRange {
+        ReportRange { start_date: Some("2024-05-06".to_string()), end_date: Some("2024-05-06".to_string()) }
+    }
+
+    #[tokio::test]
+    async fn generates_and_saves_a_report_end_to_end() {
+        let client = mock_client();
+        let (ctx, _dir) = context().await;
+        let registry = ToolRegistry::with_builtin_tools();
+
+        let outcome = agent::generate_daily_report(
+            &client,
+            &registry,
+            &ctx,
+            None,
+            range(),
+            &AgentOptions::default(),
+            |_| {},
+        )
+        .await
+        .expect("生成日报失败");
+
+        assert_eq!(outcome.iterations, 2);
+        assert_eq!(outcome.tool_calls.len(), 1);
+        assert_eq!(outcome.tool_calls[0].name, "get_history_data");
+        assert!(outcome.content.contains("评审接口设计（120 分钟）"), "{}", outcome.content);
+        assert!(outcome.content.contains("缓存可以按工作区隔离"), "{}", outcome.content);
+
+        let report = reports::get_report(&ctx.pool, outcome.report_id.expect("没有保存报告")).await.unwrap();
+        assert_eq!(report.content, outcome.content);
+        assert_eq!(report.title, "日报 2024-05-06");
+        assert_eq!((report.start_date.as_str(), report.end_date.as_str()), ("2024-05-06", "2024-05-06"));
+        assert_eq!(report.model.as_deref(), Some("mock-model"));
+
+        let audit = audit::list_entries(
+            &ctx.pool,
+            &AuditQuery { entity: Some(AuditEntity::Report), ..Default::default() },
+        )
+        .await
+        .unwrap();
+        assert_eq!(audit.len(), 1);
+        assert_eq!(audit[0].entity_id, report.id);
+    }
+
+    #[tokio::test]
+    async fn streams_the_final_answer_in_chunks() {
+        let client = mock_client();
+        let (ctx, _dir) = context().await;
+        let registry = ToolRegistry::with_builtin_tools();
+        let options = AgentOptions { stream: true, ..Default::default() };
+
+        let deltas = Mutex::new(Vec::new());
+        let outcome = agent::generate_daily_report(&client, &registry, &ctx, None, range(), &options, |event| {
+            if let AgentEvent::Delta { iteration, text } = event {
+                deltas.lock().unwrap().push((iteration, text.to_string()));
+            }
+        })
+        .await
+        .expect("生成日报失败");
+
+        let deltas = deltas.into_inner().unwrap();
+        assert!(deltas.len() > 1);
+        // 第一轮只有工具调用，文本都来自第二轮
+        assert!(deltas.iter().all(|(iteration, _)| *iteration == 2));
+        assert!(deltas.iter().all(|(_, text)| text.chars().count() <= 8));
+        let streamed: String = deltas.into_iter().map(|(_, text)| text).collect();
+        assert_eq!(streamed, outcome.content);
+    }
+}
@@ -0,0 +1,15 @@
+//! 后端 AI 能力：统一的 AI 客户端与各服务商适配器（OpenAI 兼容、Anthropic、Gemini、本地 Ollama / llama.cpp）、工具注册表与工具调用循环
+
+pub mod agent;
+pub mod anthropic;
+pub mod cache;
+pub mod client;
+pub mod context;
+pub mod error;
+pub mod gemini;
+pub mod mock;
+pub mod ollama;
+pub mod openai;
+pub mod provider;
+pub mod retry;
+pub mod tools;
@@ -0,0 +1,525 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde_json::{json, Map, Value};
+
+use crate::ai::client::{self, AiClient, AttemptFailure, ChatRequest, DeltaFn, FunctionCall, Message, ToolCall};
+use crate::ai::error::{AiError, AiErrorKind};
+use crate::ai::provider::{self, Provider};
+use crate::models::{LocalModel, ProviderKind};
+
+/// 本地 Ollama 的 /api/chat 接口
+pub(crate) struct OllamaProvider;
+
+#[async_trait]
+impl Provider for OllamaProvider {
+    async fn send(
+        &self,
+        client: &AiClient,
+        request: &ChatRequest,
+        on_delta: Option<DeltaFn<'_>>,
+    ) -> Result<String, AttemptFailure> {
+        send(client, request, on_delta).await
+    }
+}
+
+async fn send(
+    client: &AiClient,
+    request: &ChatRequest,
+    on_delta: Option<DeltaFn<'_>>,
+) -> Result<String, AttemptFailure> {
+    let config = client.config();
+    let url = format!("{}/api/chat", config.api_url.trim_end_matches('/'));
+    let body = chat_body(client, request, on_delta.is_some());
+
+    let mut builder = client.http().post(&url).json(&body);
+    if !config.api_key.is_empty() {
+        builder = builder.bearer_auth(&config.api_key);
+    }
+    let mut response = client.execute(builder).await?;
+
+    let mut reply = OllamaReply::new(provider::tool_call_count(&request.messages));
+    match on_delta {
+        Some(on_delta) => {
+            // 流式输出为 NDJSON：每行一个 JSON 对象，最后一行 done 为 true
+            client::read_lines(&mut response, client.read_timeout(), |line| {
+                let Ok(chunk) = serde_json::from_str::<Value>(line) else {
+                    return (false, false);
+                };
+                let text = reply.apply(&chunk);
+                if let Some(text) = &text {
+                    on_delta(text);
+                }
+                (reply.done || reply.error.is_some(), text.is_some())
+            })
+            .await?;
+        }
+        None => {
+            let text = client::read_body(&mut response, client.read_timeout()).await?;
+            let chunk = serde_json::from_str::<Value>(&text)
+                .map_err(|e| AttemptFailure::invalid_response(format!("解析 Ollama 响应失败: {}", e)))?;
+            reply.apply(&chunk);
+        }
+    }
+
+    if let Some(error) = reply.error.take() {
+        // Ollama 的错误只有文字说明：模型不存在重试也没有用，其余按服务端错误处理
+        let invalid_model = AiError::from_status(404, &error).kind == AiErrorKind::InvalidModel;
+        let kind = if invalid_model { AiErrorKind::InvalidModel } else { AiErrorKind::Server };
+        return Err(AttemptFailure {
+            error: AiError::new(kind, format!("Ollama 返回错误: {}", error)),
+            retryable: !invalid_model && !reply.emitted,
+            retry_after: None,
+        });
+    }
+    // 流式输出在 done 之前断开时回复不完整
+    if on_delta.is_some() && !reply.done {
+        let mut failure = AttemptFailure::network("Ollama 流式响应提前结束".to_string());
+        failure.retryable = !reply.emitted;
+        return Err(failure);
+    }
+
+    Ok(reply.into_body(&request.model))
+}
+
+/// 将 OpenAI 格式的请求转换为 Ollama 格式
+fn chat_body(client: &AiClient, request: &ChatRequest, stream: bool) -> Value {
+    let local = client.config().local.clone().unwrap_or_default();
+
+    let mut options = Map::new();
+    if let Some(temperature) = request.temperature {
+        options.insert("temperature".to_string(), json!(temperature));
+    }
+    if let Some(max_tokens) = request.max_tokens {
+        options.insert("num_predict".to_string(), json!(max_tokens));
+    }
+    if let Some(num_ctx) = local.num_ctx {
+        options.insert("num_ctx".to_string(), json!(num_ctx));
+    }
+
+    let mut body = json!({
+        "model": request.model,
+        "messages": convert_messages(&request.messages),
+        "stream": stream,
+        "options": options,
+    });
+    if let Some(tools) = &request.tools {
+        body["tools"] = json!(tools);
+    }
+    if let Some(keep_alive) = local.keep_alive.filter(|k| !k.trim().is_empty()) {
+        // 纯数字按秒处理，其余（如 "5m"）原样传给 Ollama
+        body["keep_alive"] = match keep_alive.trim().parse::<i64>() {
+            Ok(secs) => json!(secs),
+            Err(_) => json!(keep_alive.trim()),
+        };
+    }
+    body
+}
+
+/// 转换消息：工具调用参数为 JSON 对象，工具结果按函数名关联
+fn convert_messages(messages: &[Message]) -> Vec<Value> {
+    let names = provider::tool_names(messages);
+
+    messages
+        .iter()
+        .map(|message| {
+            let mut value = json!({
+                "role": message.role,
+                "content": message.content.clone().unwrap_or_default(),
+            });
+
+            if let Some(calls) = &message.tool_calls {
+                value["tool_calls"] = calls
+                    .iter()
+                    .map(|call| {
+                        let arguments = provider::arguments_object(&call.function.arguments);
+                        json!({ "function": { "name": call.function.name, "arguments": arguments } })
+                    })
+                    .collect();
+            }
+
+            if let Some(name) = message
+                .tool_call_id
+                .as_deref()
+                .and_then(|id| names.get(id))
+            {
+                value["tool_name"] = json!(name);
+            }
+            value
+        })
+        .collect()
+}
+
+/// 逐步拼装 Ollama 的回复（非流式响应视为只有一行）
+#[derive(Default)]
+struct OllamaReply {
+    call_offset: usize, // 对话中已有的工具调用数
+    content: String,
+    tool_calls: Vec<ToolCall>,
+    prompt_tokens: i64,
+    completion_tokens: i64,
+    done: bool,
+    emitted: bool,
+    error: Option<String>,
+}
+
+impl OllamaReply {
+    fn new(call_offset: usize) -> Self {
+        Self { call_offset, ..Default::default() }
+    }
+
+    /// 合并一行数据，返回其中新增的文本
+    fn apply(&mut self, chunk: &Value) -> Option<String> {
+        if let Some(error) = chunk["error"].as_str() {
+            self.error = Some(error.to_string());
+            return None;
+        }
+
+        let message = &chunk["message"];
+        if let Some(calls) = message["tool_calls"].as_array() {
+            for call in calls {
+                let arguments = match &call["function"]["arguments"] {
+                    Value::Null => "{}".to_string(),
+                    Value::String(text) => text.clone(),
+                    other => other.to_string(),
+                };
+                // Ollama 不返回调用 id，接着对话中已有的调用编号，保证整个对话内唯一
+                self.tool_calls.push(ToolCall {
+                    id: format!("ollama_call_{}", self.call_offset + self.tool_calls.len() + 1),
+                    kind: "function".to_string(),
+                    function: FunctionCall {
+                        name: call["function"]["name"].as_str().unwrap_or_default().to_string(),
+                        arguments,
+                    },
+                });
+            }
+        }
+
+        if chunk["done"].as_bool().unwrap_or(false) {
+            self.done = true;
+            self.prompt_tokens = chunk["prompt_eval_count"].as_i64().unwrap_or(0);
+            self.completion_tokens = chunk["eval_count"].as_i64().unwrap_or(0);
+        }
+
+        match message["content"].as_str() {
+            Some(text) if !text.is_empty() => {
+                self.content.push_str(text);
+                self.emitted = true;
+                Some(text.to_string())
+            }
+            _ => None,
+        }
+    }
+
+    fn into_body(self, model: &str) -> String {
+        let message = Message {
+            role: "assistant".to_string(),
+            content: Some(self.content),
+            tool_calls: if self.tool_calls.is_empty() { None } else { Some(self.tool_calls) },
+            tool_call_id: None,
+        };
+        json!({
+            "object": "chat.completion",
+            "model": model,
+            "choices": [{ "index": 0, "message": message }],
+            "usage": {
+                "prompt_tokens": self.prompt_tokens,
+                "completion_tokens": self.completion_tokens,
+                "total_tokens": self.prompt_tokens + self.completion_tokens,
+            }
+        })
+        .to_string()
+    }
+}
+
+/// 列出本地模型服务中已安装的模型
+///
+/// Ollama 读取 /api/tags，llama.cpp server 和其他 OpenAI 兼容服务读取 /v1/models。
+pub async fn list_models(
+    provider: ProviderKind,
+    api_url: &str,
+    timeout: Duration,
+) -> Result<Vec<LocalModel>, AiError> {
+    let base = api_url.trim_end_matches('/');
+    let url = match provider {
+        ProviderKind::Ollama => format!("{}/api/tags", base),
+        _ if base.ends_with("/v1") => format!("{}/models", base),
+        _ => format!("{}/v1/models", base),
+    };
+
+    let http = reqwest::Client::builder()
+        .timeout(timeout)
+        .build()
+        .map_err(|e| AiError::new(AiErrorKind::Other, format!("创建 HTTP 客户端失败: {}", e)))?;
+    let response = http
+        .get(&url)
+        .send()
+        .await
+        .map_err(|e| AiError::network(format!("连接本地模型服务失败: {}", e)))?;
+
+    let status = response.status().as_u16();
+    let text = response
+        .text()
+        .await
+        .map_err(|e| AiError::network(format!("读取模型列表失败: {}", e)))?;
+    if !(200..300).contains(&status) {
+        return Err(AiError::from_status(status, &text));
+    }
+
+    let data: Value = serde_json::from_str(&text)
+        .map_err(|e| AiError::new(AiErrorKind::InvalidResponse, format!("解析模型列表失败: {}", e)))?;
+
+    let models = match provider {
+        ProviderKind::Ollama => data["models"]
+            .as_array()
+            .cloned()
+            .unwrap_or_default()
+            .iter()
+            .map(|model| LocalModel {
+                name: model["name"].as_str().unwrap_or_default().to_string(),
+                size: model["size"].as_i64(),
+                modified_at: model["modified_at"].as_str().map(str::to_string),
+                family: model["details"]["family"].as_str().map(str::to_string),
+                parameter_size: model["details"]["parameter_size"].as_str().map(str::to_string),
+                quantization_level: model["details"]["quantization_level"].as_str().map(str::to_string),
+            })
+            .collect(),
+        _ => data["data"]
+            .as_array()
+            .cloned()
+            .unwrap_or_default()
+            .iter()
+            .map(|model| LocalModel {
+                name: model["id"].as_str().unwrap_or_default().to_string(),
+                size: model["meta"]["size"].as_i64(),
+                modified_at: None,
+                family: None,
+                parameter_size: None,
+                quantization_level: None,
+            })
+            .collect(),
+    };
+
+    Ok(models)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::ai::retry::RetryPolicy;
+    use crate::models::{ApiConfig, LocalOptions};
+    use crate::test_support::{client_for, config_for, test_policy, Reply, ScriptedServer};
+
+    fn request(messages: Vec<Message>) -> ChatRequest {
+        ChatRequest {
+            model: "test-model".to_string(),
+            messages,
+            tools: None,
+            tool_choice: None,
+            temperature: None,
+            max_tokens: None,
+            stream: None,
+        }
+    }
+
+    fn tool_call_line(name: &str, arguments: Value) -> Value {
+        json!({
+            "message": { "role": "assistant", "content": "", "tool_calls": [{ "function": { "name": name, "arguments": arguments } }] },
+            "done": false
+        })
+    }
+
+    fn done_line() -> Value {
+        json!({ "message": { "role": "assistant", "content": "" }, "done": true, "prompt_eval_count": 12, "eval_count": 3 })
+    }
+
+    #[tokio::test]
+    async fn streams_plain_text() {
+        let server = ScriptedServer::start(vec![Reply::ndjson(&[
+            json!({ "message": { "role": "assistant", "content": "今天" }, "done": false }),
+            json!({ "message": { "role": "assistant", "content": "完成了两件事" }, "done": false }),
+            done_line(),
+        ])])
+        .await;
+        let client = client_for(ProviderKind::Ollama, &server.url);
+
+        let deltas = Mutex::new(Vec::new());
+        let on_delta = |text: &str| deltas.lock().unwrap().push(text.to_string());
+        let response = client
+            .chat_completion_stream(&request(vec![Message::user("总结今天")]), &on_delta)
+            .await
+            .expect("请求失败");
+
+        assert_eq!(response.choices[0].message.content.as_deref(), Some("今天完成了两件事"));
+        assert_eq!(*deltas.lock().unwrap(), vec!["今天", "完成了两件事"]);
+        assert_eq!(response.usage.map(|u| u.total_tokens), Some(15));
+
+        let sent = &server.requests()[0];
+        assert_eq!(sent.path, "/api/chat");
+        assert_eq!(sent.json()["stream"], json!(true));
+    }
+
+    #[tokio::test]
+    async fn tool_call_ids_stay_unique_across_iterations() {
+        let server = ScriptedServer::start(vec![
+            Reply::ndjson(&[tool_call_line("get_current_time", json!({})), done_line()]),
+            Reply::ndjson(&[tool_call_line("search_records", json!({ "keyword": "周报" })), done_line()]),
+            Reply::ndjson(&[json!({ "message": { "role": "assistant", "content": "完成" }, "done": true })]),
+        ])
+        .await;
+        let client = client_for(ProviderKind::Ollama, &server.url);
+        let on_delta = |_: &str| {};
+
+        let mut messages = vec![Message::user("总结今天")];
+        let mut ids = Vec::new();
+        for _ in 0..2 {
+            let response = client.chat_completion_stream(&request(messages.clone()), &on_delta).await.expect("请求失败");
+            let message = response.choices.into_iter().next().expect("没有结果").message;
+            let call = message.tool_calls.clone().expect("没有工具调用").remove(0);
+            messages.push(message);
+            messages.push(Message::tool(call.id.clone(), format!("{} 的结果", call.function.name)));
+            ids.push(call.id);
+        }
+        assert_eq!(ids, vec!["ollama_call_1", "ollama_call_2"]);
+
+        let response = client.chat_completion_stream(&request(messages), &on_delta).await.expect("请求失败");
+        assert_eq!(response.choices[0].message.content.as_deref(), Some("完成"));
+
+        // 回传的历史中，每个工具结果都关联到发起它的那次调用
+        let history = server.requests()[2].json()["messages"].clone();
+        let tool_names: Vec<&str> = history
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter(|m| m["role"] == "tool")
+            .map(|m| m["tool_name"].as_str().unwrap_or_default())
+            .collect();
+        assert_eq!(tool_names, vec!["get_current_time", "search_records"]);
+        assert_eq!(history[1]["tool_calls"][0]["function"]["arguments"], json!({}));
+        assert_eq!(history[3]["tool_calls"][0]["function"]["arguments"], json!({ "keyword": "周报" }));
+    }
+
+    #[tokio::test]
+    async fn missing_model_is_a_permanent_error() {
+        let server = ScriptedServer::start(vec![
+            Reply::ndjson(&[json!({ "error": "model \"llama9\" not found, try pulling it first" })]),
+            Reply::ndjson(&[json!({ "error": "model \"llama9\" not found, try pulling it first" })]),
+        ])
+        .await;
+        let client = AiClient::new(config_for(ProviderKind::Ollama, &server.url))
+            .with_policy(RetryPolicy { max_attempts: 2, ..test_policy() });
+        let on_delta = |_: &str| {};
+
+        let error = client
+            .chat_completion_stream(&request(vec![Message::user("你好")]), &on_delta)
+            .await
+            .expect_err("应当返回错误");
+        assert_eq!(error.kind, AiErrorKind::InvalidModel);
+        assert!(error.message.contains("llama9"), "{}", error.message);
+        assert_eq!(server.requests().len(), 1, "模型不存在时不应重试");
+    }
+
+    #[tokio::test]
+    async fn other_error_chunk_becomes_server_error() {
+        let server = ScriptedServer::start(vec![Reply::ndjson(&[json!({ "error": "llama runner process has terminated" })])]).await;
+        let client = client_for(ProviderKind::Ollama, &server.url);
+        let on_delta = |_: &str| {};
+
+        let error = client
+            .chat_completion_stream(&request(vec![Message::user("你好")]), &on_delta)
+            .await
+            .expect_err("应当返回错误");
+        assert_eq!(error.kind, AiErrorKind::Server);
+    }
+
+    #[tokio::test]
+    async fn local_options_reach_the_request_body() {
+        let server = ScriptedServer::start(vec![
+            Reply::json(json!({ "message": { "role": "assistant", "content": "好" }, "done": true })),
+            Reply::json(json!({ "message": { "role": "assistant", "content": "好" }, "done": true })),
+        ])
+        .await;
+        let client_with = |keep_alive: &str| {
+            let local = LocalOptions { keep_alive: Some(keep_alive.to_string()), num_ctx: Some(8192) };
+            let config = ApiConfig { local: Some(local), ..config_for(ProviderKind::Ollama, &server.url) };
+            AiClient::new(config).with_policy(test_policy())
+        };
+
+        client_with("10m").chat_completion(&request(vec![Message::user("你好")])).await.expect("请求失败");
+        client_with(" 300 ").chat_completion(&request(vec![Message::user("你好")])).await.expect("请求失败");
+
+        let sent = server.requests();
+        assert_eq!(sent[0].path, "/api/chat");
+        assert_eq!(sent[0].json()["keep_alive"], "10m");
+        assert_eq!(sent[0].json()["options"]["num_ctx"], 8192);
+        assert_eq!(sent[0].json()["stream"], false);
+        // 纯数字按秒传递
+        assert_eq!(sent[1].json()["keep_alive"], 300);
+    }
+
+    #[tokio::test]
+    async fn lists_ollama_models_from_tags() {
+        let server = ScriptedServer::start(vec![Reply::json(json!({
+            "models": [{
+                "name": "qwen2.5:7b",
+                "size": 4_683_087_332_i64,
+                "modified_at": "2024-05-06T09:00:00+08:00",
+                "details": { "family": "qwen2", "parameter_size": "7.6B", "quantization_level": "Q4_K_M" }
+            }]
+        }))])
+        .await;
+
+        let models = list_models(ProviderKind::Ollama, &format!("{}/", server.url), Duration::from_secs(5)).await.unwrap();
+        assert_eq!(server.requests()[0].path, "/api/tags");
+        assert_eq!(models.len(), 1);
+        assert_eq!(models[0].name, "qwen2.5:7b");
+        assert_eq!(models[0].size, Some(4_683_087_332));
+        assert_eq!(models[0].family.as_deref(), Some("qwen2"));
+        assert_eq!(models[0].quantization_level.as_deref(), Some("Q4_K_M"));
+    }
+
+    #[tokio::test]
+    async fn lists_llama_cpp_models_from_openai_endpoint() {
+        let body = json!({ "object": "list", "data": [{ "id": "qwen2.5-7b-instruct-q4_k_m.gguf", "meta": { "size": 4_683_073_504_i64 } }] });
+        let server = ScriptedServer::start(vec![Reply::json(body.clone()), Reply::json(body)]).await;
+
+        let models = list_models(ProviderKind::LlamaCpp, &server.url, Duration::from_secs(5)).await.unwrap();
+        assert_eq!(models[0].name, "qwen2.5-7b-instruct-q4_k_m.gguf");
+        assert_eq!(models[0].size, Some(4_683_073_504));
+        assert!(models[0].family.is_none());
+
+        // 接口地址已经带 /v1 时不再重复
+        list_models(ProviderKind::LlamaCpp, &format!("{}/v1", server.url), Duration::from_secs(5)).await.unwrap();
+        let paths: Vec<String> = server.requests().into_iter().map(|r| r.path).collect();
+        assert_eq!(paths, vec!["/v1/models", "/v1/models"]);
+    }
+
+    #[tokio::test]
+    async fn model_list_errors_are_classified() {
+        let server = ScriptedServer::start(vec![Reply::status(404, json!({ "error": "not found" }))]).await;
+
+        let error = list_models(ProviderKind::Ollama, &server.url, Duration::from_secs(5)).await.expect_err("应当返回错误");
+        assert_eq!(error.status, Some(404));
+    }
+
+    #[tokio::test]
+    async fn stream_ending_before_done_is_an_error() {
+        let server = ScriptedServer::start(vec![Reply::ndjson(&[
+            json!({ "message": { "role": "assistant", "content": "写到一半" }, "done": false }),
+        ])])
+        .await;
+        let client = client_for(ProviderKind::Ollama, &server.url);
+        let on_delta = |_: &str| {};
+
+        let error = client
+            .chat_completion_stream(&request(vec![Message::user("你好")]), &on_delta)
+            .await
+            .expect_err("应当返回错误");
+        assert_eq!(error.kind, AiErrorKind::Network);
+        assert!(error.message.contains("提前结束"), "{}", error.message);
+    }
+}
@@ -0,0 +1,128 @@
+use async_trait::async_trait;
+use serde_json::Value;
+
+use crate::ai::client::{self, AiClient, AttemptFailure, ChatRequest, DeltaFn, FunctionCall, Message, ToolCall};
+use crate::ai::provider::Provider;
+use crate::models::{ProviderKind, TokenUsage};
+
+/// OpenAI chat completions 接口（包括 llama.cpp server 等兼容服务）
+pub(crate) struct OpenAiProvider;
+
+fn endpoint(client: &AiClient) -> String {
+    let config = client.config();
+    let base = config.api_url.trim_end_matches('/');
+    match config.provider {
+        // llama.cpp server 的 OpenAI 兼容接口位于 /v1 下
+        ProviderKind::LlamaCpp if !base.ends_with("/v1") => format!("{}/v1/chat/completions", base),
+        _ => format!("{}/chat/completions", base),
+    }
+}
+
+#[async_trait]
+impl Provider for OpenAiProvider {
+    async fn send(
+        &self,
+        client: &AiClient,
+        request: &ChatRequest,
+        on_delta: Option<DeltaFn<'_>>,
+    ) -> Result<String, AttemptFailure> {
+        let mut request = request.clone();
+        request.stream = on_delta.map(|_| true);
+
+        let mut builder = client.http().post(endpoint(client)).json(&request);
+        if !client.config().api_key.is_empty() {
+            builder = builder.bearer_auth(&client.config().api_key);
+        }
+        let mut response = client.execute(builder).await?;
+
+        let Some(on_delta) = on_delta else {
+            return client::read_body(&mut response, client.read_timeout()).await;
+        };
+
+        // SSE 流：`data: {...}` 行，以 `data: [DONE]` 结束
+        let mut accumulator = StreamAccumulator::default();
+        client::read_sse_data(&mut response, client.read_timeout(), |data| {
+            match serde_json::from_str::<Value>(data)
+                .ok()
+                .and_then(|value| accumulator.apply(&value))
+            {
+                Some(text) => {
+                    on_delta(&text);
+                    (false, true)
+                }
+                None => (false, false),
+            }
+        })
+        .await?;
+
+        Ok(accumulator.into_body(&request.model))
+    }
+}
+
+/// 逐步拼装流式返回的消息
+#[derive(Default)]
+struct StreamAccumulator {
+    content: String,
+    tool_calls: Vec<ToolCall>,
+    usage: Option<TokenUsage>,
+}
+
+impl StreamAccumulator {
+    /// 合并一个 OpenAI 格式的流式数据块，返回其中的增量文本
+    fn apply(&mut self, chunk: &Value) -> Option<String> {
+        if let Some(usage) = chunk.get("usage").filter(|u| !u.is_null()) {
+            self.usage = serde_json::from_value(usage.clone()).ok();
+        }
+
+        let delta = chunk.get("choices")?.get(0)?.get("delta")?;
+
+        if let Some(calls) = delta.get("tool_calls").and_then(|c| c.as_array()) {
+            for call in calls {
+                let index = call.get("index").and_then(|i| i.as_u64()).unwrap_or(0) as usize;
+                while self.tool_calls.len() <= index {
+                    self.tool_calls.push(ToolCall {
+                        id: String::new(),
+                        kind: client::default_tool_type(),
+                        function: FunctionCall { name: String::new(), arguments: String::new() },
+                    });
+                }
+                let target = &mut self.tool_calls[index];
+                if let Some(id) = call.get("id").and_then(|v| v.as_str()) {
+                    target.id = id.to_string();
+                }
+                if let Some(function) = call.get("function") {
+                    if let Some(name) = function.get("name").and_then(|v| v.as_str()) {
+                        target.function.name.push_str(name);
+                    }
+                    if let Some(arguments) = function.get("arguments").and_then(|v| v.as_str()) {
+                        target.function.arguments.push_str(arguments);
+                    }
+                }
+            }
+        }
+
+        let text = delta.get("content").and_then(|c| c.as_str())?;
+        if text.is_empty() {
+            return None;
+        }
+        self.content.push_str(text);
+        Some(text.to_string())
+    }
+
+    /// 转换为非流式响应体，便于统一解析和缓存
+    fn into_body(self, model: &str) -> String {
+        let message = Message {
+            role: "assistant".to_string(),
+            content: Some(self.content),
+            tool_calls: if self.tool_calls.is_empty() { None } else { Some(self.tool_calls) },
+            tool_call_id: None,
+        };
+        serde_json::json!({
+            "object": "chat.completion",
+            "model": model,
+            "choices": [{ "index": 0, "message": message }],
+            "usage": self.usage,
+        })
+        .to_string()
+    }
+}
@@ -0,0 +1,108 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use serde_json::{json, Value};
+
+use crate::ai::anthropic::AnthropicProvider;
+use crate::ai::client::{AiClient, AttemptFailure, ChatRequest, DeltaFn, Message};
+use crate::ai::gemini::GeminiProvider;
+use crate::ai::mock::MockProvider;
+use crate::ai::ollama::OllamaProvider;
+use crate::ai::openai::OpenAiProvider;
+use crate::models::{ApiConfig, ProviderKind};
+
+/// 服务商适配器：把 OpenAI 格式的请求转换为服务商的接口，
+/// 并把回复转换回 OpenAI 非流式格式的响应体，便于统一解析和缓存
+#[async_trait]
+pub(crate) trait Provider: Send + Sync {
+    /// 发送一次请求（不含重试），流式输出时每收到一段文本就调用 `on_delta`
+    async fn send(
+        &self,
+        client: &AiClient,
+        request: &ChatRequest,
+        on_delta: Option<DeltaFn<'_>>,
+    ) -> Result<String, AttemptFailure>;
+}
+
+/// 根据配置选择适配器
+pub(crate) fn for_config(config: &ApiConfig) -> Box<dyn Provider> {
+    match config.provider {
+        ProviderKind::OpenAi | ProviderKind::LlamaCpp => Box::new(OpenAiProvider),
+        ProviderKind::Mock => Box::new(MockProvider::new(config.mock.clone().unwrap_or_default())),
+        ProviderKind::Ollama => Box::new(OllamaProvider),
+        ProviderKind::Anthropic => Box::new(AnthropicProvider),
+        ProviderKind::Gemini => Box::new(GeminiProvider),
+    }
+}
+
+/// 解析工具调用参数，不是 JSON 对象时使用空对象
+pub(crate) fn arguments_object(arguments: &str) -> Value {
+    serde_json::from_str::<Value>(arguments)
+        .ok()
+        .filter(Value::is_object)
+        .unwrap_or_else(|| json!({}))
+}
+
+/// 工具调用 id 到函数名的映射（部分服务商按函数名关联工具结果）
+pub(crate) fn tool_names(messages: &[Message]) -> HashMap<&str, &str> {
+    messages
+        .iter()
+        .filter_map(|m| m.tool_calls.as_ref())
+        .flatten()
+        .map(|call| (call.id.as_str(), call.function.name.as_str()))
+        .collect()
+}
+
+/// 对话中已有的工具调用数，用于为不返回调用 id 的服务商生成在整个对话中唯一的 id
+pub(crate) fn tool_call_count(messages: &[Message]) -> usize {
+    messages.iter().filter_map(|m| m.tool_calls.as_ref()).map(Vec::len).sum()
+}
+
+/// 追加一轮对话；要求角色交替出现的服务商需要把相邻的同角色消息合并
+pub(crate) fn push_turn(turns: &mut Vec<Value>, role: &str, key: &str, items: Vec<Value>) {
+    if items.is_empty() {
+        return;
+    }
+    if let Some(last) = turns.last_mut().filter(|last| last["role"] == role) {
+        if let Some(existing) = last[key].as_array_mut() {
+            existing.extend(items);
+            return;
+        }
+    }
+    turns.push(json!({ "role": role, key: items }));
+}
+
+/// OpenAI 格式的工具定义拆分为 (名称, 描述, 参数 schema)
+pub(crate) fn tool_functions(request: &ChatRequest) -> Vec<(String, String, Value)> {
+    request
+        .tools
+        .iter()
+        .flatten()
+        .map(|tool| {
+            let function = &tool["function"];
+            (
+                function["name"].as_str().unwrap_or_default().to_string(),
+                function["description"].as_str().unwrap_or_default().to_string(),
+                function
+                    .get("parameters")
+                    .cloned()
+                    .unwrap_or_else(|| json!({ "type": "object", "properties": {} })),
+            )
+        })
+        .collect()
+}
+
+/// 合并所有系统消息
+pub(crate) fn system_prompt(messages: &[Message]) -> Option<String> {
+    let parts: Vec<&str> = messages
+        .iter()
+        .filter(|m| m.role == "system")
+        .filter_map(|m| m.content.as_deref())
+        .filter(|c| !c.trim().is_empty())
+        .collect();
+    if parts.is_empty() {
+        None
+    } else {
+        Some(parts.join("\n\n"))
+    }
+}
@@ -0,0 +1,188 @@
+use std::time::Duration;
+
+use rand::Rng;
+
+use crate::models::AppSettings;
+
+/// AI 请求的超时与重试策略
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    pub connect_timeout: Duration,
+    pub read_timeout: Duration, // 等待响应头以及两次读到数据之间的最长间隔
+    pub max_attempts: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration, // 单次等待的上限，同样限制 Retry-After
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::from_settings(&AppSettings::default())
+    }
+}
+
+impl RetryPolicy {
+    pub fn from_settings(settings: &AppSettings) -> Self {
+        Self {
+            connect_timeout: Duration::from_secs(settings.ai_connect_timeout_secs.max(1)),
+            read_timeout: Duration::from_secs(settings.ai_read_timeout_secs.max(1)),
+            max_attempts: settings.ai_max_attempts.max(1),
+            base_delay: Duration::from_millis(settings.ai_backoff_base_ms),
+            max_delay: Duration::from_millis(settings.ai_backoff_max_ms.max(settings.ai_backoff_base_ms)),
+        }
+    }
+
+    /// 第 `attempt` 次失败后的等待时间（指数退避 + 完全抖动）
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(16);
+        let ceiling = self
+            .base_delay
+            .saturating_mul(1u32 << exponent)
+            .min(self.max_delay);
+        let millis = ceiling.as_millis() as u64;
+        if millis == 0 {
+            return Duration::ZERO;
+        }
+        Duration::from_millis(rand::thread_rng().gen_range(0..=millis))
+    }
+
+    /// 服务端要求的等待时间，不超过单次等待上限
+    pub fn retry_after(&self, header: Option<&str>) -> Option<Duration> {
+        header.and_then(parse_retry_after).map(|d| d.min(self.max_delay))
+    }
+}
+
+/// 是否为值得重试的 HTTP 状态码
+pub fn is_retryable_status(status: u16) -> bool {
+    status == 429 || (500..=599).contains(&status) && status != 501
+}
+
+/// 解析 Retry-After 头：秒数或 HTTP 日期
+pub fn parse_retry_after(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    let wait = date.timestamp() - chrono::Utc::now().timestamp();
+    Some(Duration::from_secs(wait.max(0) as u64))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::client::{AiClient, ChatRequest, Message};
+    use crate::ai::error::AiErrorKind;
+    use crate::models::ProviderKind;
+    use crate::test_support::{config_for, test_policy, Reply, ScriptedServer};
+    use serde_json::json;
+
+    fn backoff_policy(base_ms: u64, max_ms: u64) -> RetryPolicy {
+        RetryPolicy {
+            base_delay: Duration::from_millis(base_ms),
+            max_delay: Duration::from_millis(max_ms),
+            ..test_policy()
+        }
+    }
+
+    fn request() -> ChatRequest {
+        ChatRequest {
+            model: "test-model".to_string(),
+            messages: vec![Message::user("你好")],
+            tools: None,
+            tool_choice: None,
+            temperature: None,
+            max_tokens: None,
+            stream: None,
+        }
+    }
+
+    #[test]
+    fn backoff_stays_within_the_exponential_ceiling() {
+        let policy = backoff_policy(100, 1000);
+        // （第几次失败，等待上限毫秒）
+        let cases = [(0, 100), (1, 100), (2, 200), (3, 400), (4, 800), (5, 1000), (6, 1000), (100, 1000)];
+        for (attempt, ceiling) in cases {
+            for _ in 0..200 {
+                let delay = policy.backoff(attempt);
+                assert!(delay <= Duration::from_millis(ceiling), "第 {} 次: {:?}", attempt, delay);
+            }
+        }
+        // 完全抖动：多次取样不应总是同一个值
+        let samples: std::collections::HashSet<_> = (0..50).map(|_| policy.backoff(4)).collect();
+        assert!(samples.len() > 1);
+
+        assert_eq!(backoff_policy(0, 1000).backoff(3), Duration::ZERO);
+    }
+
+    #[test]
+    fn retry_after_is_parsed_and_capped() {
+        let cases = [
+            ("120", Some(120)),
+            (" 5 ", Some(5)),
+            ("0", Some(0)),
+            ("-1", None),
+            ("soon", None),
+            ("Wed, 21 Oct 2015 07:28:00 GMT", Some(0)), // 已经过去的时间
+        ];
+        for (header, expected) in cases {
+            assert_eq!(parse_retry_after(header), expected.map(Duration::from_secs), "{}", header);
+        }
+
+        let future = (chrono::Utc::now() + chrono::Duration::seconds(60)).to_rfc2822();
+        let wait = parse_retry_after(&future).unwrap();
+        assert!((Duration::from_secs(58)..=Duration::from_secs(60)).contains(&wait), "{:?}", wait);
+
+        let policy = backoff_policy(500, 30_000);
+        assert_eq!(policy.retry_after(Some("10")), Some(Duration::from_secs(10)));
+        assert_eq!(policy.retry_after(Some("3600")), Some(Duration::from_secs(30)));
+        assert_eq!(policy.retry_after(Some("soon")), None);
+        assert_eq!(policy.retry_after(None), None);
+    }
+
+    #[test]
+    fn retryable_statuses() {
+        let cases = [(400, false), (401, false), (404, false), (429, true), (500, true), (501, false), (503, true)];
+        for (status, retryable) in cases {
+            assert_eq!(is_retryable_status(status), retryable, "{}", status);
+        }
+    }
+
+    #[test]
+    fn settings_always_allow_one_attempt() {
+        let settings =
+            AppSettings { ai_max_attempts: 0, ai_backoff_base_ms: 800, ai_backoff_max_ms: 100, ..Default::default() };
+        let policy = RetryPolicy::from_settings(&settings);
+        assert_eq!(policy.max_attempts, 1);
+        // 上限不低于初始等待
+        assert_eq!(policy.max_delay, Duration::from_millis(800));
+    }
+
+    #[tokio::test]
+    async fn client_stops_after_max_attempts() {
+        let error = || Reply::status(503, json!({ "error": { "message": "overloaded" } }));
+        let server = ScriptedServer::start(vec![error(), error(), error(), error()]).await;
+        let client = AiClient::new(config_for(ProviderKind::OpenAi, &server.url))
+            .with_policy(RetryPolicy { max_attempts: 3, ..test_policy() });
+
+        let failure = client.chat_completion(&request()).await.expect_err("应当返回错误");
+        assert_eq!(failure.kind, AiErrorKind::Server);
+        assert_eq!(failure.attempts, 3);
+        assert_eq!(server.requests().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn client_does_not_retry_permanent_errors() {
+        let server = ScriptedServer::start(vec![
+            Reply::status(429, json!({ "error": { "code": "insufficient_quota" } })),
+            Reply::json(json!({})),
+        ])
+        .await;
+        let client = AiClient::new(config_for(ProviderKind::OpenAi, &server.url))
+            .with_policy(RetryPolicy { max_attempts: 3, ..test_policy() });
+
+        let failure = client.chat_completion(&request()).await.expect_err("应当返回错误");
+        assert_eq!(failure.kind, AiErrorKind::Quota);
+        assert_eq!(failure.attempts, 1);
+        assert_eq!(server.requests().len(), 1);
+    }
+}
@@ -0,0 +1,354 @@
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use sqlx::{Pool, Row, Sqlite};
+
+use crate::ai::context::{BuiltContext, ContextBuilder, TokenEstimator};
+use crate::stats::{self, GroupBy};
+use crate::planning;
+use crate::idea_links;
+use crate::repo::{IdeaRepo, SortDirection, TaskRepo};
+
+/// 工具执行时可用的上下文
+pub struct ToolContext {
+    pub pool: Pool<Sqlite>,
+    pub estimator: TokenEstimator,
+    pub data_budget: usize, // 单次工具结果中记录数据的 token 预算
+}
+
+/// 可供模型调用的工具
+#[async_trait]
+pub trait Tool: Send + Sync {
+    /// 工具名称（模型调用时使用）
+    fn name(&self) -> &'static str;
+
+    /// 工具用途说明
+    fn description(&self) -> &'static str;
+
+    /// 参数的 JSON Schema
+    fn parameters(&self) -> Value;
+
+    /// 执行工具，返回可序列化为 JSON 的结果
+    async fn call(&self, ctx: &ToolContext, args: Value) -> Result<Value, String>;
+}
+
+/// 工具注册表
+#[derive(Default)]
+pub struct ToolRegistry {
+    tools: Vec<Box<dyn Tool>>,
+}
+
+impl ToolRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 包含全部内置工具的注册表
+    pub fn with_builtin_tools() -> Self {
+        let mut registry = Self::new();
+        registry.register(Box::new(GetCurrentTimeTool));
+        registry.register(Box::new(GetHistoryDataTool));
+        registry.register(Box::new(SearchRecordsTool));
+        registry.register(Box::new(TimeStatsTool));
+        registry.register(Box::new(ListTagsTool));
+        registry
+    }
+
+    /// 注册工具，同名工具会被替换
+    pub fn register(&mut self, tool: Box<dyn Tool>) {
+        self.tools.retain(|t| t.name() != tool.name());
+        self.tools.push(tool);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&dyn Tool> {
+        self.tools.iter().find(|t| t.name() == name).map(|t| t.as_ref())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tools.is_empty()
+    }
+
+    /// 生成 chat completions 请求中的 tools 字段
+    pub fn definitions(&self) -> Vec<Value> {
+        self.tools
+            .iter()
+            .map(|tool| {
+                json!({
+                    "type": "function",
+                    "function": {
+                        "name": tool.name(),
+                        "description": tool.description(),
+                        "parameters": tool.parameters(),
+                    }
+                })
+            })
+            .collect()
+    }
+}
+
+// ========== 参数解析 ==========
+
+fn required_str(args: &Value, key: &str) -> Result<String, String> {
+    args.get(key)
+        .and_then(|v| v.as_str())
+        .map(|v| v.to_string())
+        .ok_or_else(|| format!("缺少参数 {}", key))
+}
+
+fn optional_str(args: &Value, key: &str) -> Option<String> {
+    args.get(key)
+        .and_then(|v| v.as_str())
+        .filter(|v| !v.is_empty())
+        .map(|v| v.to_string())
+}
+
+fn parse_date(date: &str) -> Result<chrono::NaiveDate, String> {
+    chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .map_err(|e| format!("无效的日期 {}: {}", date, e))
+}
+
+// ========== 内置工具 ==========
+
+/// 获取当前时间
+pub struct GetCurrentTimeTool;
+
+#[async_trait]
+impl Tool for GetCurrentTimeTool {
+    fn name(&self) -> &'static str {
+        "get_current_time"
+    }
+
+    fn description(&self) -> &'static str {
+        "获取当前日期和时间"
+    }
+
+    fn parameters(&self) -> Value {
+        json!({ "type": "object", "properties": {}, "required": [] })
+    }
+
+    async fn call(&self, _ctx: &ToolContext, _args: Value) -> Result<Value, String> {
+        let now = chrono::Local::now();
+        Ok(json!({
+            "current_time": now.to_rfc3339(),
+            "formatted_time": now.format("%Y年%m月%d日 %H:%M:%S").to_string(),
+            "date": now.format("%Y-%m-%d").to_string(),
+            "weekday": now.format("%A").to_string(),
+        }))
+    }
+}
+
+/// 获取日期范围内的想法、已完成事项和计划
+pub struct GetHistoryDataTool;
+
+#[async_trait]
+impl Tool for GetHistoryDataTool {
+    fn name(&self) -> &'static str {
+        "get_history_data"
+    }
+
+    fn description(&self) -> &'static str {
+        "获取指定日期范围的想法、已完成事项、计划完成情况以及之后最近一天的计划"
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "start_date": { "type": "string", "description": "开始日期 (YYYY-MM-DD)" },
+                "end_date": { "type": "string", "description": "结束日期 (YYYY-MM-DD)" }
+            },
+            "required": ["start_date", "end_date"]
+        })
+    }
+
+    async fn call(&self, ctx: &ToolContext, args: Value) -> Result<Value, String> {
+        let start_date = required_str(&args, "start_date")?;
+        let end_date = required_str(&args, "end_date")?;
+
+        // 超出预算时按天降级，裁剪情况随结果一并返回
+        Ok(load_report_context(ctx, &start_date, &end_date).await?.data)
+    }
+}
+
+/// 读取日期范围内生成报告所需的全部数据，并按预算构建上下文
+pub async fn load_report_context(ctx: &ToolContext, start_date: &str, end_date: &str) -> Result<BuiltContext, String> {
+    let ideas = IdeaRepo::new(&ctx.pool).list_in_range(start_date, end_date, SortDirection::Asc).await?;
+    let tasks = TaskRepo::new(&ctx.pool).list_in_range(start_date, end_date, SortDirection::Asc).await?;
+    let plans = planning::list_planned_tasks(&ctx.pool, start_date, end_date, None).await?;
+    let carries = planning::list_carries(&ctx.pool, start_date, end_date).await?;
+    let next_plans = planning::next_planned_tasks(&ctx.pool, end_date).await?;
+    let links = idea_links::links_in_range(&ctx.pool, start_date, end_date).await?;
+
+    Ok(ContextBuilder::new(&ctx.estimator, ctx.data_budget, start_date, end_date)
+        .records(ideas, tasks)
+        .plans(plans, carries)
+        .next_plans(next_plans)
+        .idea_links(links)
+        .build())
+}
+
+/// 按关键字搜索记录
+pub struct SearchRecordsTool;
+
+#[async_trait]
+impl Tool for SearchRecordsTool {
+    fn name(&self) -> &'static str {
+        "search_records"
+    }
+
+    fn description(&self) -> &'static str {
+        "按关键字搜索想法和已完成事项，可限定日期范围"
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "keyword": { "type": "string", "description": "搜索关键字" },
+                "start_date": { "type": "string", "description": "开始日期 (YYYY-MM-DD)，可选" },
+                "end_date": { "type": "string", "description": "结束日期 (YYYY-MM-DD)，可选" },
+                "limit": { "type": "integer", "description": "最多返回的条数，默认 20" }
+            },
+            "required": ["keyword"]
+        })
+    }
+
+    async fn call(&self, ctx: &ToolContext, args: Value) -> Result<Value, String> {
+        let keyword = required_str(&args, "keyword")?;
+        let start_date = optional_str(&args, "start_date").unwrap_or_else(|| "0000-01-01".to_string());
+        let end_date = optional_str(&args, "end_date").unwrap_or_else(|| "9999-12-31".to_string());
+        let limit = args.get("limit").and_then(|v| v.as_i64()).unwrap_or(20).clamp(1, 100);
+
+        let pattern = format!(
+            "%{}%",
+            keyword.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+        );
+
+        let rows = sqlx::query(
+            r#"
+            SELECT 'idea' AS kind, id, content, date, created_at, NULL AS start_time, NULL AS end_time
+            FROM ideas WHERE content LIKE ?1 ESCAPE '\' AND date >= ?2 AND date <= ?3
+            UNION ALL
+            SELECT 'task' AS kind, id, content, date, created_at, start_time, end_time
+            FROM done_tasks WHERE content LIKE ?1 ESCAPE '\' AND date >= ?2 AND date <= ?3
+            ORDER BY date DESC, created_at DESC
+            LIMIT ?4
+            "#,
+        )
+        .bind(&pattern)
+        .bind(&start_date)
+        .bind(&end_date)
+        .bind(limit)
+        .fetch_all(&ctx.pool)
+        .await
+        .map_err(|e| format!("搜索记录失败: {}", e))?;
+
+        let read_err = |e: sqlx::Error| format!("读取搜索结果失败: {}", e);
+        let mut results = Vec::new();
+        for row in &rows {
+            results.push(json!({
+                "kind": row.try_get::<String, _>("kind").map_err(read_err)?,
+                "id": row.try_get::<i64, _>("id").map_err(read_err)?,
+                "content": row.try_get::<String, _>("content").map_err(read_err)?,
+                "date": row.try_get::<String, _>("date").map_err(read_err)?,
+                "start_time": row.try_get::<Option<i64>, _>("start_time").map_err(read_err)?,
+                "end_time": row.try_get::<Option<i64>, _>("end_time").map_err(read_err)?,
+            }));
+        }
+
+        Ok(json!({ "keyword": keyword, "count": results.len(), "results": results }))
+    }
+}
+
+/// 统计时间分布
+pub struct TimeStatsTool;
+
+#[async_trait]
+impl Tool for TimeStatsTool {
+    fn name(&self) -> &'static str {
+        "time_stats"
+    }
+
+    fn description(&self) -> &'static str {
+        "统计日期范围内已完成事项的耗时，可按天、周、星期、小时或标签分组（重叠时间不重复计算）"
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "start_date": { "type": "string", "description": "开始日期 (YYYY-MM-DD)" },
+                "end_date": { "type": "string", "description": "结束日期 (YYYY-MM-DD)" },
+                "group_by": {
+                    "type": "string",
+                    "enum": ["day", "week", "weekday", "hour", "tag"],
+                    "description": "分组方式，默认 day"
+                }
+            },
+            "required": ["start_date", "end_date"]
+        })
+    }
+
+    async fn call(&self, ctx: &ToolContext, args: Value) -> Result<Value, String> {
+        let start = parse_date(&required_str(&args, "start_date")?)?;
+        let end = parse_date(&required_str(&args, "end_date")?)?;
+        if end < start {
+            return Err("结束日期早于开始日期".to_string());
+        }
+        let group_by: GroupBy = match args.get("group_by") {
+            Some(v) if !v.is_null() => {
+                serde_json::from_value(v.clone()).map_err(|e| format!("无效的分组方式: {}", e))?
+            }
+            _ => GroupBy::Day,
+        };
+
+        let range_start = stats::local_midnight(start);
+        let range_end = stats::local_midnight(end + chrono::Duration::days(1));
+        let tasks = TaskRepo::new(&ctx.pool).overlapping(range_start, range_end).await?;
+
+        let result = stats::compute_time_stats(&tasks, start, end, group_by);
+        serde_json::to_value(result).map_err(|e| format!("序列化统计结果失败: {}", e))
+    }
+}
+
+/// 列出所有标签及使用次数
+pub struct ListTagsTool;
+
+#[async_trait]
+impl Tool for ListTagsTool {
+    fn name(&self) -> &'static str {
+        "list_tags"
+    }
+
+    fn description(&self) -> &'static str {
+        "列出已完成事项使用过的所有标签及对应的事项数量"
+    }
+
+    fn parameters(&self) -> Value {
+        json!({ "type": "object", "properties": {}, "required": [] })
+    }
+
+    async fn call(&self, ctx: &ToolContext, _args: Value) -> Result<Value, String> {
+        let rows = sqlx::query(
+            r#"
+            SELECT tag.value AS tag, COUNT(*) AS task_count
+            FROM done_tasks, json_each(CASE WHEN json_valid(done_tasks.tags) THEN done_tasks.tags ELSE '[]' END) AS tag
+            GROUP BY tag.value
+            ORDER BY task_count DESC, tag.value ASC
+            "#,
+        )
+        .fetch_all(&ctx.pool)
+        .await
+        .map_err(|e| format!("查询标签失败: {}", e))?;
+
+        let read_err = |e: sqlx::Error| format!("读取标签失败: {}", e);
+        let mut tags = Vec::new();
+        for row in &rows {
+            tags.push(json!({
+                "tag": row.try_get::<String, _>("tag").map_err(read_err)?,
+                "task_count": row.try_get::<i64, _>("task_count").map_err(read_err)?,
+            }));
+        }
+
+        Ok(json!({ "tags": tags }))
+    }
+}
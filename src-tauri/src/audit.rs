@@ -0,0 +1,223 @@
+use serde::{Deserialize, Serialize};
+use sqlx::{sqlite::SqliteRow, Pool, Row, Sqlite, SqliteConnection};
+
+use crate::models::{AuditAction, AuditEntity, AuditEntry, AuditOrigin};
+use crate::reports;
+
+const AUDIT_COLUMNS: &str = "id, at, origin, entity, entity_id, action, before, after";
+
+/// 查询时的默认条数与最大条数
+pub const DEFAULT_LIMIT: i64 = 200;
+const MAX_LIMIT: i64 = 1000;
+
+/// 单次导出的最大条数
+const MAX_EXPORT: i64 = 100_000;
+
+/// 审计日志的筛选条件，均为空时返回全部记录
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AuditQuery {
+    #[serde(default)]
+    pub entity: Option<AuditEntity>,
+    #[serde(default)]
+    pub entity_id: Option<i64>,
+    #[serde(default)]
+    pub action: Option<AuditAction>,
+    #[serde(default)]
+    pub origin: Option<AuditOrigin>,
+    #[serde(default)]
+    pub from: Option<i64>, // 起始时间戳（含）
+    #[serde(default)]
+    pub to: Option<i64>, // 结束时间戳（不含）
+    #[serde(default)]
+    pub report_id: Option<i64>, // 只看该报告生成后，其日期范围内的想法和事项的修改
+    #[serde(default)]
+    pub limit: Option<i64>,
+    #[serde(default)]
+    pub offset: Option<i64>,
+}
+
+/// 导出格式
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportFormat {
+    #[default]
+    Json,
+    Csv,
+}
+
+fn entry_from_row(row: &SqliteRow) -> Result<AuditEntry, String> {
+    let read_err = |e: sqlx::Error| format!("读取审计日志失败: {}", e);
+    let origin: String = row.try_get("origin").map_err(read_err)?;
+    let entity: String = row.try_get("entity").map_err(read_err)?;
+    let action: String = row.try_get("action").map_err(read_err)?;
+    let before: Option<String> = row.try_get("before").map_err(read_err)?;
+    let after: Option<String> = row.try_get("after").map_err(read_err)?;
+
+    Ok(AuditEntry {
+        id: row.try_get("id").map_err(read_err)?,
+        at: row.try_get("at").map_err(read_err)?,
+        origin: AuditOrigin::parse(&origin).ok_or_else(|| format!("未知的修改来源: {}", origin))?,
+        entity: AuditEntity::parse(&entity).ok_or_else(|| format!("未知的数据类型: {}", entity))?,
+        entity_id: row.try_get("entity_id").map_err(read_err)?,
+        action: AuditAction::parse(&action).ok_or_else(|| format!("未知的操作: {}", action))?,
+        before: before.and_then(|v| serde_json::from_str(&v).ok()),
+        after: after.and_then(|v| serde_json::from_str(&v).ok()),
+    })
+}
+
+/// 在调用方的事务中追加一条审计日志，与数据修改一同提交或回滚
+pub async fn record<T: Serialize>(
+    conn: &mut SqliteConnection,
+    origin: AuditOrigin,
+    entity: AuditEntity,
+    entity_id: i64,
+    action: AuditAction,
+    before: Option<&T>,
+    after: Option<&T>,
+) -> Result<(), String> {
+    let to_json = |value: Option<&T>| -> Result<Option<String>, String> {
+        value
+            .map(|v| serde_json::to_string(v).map_err(|e| format!("序列化审计快照失败: {}", e)))
+            .transpose()
+    };
+
+    sqlx::query("INSERT INTO audit_log (at, origin, entity, entity_id, action, before, after) VALUES (?, ?, ?, ?, ?, ?, ?)")
+        .bind(chrono::Local::now().timestamp())
+        .bind(origin.as_str())
+        .bind(entity.as_str())
+        .bind(entity_id)
+        .bind(action.as_str())
+        .bind(to_json(before)?)
+        .bind(to_json(after)?)
+        .execute(conn)
+        .await
+        .map_err(|e| format!("写入审计日志失败: {}", e))?;
+    Ok(())
+}
+
+pub async fn record_insert<T: Serialize>(
+    conn: &mut SqliteConnection,
+    origin: AuditOrigin,
+    entity: AuditEntity,
+    entity_id: i64,
+    after: &T,
+) -> Result<(), String> {
+    record(conn, origin, entity, entity_id, AuditAction::Insert, None, Some(after)).await
+}
+
+pub async fn record_update<T: Serialize>(
+    conn: &mut SqliteConnection,
+    origin: AuditOrigin,
+    entity: AuditEntity,
+    entity_id: i64,
+    before: &T,
+    after: &T,
+) -> Result<(), String> {
+    record(conn, origin, entity, entity_id, AuditAction::Update, Some(before), Some(after)).await
+}
+
+pub async fn record_delete<T: Serialize>(
+    conn: &mut SqliteConnection,
+    origin: AuditOrigin,
+    entity: AuditEntity,
+    entity_id: i64,
+    before: &T,
+) -> Result<(), String> {
+    record(conn, origin, entity, entity_id, AuditAction::Delete, Some(before), None).await
+}
+
+async fn query_entries(pool: &Pool<Sqlite>, query: &AuditQuery, order: &str, limit: i64) -> Result<Vec<AuditEntry>, String> {
+    // 报告筛选：报告生成之后，修改前或修改后的日期落在报告范围内的想法和事项
+    let report = match query.report_id {
+        Some(id) => Some(reports::get_report(pool, id).await?),
+        None => None,
+    };
+
+    let rows = sqlx::query(&format!(
+        r#"
+        SELECT {}
+        FROM audit_log
+        WHERE (?1 IS NULL OR entity = ?1)
+          AND (?2 IS NULL OR entity_id = ?2)
+          AND (?3 IS NULL OR action = ?3)
+          AND (?4 IS NULL OR origin = ?4)
+          AND (?5 IS NULL OR at >= ?5)
+          AND (?6 IS NULL OR at < ?6)
+          AND (?7 IS NULL OR (
+                entity IN ('idea', 'task')
+                AND at >= ?7
+                AND (json_extract(before, '$.date') BETWEEN ?8 AND ?9
+                     OR json_extract(after, '$.date') BETWEEN ?8 AND ?9)
+          ))
+        ORDER BY id {}
+        LIMIT ?10 OFFSET ?11
+        "#,
+        AUDIT_COLUMNS, order
+    ))
+    .bind(query.entity.map(|e| e.as_str()))
+    .bind(query.entity_id)
+    .bind(query.action.map(|a| a.as_str()))
+    .bind(query.origin.map(|o| o.as_str()))
+    .bind(query.from)
+    .bind(query.to)
+    .bind(report.as_ref().map(|r| r.created_at))
+    .bind(report.as_ref().map(|r| r.start_date.clone()))
+    .bind(report.as_ref().map(|r| r.end_date.clone()))
+    .bind(limit)
+    .bind(query.offset.unwrap_or(0).max(0))
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("查询审计日志失败: {}", e))?;
+
+    rows.iter().map(entry_from_row).collect()
+}
+
+/// 按条件查询审计日志，最新的在前
+pub async fn list_entries(pool: &Pool<Sqlite>, query: &AuditQuery) -> Result<Vec<AuditEntry>, String> {
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+    query_entries(pool, query, "DESC", limit).await
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// 按条件导出审计日志（按时间顺序，忽略分页条件）
+pub async fn export_entries(pool: &Pool<Sqlite>, query: &AuditQuery, format: ExportFormat) -> Result<String, String> {
+    let query = AuditQuery { limit: None, offset: None, ..query.clone() };
+    let entries = query_entries(pool, &query, "ASC", MAX_EXPORT).await?;
+
+    match format {
+        ExportFormat::Json => serde_json::to_string_pretty(&entries).map_err(|e| format!("导出审计日志失败: {}", e)),
+        ExportFormat::Csv => {
+            let mut out = String::from("id,at,time,origin,entity,entity_id,action,before,after\n");
+            for entry in &entries {
+                let time = chrono::DateTime::from_timestamp(entry.at, 0)
+                    .map(|dt| dt.with_timezone(&chrono::Local).to_rfc3339())
+                    .unwrap_or_default();
+                let snapshot = |value: &Option<serde_json::Value>| {
+                    value.as_ref().map(|v| v.to_string()).unwrap_or_default()
+                };
+                let fields = [
+                    entry.id.to_string(),
+                    entry.at.to_string(),
+                    time,
+                    entry.origin.as_str().to_string(),
+                    entry.entity.as_str().to_string(),
+                    entry.entity_id.to_string(),
+                    entry.action.as_str().to_string(),
+                    snapshot(&entry.before),
+                    snapshot(&entry.after),
+                ];
+                let line: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+                out.push_str(&line.join(","));
+                out.push('\n');
+            }
+            Ok(out)
+        }
+    }
+}
@@ -0,0 +1,431 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use chrono::{Local, NaiveDateTime, TimeZone};
+use serde_json::json;
+use sqlx::sqlite::SqlitePoolOptions;
+use sqlx::{Pool, Sqlite};
+use tauri::{AppHandle, Emitter};
+
+use crate::audit;
+use crate::config::ConfigManager;
+use crate::database::{self, DbState};
+use crate::models::{AppSettings, AuditAction, AuditEntity, AuditOrigin, Snapshot};
+use crate::workspaces::{self, WorkspacePaths, DB_FILE};
+
+/// 恢复快照后通知前端重新加载数据的事件
+pub const DATABASE_RESTORED_EVENT: &str = "database-restored";
+
+/// 后台检查是否需要备份的间隔
+const WORKER_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// 自动备份的间隔
+const BACKUP_INTERVAL_SECS: i64 = 24 * 60 * 60;
+
+/// 恢复前自动保存的快照保留份数
+const KEEP_PRE_RESTORE: usize = 3;
+
+const SNAPSHOT_PREFIX: &str = "data-";
+const PRE_RESTORE_PREFIX: &str = "pre-restore-";
+const NAME_TIME_FORMAT: &str = "%Y%m%d-%H%M%S";
+
+/// 快照保存在工作区目录中，数据库迁移到自定义目录后位置不变
+fn backup_dir(paths: &WorkspacePaths) -> PathBuf {
+    paths.data_dir.join("backups")
+}
+
+/// 旧版本把快照放在数据库目录中，数据库在自定义目录时把这些快照移到工作区目录
+fn adopt_legacy_backups(paths: &WorkspacePaths) -> Result<(), String> {
+    let legacy = paths.db_dir.join("backups");
+    let dir = backup_dir(paths);
+    if legacy == dir || !legacy.is_dir() {
+        return Ok(());
+    }
+    let snapshots = list_in(&legacy)?;
+    if snapshots.is_empty() {
+        return Ok(());
+    }
+    std::fs::create_dir_all(&dir).map_err(|e| format!("创建备份目录失败: {}", e))?;
+    for snapshot in snapshots {
+        let target = dir.join(&snapshot.name);
+        if target.exists() {
+            continue;
+        }
+        // 跨磁盘时无法直接重命名，改为复制后删除
+        if std::fs::rename(&snapshot.path, &target).is_err() {
+            std::fs::copy(&snapshot.path, &target).map_err(|e| format!("移动快照 {} 失败: {}", snapshot.name, e))?;
+            let _ = std::fs::remove_file(&snapshot.path);
+        }
+    }
+    Ok(())
+}
+
+/// 从文件名解析快照信息，不是快照文件时返回 None
+fn parse_snapshot(path: &Path) -> Option<Snapshot> {
+    let name = path.file_name()?.to_str()?.to_string();
+    let stem = name.strip_suffix(".db")?;
+    let (pre_restore, time) = match stem.strip_prefix(PRE_RESTORE_PREFIX) {
+        Some(time) => (true, time),
+        None => (false, stem.strip_prefix(SNAPSHOT_PREFIX)?),
+    };
+    let created = NaiveDateTime::parse_from_str(time, NAME_TIME_FORMAT).ok()?;
+    let created_at = Local.from_local_datetime(&created).earliest()?.timestamp();
+    let size_bytes = std::fs::metadata(path).ok()?.len();
+
+    Some(Snapshot {
+        name,
+        path: path.to_string_lossy().to_string(),
+        created_at,
+        size_bytes,
+        pre_restore,
+    })
+}
+
+fn list_in(dir: &Path) -> Result<Vec<Snapshot>, String> {
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    let entries = std::fs::read_dir(dir).map_err(|e| format!("读取备份目录失败: {}", e))?;
+    let mut snapshots: Vec<Snapshot> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| parse_snapshot(&entry.path()))
+        .collect();
+    snapshots.sort_by(|a, b| b.created_at.cmp(&a.created_at).then_with(|| b.name.cmp(&a.name)));
+    Ok(snapshots)
+}
+
+/// 列出当前工作区的快照（最新的在前）
+pub fn list_snapshots(app: &AppHandle) -> Result<Vec<Snapshot>, String> {
+    let paths = workspaces::active_paths(app)?;
+    adopt_legacy_backups(&paths)?;
+    list_in(&backup_dir(&paths))
+}
+
+/// 对快照文件执行 `PRAGMA integrity_check`
+pub async fn verify_snapshot(path: &Path) -> Result<(), String> {
+    let url = format!("sqlite://{}?mode=ro", path.to_string_lossy());
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect(&url)
+        .await
+        .map_err(|e| format!("打开快照失败: {}", e))?;
+
+    let result: Result<Vec<String>, String> = sqlx::query_scalar("PRAGMA integrity_check")
+        .fetch_all(&pool)
+        .await
+        .map_err(|e| format!("检查快照失败: {}", e));
+    pool.close().await;
+
+    let messages = result?;
+    if messages.len() == 1 && messages[0] == "ok" {
+        Ok(())
+    } else {
+        Err(format!("快照完整性检查未通过: {}", messages.join("; ")))
+    }
+}
+
+/// 用 `VACUUM INTO` 在 `dir` 中生成快照并校验，调用方需持有工作区切换锁
+async fn snapshot_locked(pool: &Pool<Sqlite>, dir: &Path, prefix: &str) -> Result<Snapshot, String> {
+    std::fs::create_dir_all(dir).map_err(|e| format!("创建备份目录失败: {}", e))?;
+
+    let path = dir.join(format!("{}{}.db", prefix, Local::now().format(NAME_TIME_FORMAT)));
+    if path.exists() {
+        // 同一秒内重复备份，直接返回已有快照
+        return parse_snapshot(&path).ok_or_else(|| "读取快照失败".to_string());
+    }
+
+    sqlx::query("VACUUM INTO ?")
+        .bind(path.to_string_lossy().to_string())
+        .execute(pool)
+        .await
+        .map_err(|e| format!("备份数据库失败: {}", e))?;
+
+    if let Err(e) = verify_snapshot(&path).await {
+        let _ = std::fs::remove_file(&path);
+        return Err(e);
+    }
+    parse_snapshot(&path).ok_or_else(|| "读取快照失败".to_string())
+}
+
+/// 按保留规则需要保留的快照：每天、每周、每月各保留最新的一份
+fn retained(snapshots: &[Snapshot], settings: &AppSettings) -> HashSet<String> {
+    let mut keep = HashSet::new();
+    let regular: Vec<&Snapshot> = snapshots.iter().filter(|s| !s.pre_restore).collect();
+
+    let rules: [(usize, &str); 3] = [
+        (settings.backup_keep_daily, "%Y-%m-%d"),
+        (settings.backup_keep_weekly, "%G-W%V"),
+        (settings.backup_keep_monthly, "%Y-%m"),
+    ];
+    for (count, period_format) in rules {
+        let mut periods: Vec<String> = Vec::new();
+        for snapshot in &regular {
+            let Some(created) = Local.timestamp_opt(snapshot.created_at, 0).single() else {
+                continue;
+            };
+            let period = created.format(period_format).to_string();
+            if periods.contains(&period) {
+                continue;
+            }
+            if periods.len() >= count {
+                break;
+            }
+            periods.push(period);
+            keep.insert(snapshot.name.clone());
+        }
+    }
+
+    // 最新的一份始终保留
+    if let Some(latest) = regular.first() {
+        keep.insert(latest.name.clone());
+    }
+    for snapshot in snapshots.iter().filter(|s| s.pre_restore).take(KEEP_PRE_RESTORE) {
+        keep.insert(snapshot.name.clone());
+    }
+    keep
+}
+
+/// 删除超出保留规则的快照，返回删除的数量
+fn prune(dir: &Path, settings: &AppSettings) -> Result<usize, String> {
+    let snapshots = list_in(dir)?;
+    let keep = retained(&snapshots, settings);
+    let mut removed = 0;
+    for snapshot in snapshots.iter().filter(|s| !keep.contains(&s.name)) {
+        std::fs::remove_file(&snapshot.path).map_err(|e| format!("删除快照 {} 失败: {}", snapshot.name, e))?;
+        removed += 1;
+    }
+    Ok(removed)
+}
+
+/// 立即为当前工作区生成快照，并按保留规则清理旧快照
+pub async fn create_snapshot(app: &AppHandle, db_state: &DbState) -> Result<Snapshot, String> {
+    let settings = ConfigManager::new(app)?.load_settings()?;
+    let _switching = workspaces::SWITCH_LOCK.lock().await;
+
+    let paths = workspaces::active_paths(app)?;
+    adopt_legacy_backups(&paths)?;
+    let dir = backup_dir(&paths);
+    let pool = db_state.get_pool().await?;
+    let snapshot = snapshot_locked(&pool, &dir, SNAPSHOT_PREFIX).await?;
+    prune(&dir, &settings)?;
+    Ok(snapshot)
+}
+
+/// 把快照之后写入的审计日志补回恢复出的数据库，并追加一条恢复记录
+///
+/// 审计日志只追加，快照中的日志是恢复前日志的前缀，按 id 补上之后的部分即可。
+async fn carry_audit_log(
+    restored: &Path,
+    snapshot: &Snapshot,
+    pre_restore: Option<&Snapshot>,
+    origin: AuditOrigin,
+) -> Result<(), String> {
+    let pool = database::open_existing(restored).await?;
+    let result = append_audit_log(&pool, snapshot, pre_restore, origin).await;
+    pool.close().await;
+    result
+}
+
+async fn append_audit_log(
+    pool: &Pool<Sqlite>,
+    snapshot: &Snapshot,
+    pre_restore: Option<&Snapshot>,
+    origin: AuditOrigin,
+) -> Result<(), String> {
+    let mut conn = pool.acquire().await.map_err(|e| format!("打开恢复的数据库失败: {}", e))?;
+
+    let carried = match pre_restore {
+        Some(previous) => {
+            sqlx::query("ATTACH DATABASE ? AS previous")
+                .bind(&previous.path)
+                .execute(&mut *conn)
+                .await
+                .map_err(|e| format!("读取恢复前的审计日志失败: {}", e))?;
+            let inserted = sqlx::query(
+                r#"
+                INSERT INTO main.audit_log (id, at, origin, entity, entity_id, action, before, after)
+                SELECT id, at, origin, entity, entity_id, action, before, after
+                FROM previous.audit_log
+                WHERE id > (SELECT COALESCE(MAX(id), 0) FROM main.audit_log)
+                ORDER BY id
+                "#,
+            )
+            .execute(&mut *conn)
+            .await
+            .map(|result| result.rows_affected())
+            .map_err(|e| format!("保留审计日志失败: {}", e));
+            let _ = sqlx::query("DETACH DATABASE previous").execute(&mut *conn).await;
+            inserted?
+        }
+        None => 0,
+    };
+
+    let before = json!({ "pre_restore": pre_restore.map(|s| &s.name) });
+    let after = json!({ "snapshot": snapshot.name, "carried_audit_entries": carried });
+    audit::record(&mut conn, origin, AuditEntity::Snapshot, 0, AuditAction::Restore, Some(&before), Some(&after)).await
+}
+
+/// 从快照恢复当前工作区的数据库
+///
+/// 先校验快照，再把当前数据保存为恢复前快照；恢复期间新的数据库请求会等待。
+/// 审计日志不随快照回退：快照之后的日志会补回恢复后的数据库，并记录这次恢复。
+pub async fn restore_snapshot(
+    app: &AppHandle,
+    db_state: &DbState,
+    name: &str,
+    origin: AuditOrigin,
+) -> Result<Snapshot, String> {
+    if name.contains('/') || name.contains('\\') {
+        return Err("快照名称无效".to_string());
+    }
+
+    let _switching = workspaces::SWITCH_LOCK.lock().await;
+    let paths = workspaces::active_paths(app)?;
+    workspaces::check_db_dir(&paths)?;
+    adopt_legacy_backups(&paths)?;
+    let dir = backup_dir(&paths);
+    let source = dir.join(name);
+    let snapshot = parse_snapshot(&source).ok_or_else(|| format!("快照 {} 不存在", name))?;
+    verify_snapshot(&source).await?;
+
+    let mut pre_restore = None;
+    if let Some(old) = db_state.take_pool() {
+        match snapshot_locked(&old, &dir, PRE_RESTORE_PREFIX).await {
+            Ok(saved) => pre_restore = Some(saved),
+            Err(e) => {
+                db_state.replace_pool(old);
+                return Err(format!("保存恢复前快照失败: {}", e));
+            }
+        }
+        old.close().await;
+    }
+
+    // 先在临时文件中准备好数据，再替换数据库文件，避免中途失败留下不完整的数据库
+    let temp = paths.db_dir.join(format!("{}.restoring", DB_FILE));
+    let replaced = async {
+        std::fs::copy(&source, &temp).map_err(|e| format!("复制快照失败: {}", e))?;
+        carry_audit_log(&temp, &snapshot, pre_restore.as_ref(), origin).await?;
+        for suffix in ["-wal", "-shm"] {
+            let path = paths.db_dir.join(format!("{}{}", DB_FILE, suffix));
+            if path.exists() {
+                std::fs::remove_file(&path).map_err(|e| format!("删除 {} 失败: {}", path.display(), e))?;
+            }
+        }
+        std::fs::rename(&temp, &paths.db_path).map_err(|e| format!("替换数据库失败: {}", e))
+    }
+    .await;
+    if replaced.is_err() {
+        let _ = std::fs::remove_file(&temp);
+    }
+
+    // 无论替换成功与否都重新打开数据库
+    db_state.reopen(&paths).await?;
+    replaced?;
+
+    let _ = app.emit(DATABASE_RESTORED_EVENT, &snapshot);
+    Ok(snapshot)
+}
+
+/// 启动时备份一次，之后每天备份一次
+async fn run_scheduled(app: &AppHandle, db_state: &DbState, startup: bool) -> Result<(), String> {
+    let settings = ConfigManager::new(app)?.load_settings()?;
+    if !settings.backup_enabled {
+        return Ok(());
+    }
+
+    let due = startup
+        || list_snapshots(app)?
+            .iter()
+            .find(|s| !s.pre_restore)
+            .is_none_or(|latest| Local::now().timestamp() - latest.created_at >= BACKUP_INTERVAL_SECS);
+    if due {
+        create_snapshot(app, db_state).await?;
+    }
+    Ok(())
+}
+
+/// 启动后台自动备份
+pub fn spawn_backup_worker(app: AppHandle, db_state: DbState) {
+    tauri::async_runtime::spawn(async move {
+        let mut startup = true;
+        loop {
+            if let Err(e) = run_scheduled(&app, &db_state, startup).await {
+                eprintln!("自动备份失败: {}", e);
+            }
+            startup = false;
+            tokio::time::sleep(WORKER_INTERVAL).await;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::AuditQuery;
+    use crate::repo::IdeaRepo;
+    use crate::test_support::{temp_pool, TempDir};
+
+    #[tokio::test]
+    async fn restore_keeps_audit_entries_written_after_the_snapshot() {
+        let (pool, dir) = temp_pool().await;
+        let ideas = IdeaRepo::new(&pool);
+        let at = Local.with_ymd_and_hms(2024, 5, 6, 12, 0, 0).unwrap().timestamp();
+        ideas.insert("快照前的想法".to_string(), Vec::new(), at, AuditOrigin::Ui).await.unwrap();
+        let snapshot = snapshot_locked(&pool, &dir.path().join("backups"), SNAPSHOT_PREFIX).await.unwrap();
+
+        let later = ideas.insert("快照后的想法".to_string(), Vec::new(), at + 60, AuditOrigin::Cli).await.unwrap();
+        ideas.delete(later.id, AuditOrigin::Cli).await.unwrap();
+        let pre_restore = snapshot_locked(&pool, &dir.path().join("backups"), PRE_RESTORE_PREFIX).await.unwrap();
+        pool.close().await;
+
+        let restored_path = dir.path().join("restored.db");
+        std::fs::copy(&snapshot.path, &restored_path).unwrap();
+        carry_audit_log(&restored_path, &snapshot, Some(&pre_restore), AuditOrigin::Ui).await.unwrap();
+
+        let restored = database::open_pool(&restored_path).await.unwrap();
+        // 数据回到快照时的状态，审计日志仍然完整
+        assert_eq!(IdeaRepo::new(&restored).list_by_date("2024-05-06").await.unwrap().len(), 1);
+        let mut entries = audit::list_entries(&restored, &AuditQuery::default()).await.unwrap();
+        entries.sort_by_key(|entry| entry.id);
+        let actions: Vec<(AuditEntity, AuditAction)> = entries.iter().map(|e| (e.entity, e.action)).collect();
+        assert_eq!(
+            actions,
+            vec![
+                (AuditEntity::Idea, AuditAction::Insert),
+                (AuditEntity::Idea, AuditAction::Insert),
+                (AuditEntity::Idea, AuditAction::Delete),
+                (AuditEntity::Snapshot, AuditAction::Restore),
+            ]
+        );
+        let restore = entries.last().unwrap();
+        assert_eq!(restore.before, Some(json!({ "pre_restore": pre_restore.name })));
+        assert_eq!(restore.after, Some(json!({ "snapshot": snapshot.name, "carried_audit_entries": 2 })));
+        restored.close().await;
+    }
+
+    #[test]
+    fn legacy_backups_move_into_the_workspace_directory() {
+        let dir = TempDir::new();
+        let paths = WorkspacePaths {
+            data_dir: dir.path().join("workspace"),
+            db_dir: dir.path().join("custom"),
+            custom_db_dir: true,
+            previous_db_dir: None,
+            db_path: dir.path().join("custom").join(DB_FILE),
+            ai_config_path: dir.path().join("workspace").join("ai_config.json"),
+            attachment_dir: dir.path().join("workspace").join("attachments"),
+        };
+        let legacy = paths.db_dir.join("backups");
+        std::fs::create_dir_all(&legacy).unwrap();
+        std::fs::write(legacy.join("data-20240506-090000.db"), b"snapshot").unwrap();
+        std::fs::write(legacy.join("notes.txt"), b"not a snapshot").unwrap();
+
+        adopt_legacy_backups(&paths).unwrap();
+        let snapshots = list_in(&backup_dir(&paths)).unwrap();
+        assert_eq!(snapshots.len(), 1);
+        assert_eq!(snapshots[0].name, "data-20240506-090000.db");
+        assert!(!legacy.join("data-20240506-090000.db").exists());
+        assert!(legacy.join("notes.txt").exists());
+    }
+}
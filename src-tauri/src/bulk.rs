@@ -0,0 +1,498 @@
+use chrono::{Local, NaiveDate, NaiveDateTime, TimeZone};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sqlx::{Pool, Sqlite, SqliteConnection, Transaction};
+
+use crate::models::{AuditOrigin, NewDoneTask};
+use crate::pagination::RecordKind;
+use crate::repo::{IdeaRepo, TaskRepo};
+use crate::validation::{self, ValidationRules};
+
+/// 单次批量操作的最大记录数
+pub const MAX_BULK_ITEMS: usize = 1000;
+
+const DAY_SECS: i64 = 24 * 60 * 60;
+
+/// 批量操作中引用的一条记录
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct RecordRef {
+    pub kind: RecordKind,
+    pub id: i64,
+}
+
+/// 批量导入的一条记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ImportRecord {
+    Idea {
+        content: String,
+        #[serde(default)]
+        attachments: Vec<String>,
+        created_at: i64,
+    },
+    Task(NewDoneTask),
+}
+
+/// 单条记录的处理结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BulkItemStatus {
+    Ok,
+    Failed,
+    RolledBack, // 本条成功，但因其他记录失败而整批回滚
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BulkItemResult {
+    pub index: usize, // 在请求中的位置
+    pub kind: RecordKind,
+    pub id: Option<i64>, // 导入时为新记录的 ID
+    pub status: BulkItemStatus,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BulkResult {
+    pub items: Vec<BulkItemResult>,
+    pub succeeded: usize,
+    pub failed: usize,
+    pub rolled_back: bool, // atomic 模式下有失败项时整批回滚
+}
+
+/// 单条记录成功后需要通知 Webhook 的事件
+type Event = (&'static str, Value);
+
+/// 批量操作的事务
+///
+/// 每条记录在各自的保存点中执行，失败时只撤销该条；`atomic` 为 true 时只要有一条失败就回滚整批。
+struct Batch {
+    tx: Transaction<'static, Sqlite>,
+    atomic: bool,
+    items: Vec<BulkItemResult>,
+    events: Vec<Event>,
+}
+
+impl Batch {
+    async fn begin(pool: &Pool<Sqlite>, atomic: bool, len: usize) -> Result<Self, String> {
+        if len > MAX_BULK_ITEMS {
+            return Err(format!("单次最多处理 {} 条记录", MAX_BULK_ITEMS));
+        }
+        let tx = pool.begin().await.map_err(|e| format!("开启事务失败: {}", e))?;
+        Ok(Self { tx, atomic, items: Vec::with_capacity(len), events: Vec::new() })
+    }
+
+    async fn begin_item(&mut self) -> Result<&mut SqliteConnection, String> {
+        sqlx::query("SAVEPOINT bulk_item")
+            .execute(&mut *self.tx)
+            .await
+            .map_err(|e| format!("创建保存点失败: {}", e))?;
+        Ok(&mut self.tx)
+    }
+
+    async fn end_item(
+        &mut self,
+        index: usize,
+        kind: RecordKind,
+        result: Result<(Option<i64>, Option<Event>), String>,
+    ) -> Result<(), String> {
+        let item = match result {
+            Ok((id, event)) => {
+                self.events.extend(event);
+                BulkItemResult { index, kind, id, status: BulkItemStatus::Ok, error: None }
+            }
+            Err(e) => {
+                sqlx::query("ROLLBACK TO bulk_item")
+                    .execute(&mut *self.tx)
+                    .await
+                    .map_err(|e| format!("回滚保存点失败: {}", e))?;
+                BulkItemResult { index, kind, id: None, status: BulkItemStatus::Failed, error: Some(e) }
+            }
+        };
+        sqlx::query("RELEASE bulk_item")
+            .execute(&mut *self.tx)
+            .await
+            .map_err(|e| format!("释放保存点失败: {}", e))?;
+        self.items.push(item);
+        Ok(())
+    }
+
+    /// 提交或回滚整批，返回结果和需要通知的事件
+    async fn finish(mut self) -> Result<(BulkResult, Vec<Event>), String> {
+        let failed = self.items.iter().filter(|i| i.status == BulkItemStatus::Failed).count();
+        let rolled_back = self.atomic && failed > 0;
+
+        if rolled_back {
+            self.tx.rollback().await.map_err(|e| format!("回滚事务失败: {}", e))?;
+            for item in self.items.iter_mut().filter(|i| i.status == BulkItemStatus::Ok) {
+                item.status = BulkItemStatus::RolledBack;
+            }
+            self.events.clear();
+        } else {
+            self.tx.commit().await.map_err(|e| format!("提交事务失败: {}", e))?;
+        }
+
+        let succeeded = self.items.iter().filter(|i| i.status == BulkItemStatus::Ok).count();
+        Ok((BulkResult { items: self.items, succeeded, failed, rolled_back }, self.events))
+    }
+}
+
+/// 在事务中校验事项时间段，能看到本批次中已写入的事项
+async fn validate_in_tx(
+    conn: &mut SqliteConnection,
+    start_time: i64,
+    end_time: i64,
+    exclude_id: Option<i64>,
+    rules: &ValidationRules,
+) -> Result<(), String> {
+    let existing = TaskRepo::overlapping_with(conn, start_time.min(end_time), end_time.max(start_time)).await?;
+    let report = validation::validate_task(start_time, end_time, &existing, exclude_id, rules);
+    if !report.is_valid() {
+        return Err(format!("事项校验失败: {}", report.error_message()));
+    }
+    Ok(())
+}
+
+fn parse_date(date: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|e| format!("无效的日期 {}: {}", date, e))
+}
+
+/// 时间戳对应的本地时间
+///
+/// 记录的 `date` 列按 UTC 计算，移动时的天数要按本地日期算，才能与 `shift_days` 一致。
+fn local_datetime(timestamp: i64) -> Result<NaiveDateTime, String> {
+    Local
+        .timestamp_opt(timestamp, 0)
+        .single()
+        .map(|t| t.naive_local())
+        .ok_or_else(|| format!("无效的时间戳: {}", timestamp))
+}
+
+/// 按整天平移时间戳，本地时刻保持不变（跨夏令时切换也一样）
+///
+/// 目标日期上该时刻不存在（夏令时跳过的一小时）时，退回按 24 小时平移。
+fn shift_days(timestamp: i64, days: i64) -> Result<i64, String> {
+    let local = local_datetime(timestamp)?;
+    let shifted = local + chrono::Duration::days(days);
+    Ok(Local
+        .from_local_datetime(&shifted)
+        .earliest()
+        .map(|t| t.timestamp())
+        .unwrap_or(timestamp + days * DAY_SECS))
+}
+
+async fn delete_one(
+    conn: &mut SqliteConnection,
+    record: RecordRef,
+    origin: AuditOrigin,
+) -> Result<(Option<i64>, Option<Event>), String> {
+    let event = match record.kind {
+        RecordKind::Idea => {
+            let idea = IdeaRepo::delete_with(conn, record.id, origin)
+                .await?
+                .ok_or_else(|| format!("想法 {} 不存在", record.id))?;
+            ("idea.deleted", json!(idea))
+        }
+        RecordKind::Task => {
+            let task = TaskRepo::delete_with(conn, record.id, origin)
+                .await?
+                .ok_or_else(|| format!("事项 {} 不存在", record.id))?;
+            ("task.deleted", json!(task))
+        }
+    };
+    Ok((Some(record.id), Some(event)))
+}
+
+/// 批量删除想法和已完成事项
+pub async fn delete_records(
+    pool: &Pool<Sqlite>,
+    records: &[RecordRef],
+    atomic: bool,
+    origin: AuditOrigin,
+) -> Result<(BulkResult, Vec<Event>), String> {
+    let mut batch = Batch::begin(pool, atomic, records.len()).await?;
+    for (index, record) in records.iter().enumerate() {
+        let conn = batch.begin_item().await?;
+        let result = delete_one(conn, *record, origin).await;
+        batch.end_item(index, record.kind, result).await?;
+    }
+    batch.finish().await
+}
+
+async fn move_one(
+    conn: &mut SqliteConnection,
+    record: RecordRef,
+    new_date: NaiveDate,
+    rules: &ValidationRules,
+    origin: AuditOrigin,
+) -> Result<(Option<i64>, Option<Event>), String> {
+    let event = match record.kind {
+        RecordKind::Idea => {
+            let idea = IdeaRepo::get_with(conn, record.id)
+                .await?
+                .ok_or_else(|| format!("想法 {} 不存在", record.id))?;
+            let days = (new_date - local_datetime(idea.created_at)?.date()).num_days();
+            if days == 0 {
+                return Ok((Some(record.id), None));
+            }
+            let created_at = shift_days(idea.created_at, days)?;
+            let idea = IdeaRepo::reschedule_with(conn, record.id, created_at, origin)
+                .await?
+                .ok_or_else(|| format!("想法 {} 不存在", record.id))?;
+            ("idea.updated", json!(idea))
+        }
+        RecordKind::Task => {
+            let task = TaskRepo::get_with(conn, record.id)
+                .await?
+                .ok_or_else(|| format!("事项 {} 不存在", record.id))?;
+            let days = (new_date - local_datetime(task.start_time)?.date()).num_days();
+            if days == 0 {
+                return Ok((Some(record.id), None));
+            }
+            let (start_time, end_time) = (shift_days(task.start_time, days)?, shift_days(task.end_time, days)?);
+            validate_in_tx(conn, start_time, end_time, Some(record.id), rules).await?;
+            let task = TaskRepo::reschedule_with(conn, record.id, start_time, end_time, origin)
+                .await?
+                .ok_or_else(|| format!("事项 {} 不存在", record.id))?;
+            ("task.updated", json!(task))
+        }
+    };
+    Ok((Some(record.id), Some(event)))
+}
+
+/// 将记录移动到另一天，按本地日期整天平移、时刻保持不变
+pub async fn move_records(
+    pool: &Pool<Sqlite>,
+    records: &[RecordRef],
+    new_date: &str,
+    rules: &ValidationRules,
+    atomic: bool,
+    origin: AuditOrigin,
+) -> Result<(BulkResult, Vec<Event>), String> {
+    let new_date = parse_date(new_date)?;
+    let mut batch = Batch::begin(pool, atomic, records.len()).await?;
+    for (index, record) in records.iter().enumerate() {
+        let conn = batch.begin_item().await?;
+        let result = move_one(conn, *record, new_date, rules, origin).await;
+        batch.end_item(index, record.kind, result).await?;
+    }
+    batch.finish().await
+}
+
+async fn tag_one(
+    conn: &mut SqliteConnection,
+    id: i64,
+    add: &[String],
+    remove: &[String],
+    origin: AuditOrigin,
+) -> Result<(Option<i64>, Option<Event>), String> {
+    let task = TaskRepo::get_with(conn, id)
+        .await?
+        .ok_or_else(|| format!("事项 {} 不存在", id))?;
+
+    let mut tags: Vec<String> = task.tags.iter().filter(|t| !remove.contains(t)).cloned().collect();
+    for tag in add {
+        if !tags.contains(tag) {
+            tags.push(tag.clone());
+        }
+    }
+    if tags == task.tags {
+        return Ok((Some(id), None));
+    }
+
+    let task = TaskRepo::set_tags_with(conn, id, &tags, origin)
+        .await?
+        .ok_or_else(|| format!("事项 {} 不存在", id))?;
+    Ok((Some(id), Some(("task.updated", json!(task)))))
+}
+
+/// 为一批已完成事项添加或移除标签，标签需由调用方事先规范化
+pub async fn bulk_tag(
+    pool: &Pool<Sqlite>,
+    task_ids: &[i64],
+    add: &[String],
+    remove: &[String],
+    atomic: bool,
+    origin: AuditOrigin,
+) -> Result<(BulkResult, Vec<Event>), String> {
+    let mut batch = Batch::begin(pool, atomic, task_ids.len()).await?;
+    for (index, id) in task_ids.iter().enumerate() {
+        let conn = batch.begin_item().await?;
+        let result = tag_one(conn, *id, add, remove, origin).await;
+        batch.end_item(index, RecordKind::Task, result).await?;
+    }
+    batch.finish().await
+}
+
+async fn import_one(
+    conn: &mut SqliteConnection,
+    record: ImportRecord,
+    rules: &ValidationRules,
+    origin: AuditOrigin,
+) -> Result<(Option<i64>, Option<Event>), String> {
+    match record {
+        ImportRecord::Idea { content, attachments, created_at } => {
+            let content = content.trim().to_string();
+            if content.is_empty() {
+                return Err("想法内容不能为空".to_string());
+            }
+            let idea = IdeaRepo::insert_with(conn, content, attachments, created_at, origin).await?;
+            Ok((Some(idea.id), Some(("idea.created", json!(idea)))))
+        }
+        ImportRecord::Task(task) => {
+            let content = task.content.trim().to_string();
+            if content.is_empty() {
+                return Err("事项内容不能为空".to_string());
+            }
+            let task = NewDoneTask { content, tags: validation::normalize_tags(task.tags), ..task };
+            validate_in_tx(conn, task.start_time, task.end_time, None, rules).await?;
+            let task = TaskRepo::insert_with(conn, task, origin).await?;
+            Ok((Some(task.id), Some(("task.created", json!(task)))))
+        }
+    }
+}
+
+/// 批量导入想法和已完成事项
+///
+/// 内容去除首尾空白且不能为空，事项标签规范化后按与单条新建相同的规则校验。
+pub async fn bulk_import(
+    pool: &Pool<Sqlite>,
+    records: Vec<ImportRecord>,
+    rules: &ValidationRules,
+    atomic: bool,
+    origin: AuditOrigin,
+) -> Result<(BulkResult, Vec<Event>), String> {
+    let mut batch = Batch::begin(pool, atomic, records.len()).await?;
+    for (index, record) in records.into_iter().enumerate() {
+        let kind = match record {
+            ImportRecord::Idea { .. } => RecordKind::Idea,
+            ImportRecord::Task(_) => RecordKind::Task,
+        };
+        let conn = batch.begin_item().await?;
+        let result = import_one(conn, record, rules, origin).await;
+        batch.end_item(index, kind, result).await?;
+    }
+    batch.finish().await
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Timelike;
+
+    use super::*;
+    use crate::test_support::temp_pool;
+
+    fn rules() -> ValidationRules {
+        ValidationRules { now: Local::now().timestamp(), max_duration_secs: 24 * 60 * 60 }
+    }
+
+    fn local_time(timestamp: i64) -> (String, u32, u32) {
+        let time = Local.timestamp_opt(timestamp, 0).unwrap();
+        (time.format("%Y-%m-%d").to_string(), time.hour(), time.minute())
+    }
+
+    #[tokio::test]
+    async fn move_keeps_the_local_clock_time() {
+        let (pool, _dir) = temp_pool().await;
+        let at = |hour: u32| Local.with_ymd_and_hms(2024, 5, 6, hour, 30, 0).unwrap().timestamp();
+        let task = TaskRepo::new(&pool)
+            .insert(
+                NewDoneTask {
+                    content: "评审".to_string(),
+                    start_time: at(9),
+                    end_time: at(10),
+                    attachments: Vec::new(),
+                    created_at: at(10),
+                    tags: Vec::new(),
+                },
+                AuditOrigin::Ui,
+            )
+            .await
+            .unwrap();
+
+        // 跨过夏令时切换（如欧洲的 2024-03-31）时也应保持时刻，可用 TZ=Europe/Berlin 验证
+        let records = [RecordRef { kind: RecordKind::Task, id: task.id }];
+        let (result, events) = move_records(&pool, &records, "2024-03-30", &rules(), true, AuditOrigin::Ui).await.unwrap();
+        assert_eq!(result.succeeded, 1);
+        assert_eq!(events.len(), 1);
+
+        let moved = TaskRepo::new(&pool).get(task.id).await.unwrap().unwrap();
+        assert_eq!(local_time(moved.start_time), ("2024-03-30".to_string(), 9, 30));
+        assert_eq!(local_time(moved.end_time), ("2024-03-30".to_string(), 10, 30));
+    }
+
+    #[tokio::test]
+    async fn move_counts_days_by_the_local_date() {
+        // 凌晨的记录在东八区等时区的 UTC 日期是前一天，可用 TZ=Asia/Shanghai 或 TZ=Australia/Sydney 验证
+        let (pool, _dir) = temp_pool().await;
+        let at = |hour: u32, minute: u32| Local.with_ymd_and_hms(2024, 5, 6, hour, minute, 0).unwrap().timestamp();
+        let task = TaskRepo::new(&pool)
+            .insert(
+                NewDoneTask {
+                    content: "上线".to_string(),
+                    start_time: at(0, 30),
+                    end_time: at(1, 30),
+                    attachments: Vec::new(),
+                    created_at: at(1, 30),
+                    tags: Vec::new(),
+                },
+                AuditOrigin::Ui,
+            )
+            .await
+            .unwrap();
+        let idea = IdeaRepo::new(&pool).insert("复盘".to_string(), Vec::new(), at(7, 45), AuditOrigin::Ui).await.unwrap();
+
+        let records = [
+            RecordRef { kind: RecordKind::Task, id: task.id },
+            RecordRef { kind: RecordKind::Idea, id: idea.id },
+        ];
+        let (result, _) = move_records(&pool, &records, "2024-05-08", &rules(), true, AuditOrigin::Ui).await.unwrap();
+        assert_eq!(result.succeeded, 2);
+
+        let moved = TaskRepo::new(&pool).get(task.id).await.unwrap().unwrap();
+        assert_eq!(local_time(moved.start_time), ("2024-05-08".to_string(), 0, 30));
+        assert_eq!(local_time(moved.end_time), ("2024-05-08".to_string(), 1, 30));
+        let moved = IdeaRepo::new(&pool).get(idea.id).await.unwrap().unwrap();
+        assert_eq!(local_time(moved.created_at), ("2024-05-08".to_string(), 7, 45));
+    }
+
+    #[tokio::test]
+    async fn import_normalizes_like_single_records() {
+        let (pool, _dir) = temp_pool().await;
+        let at = |hour: u32| Local.with_ymd_and_hms(2024, 5, 6, hour, 0, 0).unwrap().timestamp();
+        let records = vec![
+            ImportRecord::Idea { content: "  想法  ".to_string(), attachments: Vec::new(), created_at: at(8) },
+            ImportRecord::Idea { content: "   ".to_string(), attachments: Vec::new(), created_at: at(8) },
+            ImportRecord::Task(NewDoneTask {
+                content: "\t写文档\n".to_string(),
+                start_time: at(9),
+                end_time: at(10),
+                attachments: Vec::new(),
+                created_at: at(10),
+                tags: vec![" #文档 ".to_string(), "文档".to_string(), "#".to_string()],
+            }),
+            ImportRecord::Task(NewDoneTask {
+                content: String::new(),
+                start_time: at(11),
+                end_time: at(12),
+                attachments: Vec::new(),
+                created_at: at(12),
+                tags: Vec::new(),
+            }),
+        ];
+
+        let (result, _) = bulk_import(&pool, records, &rules(), false, AuditOrigin::Ui).await.unwrap();
+        let statuses: Vec<BulkItemStatus> = result.items.iter().map(|i| i.status).collect();
+        assert_eq!(
+            statuses,
+            vec![BulkItemStatus::Ok, BulkItemStatus::Failed, BulkItemStatus::Ok, BulkItemStatus::Failed]
+        );
+        assert_eq!(result.items[1].error.as_deref(), Some("想法内容不能为空"));
+        assert_eq!(result.items[3].error.as_deref(), Some("事项内容不能为空"));
+
+        let idea = IdeaRepo::new(&pool).get(result.items[0].id.unwrap()).await.unwrap().unwrap();
+        assert_eq!(idea.content, "想法");
+        let task = TaskRepo::new(&pool).get(result.items[2].id.unwrap()).await.unwrap().unwrap();
+        assert_eq!(task.content, "写文档");
+        assert_eq!(task.tags, vec!["文档"]);
+    }
+}
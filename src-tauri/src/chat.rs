@@ -0,0 +1,305 @@
+use sqlx::{sqlite::SqliteRow, Pool, Row, Sqlite};
+
+use crate::models::{ChatMessage, ChatSearchHit, ChatSession, NewChatMessage, TokenUsage};
+
+/// 允许的消息角色
+const ROLES: [&str; 4] = ["system", "user", "assistant", "tool"];
+
+/// 搜索结果片段中匹配位置前后保留的字符数
+const SNIPPET_CONTEXT_CHARS: usize = 40;
+
+const SESSION_COLUMNS: &str = r#"
+    s.id, s.title, s.range_start, s.range_end, s.created_at, s.updated_at,
+    (SELECT COUNT(*) FROM chat_messages m WHERE m.session_id = s.id) AS message_count
+"#;
+
+fn session_from_row(row: &SqliteRow) -> Result<ChatSession, String> {
+    let read_err = |e: sqlx::Error| format!("读取会话失败: {}", e);
+    Ok(ChatSession {
+        id: row.try_get("id").map_err(read_err)?,
+        title: row.try_get("title").map_err(read_err)?,
+        range_start: row.try_get("range_start").map_err(read_err)?,
+        range_end: row.try_get("range_end").map_err(read_err)?,
+        message_count: row.try_get("message_count").map_err(read_err)?,
+        created_at: row.try_get("created_at").map_err(read_err)?,
+        updated_at: row.try_get("updated_at").map_err(read_err)?,
+    })
+}
+
+fn message_from_row(row: &SqliteRow) -> Result<ChatMessage, String> {
+    let read_err = |e: sqlx::Error| format!("读取消息失败: {}", e);
+
+    let tool_calls: Option<String> = row.try_get("tool_calls").map_err(read_err)?;
+    let prompt_tokens: Option<i64> = row.try_get("prompt_tokens").map_err(read_err)?;
+    let completion_tokens: Option<i64> = row.try_get("completion_tokens").map_err(read_err)?;
+    let total_tokens: Option<i64> = row.try_get("total_tokens").map_err(read_err)?;
+
+    let usage = match (prompt_tokens, completion_tokens, total_tokens) {
+        (None, None, None) => None,
+        (p, c, t) => Some(TokenUsage {
+            prompt_tokens: p.unwrap_or(0),
+            completion_tokens: c.unwrap_or(0),
+            total_tokens: t.unwrap_or(0),
+        }),
+    };
+
+    Ok(ChatMessage {
+        id: row.try_get("id").map_err(read_err)?,
+        session_id: row.try_get("session_id").map_err(read_err)?,
+        role: row.try_get("role").map_err(read_err)?,
+        content: row.try_get("content").map_err(read_err)?,
+        tool_calls: tool_calls.and_then(|json| serde_json::from_str(&json).ok()),
+        tool_call_id: row.try_get("tool_call_id").map_err(read_err)?,
+        model: row.try_get("model").map_err(read_err)?,
+        usage,
+        created_at: row.try_get("created_at").map_err(read_err)?,
+    })
+}
+
+/// 读取单个会话
+pub async fn get_session(pool: &Pool<Sqlite>, id: i64) -> Result<ChatSession, String> {
+    let row = sqlx::query(&format!("SELECT {} FROM chat_sessions s WHERE s.id = ?", SESSION_COLUMNS))
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| format!("查询会话失败: {}", e))?
+        .ok_or_else(|| format!("会话 {} 不存在", id))?;
+    session_from_row(&row)
+}
+
+/// 创建会话
+pub async fn create_session(
+    pool: &Pool<Sqlite>,
+    title: &str,
+    range_start: Option<String>,
+    range_end: Option<String>,
+) -> Result<ChatSession, String> {
+    let now = chrono::Local::now().timestamp();
+    let title = if title.trim().is_empty() { "新对话" } else { title.trim() };
+
+    let id = sqlx::query(
+        "INSERT INTO chat_sessions (title, range_start, range_end, created_at, updated_at) VALUES (?, ?, ?, ?, ?)",
+    )
+    .bind(title)
+    .bind(range_start)
+    .bind(range_end)
+    .bind(now)
+    .bind(now)
+    .execute(pool)
+    .await
+    .map_err(|e| format!("创建会话失败: {}", e))?
+    .last_insert_rowid();
+
+    get_session(pool, id).await
+}
+
+/// 按最近更新时间列出会话
+pub async fn list_sessions(pool: &Pool<Sqlite>) -> Result<Vec<ChatSession>, String> {
+    let rows = sqlx::query(&format!(
+        "SELECT {} FROM chat_sessions s ORDER BY s.updated_at DESC, s.id DESC",
+        SESSION_COLUMNS
+    ))
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("查询会话失败: {}", e))?;
+
+    rows.iter().map(session_from_row).collect()
+}
+
+/// 重命名会话
+pub async fn rename_session(pool: &Pool<Sqlite>, id: i64, title: &str) -> Result<(), String> {
+    let title = title.trim();
+    if title.is_empty() {
+        return Err("会话标题不能为空".to_string());
+    }
+
+    let result = sqlx::query("UPDATE chat_sessions SET title = ?, updated_at = ? WHERE id = ?")
+        .bind(title)
+        .bind(chrono::Local::now().timestamp())
+        .bind(id)
+        .execute(pool)
+        .await
+        .map_err(|e| format!("重命名会话失败: {}", e))?;
+
+    if result.rows_affected() == 0 {
+        return Err(format!("会话 {} 不存在", id));
+    }
+    Ok(())
+}
+
+/// 删除会话及其全部消息
+pub async fn delete_session(pool: &Pool<Sqlite>, id: i64) -> Result<(), String> {
+    let mut tx = pool.begin().await.map_err(|e| format!("开启事务失败: {}", e))?;
+
+    sqlx::query("DELETE FROM chat_messages WHERE session_id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("删除消息失败: {}", e))?;
+
+    sqlx::query("DELETE FROM chat_sessions WHERE id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("删除会话失败: {}", e))?;
+
+    tx.commit().await.map_err(|e| format!("提交事务失败: {}", e))?;
+    Ok(())
+}
+
+/// 向会话追加一条消息
+pub async fn append_message(
+    pool: &Pool<Sqlite>,
+    session_id: i64,
+    message: NewChatMessage,
+) -> Result<ChatMessage, String> {
+    if !ROLES.contains(&message.role.as_str()) {
+        return Err(format!("无效的消息角色: {}", message.role));
+    }
+
+    let now = chrono::Local::now().timestamp();
+    let tool_calls_json = message
+        .tool_calls
+        .as_ref()
+        .map(|calls| serde_json::to_string(calls).unwrap_or_else(|_| "[]".to_string()));
+    let usage = message.usage.as_ref();
+
+    let mut tx = pool.begin().await.map_err(|e| format!("开启事务失败: {}", e))?;
+
+    let touched = sqlx::query("UPDATE chat_sessions SET updated_at = ? WHERE id = ?")
+        .bind(now)
+        .bind(session_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("更新会话失败: {}", e))?;
+    if touched.rows_affected() == 0 {
+        return Err(format!("会话 {} 不存在", session_id));
+    }
+
+    let id = sqlx::query(
+        r#"
+        INSERT INTO chat_messages (
+            session_id, role, content, tool_calls, tool_call_id, model,
+            prompt_tokens, completion_tokens, total_tokens, created_at
+        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        "#,
+    )
+    .bind(session_id)
+    .bind(&message.role)
+    .bind(&message.content)
+    .bind(tool_calls_json)
+    .bind(&message.tool_call_id)
+    .bind(&message.model)
+    .bind(usage.map(|u| u.prompt_tokens))
+    .bind(usage.map(|u| u.completion_tokens))
+    .bind(usage.map(|u| u.total_tokens))
+    .bind(now)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("保存消息失败: {}", e))?
+    .last_insert_rowid();
+
+    tx.commit().await.map_err(|e| format!("提交事务失败: {}", e))?;
+
+    Ok(ChatMessage {
+        id,
+        session_id,
+        role: message.role,
+        content: message.content,
+        tool_calls: message.tool_calls,
+        tool_call_id: message.tool_call_id,
+        model: message.model,
+        usage: message.usage,
+        created_at: now,
+    })
+}
+
+/// 按时间顺序读取会话的全部消息
+pub async fn get_messages(pool: &Pool<Sqlite>, session_id: i64) -> Result<Vec<ChatMessage>, String> {
+    let rows = sqlx::query("SELECT * FROM chat_messages WHERE session_id = ? ORDER BY id ASC")
+        .bind(session_id)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| format!("查询消息失败: {}", e))?;
+
+    rows.iter().map(message_from_row).collect()
+}
+
+/// 转义 LIKE 模式中的通配符
+fn escape_like(keyword: &str) -> String {
+    keyword
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+/// 截取关键字附近的文本作为搜索结果片段
+fn make_snippet(content: &str, keyword: &str) -> String {
+    let lower_content = content.to_lowercase();
+    let lower_keyword = keyword.to_lowercase();
+    let chars: Vec<char> = content.chars().collect();
+
+    // 小写化可能改变字节长度，按字符位置定位
+    let match_char_index = lower_content
+        .find(&lower_keyword)
+        .map(|byte_index| lower_content[..byte_index].chars().count())
+        .unwrap_or(0)
+        .min(chars.len());
+
+    let start = match_char_index.saturating_sub(SNIPPET_CONTEXT_CHARS);
+    let end = (match_char_index + keyword.chars().count() + SNIPPET_CONTEXT_CHARS).min(chars.len());
+
+    let mut snippet: String = chars[start..end].iter().collect();
+    if start > 0 {
+        snippet = format!("…{}", snippet);
+    }
+    if end < chars.len() {
+        snippet.push('…');
+    }
+    snippet
+}
+
+/// 在所有会话的消息与标题中搜索关键字
+pub async fn search_messages(
+    pool: &Pool<Sqlite>,
+    keyword: &str,
+    limit: i64,
+) -> Result<Vec<ChatSearchHit>, String> {
+    let keyword = keyword.trim();
+    if keyword.is_empty() {
+        return Ok(Vec::new());
+    }
+    let pattern = format!("%{}%", escape_like(keyword));
+
+    let rows = sqlx::query(
+        r#"
+        SELECT m.id AS message_id, m.session_id, m.role, m.content, m.created_at, s.title
+        FROM chat_messages m
+        JOIN chat_sessions s ON s.id = m.session_id
+        WHERE m.role IN ('user', 'assistant')
+          AND (m.content LIKE ?1 ESCAPE '\' OR s.title LIKE ?1 ESCAPE '\')
+        ORDER BY m.created_at DESC, m.id DESC
+        LIMIT ?2
+        "#,
+    )
+    .bind(&pattern)
+    .bind(limit.clamp(1, 500))
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("搜索对话失败: {}", e))?;
+
+    let read_err = |e: sqlx::Error| format!("读取搜索结果失败: {}", e);
+    rows.iter()
+        .map(|row| {
+            let content: String = row.try_get("content").map_err(read_err)?;
+            Ok(ChatSearchHit {
+                session_id: row.try_get("session_id").map_err(read_err)?,
+                session_title: row.try_get("title").map_err(read_err)?,
+                message_id: row.try_get("message_id").map_err(read_err)?,
+                role: row.try_get("role").map_err(read_err)?,
+                snippet: make_snippet(&content, keyword),
+                created_at: row.try_get("created_at").map_err(read_err)?,
+            })
+        })
+        .collect()
+}
@@ -1,85 +1,274 @@
-use tauri::State;
-use sqlx::{Pool, Sqlite, Row, Column};
+use tauri::{Emitter, Manager, State};
+use sqlx::{Pool, Sqlite};
 
-use crate::models::{TodayRecords, Idea, DoneTask, Prompt, ApiConfig};
-use crate::database::DbState;
+use crate::models::{TodayRecords, DoneTask, NewDoneTask, Prompt, ApiConfig, AppSettings, ProviderKind, LocalOptions, LocalModel, ChatSession, ChatMessage, NewChatMessage, ChatSearchHit, Report, PublishPlatform, PublishTarget, ReportPublication, SmtpConfig, ReportEmail, Webhook, NewWebhook, WebhookDelivery, PlannedTask, NewPlannedTask, PlanStatus, RecurringTemplate, NewRecurringTemplate, RecurringOccurrence, OccurrenceStatus, PromoteTarget, IdeaPromotion, IdeaLink, Workspace, Snapshot, DiagnosticsReport, DbStatus, AuditEntry, AuditOrigin};
+use crate::database::{self, DbState};
 use crate::config::ConfigManager;
+use crate::validation::{self, normalize_tags, DayConsistencyReport, ValidationReport, ValidationRules};
+use crate::stats::{self, GroupBy, TimeStats};
+use crate::pagination::{self, PageQuery, RecordKind, RecordPage};
+use crate::chat;
+use crate::reports;
+use crate::publish;
+use crate::email;
+use crate::webhooks;
+use crate::planning;
+use crate::recurring;
+use crate::idea_links;
+use crate::workspaces;
+use crate::relocation;
+use crate::backups;
+use crate::diagnostics;
+use crate::audit::{self, AuditQuery, ExportFormat};
+use crate::bulk::{self, BulkResult, ImportRecord, RecordRef};
+use crate::repo::{IdeaRepo, PromptRepo, SortDirection, TaskRepo};
+use crate::ai::agent::{self, AgentEvent, AgentOptions, AgentOutcome, StreamDeltaEvent};
+use crate::ai::cache::{self as ai_cache, CacheCounters, CacheStats, ResponseCache};
+use crate::ai::client::{AiClient, Message};
+use crate::ai::error::{AiError, AiErrorKind};
+use crate::ai::mock::MockOptions;
+use crate::ai::ollama;
+use crate::ai::retry::RetryPolicy;
+use crate::ai::context::{self, BuiltContext, TokenEstimator};
+use crate::ai::tools::{self, ToolContext, ToolRegistry};
 
 // 辅助函数：获取数据库连接池
 async fn get_pool(state: &State<'_, DbState>) -> Result<Pool<Sqlite>, String> {
     state.get_pool().await
 }
 
-// 辅助函数：执行查询并返回结果
-async fn execute_query(
+// 辅助函数：根据应用设置构造校验规则
+fn validation_rules(app: &tauri::AppHandle) -> Result<ValidationRules, String> {
+    let settings = ConfigManager::new(app)?.load_settings()?;
+    Ok(ValidationRules {
+        now: chrono::Local::now().timestamp(),
+        max_duration_secs: settings.max_task_duration_minutes * 60,
+    })
+}
+
+// 辅助函数：校验待保存的已完成事项
+async fn validate_task_range(
+    app: &tauri::AppHandle,
     pool: &Pool<Sqlite>,
-    query: &str,
-    params: &[&str],
-) -> Result<Vec<serde_json::Value>, String> {
-    let mut query_builder = sqlx::query(query);
+    start_time: i64,
+    end_time: i64,
+    exclude_id: Option<i64>,
+) -> Result<ValidationReport, String> {
+    let rules = validation_rules(app)?;
 
-    for param in params {
-        query_builder = query_builder.bind(param);
-    }
+    // 只取可能与之重叠的事项
+    let existing = TaskRepo::new(pool)
+        .overlapping(start_time.min(end_time), end_time.max(start_time))
+        .await?;
 
-    let rows = query_builder
-        .fetch_all(pool)
-        .await
-        .map_err(|e| format!("查询失败: {}", e))?;
-
-    let result: Vec<serde_json::Value> = rows.iter().map(|row| {
-        let mut map = serde_json::Map::new();
-        for (i, column) in row.columns().iter().enumerate() {
-            let name = column.name();
-            let value: serde_json::Value = if let Ok(v) = row.try_get::<i64, _>(i) {
-                serde_json::Value::from(v)
-            } else if let Ok(v) = row.try_get::<f64, _>(i) {
-                serde_json::Value::from(v)
-            } else if let Ok(v) = row.try_get::<bool, _>(i) {
-                serde_json::Value::from(v)
-            } else if let Ok(v) = row.try_get::<String, _>(i) {
-                serde_json::Value::from(v)
-            } else {
-                serde_json::Value::Null
-            };
-            map.insert(name.to_string(), value);
-        }
-        serde_json::Value::Object(map)
-    }).collect();
+    Ok(validation::validate_task(start_time, end_time, &existing, exclude_id, &rules))
+}
 
-    Ok(result)
+// ========== 应用设置命令 (JSON 文件存储) ==========
+
+#[tauri::command]
+pub async fn get_app_settings(
+    app: tauri::AppHandle,
+) -> Result<AppSettings, String> {
+    ConfigManager::new(&app)?.load_settings()
 }
 
-// 辅助函数：执行写操作
-async fn execute_write(
-    pool: &Pool<Sqlite>,
-    query: &str,
-    params: &[&str],
+#[tauri::command]
+pub async fn save_app_settings(
+    app: tauri::AppHandle,
+    settings: AppSettings,
 ) -> Result<(), String> {
-    let mut query_builder = sqlx::query(query);
+    ConfigManager::new(&app)?.save_settings(&settings)
+}
+
+// ========== 工作区命令 ==========
+
+#[tauri::command]
+pub async fn list_workspaces(
+    app: tauri::AppHandle,
+) -> Result<Vec<Workspace>, String> {
+    workspaces::list_workspaces(&app)
+}
+
+#[tauri::command]
+pub async fn create_workspace(
+    app: tauri::AppHandle,
+    name: String,
+) -> Result<Workspace, String> {
+    workspaces::create_workspace(&app, &name)
+}
+
+#[tauri::command]
+pub async fn rename_workspace(
+    app: tauri::AppHandle,
+    id: String,
+    name: String,
+) -> Result<Workspace, String> {
+    workspaces::rename_workspace(&app, &id, &name)
+}
+
+#[tauri::command]
+pub async fn switch_workspace(
+    app: tauri::AppHandle,
+    state: State<'_, DbState>,
+    id: String,
+) -> Result<Workspace, String> {
+    let workspace = workspaces::switch_workspace(&app, &state, &id).await?;
 
-    for param in params {
-        query_builder = query_builder.bind(param);
+    // 新工作区可能有尚未顺延的计划
+    let pool = get_pool(&state).await?;
+    if let Err(e) = planning::auto_carry_over(&app, &pool).await {
+        eprintln!("顺延计划失败: {}", e);
     }
+    Ok(workspace)
+}
 
-    query_builder
-        .execute(pool)
-        .await
-        .map_err(|e| format!("写入失败: {}", e))?;
-    Ok(())
+#[tauri::command]
+pub async fn delete_workspace(
+    app: tauri::AppHandle,
+    id: String,
+) -> Result<(), String> {
+    workspaces::delete_workspace(&app, &id).await
+}
+
+/// 将当前工作区的数据库迁移到新目录，旧文件保留到确认为止
+#[tauri::command(rename_all = "snake_case")]
+pub async fn relocate_database(
+    app: tauri::AppHandle,
+    state: State<'_, DbState>,
+    new_dir: String,
+) -> Result<Workspace, String> {
+    relocation::relocate_database(&app, &state, &new_dir).await
+}
+
+/// 确认迁移成功并删除旧位置的数据库文件
+#[tauri::command]
+pub async fn confirm_relocation(
+    app: tauri::AppHandle,
+) -> Result<Workspace, String> {
+    relocation::confirm_relocation(&app).await
+}
+
+/// 当前工作区的附件目录，前端保存附件时使用
+#[tauri::command]
+pub async fn get_attachment_dir(
+    app: tauri::AppHandle,
+) -> Result<String, String> {
+    Ok(workspaces::attachment_dir(&app)?.to_string_lossy().to_string())
+}
+
+// ========== 数据库状态命令 ==========
+
+#[tauri::command]
+pub async fn get_db_status(
+    state: State<'_, DbState>,
+) -> Result<DbStatus, String> {
+    Ok(state.status())
+}
+
+#[tauri::command]
+pub async fn retry_db_init(
+    app: tauri::AppHandle,
+    state: State<'_, DbState>,
+) -> Result<DbStatus, String> {
+    let status = database::retry_init(&app, &state).await?;
+
+    // 与启动时一样，初始化成功后顺延过期的计划
+    let pool = get_pool(&state).await?;
+    if let Err(e) = planning::auto_carry_over(&app, &pool).await {
+        eprintln!("顺延计划失败: {}", e);
+    }
+    Ok(status)
+}
+
+// ========== 数据库备份命令 ==========
+
+#[tauri::command]
+pub async fn create_snapshot(
+    app: tauri::AppHandle,
+    state: State<'_, DbState>,
+) -> Result<Snapshot, String> {
+    backups::create_snapshot(&app, &state).await
+}
+
+#[tauri::command]
+pub async fn list_snapshots(
+    app: tauri::AppHandle,
+) -> Result<Vec<Snapshot>, String> {
+    backups::list_snapshots(&app)
+}
+
+#[tauri::command]
+pub async fn restore_snapshot(
+    app: tauri::AppHandle,
+    state: State<'_, DbState>,
+    name: String,
+) -> Result<Snapshot, String> {
+    backups::restore_snapshot(&app, &state, &name, AuditOrigin::Ui).await
+}
+
+// ========== 数据库诊断命令 ==========
+
+#[tauri::command]
+pub async fn run_diagnostics(
+    app: tauri::AppHandle,
+    state: State<'_, DbState>,
+    repair: Option<bool>,
+) -> Result<DiagnosticsReport, String> {
+    diagnostics::run_diagnostics(&app, &state, repair.unwrap_or(false), AuditOrigin::Ui).await
+}
+
+// ========== 审计日志命令 ==========
+
+#[tauri::command]
+pub async fn list_audit_log(
+    state: State<'_, DbState>,
+    query: Option<AuditQuery>,
+) -> Result<Vec<AuditEntry>, String> {
+    let pool = get_pool(&state).await?;
+    audit::list_entries(&pool, &query.unwrap_or_default()).await
+}
+
+#[tauri::command]
+pub async fn export_audit_log(
+    state: State<'_, DbState>,
+    query: Option<AuditQuery>,
+    format: Option<ExportFormat>,
+) -> Result<String, String> {
+    let pool = get_pool(&state).await?;
+    audit::export_entries(&pool, &query.unwrap_or_default(), format.unwrap_or_default()).await
 }
 
 // ========== AI 配置命令 (JSON 文件存储) ==========
 
 #[tauri::command(rename_all = "snake_case")]
+#[allow(clippy::too_many_arguments)]
 pub async fn save_api_config(
     app: tauri::AppHandle,
     api_key: String,
     api_url: String,
     model: String,
+    context_budget: Option<usize>,
+    provider: Option<ProviderKind>,
+    mock: Option<MockOptions>,
+    local: Option<LocalOptions>,
 ) -> Result<(), String> {
     let config_manager = ConfigManager::new(&app)?;
-    let config = ApiConfig { api_key, api_url, model };
+    let provider = provider.unwrap_or_default();
+    // 未填写地址时使用默认地址
+    let api_url = match provider.default_url() {
+        Some(default_url) if api_url.trim().is_empty() => default_url.to_string(),
+        _ => api_url,
+    };
+    let config = ApiConfig {
+        api_key,
+        api_url,
+        model,
+        context_budget,
+        provider,
+        mock,
+        local,
+    };
     config_manager.save_config(&config)
 }
 
@@ -91,6 +280,21 @@ pub async fn get_api_config(
     config_manager.load_config()
 }
 
+#[tauri::command(rename_all = "snake_case")]
+pub async fn list_local_models(
+    app: tauri::AppHandle,
+    provider: ProviderKind,
+    api_url: Option<String>,
+) -> Result<Vec<LocalModel>, AiError> {
+    let settings = ConfigManager::new(&app)?.load_settings()?;
+    let api_url = api_url
+        .filter(|url| !url.trim().is_empty())
+        .or_else(|| provider.default_url().map(str::to_string))
+        .ok_or_else(|| AiError::new(AiErrorKind::NotConfigured, "请填写模型服务地址"))?;
+    let timeout = std::time::Duration::from_secs(settings.ai_connect_timeout_secs.max(1));
+    ollama::list_models(provider, &api_url, timeout).await
+}
+
 // ========== 随手记命令 (使用时间戳) ==========
 
 #[tauri::command(rename_all = "snake_case")]
@@ -103,79 +307,42 @@ pub async fn add_idea(
     println!("add_idea called with content: {}, attachments: {:?}, created_at: {}", content, attachments, created_at);
     let pool = get_pool(&state).await?;
 
-    let init_query = r#"
-        CREATE TABLE IF NOT EXISTS ideas (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            content TEXT NOT NULL,
-            attachments TEXT NOT NULL,
-            created_at INTEGER NOT NULL,
-            date TEXT NOT NULL
-        );
-        CREATE INDEX IF NOT EXISTS idx_ideas_date ON ideas(date);
-    "#;
-    execute_write(&pool, init_query, &[]).await?;
-
-    // 将时间戳转换为日期字符串
-    let date = chrono::DateTime::from_timestamp(created_at, 0)
-        .ok_or("无效的时间戳")?
-        .format("%Y-%m-%d")
-        .to_string();
-
-    let attachments_json = serde_json::to_string(&attachments).unwrap_or_else(|_| "[]".to_string());
-
-    execute_write(
-        &pool,
-        "INSERT INTO ideas (content, attachments, created_at, date) VALUES (?, ?, ?, ?)",
-        &[&content, &attachments_json, &created_at.to_string(), &date],
-    ).await?;
-
-    let result = execute_query(&pool, "SELECT last_insert_rowid() as id", &[]).await?;
-    let id = result.first().and_then(|r| r["id"].as_i64()).unwrap_or(0);
-    Ok(id)
+    let idea = IdeaRepo::new(&pool).insert(content, attachments, created_at, AuditOrigin::Ui).await?;
+    webhooks::emit(&pool, "idea.created", serde_json::json!(idea)).await;
+    Ok(idea.id)
 }
 
 #[tauri::command(rename_all = "snake_case")]
+#[allow(clippy::too_many_arguments)]
 pub async fn add_done_task(
+    app: tauri::AppHandle,
     state: State<'_, DbState>,
     content: String,
     start_time: i64,
     end_time: i64,
     attachments: Vec<String>,
     created_at: i64,
+    tags: Option<Vec<String>>,
 ) -> Result<i64, String> {
     let pool = get_pool(&state).await?;
 
-    let init_query = r#"
-        CREATE TABLE IF NOT EXISTS done_tasks (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            content TEXT NOT NULL,
-            start_time INTEGER NOT NULL,
-            end_time INTEGER NOT NULL,
-            attachments TEXT NOT NULL,
-            created_at INTEGER NOT NULL,
-            date TEXT NOT NULL
-        );
-        CREATE INDEX IF NOT EXISTS idx_tasks_date ON done_tasks(date);
-    "#;
-    execute_write(&pool, init_query, &[]).await?;
-
-    // 将开始时间戳转换为日期字符串
-    let date = chrono::DateTime::from_timestamp(start_time, 0)
-        .ok_or("无效的时间戳")?
-        .format("%Y-%m-%d")
-        .to_string();
-
-    let attachments_json = serde_json::to_string(&attachments).unwrap_or_else(|_| "[]".to_string());
-
-    execute_write(
-        &pool,
-        "INSERT INTO done_tasks (content, start_time, end_time, attachments, created_at, date) VALUES (?, ?, ?, ?, ?, ?)",
-        &[&content, &start_time.to_string(), &end_time.to_string(), &attachments_json, &created_at.to_string(), &date],
-    ).await?;
+    // 校验时间段，存在错误时拒绝写入
+    let report = validate_task_range(&app, &pool, start_time, end_time, None).await?;
+    if !report.is_valid() {
+        return Err(format!("事项校验失败: {}", report.error_message()));
+    }
 
-    let result = execute_query(&pool, "SELECT last_insert_rowid() as id", &[]).await?;
-    let id = result.first().and_then(|r| r["id"].as_i64()).unwrap_or(0);
-    Ok(id)
+    let new_task = NewDoneTask {
+        content,
+        start_time,
+        end_time,
+        attachments,
+        created_at,
+        tags: normalize_tags(tags.unwrap_or_default()),
+    };
+    let task = TaskRepo::new(&pool).insert(new_task, AuditOrigin::Ui).await?;
+    webhooks::emit(&pool, "task.created", serde_json::json!(task)).await;
+    Ok(task.id)
 }
 
 #[tauri::command]
@@ -185,50 +352,8 @@ pub async fn get_today_records(
     let pool = get_pool(&state).await?;
     let date = chrono::Local::now().format("%Y-%m-%d").to_string();
 
-    let ideas_result = execute_query(
-        &pool,
-        "SELECT * FROM ideas WHERE date = ? ORDER BY created_at DESC",
-        &[&date],
-    ).await?;
-
-    let mut ideas = Vec::new();
-    for row in ideas_result {
-        let attachments_json = row["attachments"].as_str().unwrap_or("[]");
-        let attachments: Vec<String> = serde_json::from_str(attachments_json)
-            .unwrap_or_else(|_| Vec::new());
-
-        ideas.push(Idea {
-            id: row["id"].as_i64().unwrap_or(0),
-            content: row["content"].as_str().unwrap_or("").to_string(),
-            attachments,
-            created_at: row["created_at"].as_i64().unwrap_or(0),
-            date: row["date"].as_str().unwrap_or("").to_string(),
-        });
-    }
-
-    let tasks_result = execute_query(
-        &pool,
-        "SELECT * FROM done_tasks WHERE date = ? ORDER BY start_time ASC",
-        &[&date],
-    ).await?;
-
-    let mut tasks = Vec::new();
-    for row in tasks_result {
-        let attachments_json = row["attachments"].as_str().unwrap_or("[]");
-        let attachments: Vec<String> = serde_json::from_str(attachments_json)
-            .unwrap_or_else(|_| Vec::new());
-
-        tasks.push(DoneTask {
-            id: row["id"].as_i64().unwrap_or(0),
-            content: row["content"].as_str().unwrap_or("").to_string(),
-            start_time: row["start_time"].as_i64().unwrap_or(0),
-            end_time: row["end_time"].as_i64().unwrap_or(0),
-            attachments,
-            created_at: row["created_at"].as_i64().unwrap_or(0),
-            date: row["date"].as_str().unwrap_or("").to_string(),
-        });
-    }
-
+    let ideas = IdeaRepo::new(&pool).list_by_date(&date).await?;
+    let tasks = TaskRepo::new(&pool).list_by_date(&date).await?;
     Ok(TodayRecords { ideas, tasks })
 }
 
@@ -240,51 +365,161 @@ pub async fn get_records_by_date_range(
 ) -> Result<TodayRecords, String> {
     let pool = get_pool(&state).await?;
 
-    let ideas_result = execute_query(
-        &pool,
-        "SELECT * FROM ideas WHERE date >= ? AND date <= ? ORDER BY date DESC, created_at DESC",
-        &[&start_date, &end_date],
-    ).await?;
-
-    let mut ideas = Vec::new();
-    for row in ideas_result {
-        let attachments_json = row["attachments"].as_str().unwrap_or("[]");
-        let attachments: Vec<String> = serde_json::from_str(attachments_json)
-            .unwrap_or_else(|_| Vec::new());
-
-        ideas.push(Idea {
-            id: row["id"].as_i64().unwrap_or(0),
-            content: row["content"].as_str().unwrap_or("").to_string(),
-            attachments,
-            created_at: row["created_at"].as_i64().unwrap_or(0),
-            date: row["date"].as_str().unwrap_or("").to_string(),
-        });
+    let ideas = IdeaRepo::new(&pool).list_in_range(&start_date, &end_date, SortDirection::Desc).await?;
+    let tasks = TaskRepo::new(&pool).list_in_range(&start_date, &end_date, SortDirection::Desc).await?;
+    Ok(TodayRecords { ideas, tasks })
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn get_records_page(
+    state: State<'_, DbState>,
+    start_date: String,
+    end_date: String,
+    cursor: Option<String>,
+    page_size: Option<i64>,
+    kinds: Option<Vec<RecordKind>>,
+    direction: Option<SortDirection>,
+) -> Result<RecordPage, String> {
+    let pool = get_pool(&state).await?;
+
+    pagination::fetch_records_page(&pool, PageQuery {
+        start_date,
+        end_date,
+        cursor,
+        page_size: page_size.unwrap_or(pagination::DEFAULT_PAGE_SIZE),
+        kinds: kinds.unwrap_or_default(),
+        direction: direction.unwrap_or_default(),
+    }).await
+}
+
+// ========== 批量操作命令 ==========
+
+// 辅助函数：通知批量操作中成功的记录
+async fn emit_bulk_events(pool: &Pool<Sqlite>, events: Vec<(&'static str, serde_json::Value)>) {
+    for (event, data) in events {
+        webhooks::emit(pool, event, data).await;
     }
+}
+
+#[tauri::command]
+pub async fn delete_records(
+    state: State<'_, DbState>,
+    records: Vec<RecordRef>,
+    atomic: Option<bool>,
+) -> Result<BulkResult, String> {
+    let pool = get_pool(&state).await?;
+    let (result, events) = bulk::delete_records(&pool, &records, atomic.unwrap_or(false), AuditOrigin::Ui).await?;
+    emit_bulk_events(&pool, events).await;
+    Ok(result)
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn move_records(
+    app: tauri::AppHandle,
+    state: State<'_, DbState>,
+    records: Vec<RecordRef>,
+    new_date: String,
+    atomic: Option<bool>,
+) -> Result<BulkResult, String> {
+    let pool = get_pool(&state).await?;
+    let rules = validation_rules(&app)?;
+    let (result, events) = bulk::move_records(&pool, &records, &new_date, &rules, atomic.unwrap_or(false), AuditOrigin::Ui).await?;
+    emit_bulk_events(&pool, events).await;
+    Ok(result)
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn bulk_tag(
+    state: State<'_, DbState>,
+    task_ids: Vec<i64>,
+    add: Option<Vec<String>>,
+    remove: Option<Vec<String>>,
+    atomic: Option<bool>,
+) -> Result<BulkResult, String> {
+    let pool = get_pool(&state).await?;
+    let add = normalize_tags(add.unwrap_or_default());
+    let remove = normalize_tags(remove.unwrap_or_default());
+    let (result, events) = bulk::bulk_tag(&pool, &task_ids, &add, &remove, atomic.unwrap_or(false), AuditOrigin::Ui).await?;
+    emit_bulk_events(&pool, events).await;
+    Ok(result)
+}
+
+#[tauri::command]
+pub async fn bulk_import(
+    app: tauri::AppHandle,
+    state: State<'_, DbState>,
+    records: Vec<ImportRecord>,
+    atomic: Option<bool>,
+) -> Result<BulkResult, String> {
+    let pool = get_pool(&state).await?;
+    let rules = validation_rules(&app)?;
+    let (result, events) = bulk::bulk_import(&pool, records, &rules, atomic.unwrap_or(false), AuditOrigin::Ui).await?;
+    emit_bulk_events(&pool, events).await;
+    Ok(result)
+}
+
+// ========== 校验命令 ==========
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn validate_done_task(
+    app: tauri::AppHandle,
+    state: State<'_, DbState>,
+    start_time: i64,
+    end_time: i64,
+    exclude_id: Option<i64>,
+) -> Result<ValidationReport, String> {
+    let pool = get_pool(&state).await?;
+    validate_task_range(&app, &pool, start_time, end_time, exclude_id).await
+}
+
+#[tauri::command]
+pub async fn check_day_consistency(
+    app: tauri::AppHandle,
+    state: State<'_, DbState>,
+    date: String,
+) -> Result<DayConsistencyReport, String> {
+    let pool = get_pool(&state).await?;
+    let rules = validation_rules(&app)?;
+
+    let tasks = TaskRepo::new(&pool).list_by_date(&date).await?;
+
+    let issues = validation::check_tasks(&tasks, &rules);
+    Ok(DayConsistencyReport {
+        date,
+        task_count: tasks.len(),
+        issues,
+    })
+}
+
+// ========== 统计命令 ==========
+
+// 辅助函数：解析 YYYY-MM-DD 日期
+fn parse_date(date: &str) -> Result<chrono::NaiveDate, String> {
+    chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .map_err(|e| format!("无效的日期 {}: {}", date, e))
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn time_stats(
+    state: State<'_, DbState>,
+    start_date: String,
+    end_date: String,
+    group_by: GroupBy,
+) -> Result<TimeStats, String> {
+    let pool = get_pool(&state).await?;
 
-    let tasks_result = execute_query(
-        &pool,
-        "SELECT * FROM done_tasks WHERE date >= ? AND date <= ? ORDER BY date DESC, start_time DESC",
-        &[&start_date, &end_date],
-    ).await?;
-
-    let mut tasks = Vec::new();
-    for row in tasks_result {
-        let attachments_json = row["attachments"].as_str().unwrap_or("[]");
-        let attachments: Vec<String> = serde_json::from_str(attachments_json)
-            .unwrap_or_else(|_| Vec::new());
-
-        tasks.push(DoneTask {
-            id: row["id"].as_i64().unwrap_or(0),
-            content: row["content"].as_str().unwrap_or("").to_string(),
-            start_time: row["start_time"].as_i64().unwrap_or(0),
-            end_time: row["end_time"].as_i64().unwrap_or(0),
-            attachments,
-            created_at: row["created_at"].as_i64().unwrap_or(0),
-            date: row["date"].as_str().unwrap_or("").to_string(),
-        });
+    let start = parse_date(&start_date)?;
+    let end = parse_date(&end_date)?;
+    if end < start {
+        return Err("结束日期早于开始日期".to_string());
     }
 
-    Ok(TodayRecords { ideas, tasks })
+    // 取出与范围有交集的事项，跨天事项在统计时裁剪
+    let range_start = stats::local_midnight(start);
+    let range_end = stats::local_midnight(end + chrono::Duration::days(1));
+    let tasks = TaskRepo::new(&pool).overlapping(range_start, range_end).await?;
+
+    Ok(stats::compute_time_stats(&tasks, start, end, group_by))
 }
 
 // ========== 删除命令 ==========
@@ -296,25 +531,9 @@ pub async fn delete_idea(
 ) -> Result<(), String> {
     let pool = get_pool(&state).await?;
 
-    // 确保表存在
-    let init_query = r#"
-        CREATE TABLE IF NOT EXISTS ideas (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            content TEXT NOT NULL,
-            attachments TEXT NOT NULL,
-            created_at INTEGER NOT NULL,
-            date TEXT NOT NULL
-        );
-    "#;
-    execute_write(&pool, init_query, &[]).await?;
-
-    // 执行删除
-    execute_write(
-        &pool,
-        "DELETE FROM ideas WHERE id = ?",
-        &[&id.to_string()],
-    ).await?;
-
+    if let Some(idea) = IdeaRepo::new(&pool).delete(id, AuditOrigin::Ui).await? {
+        webhooks::emit(&pool, "idea.deleted", serde_json::json!(idea)).await;
+    }
     Ok(())
 }
 
@@ -325,158 +544,752 @@ pub async fn delete_task(
 ) -> Result<(), String> {
     let pool = get_pool(&state).await?;
 
-    // 确保表存在
-    let init_query = r#"
-        CREATE TABLE IF NOT EXISTS done_tasks (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            content TEXT NOT NULL,
-            start_time INTEGER NOT NULL,
-            end_time INTEGER NOT NULL,
-            attachments TEXT NOT NULL,
-            created_at INTEGER NOT NULL,
-            date TEXT NOT NULL
-        );
-    "#;
-    execute_write(&pool, init_query, &[]).await?;
-
-    // 执行删除
-    execute_write(
-        &pool,
-        "DELETE FROM done_tasks WHERE id = ?",
-        &[&id.to_string()],
-    ).await?;
-
+    if let Some(task) = TaskRepo::new(&pool).delete(id, AuditOrigin::Ui).await? {
+        webhooks::emit(&pool, "task.deleted", serde_json::json!(task)).await;
+    }
     Ok(())
 }
 
-// ========== 提示词管理命令 ==========
+// ========== 计划命令 ==========
 
 #[tauri::command]
-pub async fn add_prompt(
+pub async fn add_planned_task(
     state: State<'_, DbState>,
-    name: String,
-    content: String,
-) -> Result<i64, String> {
+    plan: NewPlannedTask,
+) -> Result<PlannedTask, String> {
     let pool = get_pool(&state).await?;
+    let plan = NewPlannedTask { tags: normalize_tags(plan.tags), ..plan };
+    planning::add_planned_task(&pool, plan, AuditOrigin::Ui).await
+}
 
-    let init_query = r#"
-        CREATE TABLE IF NOT EXISTS prompts (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            name TEXT NOT NULL UNIQUE,
-            content TEXT NOT NULL,
-            created_at INTEGER NOT NULL,
-            updated_at INTEGER NOT NULL
-        );
-        CREATE INDEX IF NOT EXISTS idx_prompts_name ON prompts(name);
-    "#;
-    execute_write(&pool, init_query, &[]).await?;
-
-    let now = chrono::Local::now().timestamp();
+#[tauri::command(rename_all = "snake_case")]
+pub async fn list_planned_tasks(
+    app: tauri::AppHandle,
+    state: State<'_, DbState>,
+    start_date: String,
+    end_date: String,
+    status: Option<PlanStatus>,
+) -> Result<Vec<PlannedTask>, String> {
+    let pool = get_pool(&state).await?;
+    // 跨天后首次读取时顺延昨天未完成的计划
+    planning::auto_carry_over(&app, &pool).await?;
+    planning::list_planned_tasks(&pool, &start_date, &end_date, status).await
+}
 
-    execute_write(
-        &pool,
-        "INSERT INTO prompts (name, content, created_at, updated_at) VALUES (?, ?, ?, ?)",
-        &[&name, &content, &now.to_string(), &now.to_string()],
-    ).await?;
+#[tauri::command]
+pub async fn update_planned_task(
+    state: State<'_, DbState>,
+    id: i64,
+    plan: NewPlannedTask,
+) -> Result<PlannedTask, String> {
+    let pool = get_pool(&state).await?;
+    let plan = NewPlannedTask { tags: normalize_tags(plan.tags), ..plan };
+    planning::update_planned_task(&pool, id, plan, AuditOrigin::Ui).await
+}
 
-    let result = execute_query(&pool, "SELECT last_insert_rowid() as id", &[]).await?;
-    let id = result.first().and_then(|r| r["id"].as_i64()).unwrap_or(0);
-    Ok(id)
+#[tauri::command]
+pub async fn set_planned_task_status(
+    state: State<'_, DbState>,
+    id: i64,
+    status: PlanStatus,
+) -> Result<PlannedTask, String> {
+    let pool = get_pool(&state).await?;
+    planning::set_planned_task_status(&pool, id, status, AuditOrigin::Ui).await
 }
 
 #[tauri::command]
-pub async fn get_prompts(
+pub async fn delete_planned_task(
     state: State<'_, DbState>,
-) -> Result<Vec<Prompt>, String> {
+    id: i64,
+) -> Result<(), String> {
+    let pool = get_pool(&state).await?;
+    planning::delete_planned_task(&pool, id, AuditOrigin::Ui).await
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn complete_planned_task(
+    app: tauri::AppHandle,
+    state: State<'_, DbState>,
+    id: i64,
+    start_time: i64,
+    end_time: i64,
+    attachments: Option<Vec<String>>,
+) -> Result<DoneTask, String> {
     let pool = get_pool(&state).await?;
 
-    let init_query = r#"
-        CREATE TABLE IF NOT EXISTS prompts (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            name TEXT NOT NULL UNIQUE,
-            content TEXT NOT NULL,
-            created_at INTEGER NOT NULL,
-            updated_at INTEGER NOT NULL
-        );
-    "#;
-    execute_write(&pool, init_query, &[]).await?;
-
-    let prompts_result = execute_query(
-        &pool,
-        "SELECT * FROM prompts ORDER BY updated_at DESC",
-        &[],
-    ).await?;
-
-    let mut prompts = Vec::new();
-    for row in prompts_result {
-        prompts.push(Prompt {
-            id: row["id"].as_i64().unwrap_or(0),
-            name: row["name"].as_str().unwrap_or("").to_string(),
-            content: row["content"].as_str().unwrap_or("").to_string(),
-            created_at: row["created_at"].as_i64().unwrap_or(0),
-            updated_at: row["updated_at"].as_i64().unwrap_or(0),
-        });
+    // 与手动添加事项相同的校验，存在错误时拒绝写入
+    let report = validate_task_range(&app, &pool, start_time, end_time, None).await?;
+    if !report.is_valid() {
+        return Err(format!("事项校验失败: {}", report.error_message()));
     }
 
-    Ok(prompts)
+    let task = planning::complete_planned_task(&pool, id, start_time, end_time, attachments.unwrap_or_default(), AuditOrigin::Ui).await?;
+    webhooks::emit(&pool, "task.created", serde_json::json!(task)).await;
+    Ok(task)
 }
 
 #[tauri::command]
-pub async fn update_prompt(
+pub async fn carry_over_planned_tasks(
+    app: tauri::AppHandle,
     state: State<'_, DbState>,
-    id: i64,
-    name: String,
-    content: String,
-) -> Result<(), String> {
+) -> Result<Vec<PlannedTask>, String> {
     let pool = get_pool(&state).await?;
+    let settings = ConfigManager::new(&app)?.load_settings()?;
+    planning::carry_over(&pool, chrono::Local::now().date_naive(), &settings.plan_workdays, AuditOrigin::Ui).await
+}
 
-    let init_query = r#"
-        CREATE TABLE IF NOT EXISTS prompts (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            name TEXT NOT NULL UNIQUE,
-            content TEXT NOT NULL,
-            created_at INTEGER NOT NULL,
-            updated_at INTEGER NOT NULL
-        );
-    "#;
-    execute_write(&pool, init_query, &[]).await?;
+// ========== 想法转化命令 ==========
 
-    let now = chrono::Local::now().timestamp();
+/// 将想法转为计划或已完成事项，`archive` 为 true 时同时归档想法
+#[tauri::command]
+pub async fn promote_idea(
+    app: tauri::AppHandle,
+    state: State<'_, DbState>,
+    id: i64,
+    target: PromoteTarget,
+    archive: Option<bool>,
+) -> Result<IdeaPromotion, String> {
+    let pool = get_pool(&state).await?;
 
-    execute_write(
-        &pool,
-        "UPDATE prompts SET name = ?, content = ?, updated_at = ? WHERE id = ?",
-        &[&name, &content, &now.to_string(), &id.to_string()],
-    ).await?;
+    let target = match target {
+        PromoteTarget::Planned { target_date, estimate_minutes, priority, content, tags } => {
+            PromoteTarget::Planned { target_date, estimate_minutes, priority, content, tags: normalize_tags(tags) }
+        }
+        PromoteTarget::Done { start_time, end_time, content, tags } => {
+            // 与手动添加事项相同的校验，存在错误时拒绝写入
+            let report = validate_task_range(&app, &pool, start_time, end_time, None).await?;
+            if !report.is_valid() {
+                return Err(format!("事项校验失败: {}", report.error_message()));
+            }
+            PromoteTarget::Done { start_time, end_time, content, tags: normalize_tags(tags) }
+        }
+    };
 
-    Ok(())
+    let archive = archive.unwrap_or(false);
+    let promotion = idea_links::promote_idea(&pool, id, target, archive, AuditOrigin::Ui).await?;
+    if let Some(task) = &promotion.done_task {
+        webhooks::emit(&pool, "task.created", serde_json::json!(task)).await;
+    }
+    if archive {
+        if let Some(idea) = IdeaRepo::new(&pool).get(id).await? {
+            webhooks::emit(&pool, "idea.updated", serde_json::json!(idea)).await;
+        }
+    }
+    Ok(promotion)
 }
 
 #[tauri::command]
-pub async fn delete_prompt(
+pub async fn set_idea_archived(
     state: State<'_, DbState>,
     id: i64,
+    archived: bool,
 ) -> Result<(), String> {
     let pool = get_pool(&state).await?;
+    let idea = idea_links::set_archived(&pool, id, archived, AuditOrigin::Ui).await?;
+    webhooks::emit(&pool, "idea.updated", serde_json::json!(idea)).await;
+    Ok(())
+}
+
+/// 按想法、计划或已完成事项查询关联
+#[tauri::command(rename_all = "snake_case")]
+pub async fn list_idea_links(
+    state: State<'_, DbState>,
+    idea_id: Option<i64>,
+    planned_task_id: Option<i64>,
+    done_task_id: Option<i64>,
+) -> Result<Vec<IdeaLink>, String> {
+    let pool = get_pool(&state).await?;
+    idea_links::list_links(&pool, idea_id, planned_task_id, done_task_id).await
+}
 
-    // 确保表存在
-    let init_query = r#"
-        CREATE TABLE IF NOT EXISTS prompts (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            name TEXT NOT NULL UNIQUE,
-            content TEXT NOT NULL,
-            created_at INTEGER NOT NULL,
-            updated_at INTEGER NOT NULL
-        );
-    "#;
-    execute_write(&pool, init_query, &[]).await?;
-
-    // 执行删除
-    execute_write(
-        &pool,
-        "DELETE FROM prompts WHERE id = ?",
-        &[&id.to_string()],
-    ).await?;
+// ========== 重复事项命令 ==========
 
-    Ok(())
-}
\ No newline at end of file
+#[tauri::command]
+pub async fn add_recurring_template(
+    state: State<'_, DbState>,
+    template: NewRecurringTemplate,
+) -> Result<RecurringTemplate, String> {
+    let pool = get_pool(&state).await?;
+    let template = NewRecurringTemplate { tags: normalize_tags(template.tags), ..template };
+    recurring::add_template(&pool, template).await
+}
+
+#[tauri::command]
+pub async fn list_recurring_templates(
+    state: State<'_, DbState>,
+) -> Result<Vec<RecurringTemplate>, String> {
+    let pool = get_pool(&state).await?;
+    recurring::list_templates(&pool).await
+}
+
+#[tauri::command]
+pub async fn update_recurring_template(
+    state: State<'_, DbState>,
+    id: i64,
+    template: NewRecurringTemplate,
+) -> Result<RecurringTemplate, String> {
+    let pool = get_pool(&state).await?;
+    let template = NewRecurringTemplate { tags: normalize_tags(template.tags), ..template };
+    recurring::update_template(&pool, id, template).await
+}
+
+#[tauri::command]
+pub async fn delete_recurring_template(
+    state: State<'_, DbState>,
+    id: i64,
+) -> Result<(), String> {
+    let pool = get_pool(&state).await?;
+    recurring::delete_template(&pool, id).await
+}
+
+#[tauri::command]
+pub async fn materialize_recurring(
+    app: tauri::AppHandle,
+    state: State<'_, DbState>,
+    date: String,
+) -> Result<Vec<RecurringOccurrence>, String> {
+    let pool = get_pool(&state).await?;
+    let settings = ConfigManager::new(&app)?.load_settings()?;
+    recurring::materialize(&pool, &date, &settings.plan_workdays).await
+}
+
+#[tauri::command]
+pub async fn list_recurring_occurrences(
+    state: State<'_, DbState>,
+    date: String,
+    status: Option<OccurrenceStatus>,
+) -> Result<Vec<RecurringOccurrence>, String> {
+    let pool = get_pool(&state).await?;
+    recurring::list_occurrences(&pool, &date, status).await
+}
+
+/// 确认草稿，可在确认时调整内容和起止时间
+#[tauri::command(rename_all = "snake_case")]
+pub async fn confirm_recurring_occurrence(
+    app: tauri::AppHandle,
+    state: State<'_, DbState>,
+    id: i64,
+    content: Option<String>,
+    start_time: Option<i64>,
+    end_time: Option<i64>,
+) -> Result<DoneTask, String> {
+    let pool = get_pool(&state).await?;
+    let occurrence = recurring::get_occurrence(&pool, id).await?;
+    let start_time = start_time.unwrap_or(occurrence.start_time);
+    let end_time = end_time.unwrap_or(occurrence.end_time);
+
+    // 与手动添加事项相同的校验，存在错误时拒绝写入
+    let report = validate_task_range(&app, &pool, start_time, end_time, None).await?;
+    if !report.is_valid() {
+        return Err(format!("事项校验失败: {}", report.error_message()));
+    }
+
+    let content = content.unwrap_or(occurrence.content);
+    let task = recurring::confirm_occurrence(&pool, id, &content, start_time, end_time, AuditOrigin::Ui).await?;
+    webhooks::emit(&pool, "task.created", serde_json::json!(task)).await;
+    Ok(task)
+}
+
+#[tauri::command]
+pub async fn skip_recurring_occurrence(
+    state: State<'_, DbState>,
+    id: i64,
+) -> Result<RecurringOccurrence, String> {
+    let pool = get_pool(&state).await?;
+    recurring::skip_occurrence(&pool, id).await
+}
+
+// ========== 提示词管理命令 ==========
+
+#[tauri::command]
+pub async fn add_prompt(
+    state: State<'_, DbState>,
+    name: String,
+    content: String,
+) -> Result<i64, String> {
+    let pool = get_pool(&state).await?;
+    Ok(PromptRepo::new(&pool).insert(name, content, AuditOrigin::Ui).await?.id)
+}
+
+#[tauri::command]
+pub async fn get_prompts(
+    state: State<'_, DbState>,
+) -> Result<Vec<Prompt>, String> {
+    let pool = get_pool(&state).await?;
+    PromptRepo::new(&pool).list().await
+}
+
+#[tauri::command]
+pub async fn update_prompt(
+    state: State<'_, DbState>,
+    id: i64,
+    name: String,
+    content: String,
+) -> Result<(), String> {
+    let pool = get_pool(&state).await?;
+    PromptRepo::new(&pool).update(id, &name, &content, AuditOrigin::Ui).await
+}
+
+#[tauri::command]
+pub async fn delete_prompt(
+    state: State<'_, DbState>,
+    id: i64,
+) -> Result<(), String> {
+    let pool = get_pool(&state).await?;
+    PromptRepo::new(&pool).delete(id, AuditOrigin::Ui).await
+}
+
+// ========== AI 对话记录命令 ==========
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn create_chat_session(
+    state: State<'_, DbState>,
+    title: String,
+    range_start: Option<String>,
+    range_end: Option<String>,
+) -> Result<ChatSession, String> {
+    let pool = get_pool(&state).await?;
+    chat::create_session(&pool, &title, range_start, range_end).await
+}
+
+#[tauri::command]
+pub async fn list_chat_sessions(
+    state: State<'_, DbState>,
+) -> Result<Vec<ChatSession>, String> {
+    let pool = get_pool(&state).await?;
+    chat::list_sessions(&pool).await
+}
+
+#[tauri::command]
+pub async fn rename_chat_session(
+    state: State<'_, DbState>,
+    id: i64,
+    title: String,
+) -> Result<(), String> {
+    let pool = get_pool(&state).await?;
+    chat::rename_session(&pool, id, &title).await
+}
+
+#[tauri::command]
+pub async fn delete_chat_session(
+    state: State<'_, DbState>,
+    id: i64,
+) -> Result<(), String> {
+    let pool = get_pool(&state).await?;
+    chat::delete_session(&pool, id).await
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn append_chat_message(
+    state: State<'_, DbState>,
+    session_id: i64,
+    message: NewChatMessage,
+) -> Result<ChatMessage, String> {
+    let pool = get_pool(&state).await?;
+    chat::append_message(&pool, session_id, message).await
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn get_chat_messages(
+    state: State<'_, DbState>,
+    session_id: i64,
+) -> Result<Vec<ChatMessage>, String> {
+    let pool = get_pool(&state).await?;
+    chat::get_messages(&pool, session_id).await
+}
+
+#[tauri::command]
+pub async fn search_chat_messages(
+    state: State<'_, DbState>,
+    query: String,
+    limit: Option<i64>,
+) -> Result<Vec<ChatSearchHit>, String> {
+    let pool = get_pool(&state).await?;
+    chat::search_messages(&pool, &query, limit.unwrap_or(50)).await
+}
+
+// ========== AI 工具调用命令 ==========
+
+// 辅助函数：读取已保存的 AI 配置
+fn load_ai_config(app: &tauri::AppHandle) -> Result<ApiConfig, AiError> {
+    ConfigManager::new(app)?
+        .load_config()?
+        .ok_or_else(|| AiError::new(AiErrorKind::NotConfigured, "请先在设置中填写 AI 配置"))
+}
+
+// 辅助函数：根据 AI 配置创建工具上下文
+fn tool_context(pool: Pool<Sqlite>, config: &ApiConfig) -> ToolContext {
+    ToolContext {
+        pool,
+        estimator: TokenEstimator::for_model(&config.model),
+        data_budget: context::data_budget(&config.model, config.effective_context_budget()),
+    }
+}
+
+// 辅助函数：根据应用设置创建 AI 客户端（`use_cache` 且设置中开启时才启用响应缓存）
+fn ai_client(
+    app: &tauri::AppHandle,
+    pool: &Pool<Sqlite>,
+    config: ApiConfig,
+    use_cache: bool,
+    bypass_cache: bool,
+) -> Result<AiClient, String> {
+    let settings = ConfigManager::new(app)?.load_settings()?;
+    let client = AiClient::new(config).with_policy(RetryPolicy::from_settings(&settings));
+    if !use_cache || !settings.ai_cache_enabled {
+        return Ok(client);
+    }
+
+    let counters = app.state::<CacheCounters>().inner().clone();
+    let cache = ResponseCache::new(
+        pool.clone(),
+        settings.ai_cache_ttl_hours * 3600,
+        settings.ai_cache_max_mb * 1024 * 1024,
+        counters,
+    );
+    Ok(client.with_cache(cache, bypass_cache))
+}
+
+// 辅助函数：把工具调用和流式输出作为事件发送给前端
+fn emit_agent_event(app: &tauri::AppHandle, event: AgentEvent<'_>) {
+    let result = match event {
+        AgentEvent::ToolCall(call) => app.emit("ai-tool-call", call),
+        AgentEvent::Delta { iteration, text } => app.emit("ai-stream-delta", StreamDeltaEvent {
+            iteration,
+            delta: text.to_string(),
+        }),
+    };
+    if let Err(e) = result {
+        eprintln!("发送 AI 事件失败: {}", e);
+    }
+}
+
+// 辅助函数：运行工具调用循环，并把每次工具调用作为事件发送给前端
+async fn run_agent_with_events(
+    app: &tauri::AppHandle,
+    pool: Pool<Sqlite>,
+    messages: Vec<Message>,
+    options: AgentOptions,
+    use_cache: bool,
+    bypass_cache: bool,
+) -> Result<AgentOutcome, AiError> {
+    let config = load_ai_config(app)?;
+    let client = ai_client(app, &pool, config.clone(), use_cache, bypass_cache)?;
+    let ctx = tool_context(pool, &config);
+    let registry = ToolRegistry::with_builtin_tools();
+
+    agent::run_agent(&client, &registry, &ctx, messages, &options, |event| emit_agent_event(app, event)).await
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn generate_daily_report(
+    app: tauri::AppHandle,
+    state: State<'_, DbState>,
+    system_prompt: Option<String>,
+    start_date: Option<String>,
+    end_date: Option<String>,
+    bypass_cache: Option<bool>,
+    stream: Option<bool>,
+) -> Result<AgentOutcome, AiError> {
+    let pool = get_pool(&state).await?;
+    let config = load_ai_config(&app)?;
+    let client = ai_client(&app, &pool, config.clone(), true, bypass_cache.unwrap_or(false))?;
+    let ctx = tool_context(pool, &config);
+    let registry = ToolRegistry::with_builtin_tools();
+
+    let options = AgentOptions {
+        stream: stream.unwrap_or(false),
+        ..Default::default()
+    };
+    let range = agent::ReportRange { start_date, end_date };
+
+    agent::generate_daily_report(&client, &registry, &ctx, system_prompt, range, &options, |event| {
+        emit_agent_event(&app, event)
+    })
+    .await
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn run_ai_agent(
+    app: tauri::AppHandle,
+    state: State<'_, DbState>,
+    messages: Vec<Message>,
+    max_iterations: Option<usize>,
+    use_cache: Option<bool>, // 对话默认不缓存
+    bypass_cache: Option<bool>,
+    stream: Option<bool>,
+) -> Result<AgentOutcome, AiError> {
+    let pool = get_pool(&state).await?;
+
+    let mut options = AgentOptions {
+        stream: stream.unwrap_or(false),
+        ..Default::default()
+    };
+    if let Some(max_iterations) = max_iterations {
+        options.max_iterations = max_iterations.clamp(1, 20);
+    }
+
+    run_agent_with_events(
+        &app,
+        pool,
+        messages,
+        options,
+        use_cache.unwrap_or(false),
+        bypass_cache.unwrap_or(false),
+    )
+    .await
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn build_report_context(
+    app: tauri::AppHandle,
+    state: State<'_, DbState>,
+    start_date: String,
+    end_date: String,
+) -> Result<BuiltContext, String> {
+    let pool = get_pool(&state).await?;
+    let config = load_ai_config(&app).map_err(|e| e.to_string())?;
+    let ctx = tool_context(pool, &config);
+    tools::load_report_context(&ctx, &start_date, &end_date).await
+}
+
+// ========== AI 响应缓存命令 ==========
+
+#[tauri::command]
+pub async fn get_ai_cache_stats(
+    state: State<'_, DbState>,
+    counters: State<'_, CacheCounters>,
+) -> Result<CacheStats, String> {
+    let pool = get_pool(&state).await?;
+    ai_cache::stats(&pool, &counters).await
+}
+
+#[tauri::command]
+pub async fn clear_ai_cache(
+    state: State<'_, DbState>,
+) -> Result<(), String> {
+    let pool = get_pool(&state).await?;
+    ai_cache::clear(&pool).await
+}
+
+// ========== 报告命令 ==========
+
+#[tauri::command]
+pub async fn list_reports(
+    state: State<'_, DbState>,
+    limit: Option<i64>,
+    offset: Option<i64>,
+) -> Result<Vec<Report>, String> {
+    let pool = get_pool(&state).await?;
+    reports::list_reports(&pool, limit.unwrap_or(50).clamp(1, 500), offset.unwrap_or(0).max(0)).await
+}
+
+#[tauri::command]
+pub async fn get_report(
+    state: State<'_, DbState>,
+    id: i64,
+) -> Result<Report, String> {
+    let pool = get_pool(&state).await?;
+    reports::get_report(&pool, id).await
+}
+
+#[tauri::command]
+pub async fn delete_report(
+    state: State<'_, DbState>,
+    id: i64,
+) -> Result<(), String> {
+    let pool = get_pool(&state).await?;
+    let report = reports::get_report(&pool, id).await?;
+    reports::delete_report(&pool, id, AuditOrigin::Ui).await?;
+    webhooks::emit(&pool, "report.deleted", serde_json::json!(report)).await;
+    Ok(())
+}
+
+// ========== 报告推送命令 ==========
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn add_publish_target(
+    state: State<'_, DbState>,
+    name: String,
+    platform: PublishPlatform,
+    webhook_url: String,
+    secret: Option<String>,
+) -> Result<PublishTarget, String> {
+    let pool = get_pool(&state).await?;
+    publish::add_target(&pool, &name, platform, &webhook_url, secret).await
+}
+
+#[tauri::command]
+pub async fn list_publish_targets(
+    state: State<'_, DbState>,
+) -> Result<Vec<PublishTarget>, String> {
+    let pool = get_pool(&state).await?;
+    publish::list_targets(&pool).await
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn update_publish_target(
+    state: State<'_, DbState>,
+    id: i64,
+    name: String,
+    webhook_url: String,
+    secret: Option<String>,
+    enabled: bool,
+) -> Result<PublishTarget, String> {
+    let pool = get_pool(&state).await?;
+    publish::update_target(&pool, id, &name, &webhook_url, secret, enabled).await
+}
+
+#[tauri::command]
+pub async fn delete_publish_target(
+    state: State<'_, DbState>,
+    id: i64,
+) -> Result<(), String> {
+    let pool = get_pool(&state).await?;
+    publish::delete_target(&pool, id).await
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn publish_report(
+    state: State<'_, DbState>,
+    report_id: i64,
+    target_ids: Option<Vec<i64>>,
+) -> Result<Vec<ReportPublication>, String> {
+    let pool = get_pool(&state).await?;
+    let publications = publish::publish_report(&pool, report_id, target_ids).await?;
+    if publications.iter().any(|p| p.success) {
+        let data = serde_json::json!({ "report_id": report_id, "publications": publications });
+        webhooks::emit(&pool, "report.published", data).await;
+    }
+    Ok(publications)
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn list_report_publications(
+    state: State<'_, DbState>,
+    report_id: i64,
+) -> Result<Vec<ReportPublication>, String> {
+    let pool = get_pool(&state).await?;
+    publish::list_publications(&pool, report_id).await
+}
+
+// ========== 邮件发送命令 ==========
+
+#[tauri::command]
+pub async fn save_smtp_config(
+    app: tauri::AppHandle,
+    config: SmtpConfig,
+) -> Result<(), String> {
+    email::validate_config(&config)?;
+    let manager = ConfigManager::new(&app)?;
+    let config = email::keep_stored_password(config, manager.load_smtp_config()?.as_ref());
+    manager.save_smtp_config(&config)
+}
+
+/// 返回的密码为占位符，不把明文密码交给前端
+#[tauri::command]
+pub async fn get_smtp_config(
+    app: tauri::AppHandle,
+) -> Result<Option<SmtpConfig>, String> {
+    Ok(ConfigManager::new(&app)?.load_smtp_config()?.map(email::masked))
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn send_report_email(
+    app: tauri::AppHandle,
+    state: State<'_, DbState>,
+    report_id: i64,
+    recipients: Option<Vec<String>>,
+    attach_images: Option<bool>,
+) -> Result<ReportEmail, String> {
+    let config = ConfigManager::new(&app)?
+        .load_smtp_config()?
+        .ok_or_else(|| "请先在设置中填写 SMTP 配置".to_string())?;
+    let pool = get_pool(&state).await?;
+    let attachment_dir = workspaces::attachment_dir(&app)?;
+    let sent = email::send_report(&pool, &config, report_id, recipients, attach_images, &attachment_dir).await?;
+    if sent.success {
+        webhooks::emit(&pool, "report.emailed", serde_json::json!(sent)).await;
+    }
+    Ok(sent)
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn list_report_emails(
+    state: State<'_, DbState>,
+    report_id: i64,
+) -> Result<Vec<ReportEmail>, String> {
+    let pool = get_pool(&state).await?;
+    email::list_sends(&pool, report_id).await
+}
+
+// ========== Webhook 命令 ==========
+
+#[tauri::command]
+pub async fn get_webhook_events() -> Result<Vec<String>, String> {
+    Ok(webhooks::EVENTS.iter().map(|e| e.to_string()).collect())
+}
+
+#[tauri::command]
+pub async fn add_webhook(
+    state: State<'_, DbState>,
+    webhook: NewWebhook,
+) -> Result<Webhook, String> {
+    let pool = get_pool(&state).await?;
+    webhooks::add_webhook(&pool, webhook).await
+}
+
+#[tauri::command]
+pub async fn list_webhooks(
+    state: State<'_, DbState>,
+) -> Result<Vec<Webhook>, String> {
+    let pool = get_pool(&state).await?;
+    webhooks::list_webhooks(&pool).await
+}
+
+#[tauri::command]
+pub async fn update_webhook(
+    state: State<'_, DbState>,
+    id: i64,
+    webhook: NewWebhook,
+) -> Result<Webhook, String> {
+    let pool = get_pool(&state).await?;
+    webhooks::update_webhook(&pool, id, webhook).await
+}
+
+#[tauri::command]
+pub async fn delete_webhook(
+    state: State<'_, DbState>,
+    id: i64,
+) -> Result<(), String> {
+    let pool = get_pool(&state).await?;
+    webhooks::delete_webhook(&pool, id).await
+}
+
+#[tauri::command]
+pub async fn test_webhook(
+    state: State<'_, DbState>,
+    id: i64,
+) -> Result<WebhookDelivery, String> {
+    let pool = get_pool(&state).await?;
+    webhooks::send_test(&pool, id).await
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn list_webhook_deliveries(
+    state: State<'_, DbState>,
+    webhook_id: i64,
+    limit: Option<i64>,
+) -> Result<Vec<WebhookDelivery>, String> {
+    let pool = get_pool(&state).await?;
+    webhooks::list_deliveries(&pool, webhook_id, limit.unwrap_or(100).clamp(1, 500)).await
+}
+
+#[tauri::command]
+pub async fn retry_webhook_delivery(
+    state: State<'_, DbState>,
+    id: i64,
+) -> Result<WebhookDelivery, String> {
+    let pool = get_pool(&state).await?;
+    webhooks::retry_delivery(&pool, id).await
+}
@@ -1,10 +1,13 @@
-use crate::models::ApiConfig;
+use crate::models::{ApiConfig, AppSettings, SmtpConfig};
+use crate::workspaces;
 use std::path::PathBuf;
 use tauri::{AppHandle, Manager};
 
 /// AI 配置管理器
 pub struct ConfigManager {
     config_path: PathBuf,
+    settings_path: PathBuf,
+    smtp_path: PathBuf,
 }
 
 impl ConfigManager {
@@ -18,9 +21,12 @@ impl ConfigManager {
         std::fs::create_dir_all(&app_config_dir)
             .map_err(|e| format!("无法创建目录: {}", e))?;
 
-        let config_path = app_config_dir.join("ai_config.json");
+        // AI 配置跟随当前工作区，其余设置全局共享
+        let config_path = workspaces::active_paths(app)?.ai_config_path;
+        let settings_path = app_config_dir.join("settings.json");
+        let smtp_path = app_config_dir.join("smtp_config.json");
         
-        Ok(Self { config_path })
+        Ok(Self { config_path, settings_path, smtp_path })
     }
 
     /// 保存 AI 配置
@@ -48,4 +54,56 @@ impl ConfigManager {
 
         Ok(Some(config))
     }
-}
\ No newline at end of file
+
+    /// 保存应用设置
+    pub fn save_settings(&self, settings: &AppSettings) -> Result<(), String> {
+        let settings_json = serde_json::to_string_pretty(settings)
+            .map_err(|e| format!("序列化设置失败: {}", e))?;
+
+        std::fs::write(&self.settings_path, settings_json)
+            .map_err(|e| format!("写入设置文件失败: {}", e))?;
+
+        Ok(())
+    }
+
+    /// 读取应用设置（文件不存在时返回默认值）
+    pub fn load_settings(&self) -> Result<AppSettings, String> {
+        if !self.settings_path.exists() {
+            return Ok(AppSettings::default());
+        }
+
+        let settings_content = std::fs::read_to_string(&self.settings_path)
+            .map_err(|e| format!("读取设置文件失败: {}", e))?;
+
+        let settings: AppSettings = serde_json::from_str(&settings_content)
+            .map_err(|e| format!("解析设置文件失败: {}", e))?;
+
+        Ok(settings)
+    }
+
+    /// 保存 SMTP 配置
+    pub fn save_smtp_config(&self, config: &SmtpConfig) -> Result<(), String> {
+        let config_json = serde_json::to_string_pretty(config)
+            .map_err(|e| format!("序列化 SMTP 配置失败: {}", e))?;
+
+        std::fs::write(&self.smtp_path, config_json)
+            .map_err(|e| format!("写入 SMTP 配置文件失败: {}", e))?;
+
+        Ok(())
+    }
+
+    /// 读取 SMTP 配置
+    pub fn load_smtp_config(&self) -> Result<Option<SmtpConfig>, String> {
+        if !self.smtp_path.exists() {
+            return Ok(None);
+        }
+
+        let config_content = std::fs::read_to_string(&self.smtp_path)
+            .map_err(|e| format!("读取 SMTP 配置文件失败: {}", e))?;
+
+        let config: SmtpConfig = serde_json::from_str(&config_content)
+            .map_err(|e| format!("解析 SMTP 配置文件失败: {}", e))?;
+
+        Ok(Some(config))
+    }
+}
@@ -1,39 +1,201 @@
-use sqlx::{sqlite::SqlitePoolOptions, Pool, Sqlite};
-use tauri::{AppHandle, Manager};
+use arc_swap::ArcSwapOption;
+use sqlx::{sqlite::SqlitePoolOptions, Pool, Row, Sqlite};
+use tauri::{AppHandle, Emitter};
+use std::path::Path;
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use std::time::Duration;
+use tokio::sync::watch;
+
+use crate::models::DbStatus;
+use crate::workspaces::{self, WorkspacePaths};
+
+/// 数据库状态变化时通知前端的事件
+pub const DB_STATUS_EVENT: &str = "db-status-changed";
+
+/// 命令等待数据库就绪的最长时间
+const READY_TIMEOUT: Duration = Duration::from_secs(15);
 
 #[derive(Clone)]
 pub struct DbState {
-    pub pool: Arc<Mutex<Option<Pool<Sqlite>>>>,
+    pool: Arc<ArcSwapOption<Pool<Sqlite>>>, // 读取时无需加锁
+    status: Arc<watch::Sender<DbStatus>>,
 }
 
 impl DbState {
     pub fn new() -> Self {
         Self {
-            pool: Arc::new(Mutex::new(None)),
+            pool: Arc::new(ArcSwapOption::empty()),
+            status: Arc::new(watch::Sender::new(DbStatus::Initializing)),
         }
     }
 
+    /// 当前初始化状态
+    pub fn status(&self) -> DbStatus {
+        self.status.borrow().clone()
+    }
+
+    fn set_status(&self, status: DbStatus) {
+        self.status.send_if_modified(|current| {
+            if *current == status {
+                return false;
+            }
+            *current = status;
+            true
+        });
+    }
+
+    /// 获取连接池；数据库尚未就绪时等待，超时或初始化失败时返回错误
     pub async fn get_pool(&self) -> Result<Pool<Sqlite>, String> {
-        let lock = self.pool.lock().await;
-        lock.clone().ok_or_else(|| "Database not initialized".to_string())
+        if let Some(pool) = self.pool.load_full() {
+            return Ok((*pool).clone());
+        }
+
+        let mut status = self.status.subscribe();
+        let waited = tokio::time::timeout(READY_TIMEOUT, async {
+            loop {
+                let ready = status
+                    .wait_for(|s| *s != DbStatus::Initializing)
+                    .await
+                    .map_err(|_| "数据库状态不可用".to_string())?
+                    .clone();
+                if let DbStatus::Failed { error } = ready {
+                    return Err(error);
+                }
+                // 就绪后连接池可能又被迁移或恢复取走（状态会先变回初始化中），继续等待
+                if let Some(pool) = self.pool.load_full() {
+                    return Ok((*pool).clone());
+                }
+            }
+        })
+        .await;
+
+        waited.unwrap_or_else(|_| Err("等待数据库初始化超时，请稍后重试".to_string()))
+    }
+
+    /// 替换连接池并返回旧的连接池
+    ///
+    /// 已取出的旧连接池克隆在调用方关闭前仍可继续使用，新的请求立即使用新连接池。
+    pub fn replace_pool(&self, pool: Pool<Sqlite>) -> Option<Pool<Sqlite>> {
+        let old = self.pool.swap(Some(Arc::new(pool)));
+        self.set_status(DbStatus::Ready);
+        old.map(|old| (*old).clone())
+    }
+
+    /// 暂时取走连接池（迁移、恢复数据库时使用），期间新的请求会等待
+    pub fn take_pool(&self) -> Option<Pool<Sqlite>> {
+        self.set_status(DbStatus::Initializing);
+        self.pool.swap(None).map(|old| (*old).clone())
+    }
+
+    fn set_failed(&self, error: String) {
+        self.pool.store(None);
+        self.set_status(DbStatus::Failed { error });
+    }
+
+    /// 重新打开工作区已有的数据库文件，失败时记录为初始化失败，以便之后重试
+    ///
+    /// 不会创建目录或新数据库，避免卷未挂载时在挂载点下写入空数据库。
+    pub async fn reopen(&self, paths: &WorkspacePaths) -> Result<(), String> {
+        let opened = match workspaces::check_db_dir(paths) {
+            Ok(()) => open_existing(&paths.db_path).await,
+            Err(e) => Err(e),
+        };
+        match opened {
+            Ok(pool) => {
+                self.replace_pool(pool);
+                Ok(())
+            }
+            Err(e) => {
+                self.set_failed(e.clone());
+                Err(e)
+            }
+        }
     }
 }
 
+/// 启动时初始化数据库
+///
+/// 持有切换锁，避免启动期间切换工作区、迁移或恢复数据库后又被旧工作区的连接池覆盖。
 pub async fn init_database(app: &AppHandle, db_state: DbState) -> Result<(), String> {
-    // 获取应用配置目录（用于存储数据库文件）
-    let app_config_dir = app.path()
-        .app_config_dir()
-        .map_err(|e| format!("无法获取应用配置目录: {}", e))?;
+    let _switching = workspaces::SWITCH_LOCK.lock().await;
+    init_locked(app, &db_state).await
+}
 
+/// 打开当前工作区的数据库，调用方须持有切换锁
+async fn init_locked(app: &AppHandle, db_state: &DbState) -> Result<(), String> {
+    db_state.set_status(DbStatus::Initializing);
+
+    // 当前工作区的数据库文件
+    let opened = match workspaces::active_paths(app) {
+        Ok(paths) => open_workspace(&paths).await,
+        Err(e) => Err(e),
+    };
+    let pool = match opened {
+        Ok(pool) => pool,
+        Err(e) => {
+            db_state.set_failed(e.clone());
+            return Err(e);
+        }
+    };
+
+    // 保存到状态
+    db_state.replace_pool(pool);
+    Ok(())
+}
+
+/// 初始化失败后重试；数据库已就绪时直接返回当前状态
+pub async fn retry_init(app: &AppHandle, db_state: &DbState) -> Result<DbStatus, String> {
+    let _switching = workspaces::SWITCH_LOCK.lock().await;
+    if let DbStatus::Failed { .. } = db_state.status() {
+        init_locked(app, db_state).await?;
+    }
+    Ok(db_state.status())
+}
+
+/// 将状态变化转发给前端
+pub fn spawn_status_events(app: AppHandle, db_state: &DbState) {
+    let mut status = db_state.status.subscribe();
+    tauri::async_runtime::spawn(async move {
+        while status.changed().await.is_ok() {
+            let current = status.borrow_and_update().clone();
+            let _ = app.emit(DB_STATUS_EVENT, &current);
+        }
+    });
+}
+
+/// 打开工作区的数据库
+///
+/// 只有工作区目录中尚不存在的数据库（新建的工作区）才会创建，其余情况都只打开已有的文件。
+pub async fn open_workspace(paths: &WorkspacePaths) -> Result<Pool<Sqlite>, String> {
+    workspaces::check_db_dir(paths)?;
+    if paths.db_path.exists() {
+        open_existing(&paths.db_path).await
+    } else {
+        open_pool(&paths.db_path).await
+    }
+}
+
+/// 打开（必要时创建）数据库文件并初始化表
+pub async fn open_pool(db_path: &Path) -> Result<Pool<Sqlite>, String> {
     // 确保目录存在
-    std::fs::create_dir_all(&app_config_dir)
-        .map_err(|e| format!("无法创建目录: {}", e))?;
+    if let Some(dir) = db_path.parent() {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| format!("无法创建目录: {}", e))?;
+    }
 
-    // 数据库文件路径
-    let db_path = app_config_dir.join("data.db");
-    let db_url = format!("sqlite://{}?mode=rwc", db_path.to_string_lossy());
+    connect(db_path, "rwc").await
+}
+
+/// 打开已有的数据库文件并初始化表，文件不存在时返回错误
+pub async fn open_existing(db_path: &Path) -> Result<Pool<Sqlite>, String> {
+    if !db_path.is_file() {
+        return Err(format!("数据库文件 {} 不存在", db_path.display()));
+    }
+    connect(db_path, "rw").await
+}
+
+async fn connect(db_path: &Path, mode: &str) -> Result<Pool<Sqlite>, String> {
+    let db_url = format!("sqlite://{}?mode={}", db_path.to_string_lossy(), mode);
 
     println!("数据库路径: {}", db_url);
 
@@ -47,12 +209,7 @@ pub async fn init_database(app: &AppHandle, db_state: DbState) -> Result<(), Str
     // 初始化表
     init_tables(&pool).await?;
 
-    // 保存到状态
-    let mut lock = db_state.pool.lock().await;
-    *lock = Some(pool);
-
-    println!("数据库初始化成功");
-    Ok(())
+    Ok(pool)
 }
 
 async fn init_tables(pool: &Pool<Sqlite>) -> Result<(), String> {
@@ -78,6 +235,12 @@ async fn init_tables(pool: &Pool<Sqlite>) -> Result<(), String> {
         .await
         .map_err(|e| format!("创建索引失败: {}", e))?;
 
+    // 分页查询使用的键集索引
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_ideas_page ON ideas(date, created_at, id)")
+        .execute(pool)
+        .await
+        .map_err(|e| format!("创建索引失败: {}", e))?;
+
     // done_tasks 表
     sqlx::query(
         r#"
@@ -88,7 +251,8 @@ async fn init_tables(pool: &Pool<Sqlite>) -> Result<(), String> {
             end_time INTEGER NOT NULL,
             attachments TEXT NOT NULL,
             created_at INTEGER NOT NULL,
-            date TEXT NOT NULL
+            date TEXT NOT NULL,
+            tags TEXT NOT NULL DEFAULT '[]'
         );
         "#
     )
@@ -96,12 +260,23 @@ async fn init_tables(pool: &Pool<Sqlite>) -> Result<(), String> {
     .await
     .map_err(|e| format!("创建 done_tasks 表失败: {}", e))?;
 
+    // 旧版本数据库没有 tags 列
+    ensure_column(pool, "done_tasks", "tags", "TEXT NOT NULL DEFAULT '[]'").await?;
+
+    // 旧版本数据库没有 archived 列
+    ensure_column(pool, "ideas", "archived", "INTEGER NOT NULL DEFAULT 0").await?;
+
     // 创建索引
     sqlx::query("CREATE INDEX IF NOT EXISTS idx_tasks_date ON done_tasks(date)")
         .execute(pool)
         .await
         .map_err(|e| format!("创建索引失败: {}", e))?;
 
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_tasks_page ON done_tasks(date, created_at, id)")
+        .execute(pool)
+        .await
+        .map_err(|e| format!("创建索引失败: {}", e))?;
+
     // prompts 表
     sqlx::query(
         r#"
@@ -124,5 +299,483 @@ async fn init_tables(pool: &Pool<Sqlite>) -> Result<(), String> {
         .await
         .map_err(|e| format!("创建索引失败: {}", e))?;
 
+    // chat_sessions 表
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS chat_sessions (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            title TEXT NOT NULL,
+            range_start TEXT,
+            range_end TEXT,
+            created_at INTEGER NOT NULL,
+            updated_at INTEGER NOT NULL
+        );
+        "#
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| format!("创建 chat_sessions 表失败: {}", e))?;
+
+    // chat_messages 表
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS chat_messages (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            session_id INTEGER NOT NULL REFERENCES chat_sessions(id) ON DELETE CASCADE,
+            role TEXT NOT NULL,
+            content TEXT NOT NULL,
+            tool_calls TEXT,
+            tool_call_id TEXT,
+            model TEXT,
+            prompt_tokens INTEGER,
+            completion_tokens INTEGER,
+            total_tokens INTEGER,
+            created_at INTEGER NOT NULL
+        );
+        "#
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| format!("创建 chat_messages 表失败: {}", e))?;
+
+    // 创建索引
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_chat_messages_session ON chat_messages(session_id, id)")
+        .execute(pool)
+        .await
+        .map_err(|e| format!("创建索引失败: {}", e))?;
+
+    // ai_response_cache 表
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS ai_response_cache (
+            cache_key TEXT PRIMARY KEY,
+            model TEXT NOT NULL,
+            response TEXT NOT NULL,
+            size_bytes INTEGER NOT NULL,
+            created_at INTEGER NOT NULL,
+            last_accessed_at INTEGER NOT NULL,
+            hit_count INTEGER NOT NULL DEFAULT 0
+        );
+        "#
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| format!("创建 ai_response_cache 表失败: {}", e))?;
+
+    // 创建索引
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_ai_cache_accessed ON ai_response_cache(last_accessed_at)")
+        .execute(pool)
+        .await
+        .map_err(|e| format!("创建索引失败: {}", e))?;
+
+    // reports 表
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS reports (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            title TEXT NOT NULL,
+            content TEXT NOT NULL,
+            start_date TEXT NOT NULL,
+            end_date TEXT NOT NULL,
+            model TEXT,
+            created_at INTEGER NOT NULL
+        );
+        "#
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| format!("创建 reports 表失败: {}", e))?;
+
+    // 创建索引
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_reports_created ON reports(created_at)")
+        .execute(pool)
+        .await
+        .map_err(|e| format!("创建索引失败: {}", e))?;
+
+    // publish_targets 表
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS publish_targets (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL,
+            platform TEXT NOT NULL,
+            webhook_url TEXT NOT NULL,
+            secret TEXT,
+            enabled INTEGER NOT NULL DEFAULT 1,
+            created_at INTEGER NOT NULL,
+            updated_at INTEGER NOT NULL
+        );
+        "#
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| format!("创建 publish_targets 表失败: {}", e))?;
+
+    // report_publications 表
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS report_publications (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            report_id INTEGER NOT NULL REFERENCES reports(id) ON DELETE CASCADE,
+            target_id INTEGER REFERENCES publish_targets(id) ON DELETE SET NULL,
+            target_name TEXT NOT NULL,
+            platform TEXT NOT NULL,
+            success INTEGER NOT NULL,
+            error TEXT,
+            response TEXT,
+            created_at INTEGER NOT NULL
+        );
+        "#
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| format!("创建 report_publications 表失败: {}", e))?;
+
+    // 创建索引
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_report_publications_report ON report_publications(report_id, id)")
+        .execute(pool)
+        .await
+        .map_err(|e| format!("创建索引失败: {}", e))?;
+
+    // report_emails 表
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS report_emails (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            report_id INTEGER NOT NULL REFERENCES reports(id) ON DELETE CASCADE,
+            recipients TEXT NOT NULL,
+            subject TEXT NOT NULL,
+            attached_images INTEGER NOT NULL DEFAULT 0,
+            success INTEGER NOT NULL,
+            error TEXT,
+            created_at INTEGER NOT NULL
+        );
+        "#
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| format!("创建 report_emails 表失败: {}", e))?;
+
+    // 创建索引
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_report_emails_report ON report_emails(report_id, id)")
+        .execute(pool)
+        .await
+        .map_err(|e| format!("创建索引失败: {}", e))?;
+
+    // webhooks 表
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS webhooks (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL,
+            url TEXT NOT NULL,
+            events TEXT NOT NULL DEFAULT '[]',
+            body_template TEXT,
+            headers TEXT NOT NULL DEFAULT '{}',
+            secret TEXT,
+            enabled INTEGER NOT NULL DEFAULT 1,
+            created_at INTEGER NOT NULL,
+            updated_at INTEGER NOT NULL
+        );
+        "#
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| format!("创建 webhooks 表失败: {}", e))?;
+
+    // webhook_deliveries 表（status 为 pending 的记录即重试队列）
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS webhook_deliveries (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            webhook_id INTEGER NOT NULL REFERENCES webhooks(id) ON DELETE CASCADE,
+            event TEXT NOT NULL,
+            payload TEXT NOT NULL,
+            status TEXT NOT NULL,
+            attempts INTEGER NOT NULL DEFAULT 0,
+            next_attempt_at INTEGER,
+            response_status INTEGER,
+            response_body TEXT,
+            error TEXT,
+            created_at INTEGER NOT NULL,
+            updated_at INTEGER NOT NULL
+        );
+        "#
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| format!("创建 webhook_deliveries 表失败: {}", e))?;
+
+    // 创建索引
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_webhook_deliveries_webhook ON webhook_deliveries(webhook_id, id)")
+        .execute(pool)
+        .await
+        .map_err(|e| format!("创建索引失败: {}", e))?;
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_webhook_deliveries_due ON webhook_deliveries(status, next_attempt_at)")
+        .execute(pool)
+        .await
+        .map_err(|e| format!("创建索引失败: {}", e))?;
+
+    // planned_tasks 表
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS planned_tasks (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            content TEXT NOT NULL,
+            target_date TEXT NOT NULL,
+            original_date TEXT NOT NULL,
+            estimate_minutes INTEGER,
+            priority TEXT NOT NULL DEFAULT 'normal',
+            status TEXT NOT NULL DEFAULT 'pending',
+            tags TEXT NOT NULL DEFAULT '[]',
+            carry_count INTEGER NOT NULL DEFAULT 0,
+            done_task_id INTEGER,
+            created_at INTEGER NOT NULL,
+            updated_at INTEGER NOT NULL,
+            completed_at INTEGER
+        );
+        "#
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| format!("创建 planned_tasks 表失败: {}", e))?;
+
+    // planned_task_carries 表（顺延历史，用于还原某天原本的计划）
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS planned_task_carries (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            planned_task_id INTEGER NOT NULL REFERENCES planned_tasks(id) ON DELETE CASCADE,
+            from_date TEXT NOT NULL,
+            to_date TEXT NOT NULL,
+            created_at INTEGER NOT NULL
+        );
+        "#
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| format!("创建 planned_task_carries 表失败: {}", e))?;
+
+    // 创建索引
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_planned_tasks_date ON planned_tasks(target_date, status)")
+        .execute(pool)
+        .await
+        .map_err(|e| format!("创建索引失败: {}", e))?;
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_planned_task_carries_from ON planned_task_carries(from_date)")
+        .execute(pool)
+        .await
+        .map_err(|e| format!("创建索引失败: {}", e))?;
+
+    // recurring_templates 表
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS recurring_templates (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            content TEXT NOT NULL,
+            rule TEXT NOT NULL,
+            start_time TEXT NOT NULL,
+            end_time TEXT NOT NULL,
+            tags TEXT NOT NULL DEFAULT '[]',
+            enabled INTEGER NOT NULL DEFAULT 1,
+            created_at INTEGER NOT NULL,
+            updated_at INTEGER NOT NULL
+        );
+        "#
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| format!("创建 recurring_templates 表失败: {}", e))?;
+
+    // recurring_occurrences 表（每个模板每天最多一条，确认或跳过后也保留，避免重复生成）
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS recurring_occurrences (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            template_id INTEGER NOT NULL REFERENCES recurring_templates(id) ON DELETE CASCADE,
+            date TEXT NOT NULL,
+            content TEXT NOT NULL,
+            start_time INTEGER NOT NULL,
+            end_time INTEGER NOT NULL,
+            tags TEXT NOT NULL DEFAULT '[]',
+            status TEXT NOT NULL DEFAULT 'draft',
+            done_task_id INTEGER,
+            created_at INTEGER NOT NULL,
+            updated_at INTEGER NOT NULL,
+            UNIQUE (template_id, date)
+        );
+        "#
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| format!("创建 recurring_occurrences 表失败: {}", e))?;
+
+    // 创建索引
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_recurring_occurrences_date ON recurring_occurrences(date, status)")
+        .execute(pool)
+        .await
+        .map_err(|e| format!("创建索引失败: {}", e))?;
+
+    // idea_links 表（想法与由其转化的计划、已完成事项）
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS idea_links (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            idea_id INTEGER NOT NULL,
+            planned_task_id INTEGER,
+            done_task_id INTEGER,
+            created_at INTEGER NOT NULL
+        );
+        "#
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| format!("创建 idea_links 表失败: {}", e))?;
+
+    // 创建索引
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_idea_links_idea ON idea_links(idea_id)")
+        .execute(pool)
+        .await
+        .map_err(|e| format!("创建索引失败: {}", e))?;
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_idea_links_done ON idea_links(done_task_id)")
+        .execute(pool)
+        .await
+        .map_err(|e| format!("创建索引失败: {}", e))?;
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_idea_links_planned ON idea_links(planned_task_id)")
+        .execute(pool)
+        .await
+        .map_err(|e| format!("创建索引失败: {}", e))?;
+
+    // audit_log 表（只追加）
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS audit_log (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            at INTEGER NOT NULL,
+            origin TEXT NOT NULL,
+            entity TEXT NOT NULL,
+            entity_id INTEGER NOT NULL,
+            action TEXT NOT NULL,
+            before TEXT,
+            after TEXT
+        );
+        "#
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| format!("创建 audit_log 表失败: {}", e))?;
+
+    // 禁止修改和删除审计日志
+    sqlx::query(
+        r#"
+        CREATE TRIGGER IF NOT EXISTS audit_log_no_update BEFORE UPDATE ON audit_log
+        BEGIN
+            SELECT RAISE(ABORT, '审计日志不可修改');
+        END;
+        "#
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| format!("创建触发器失败: {}", e))?;
+
+    sqlx::query(
+        r#"
+        CREATE TRIGGER IF NOT EXISTS audit_log_no_delete BEFORE DELETE ON audit_log
+        BEGIN
+            SELECT RAISE(ABORT, '审计日志不可删除');
+        END;
+        "#
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| format!("创建触发器失败: {}", e))?;
+
+    // 创建索引
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_audit_log_entity ON audit_log(entity, entity_id)")
+        .execute(pool)
+        .await
+        .map_err(|e| format!("创建索引失败: {}", e))?;
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_audit_log_at ON audit_log(at)")
+        .execute(pool)
+        .await
+        .map_err(|e| format!("创建索引失败: {}", e))?;
+
     Ok(())
 }
+
+/// 若表中缺少指定列则添加（用于旧版本数据库迁移）
+async fn ensure_column(
+    pool: &Pool<Sqlite>,
+    table: &str,
+    column: &str,
+    definition: &str,
+) -> Result<(), String> {
+    let rows = sqlx::query(&format!("PRAGMA table_info({})", table))
+        .fetch_all(pool)
+        .await
+        .map_err(|e| format!("读取 {} 表结构失败: {}", table, e))?;
+
+    let exists = rows
+        .iter()
+        .any(|row| row.try_get::<String, _>("name").map(|name| name == column).unwrap_or(false));
+
+    if !exists {
+        sqlx::query(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition))
+            .execute(pool)
+            .await
+            .map_err(|e| format!("为 {} 表添加 {} 列失败: {}", table, column, e))?;
+    }
+
+    Ok(())
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    fn paths(data_dir: &Path, db_dir: &Path) -> WorkspacePaths {
+        WorkspacePaths {
+            data_dir: data_dir.to_path_buf(),
+            db_dir: db_dir.to_path_buf(),
+            custom_db_dir: db_dir != data_dir,
+            previous_db_dir: None,
+            db_path: db_dir.join(workspaces::DB_FILE),
+            ai_config_path: data_dir.join("ai_config.json"),
+            attachment_dir: data_dir.join("attachments"),
+        }
+    }
+
+    #[tokio::test]
+    async fn unmounted_custom_dir_is_never_populated() {
+        let dir = TempDir::new();
+        // 卷未挂载时挂载点通常仍是一个空目录
+        let mount_point = dir.path().join("vault");
+        std::fs::create_dir_all(&mount_point).unwrap();
+        let paths = paths(dir.path(), &mount_point);
+
+        assert!(open_workspace(&paths).await.is_err());
+
+        let state = DbState::new();
+        assert!(state.reopen(&paths).await.is_err());
+        assert!(matches!(state.status(), DbStatus::Failed { .. }));
+        assert!(!paths.db_path.exists());
+    }
+
+    #[tokio::test]
+    async fn new_workspace_database_is_created_in_data_dir() {
+        let dir = TempDir::new();
+        let paths = paths(dir.path(), dir.path());
+
+        let pool = open_workspace(&paths).await.expect("应当创建数据库");
+        pool.close().await;
+        assert!(paths.db_path.is_file());
+
+        let state = DbState::new();
+        state.reopen(&paths).await.expect("应当重新打开已有的数据库");
+        assert_eq!(state.status(), DbStatus::Ready);
+    }
+}
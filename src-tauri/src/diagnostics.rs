@@ -0,0 +1,405 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use sqlx::{Pool, Row, Sqlite};
+use tauri::AppHandle;
+
+use crate::audit;
+use crate::backups;
+use crate::database::DbState;
+use crate::models::{AuditEntity, AuditOrigin, DiagnosticIssue, DiagnosticsReport, IssueKind, RecurrenceRule};
+use crate::repo::{IdeaRepo, TaskRepo};
+
+/// JSON 列应有的格式
+#[derive(Clone, Copy)]
+enum JsonShape {
+    StringList, // 字符串数组，无法解析时可重置为 []
+    StringMap, // 字符串键值对象，无法解析时可重置为 {}
+    RecurrenceRule, // 重复规则，无法自动修复
+}
+
+const JSON_COLUMNS: &[(&str, &str, JsonShape)] = &[
+    ("ideas", "attachments", JsonShape::StringList),
+    ("done_tasks", "attachments", JsonShape::StringList),
+    ("done_tasks", "tags", JsonShape::StringList),
+    ("planned_tasks", "tags", JsonShape::StringList),
+    ("recurring_templates", "tags", JsonShape::StringList),
+    ("recurring_templates", "rule", JsonShape::RecurrenceRule),
+    ("recurring_occurrences", "tags", JsonShape::StringList),
+    ("webhooks", "events", JsonShape::StringList),
+    ("webhooks", "headers", JsonShape::StringMap),
+];
+
+/// 带附件的表
+const ATTACHMENT_TABLES: &[&str] = &["ideas", "done_tasks"];
+
+/// date 列及其来源时间戳列
+const DATE_COLUMNS: &[(&str, &str)] = &[("ideas", "created_at"), ("done_tasks", "start_time")];
+
+fn issue(kind: IssueKind, table: &str, row_id: Option<i64>, column: Option<&str>, message: String, repairable: bool) -> DiagnosticIssue {
+    DiagnosticIssue {
+        kind,
+        table: Some(table.to_string()).filter(|t| !t.is_empty()),
+        row_id,
+        column: column.map(str::to_string),
+        message,
+        repairable,
+        repaired: false,
+    }
+}
+
+/// 与写入时相同的规则：由时间戳得到日期字符串
+fn date_of(timestamp: i64) -> Option<String> {
+    chrono::DateTime::from_timestamp(timestamp, 0).map(|dt| dt.format("%Y-%m-%d").to_string())
+}
+
+fn shape_ok(shape: JsonShape, value: &str) -> bool {
+    match shape {
+        JsonShape::StringList => serde_json::from_str::<Vec<String>>(value).is_ok(),
+        JsonShape::StringMap => serde_json::from_str::<BTreeMap<String, String>>(value).is_ok(),
+        JsonShape::RecurrenceRule => serde_json::from_str::<RecurrenceRule>(value).is_ok(),
+    }
+}
+
+/// 修复单行的某一列；想法和事项的修复同时写入审计日志
+async fn repair_column(
+    pool: &Pool<Sqlite>,
+    table: &str,
+    column: &str,
+    value: &str,
+    id: i64,
+    origin: AuditOrigin,
+) -> Result<(), String> {
+    let mut tx = pool.begin().await.map_err(|e| format!("开启事务失败: {}", e))?;
+    let update = format!("UPDATE {} SET {} = ? WHERE id = ?", table, column);
+    let write_err = |e: sqlx::Error| format!("修复 {}.{} 失败: {}", table, column, e);
+
+    match table {
+        "ideas" => {
+            let before = IdeaRepo::get_with(&mut tx, id).await?;
+            sqlx::query(&update).bind(value).bind(id).execute(&mut *tx).await.map_err(write_err)?;
+            let after = IdeaRepo::get_with(&mut tx, id).await?;
+            if let (Some(before), Some(after)) = (before, after) {
+                audit::record_update(&mut tx, origin, AuditEntity::Idea, id, &before, &after).await?;
+            }
+        }
+        "done_tasks" => {
+            let before = TaskRepo::get_with(&mut tx, id).await?;
+            sqlx::query(&update).bind(value).bind(id).execute(&mut *tx).await.map_err(write_err)?;
+            let after = TaskRepo::get_with(&mut tx, id).await?;
+            if let (Some(before), Some(after)) = (before, after) {
+                audit::record_update(&mut tx, origin, AuditEntity::Task, id, &before, &after).await?;
+            }
+        }
+        _ => {
+            sqlx::query(&update).bind(value).bind(id).execute(&mut *tx).await.map_err(write_err)?;
+        }
+    }
+
+    tx.commit().await.map_err(|e| format!("提交事务失败: {}", e))
+}
+
+async fn check_integrity(pool: &Pool<Sqlite>, issues: &mut Vec<DiagnosticIssue>) -> Result<bool, String> {
+    let messages: Vec<String> = sqlx::query_scalar("PRAGMA integrity_check")
+        .fetch_all(pool)
+        .await
+        .map_err(|e| format!("完整性检查失败: {}", e))?;
+
+    let ok = messages.len() == 1 && messages[0] == "ok";
+    if !ok {
+        for message in messages {
+            issues.push(issue(IssueKind::Integrity, "", None, None, message, false));
+        }
+    }
+    Ok(ok)
+}
+
+async fn check_foreign_keys(pool: &Pool<Sqlite>, repair: bool, issues: &mut Vec<DiagnosticIssue>) -> Result<(), String> {
+    let rows = sqlx::query("PRAGMA foreign_key_check")
+        .fetch_all(pool)
+        .await
+        .map_err(|e| format!("外键检查失败: {}", e))?;
+
+    for row in &rows {
+        let table: String = row.try_get(0).map_err(|e| format!("读取外键检查结果失败: {}", e))?;
+        let row_id: Option<i64> = row.try_get(1).map_err(|e| format!("读取外键检查结果失败: {}", e))?;
+        let parent: String = row.try_get(2).map_err(|e| format!("读取外键检查结果失败: {}", e))?;
+
+        // 引用已不存在的子记录没有意义，可以删除
+        let mut found = issue(
+            IssueKind::ForeignKey,
+            &table,
+            row_id,
+            None,
+            format!("{} 的记录引用的 {} 记录不存在", table, parent),
+            row_id.is_some(),
+        );
+        if repair {
+            if let Some(row_id) = row_id {
+                sqlx::query(&format!("DELETE FROM \"{}\" WHERE rowid = ?", table.replace('"', "\"\"")))
+                    .bind(row_id)
+                    .execute(pool)
+                    .await
+                    .map_err(|e| format!("删除孤立记录失败: {}", e))?;
+                found.repaired = true;
+            }
+        }
+        issues.push(found);
+    }
+    Ok(())
+}
+
+/// idea_links 没有外键约束，需单独检查想法或事项已被删除的关联
+async fn check_idea_links(pool: &Pool<Sqlite>, repair: bool, issues: &mut Vec<DiagnosticIssue>) -> Result<(), String> {
+    let rows = sqlx::query(
+        r#"
+        SELECT id FROM idea_links
+        WHERE idea_id NOT IN (SELECT id FROM ideas)
+           OR (planned_task_id IS NOT NULL AND planned_task_id NOT IN (SELECT id FROM planned_tasks))
+           OR (done_task_id IS NOT NULL AND done_task_id NOT IN (SELECT id FROM done_tasks))
+        "#,
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("检查想法关联失败: {}", e))?;
+
+    for row in &rows {
+        let id: i64 = row.try_get("id").map_err(|e| format!("读取想法关联失败: {}", e))?;
+        let mut found = issue(
+            IssueKind::ForeignKey,
+            "idea_links",
+            Some(id),
+            None,
+            format!("想法关联 {} 引用的想法、计划或事项不存在", id),
+            true,
+        );
+        if repair {
+            // 与删除时的处理一致：解除失效的一端，两端都失效或想法不存在时删除关联
+            sqlx::query(
+                r#"
+                UPDATE idea_links SET
+                    planned_task_id = CASE WHEN planned_task_id IN (SELECT id FROM planned_tasks) THEN planned_task_id END,
+                    done_task_id = CASE WHEN done_task_id IN (SELECT id FROM done_tasks) THEN done_task_id END
+                WHERE id = ?
+                "#,
+            )
+            .bind(id)
+            .execute(pool)
+            .await
+            .map_err(|e| format!("修复想法关联失败: {}", e))?;
+            sqlx::query(
+                r#"
+                DELETE FROM idea_links
+                WHERE id = ?
+                  AND (idea_id NOT IN (SELECT id FROM ideas) OR (planned_task_id IS NULL AND done_task_id IS NULL))
+                "#,
+            )
+            .bind(id)
+            .execute(pool)
+            .await
+            .map_err(|e| format!("修复想法关联失败: {}", e))?;
+            found.repaired = true;
+        }
+        issues.push(found);
+    }
+    Ok(())
+}
+
+async fn check_json_columns(
+    pool: &Pool<Sqlite>,
+    repair: bool,
+    origin: AuditOrigin,
+    issues: &mut Vec<DiagnosticIssue>,
+) -> Result<(), String> {
+    for &(table, column, shape) in JSON_COLUMNS {
+        let rows = sqlx::query(&format!("SELECT id, {} AS value FROM {}", column, table))
+            .fetch_all(pool)
+            .await
+            .map_err(|e| format!("读取 {}.{} 失败: {}", table, column, e))?;
+
+        for row in &rows {
+            let id: i64 = row.try_get("id").map_err(|e| format!("读取 {} 失败: {}", table, e))?;
+            let value: Option<String> = row.try_get("value").unwrap_or(None);
+            if value.as_deref().is_some_and(|v| shape_ok(shape, v)) {
+                continue;
+            }
+
+            let reset = match shape {
+                JsonShape::StringList => Some("[]"),
+                JsonShape::StringMap => Some("{}"),
+                JsonShape::RecurrenceRule => None,
+            };
+            let mut found = issue(
+                IssueKind::InvalidJson,
+                table,
+                Some(id),
+                Some(column),
+                format!("{} {} 的 {} 不是有效的 JSON: {}", table, id, column, value.unwrap_or_else(|| "NULL".to_string())),
+                reset.is_some(),
+            );
+            if let (true, Some(reset)) = (repair, reset) {
+                repair_column(pool, table, column, reset, id, origin).await?;
+                found.repaired = true;
+            }
+            issues.push(found);
+        }
+    }
+    Ok(())
+}
+
+async fn check_attachments(
+    pool: &Pool<Sqlite>,
+    repair: bool,
+    origin: AuditOrigin,
+    issues: &mut Vec<DiagnosticIssue>,
+) -> Result<(), String> {
+    for &table in ATTACHMENT_TABLES {
+        let rows = sqlx::query(&format!("SELECT id, attachments FROM {}", table))
+            .fetch_all(pool)
+            .await
+            .map_err(|e| format!("读取 {} 附件失败: {}", table, e))?;
+
+        for row in &rows {
+            let id: i64 = row.try_get("id").map_err(|e| format!("读取 {} 失败: {}", table, e))?;
+            let value: Option<String> = row.try_get("attachments").unwrap_or(None);
+            // 无法解析的已在 JSON 检查中报告
+            let Some(attachments) = value.and_then(|v| serde_json::from_str::<Vec<String>>(&v).ok()) else {
+                continue;
+            };
+
+            let (existing, missing): (Vec<String>, Vec<String>) =
+                attachments.into_iter().partition(|path| Path::new(path).exists());
+            if missing.is_empty() {
+                continue;
+            }
+
+            // 只移除失效的引用，不影响其他附件
+            let mut repaired = false;
+            if repair {
+                let existing = serde_json::to_string(&existing).unwrap_or_else(|_| "[]".to_string());
+                repair_column(pool, table, "attachments", &existing, id, origin).await?;
+                repaired = true;
+            }
+            for path in missing {
+                let mut found = issue(
+                    IssueKind::MissingAttachment,
+                    table,
+                    Some(id),
+                    Some("attachments"),
+                    format!("{} {} 的附件不存在: {}", table, id, path),
+                    true,
+                );
+                found.repaired = repaired;
+                issues.push(found);
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn check_dates(
+    pool: &Pool<Sqlite>,
+    repair: bool,
+    origin: AuditOrigin,
+    issues: &mut Vec<DiagnosticIssue>,
+) -> Result<(), String> {
+    for &(table, timestamp_column) in DATE_COLUMNS {
+        let rows = sqlx::query(&format!("SELECT id, date, {} AS ts FROM {}", timestamp_column, table))
+            .fetch_all(pool)
+            .await
+            .map_err(|e| format!("读取 {} 日期失败: {}", table, e))?;
+
+        for row in &rows {
+            let id: i64 = row.try_get("id").map_err(|e| format!("读取 {} 失败: {}", table, e))?;
+            let date: Option<String> = row.try_get("date").unwrap_or(None);
+            let timestamp: Option<i64> = row.try_get("ts").unwrap_or(None);
+            let expected = timestamp.and_then(date_of);
+            if expected.is_none() || date == expected {
+                continue;
+            }
+
+            let expected = expected.unwrap_or_default();
+            let mut found = issue(
+                IssueKind::DateMismatch,
+                table,
+                Some(id),
+                Some("date"),
+                format!(
+                    "{} {} 的日期 {} 与 {} 对应的日期 {} 不一致",
+                    table,
+                    id,
+                    date.unwrap_or_else(|| "NULL".to_string()),
+                    timestamp_column,
+                    expected
+                ),
+                true,
+            );
+            if repair {
+                repair_column(pool, table, "date", &expected, id, origin).await?;
+                found.repaired = true;
+            }
+            issues.push(found);
+        }
+    }
+    Ok(())
+}
+
+async fn check_ranges(pool: &Pool<Sqlite>, issues: &mut Vec<DiagnosticIssue>) -> Result<(), String> {
+    let rows = sqlx::query("SELECT id, start_time, end_time FROM done_tasks WHERE end_time < start_time")
+        .fetch_all(pool)
+        .await
+        .map_err(|e| format!("读取事项时间失败: {}", e))?;
+
+    for row in &rows {
+        let read_err = |e: sqlx::Error| format!("读取事项失败: {}", e);
+        let id: i64 = row.try_get("id").map_err(read_err)?;
+        let start_time: i64 = row.try_get("start_time").map_err(read_err)?;
+        let end_time: i64 = row.try_get("end_time").map_err(read_err)?;
+        // 无法判断哪个时间是对的，需要人工处理
+        issues.push(issue(
+            IssueKind::InvertedRange,
+            "done_tasks",
+            Some(id),
+            Some("end_time"),
+            format!("事项 {} 的结束时间 {} 早于开始时间 {}", id, end_time, start_time),
+            false,
+        ));
+    }
+    Ok(())
+}
+
+/// 检查数据库；`repair` 为 true 时先生成快照，再修复可安全修复的问题
+///
+/// 完整性检查未通过时不做任何修复，避免在损坏的数据库上继续写入。
+pub async fn run_diagnostics(
+    app: &AppHandle,
+    db_state: &DbState,
+    repair: bool,
+    origin: AuditOrigin,
+) -> Result<DiagnosticsReport, String> {
+    let pool = db_state.get_pool().await?;
+    let mut issues = Vec::new();
+
+    let integrity_ok = check_integrity(&pool, &mut issues).await?;
+    let repair = repair && integrity_ok;
+    let snapshot = if repair {
+        Some(backups::create_snapshot(app, db_state).await?.name)
+    } else {
+        None
+    };
+
+    check_foreign_keys(&pool, repair, &mut issues).await?;
+    check_idea_links(&pool, repair, &mut issues).await?;
+    check_json_columns(&pool, repair, origin, &mut issues).await?;
+    check_attachments(&pool, repair, origin, &mut issues).await?;
+    check_dates(&pool, repair, origin, &mut issues).await?;
+    check_ranges(&pool, &mut issues).await?;
+
+    Ok(DiagnosticsReport {
+        integrity_ok,
+        repaired_count: issues.iter().filter(|i| i.repaired).count(),
+        issues,
+        snapshot,
+        checked_at: chrono::Local::now().timestamp(),
+    })
+}
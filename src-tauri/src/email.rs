@@ -0,0 +1,501 @@
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use lettre::message::header::ContentType;
+use lettre::message::{Attachment, Mailbox, MultiPart, SinglePart};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use pulldown_cmark::{html, CowStr, Event, Options, Parser, Tag};
+use sqlx::{sqlite::SqliteRow, Pool, Row, Sqlite};
+
+use crate::models::{Report, ReportEmail, SmtpConfig, SmtpTls};
+use crate::reports;
+
+/// SMTP 连接和发送超时
+const SMTP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// 单张图片附件的大小上限
+const MAX_IMAGE_BYTES: u64 = 10 * 1024 * 1024;
+
+/// 返回给前端的密码占位符；保存时收到占位符表示沿用已保存的密码
+pub const PASSWORD_MASK: &str = "********";
+
+const EMAIL_COLUMNS: &str = "id, report_id, recipients, subject, attached_images, success, error, created_at";
+
+fn email_from_row(row: &SqliteRow) -> Result<ReportEmail, String> {
+    let read_err = |e: sqlx::Error| format!("读取邮件发送记录失败: {}", e);
+    let recipients: String = row.try_get("recipients").map_err(read_err)?;
+    Ok(ReportEmail {
+        id: row.try_get("id").map_err(read_err)?,
+        report_id: row.try_get("report_id").map_err(read_err)?,
+        recipients: serde_json::from_str(&recipients).unwrap_or_default(),
+        subject: row.try_get("subject").map_err(read_err)?,
+        attached_images: row.try_get("attached_images").map_err(read_err)?,
+        success: row.try_get("success").map_err(read_err)?,
+        error: row.try_get("error").map_err(read_err)?,
+        created_at: row.try_get("created_at").map_err(read_err)?,
+    })
+}
+
+/// 校验 SMTP 配置
+pub fn validate_config(config: &SmtpConfig) -> Result<(), String> {
+    if config.host.trim().is_empty() {
+        return Err("SMTP 服务器地址不能为空".to_string());
+    }
+    if config.port == 0 {
+        return Err("SMTP 端口无效".to_string());
+    }
+    sender(config)?;
+    for recipient in &config.recipients {
+        parse_mailbox(recipient)?;
+    }
+    Ok(())
+}
+
+/// 返回给前端的配置，已保存的密码替换为占位符
+pub fn masked(mut config: SmtpConfig) -> SmtpConfig {
+    if !config.password.is_empty() {
+        config.password = PASSWORD_MASK.to_string();
+    }
+    config
+}
+
+/// 前端回传占位符时沿用已保存的密码
+pub fn keep_stored_password(mut config: SmtpConfig, stored: Option<&SmtpConfig>) -> SmtpConfig {
+    if config.password == PASSWORD_MASK {
+        config.password = stored.map(|s| s.password.clone()).unwrap_or_default();
+    }
+    config
+}
+
+fn parse_mailbox(address: &str) -> Result<Mailbox, String> {
+    address
+        .trim()
+        .parse::<Mailbox>()
+        .map_err(|e| format!("邮件地址无效 {}: {}", address, e))
+}
+
+fn sender(config: &SmtpConfig) -> Result<Mailbox, String> {
+    let mut mailbox = parse_mailbox(&config.from_address)?;
+    if let Some(name) = config.from_name.as_ref().filter(|n| !n.trim().is_empty()) {
+        mailbox.name = Some(name.trim().to_string());
+    }
+    Ok(mailbox)
+}
+
+/// 报告中引用的本地图片
+struct InlineImage {
+    content_id: String,
+    path: PathBuf,
+    content_type: &'static str,
+}
+
+/// 渲染后的邮件内容
+struct RenderedEmail {
+    text: String,
+    html: String,
+    images: Vec<InlineImage>,
+}
+
+fn image_content_type(path: &Path) -> Option<&'static str> {
+    let extension = path.extension()?.to_str()?.to_lowercase();
+    match extension.as_str() {
+        "png" => Some("image/png"),
+        "jpg" | "jpeg" => Some("image/jpeg"),
+        "gif" => Some("image/gif"),
+        "webp" => Some("image/webp"),
+        "bmp" => Some("image/bmp"),
+        _ => None,
+    }
+}
+
+/// 解码地址中的百分号编码，结果不是合法的 UTF-8 时返回 None
+fn percent_decode(value: &str) -> Option<String> {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            decoded.push(u8::from_str_radix(value.get(i + 1..i + 3)?, 16).ok()?);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
+
+/// 将图片地址解析为附件目录中的本地文件（附件保存的是本地绝对路径）
+///
+/// 报告内容可能由 AI 生成，只接受附件目录下的图片，避免把其他本地文件发送出去。
+fn local_image(url: &str, attachment_dir: &Path) -> Option<(PathBuf, &'static str)> {
+    let path = if let Some(rest) = url.strip_prefix("file://") {
+        // file://localhost/path 与 file:///path 等价
+        percent_decode(rest.strip_prefix("localhost").unwrap_or(rest))?
+    } else if let Some(rest) = url.strip_prefix("asset://localhost") {
+        // convertFileSrc 会对整个路径编码
+        percent_decode(rest)?
+    } else {
+        url.to_string()
+    };
+
+    // 相对路径和 http(s)、data 等其他地址都不处理
+    let path = PathBuf::from(path);
+    if !path.is_absolute() {
+        return None;
+    }
+    let content_type = image_content_type(&path)?;
+    let path = path.canonicalize().ok()?;
+    if !path.starts_with(attachment_dir.canonicalize().ok()?) {
+        return None;
+    }
+    let metadata = std::fs::metadata(&path).ok()?;
+    if !metadata.is_file() || metadata.len() > MAX_IMAGE_BYTES {
+        return None;
+    }
+    Some((path, content_type))
+}
+
+/// 将报告渲染为纯文本和 HTML；`attach_images` 为 true 时本地图片改为内嵌附件
+fn render(report: &Report, attach_images: bool, attachment_dir: &Path) -> RenderedEmail {
+    let mut images: Vec<InlineImage> = Vec::new();
+
+    let options = Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS;
+    let events = Parser::new_ext(&report.content, options).map(|event| match event {
+        Event::Start(Tag::Image { link_type, dest_url, title, id }) if attach_images => {
+            let dest_url = match local_image(&dest_url, attachment_dir) {
+                Some((path, content_type)) => {
+                    // 同一张图片只附加一次
+                    let content_id = match images.iter().find(|image| image.path == path) {
+                        Some(image) => image.content_id.clone(),
+                        None => {
+                            let content_id = format!("image{}@report-{}", images.len() + 1, report.id);
+                            images.push(InlineImage { content_id: content_id.clone(), path, content_type });
+                            content_id
+                        }
+                    };
+                    CowStr::from(format!("cid:{}", content_id))
+                }
+                None => dest_url,
+            };
+            Event::Start(Tag::Image { link_type, dest_url, title, id })
+        }
+        event => event,
+    });
+
+    let mut body = String::new();
+    html::push_html(&mut body, events);
+
+    let html = format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>{title}</title>
+<style>
+body {{ font-family: -apple-system, "PingFang SC", "Microsoft YaHei", sans-serif; font-size: 14px; line-height: 1.6; color: #1f2937; max-width: 760px; margin: 0 auto; padding: 16px; }}
+h1, h2, h3 {{ color: #111827; }}
+table {{ border-collapse: collapse; }}
+th, td {{ border: 1px solid #d1d5db; padding: 4px 8px; }}
+code {{ background: #f3f4f6; padding: 1px 4px; border-radius: 3px; }}
+pre {{ background: #f3f4f6; padding: 8px; overflow-x: auto; }}
+img {{ max-width: 100%; }}
+</style>
+</head>
+<body>
+{body}
+</body>
+</html>
+"#,
+        title = html_escape(&report.title),
+        body = body,
+    );
+
+    RenderedEmail {
+        text: report.content.clone(),
+        html,
+        images,
+    }
+}
+
+fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// 构建邮件，返回邮件和实际附加的图片数
+fn build_message(
+    config: &SmtpConfig,
+    recipients: &[Mailbox],
+    report: &Report,
+    attach_images: bool,
+    attachment_dir: &Path,
+) -> Result<(Message, usize), String> {
+    let rendered = render(report, attach_images, attachment_dir);
+
+    let mut builder = Message::builder().from(sender(config)?).subject(report.title.clone());
+    for recipient in recipients {
+        builder = builder.to(recipient.clone());
+    }
+
+    let mut related = MultiPart::related().singlepart(SinglePart::html(rendered.html.clone()));
+    let mut attached = 0;
+    for image in &rendered.images {
+        // 读取失败的图片跳过，HTML 中显示为无法加载
+        let Ok(bytes) = std::fs::read(&image.path) else {
+            continue;
+        };
+        let Ok(content_type) = ContentType::parse(image.content_type) else {
+            continue;
+        };
+        related = related.singlepart(Attachment::new_inline(image.content_id.clone()).body(bytes, content_type));
+        attached += 1;
+    }
+
+    let body = if attached == 0 {
+        MultiPart::alternative_plain_html(rendered.text, rendered.html)
+    } else {
+        MultiPart::alternative()
+            .singlepart(SinglePart::plain(rendered.text))
+            .multipart(related)
+    };
+
+    let message = builder
+        .multipart(body)
+        .map_err(|e| format!("构建邮件失败: {}", e))?;
+    Ok((message, attached))
+}
+
+async fn deliver(config: &SmtpConfig, message: Message) -> Result<(), String> {
+    let host = config.host.trim();
+    let builder = match config.tls {
+        SmtpTls::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(host),
+        SmtpTls::StartTls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host),
+        SmtpTls::None => Ok(AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(host)),
+    }
+    .map_err(|e| format!("SMTP 配置无效: {}", e))?;
+
+    let mut builder = builder.port(config.port).timeout(Some(SMTP_TIMEOUT));
+    if !config.username.is_empty() {
+        builder = builder.credentials(Credentials::new(config.username.clone(), config.password.clone()));
+    }
+
+    builder
+        .build()
+        .send(message)
+        .await
+        .map_err(|e| format!("发送邮件失败: {}", e))?;
+    Ok(())
+}
+
+/// 通过 SMTP 发送报告；`recipients` 为空时使用配置中的默认收件人
+///
+/// 只内嵌 `attachment_dir` 中的图片。发送结果（无论成功与否）都会记录到 report_emails。
+pub async fn send_report(
+    pool: &Pool<Sqlite>,
+    config: &SmtpConfig,
+    report_id: i64,
+    recipients: Option<Vec<String>>,
+    attach_images: Option<bool>,
+    attachment_dir: &Path,
+) -> Result<ReportEmail, String> {
+    validate_config(config)?;
+    let report = reports::get_report(pool, report_id).await?;
+
+    let recipients: Vec<String> = recipients
+        .filter(|r| !r.is_empty())
+        .unwrap_or_else(|| config.recipients.clone())
+        .into_iter()
+        .map(|r| r.trim().to_string())
+        .filter(|r| !r.is_empty())
+        .collect();
+    if recipients.is_empty() {
+        return Err("请填写收件人".to_string());
+    }
+    let mailboxes = recipients
+        .iter()
+        .map(|r| parse_mailbox(r))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let attach_images = attach_images.unwrap_or(config.attach_images);
+    let (attached, result) = match build_message(config, &mailboxes, &report, attach_images, attachment_dir) {
+        Ok((message, attached)) => (attached, deliver(config, message).await),
+        Err(e) => (0, Err(e)),
+    };
+
+    let id = sqlx::query(
+        r#"
+        INSERT INTO report_emails (report_id, recipients, subject, attached_images, success, error, created_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?)
+        "#,
+    )
+    .bind(report.id)
+    .bind(serde_json::to_string(&recipients).unwrap_or_else(|_| "[]".to_string()))
+    .bind(&report.title)
+    .bind(attached as i64)
+    .bind(result.is_ok())
+    .bind(result.err())
+    .bind(chrono::Local::now().timestamp())
+    .execute(pool)
+    .await
+    .map_err(|e| format!("保存邮件发送记录失败: {}", e))?
+    .last_insert_rowid();
+
+    let row = sqlx::query(&format!("SELECT {} FROM report_emails WHERE id = ?", EMAIL_COLUMNS))
+        .bind(id)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| format!("查询邮件发送记录失败: {}", e))?;
+    email_from_row(&row)
+}
+
+/// 读取报告的邮件发送记录
+pub async fn list_sends(pool: &Pool<Sqlite>, report_id: i64) -> Result<Vec<ReportEmail>, String> {
+    let rows = sqlx::query(&format!(
+        "SELECT {} FROM report_emails WHERE report_id = ? ORDER BY id DESC",
+        EMAIL_COLUMNS
+    ))
+    .bind(report_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("查询邮件发送记录失败: {}", e))?;
+    rows.iter().map(email_from_row).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use base64::Engine;
+
+    use super::*;
+    use crate::models::AuditOrigin;
+    use crate::test_support::{temp_pool, SmtpSink, TempDir};
+
+    fn sink_config(sink: &SmtpSink) -> SmtpConfig {
+        SmtpConfig {
+            host: "127.0.0.1".to_string(),
+            port: sink.port,
+            tls: SmtpTls::None,
+            from_address: "reporter@example.com".to_string(),
+            from_name: Some("日报助手".to_string()),
+            recipients: vec!["lead@example.com".to_string()],
+            ..Default::default()
+        }
+    }
+
+    fn attachments(dir: &TempDir) -> PathBuf {
+        dir.path().join("attachments")
+    }
+
+    /// 保存一份引用了附件目录中图片的报告
+    async fn report_with_image() -> (Pool<Sqlite>, i64, TempDir) {
+        let (pool, dir) = temp_pool().await;
+        std::fs::create_dir_all(attachments(&dir)).unwrap();
+        let image = attachments(&dir).join("shot.png");
+        std::fs::write(&image, b"\x89PNG\r\n\x1a\nfake").unwrap();
+        let content = format!("# Weekly\n\n![shot]({})\n\n- done", image.display());
+        let report_id =
+            reports::save_report(&pool, "Daily report", &content, "2024-05-06", "2024-05-06", None, AuditOrigin::Ui)
+                .await
+                .unwrap();
+        (pool, report_id, dir)
+    }
+
+    #[tokio::test]
+    async fn delivers_report_with_inline_images() {
+        let sink = SmtpSink::start().await;
+        let (pool, report_id, dir) = report_with_image().await;
+
+        let sent = send_report(&pool, &sink_config(&sink), report_id, None, None, &attachments(&dir)).await.unwrap();
+        assert!(sent.success, "{:?}", sent.error);
+        assert_eq!(sent.attached_images, 1);
+        assert_eq!(sent.recipients, vec!["lead@example.com"]);
+
+        let mails = sink.mails();
+        assert_eq!(mails.len(), 1);
+        let mail = &mails[0];
+        assert_eq!(mail.auth, None);
+        assert_eq!(mail.from, "reporter@example.com");
+        assert_eq!(mail.recipients, vec!["lead@example.com"]);
+        assert!(mail.data.contains("Subject: Daily report"));
+        assert!(mail.data.contains("multipart/alternative"));
+        assert!(mail.data.contains("multipart/related"));
+        assert!(mail.data.contains(&format!("Content-ID: <image1@report-{}>", report_id)));
+    }
+
+    #[tokio::test]
+    async fn explicit_recipients_and_credentials_are_used() {
+        let sink = SmtpSink::start().await;
+        let (pool, report_id, dir) = report_with_image().await;
+        let config = SmtpConfig { username: "reporter".to_string(), password: "s3cret".to_string(), ..sink_config(&sink) };
+
+        let recipients = vec![" a@example.com ".to_string(), "b@example.com".to_string()];
+        let sent = send_report(&pool, &config, report_id, Some(recipients), Some(false), &attachments(&dir)).await.unwrap();
+        assert!(sent.success, "{:?}", sent.error);
+        assert_eq!(sent.attached_images, 0);
+
+        let mail = sink.mails().remove(0);
+        assert_eq!(mail.recipients, vec!["a@example.com", "b@example.com"]);
+        assert!(!mail.data.contains("multipart/related"));
+        let auth = mail.auth.expect("没有进行 SMTP 认证");
+        let (mechanism, credentials) = auth.split_once(' ').unwrap();
+        assert_eq!(mechanism, "PLAIN");
+        let decoded = base64::engine::general_purpose::STANDARD.decode(credentials).unwrap();
+        assert_eq!(decoded, b"\0reporter\0s3cret");
+    }
+
+    #[tokio::test]
+    async fn rejected_delivery_is_recorded_as_failure() {
+        let sink = SmtpSink::rejecting().await;
+        let (pool, report_id, dir) = report_with_image().await;
+
+        let sent = send_report(&pool, &sink_config(&sink), report_id, None, None, &attachments(&dir)).await.unwrap();
+        assert!(!sent.success);
+        assert!(sent.error.as_deref().unwrap().starts_with("发送邮件失败"));
+        assert!(sink.mails().is_empty());
+
+        let sends = list_sends(&pool, report_id).await.unwrap();
+        assert_eq!(sends.len(), 1);
+        assert!(!sends[0].success);
+    }
+
+    #[test]
+    fn only_images_in_the_attachment_dir_are_embedded() {
+        let dir = TempDir::new();
+        std::fs::create_dir_all(attachments(&dir)).unwrap();
+        let inside = attachments(&dir).join("截图 1.png");
+        let outside = dir.path().join("id.png");
+        std::fs::write(&inside, b"png").unwrap();
+        std::fs::write(&outside, b"png").unwrap();
+        let root = attachments(&dir);
+        let resolved = |url: &str| local_image(url, &root).map(|(path, _)| path);
+        let expected = Some(inside.canonicalize().unwrap());
+
+        assert_eq!(resolved(&inside.display().to_string()), expected);
+        let encoded = inside.display().to_string().replace("截图", "%E6%88%AA%E5%9B%BE").replace(' ', "%20");
+        assert_eq!(resolved(&format!("file://{}", encoded)), expected);
+        assert_eq!(resolved(&format!("asset://localhost/{}", encoded.replace('/', "%2F"))), expected);
+
+        assert_eq!(resolved(&outside.display().to_string()), None);
+        assert_eq!(resolved(&format!("file://{}", outside.display())), None);
+        let escaped = attachments(&dir).join("..").join("id.png");
+        assert_eq!(resolved(&escaped.display().to_string()), None);
+        assert_eq!(resolved("https://example.com/a.png"), None);
+    }
+
+    #[test]
+    fn masked_password_keeps_stored_value() {
+        let stored = SmtpConfig { password: "s3cret".to_string(), ..Default::default() };
+        let shown = masked(stored.clone());
+        assert_eq!(shown.password, PASSWORD_MASK);
+
+        let saved = keep_stored_password(shown.clone(), Some(&stored));
+        assert_eq!(saved.password, "s3cret");
+
+        let changed = keep_stored_password(SmtpConfig { password: "new".to_string(), ..shown }, Some(&stored));
+        assert_eq!(changed.password, "new");
+        assert_eq!(masked(SmtpConfig::default()).password, "");
+    }
+}
@@ -0,0 +1,306 @@
+use sqlx::{sqlite::SqliteRow, Pool, Row, Sqlite, SqliteConnection};
+
+use crate::models::{AuditOrigin, Idea, IdeaLink, IdeaPromotion, NewDoneTask, NewPlannedTask, PromoteTarget};
+use crate::planning;
+use crate::repo::{IdeaRepo, TaskRepo};
+
+/// 关联查询会同时带出想法的内容和日期
+const LINK_SELECT: &str = r#"
+    SELECT l.id, l.idea_id, i.content AS idea_content, i.date AS idea_date,
+           l.planned_task_id, l.done_task_id, l.created_at
+    FROM idea_links l
+    JOIN ideas i ON i.id = l.idea_id
+"#;
+
+fn link_from_row(row: &SqliteRow) -> Result<IdeaLink, String> {
+    let read_err = |e: sqlx::Error| format!("读取想法关联失败: {}", e);
+    Ok(IdeaLink {
+        id: row.try_get("id").map_err(read_err)?,
+        idea_id: row.try_get("idea_id").map_err(read_err)?,
+        idea_content: row.try_get("idea_content").map_err(read_err)?,
+        idea_date: row.try_get("idea_date").map_err(read_err)?,
+        planned_task_id: row.try_get("planned_task_id").map_err(read_err)?,
+        done_task_id: row.try_get("done_task_id").map_err(read_err)?,
+        created_at: row.try_get("created_at").map_err(read_err)?,
+    })
+}
+
+async fn get_link_with(conn: &mut SqliteConnection, id: i64) -> Result<IdeaLink, String> {
+    let row = sqlx::query(&format!("{} WHERE l.id = ?", LINK_SELECT))
+        .bind(id)
+        .fetch_optional(conn)
+        .await
+        .map_err(|e| format!("查询想法关联失败: {}", e))?
+        .ok_or_else(|| format!("想法关联 {} 不存在", id))?;
+    link_from_row(&row)
+}
+
+async fn insert_link_with(
+    conn: &mut SqliteConnection,
+    idea_id: i64,
+    planned_task_id: Option<i64>,
+    done_task_id: Option<i64>,
+) -> Result<IdeaLink, String> {
+    let id = sqlx::query("INSERT INTO idea_links (idea_id, planned_task_id, done_task_id, created_at) VALUES (?, ?, ?, ?)")
+        .bind(idea_id)
+        .bind(planned_task_id)
+        .bind(done_task_id)
+        .bind(chrono::Local::now().timestamp())
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| format!("保存想法关联失败: {}", e))?
+        .last_insert_rowid();
+    get_link_with(conn, id).await
+}
+
+/// 设置想法的归档状态，返回修改后的想法
+pub async fn set_archived(pool: &Pool<Sqlite>, idea_id: i64, archived: bool, origin: AuditOrigin) -> Result<Idea, String> {
+    let mut tx = pool.begin().await.map_err(|e| format!("开启事务失败: {}", e))?;
+    let idea = IdeaRepo::set_archived_with(&mut tx, idea_id, archived, origin)
+        .await?
+        .ok_or_else(|| format!("想法 {} 不存在", idea_id))?;
+    tx.commit().await.map_err(|e| format!("提交事务失败: {}", e))?;
+    Ok(idea)
+}
+
+/// 将想法转为计划或已完成事项，并记录双向关联
+///
+/// 创建、关联和归档在同一个事务中完成；已有关联的想法不能再次转化。
+/// 转为已完成事项时，时间段需由调用方事先校验；想法的附件会一并带到事项上。
+pub async fn promote_idea(
+    pool: &Pool<Sqlite>,
+    idea_id: i64,
+    target: PromoteTarget,
+    archive: bool,
+    origin: AuditOrigin,
+) -> Result<IdeaPromotion, String> {
+    let mut tx = pool.begin().await.map_err(|e| format!("开启事务失败: {}", e))?;
+
+    let idea = IdeaRepo::get_with(&mut tx, idea_id)
+        .await?
+        .ok_or_else(|| format!("想法 {} 不存在", idea_id))?;
+    let linked: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM idea_links WHERE idea_id = ?")
+        .bind(idea_id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| format!("查询想法关联失败: {}", e))?;
+    if linked > 0 {
+        return Err(format!("想法 {} 已转化过，不能重复转化", idea_id));
+    }
+
+    let promotion = match target {
+        PromoteTarget::Planned { target_date, estimate_minutes, priority, content, tags } => {
+            let plan = planning::add_planned_task_with(
+                &mut tx,
+                NewPlannedTask {
+                    content: content.filter(|c| !c.trim().is_empty()).unwrap_or(idea.content),
+                    target_date,
+                    estimate_minutes,
+                    priority,
+                    tags,
+                },
+                origin,
+            )
+            .await?;
+            let link = insert_link_with(&mut tx, idea_id, Some(plan.id), None).await?;
+            IdeaPromotion { link, planned_task: Some(plan), done_task: None }
+        }
+        PromoteTarget::Done { start_time, end_time, content, tags } => {
+            let content = content.filter(|c| !c.trim().is_empty()).unwrap_or(idea.content);
+            let new_task = NewDoneTask {
+                content,
+                start_time,
+                end_time,
+                attachments: idea.attachments,
+                created_at: chrono::Local::now().timestamp(),
+                tags,
+            };
+            let task = TaskRepo::insert_with(&mut tx, new_task, origin).await?;
+            let link = insert_link_with(&mut tx, idea_id, None, Some(task.id)).await?;
+            IdeaPromotion { link, planned_task: None, done_task: Some(task) }
+        }
+    };
+
+    if archive {
+        IdeaRepo::set_archived_with(&mut tx, idea_id, true, origin).await?;
+    }
+    tx.commit().await.map_err(|e| format!("提交事务失败: {}", e))?;
+    Ok(promotion)
+}
+
+/// 按想法、计划或已完成事项查询关联；都为空时返回全部关联
+pub async fn list_links(
+    pool: &Pool<Sqlite>,
+    idea_id: Option<i64>,
+    planned_task_id: Option<i64>,
+    done_task_id: Option<i64>,
+) -> Result<Vec<IdeaLink>, String> {
+    let rows = sqlx::query(&format!(
+        r#"{}
+        WHERE (?1 IS NULL OR l.idea_id = ?1)
+          AND (?2 IS NULL OR l.planned_task_id = ?2)
+          AND (?3 IS NULL OR l.done_task_id = ?3)
+        ORDER BY l.id"#,
+        LINK_SELECT
+    ))
+    .bind(idea_id)
+    .bind(planned_task_id)
+    .bind(done_task_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("查询想法关联失败: {}", e))?;
+    rows.iter().map(link_from_row).collect()
+}
+
+/// 日期范围内的已完成事项或计划所关联的想法（用于报告上下文）
+pub async fn links_in_range(pool: &Pool<Sqlite>, start_date: &str, end_date: &str) -> Result<Vec<IdeaLink>, String> {
+    let rows = sqlx::query(&format!(
+        r#"{}
+        WHERE l.done_task_id IN (SELECT id FROM done_tasks WHERE date >= ?1 AND date <= ?2)
+           OR l.planned_task_id IN (SELECT id FROM planned_tasks WHERE target_date >= ?1 AND target_date <= ?2)
+           OR l.idea_id IN (SELECT id FROM ideas WHERE date >= ?1 AND date <= ?2)
+        ORDER BY l.id"#,
+        LINK_SELECT
+    ))
+    .bind(start_date)
+    .bind(end_date)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("查询想法关联失败: {}", e))?;
+    rows.iter().map(link_from_row).collect()
+}
+
+/// 计划完成后，将其关联的想法也关联到生成的已完成事项（在完成计划的事务中调用）
+pub async fn on_plan_completed(conn: &mut SqliteConnection, planned_task_id: i64, done_task_id: i64) -> Result<(), String> {
+    sqlx::query("UPDATE idea_links SET done_task_id = ? WHERE planned_task_id = ? AND done_task_id IS NULL")
+        .bind(done_task_id)
+        .bind(planned_task_id)
+        .execute(conn)
+        .await
+        .map_err(|e| format!("更新想法关联失败: {}", e))?;
+    Ok(())
+}
+
+/// 想法被删除后移除其关联（在删除想法的事务中调用）
+pub async fn on_idea_deleted(conn: &mut SqliteConnection, idea_id: i64) -> Result<(), String> {
+    sqlx::query("DELETE FROM idea_links WHERE idea_id = ?")
+        .bind(idea_id)
+        .execute(conn)
+        .await
+        .map_err(|e| format!("删除想法关联失败: {}", e))?;
+    Ok(())
+}
+
+/// 已完成事项被删除后解除关联，关联两端都不存在时移除（在删除事项的事务中调用）
+pub async fn on_task_deleted(conn: &mut SqliteConnection, done_task_id: i64) -> Result<(), String> {
+    sqlx::query("UPDATE idea_links SET done_task_id = NULL WHERE done_task_id = ?")
+        .bind(done_task_id)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| format!("更新想法关联失败: {}", e))?;
+    prune(conn).await
+}
+
+/// 计划被删除后解除关联，关联两端都不存在时移除（在删除计划的事务中调用）
+pub async fn on_plan_deleted(conn: &mut SqliteConnection, planned_task_id: i64) -> Result<(), String> {
+    sqlx::query("UPDATE idea_links SET planned_task_id = NULL WHERE planned_task_id = ?")
+        .bind(planned_task_id)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| format!("更新想法关联失败: {}", e))?;
+    prune(conn).await
+}
+
+async fn prune(conn: &mut SqliteConnection) -> Result<(), String> {
+    sqlx::query("DELETE FROM idea_links WHERE planned_task_id IS NULL AND done_task_id IS NULL")
+        .execute(conn)
+        .await
+        .map_err(|e| format!("删除想法关联失败: {}", e))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+    use crate::models::PlanPriority;
+    use crate::test_support::temp_pool;
+
+    fn plan_target() -> PromoteTarget {
+        PromoteTarget::Planned {
+            target_date: "2024-05-07".to_string(),
+            estimate_minutes: Some(30),
+            priority: PlanPriority::High,
+            content: None,
+            tags: vec!["缓存".to_string()],
+        }
+    }
+
+    async fn link_count(pool: &Pool<Sqlite>) -> i64 {
+        sqlx::query_scalar("SELECT COUNT(*) FROM idea_links").fetch_one(pool).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn promotes_once_and_archives_in_the_same_transaction() {
+        let (pool, _dir) = temp_pool().await;
+        let at = chrono::Local.with_ymd_and_hms(2024, 5, 6, 9, 0, 0).unwrap().timestamp();
+        let idea = IdeaRepo::new(&pool).insert("缓存按工作区隔离".to_string(), Vec::new(), at, AuditOrigin::Ui).await.unwrap();
+
+        let promotion = promote_idea(&pool, idea.id, plan_target(), true, AuditOrigin::Ui).await.unwrap();
+        let plan = promotion.planned_task.unwrap();
+        assert_eq!(plan.content, "缓存按工作区隔离");
+        assert_eq!(promotion.link.planned_task_id, Some(plan.id));
+        assert!(IdeaRepo::new(&pool).get(idea.id).await.unwrap().unwrap().archived);
+
+        // 重复转化被拒绝，不会多出计划或关联
+        let again = promote_idea(&pool, idea.id, plan_target(), false, AuditOrigin::Ui).await;
+        assert!(again.unwrap_err().contains("已转化过"));
+        let plans: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM planned_tasks").fetch_one(&pool).await.unwrap();
+        assert_eq!(plans, 1);
+        assert_eq!(link_count(&pool).await, 1);
+    }
+
+    #[tokio::test]
+    async fn failed_promotion_leaves_nothing_behind() {
+        let (pool, _dir) = temp_pool().await;
+        let idea = IdeaRepo::new(&pool).insert("想法".to_string(), Vec::new(), 1_715_000_000, AuditOrigin::Ui).await.unwrap();
+
+        let target = PromoteTarget::Planned {
+            target_date: "不是日期".to_string(),
+            estimate_minutes: None,
+            priority: PlanPriority::Normal,
+            content: None,
+            tags: Vec::new(),
+        };
+        assert!(promote_idea(&pool, idea.id, target, true, AuditOrigin::Ui).await.is_err());
+        assert_eq!(link_count(&pool).await, 0);
+        assert!(!IdeaRepo::new(&pool).get(idea.id).await.unwrap().unwrap().archived);
+
+        // 失败后可以重新转化
+        promote_idea(&pool, idea.id, plan_target(), false, AuditOrigin::Ui).await.unwrap();
+        assert_eq!(link_count(&pool).await, 1);
+    }
+
+    #[tokio::test]
+    async fn completing_and_deleting_plans_update_links() {
+        let (pool, _dir) = temp_pool().await;
+        let ideas = IdeaRepo::new(&pool);
+        let first = ideas.insert("第一条".to_string(), Vec::new(), 1_715_000_000, AuditOrigin::Ui).await.unwrap();
+        let second = ideas.insert("第二条".to_string(), Vec::new(), 1_715_000_100, AuditOrigin::Ui).await.unwrap();
+        let completed = promote_idea(&pool, first.id, plan_target(), false, AuditOrigin::Ui).await.unwrap();
+        let deleted = promote_idea(&pool, second.id, plan_target(), false, AuditOrigin::Ui).await.unwrap();
+
+        let plan_id = completed.planned_task.unwrap().id;
+        let task = planning::complete_planned_task(&pool, plan_id, 1_715_000_000, 1_715_001_800, Vec::new(), AuditOrigin::Ui)
+            .await
+            .unwrap();
+        let links = list_links(&pool, Some(first.id), None, None).await.unwrap();
+        assert_eq!(links[0].planned_task_id, Some(plan_id));
+        assert_eq!(links[0].done_task_id, Some(task.id));
+
+        planning::delete_planned_task(&pool, deleted.planned_task.unwrap().id, AuditOrigin::Ui).await.unwrap();
+        assert!(list_links(&pool, Some(second.id), None, None).await.unwrap().is_empty());
+        assert_eq!(link_count(&pool).await, 1);
+    }
+}
@@ -1,10 +1,32 @@
-mod models;
+pub mod models;
 mod database;
 mod commands;
 mod config;
+mod validation;
+mod stats;
+mod pagination;
+mod chat;
+mod ai;
+mod reports;
+mod publish;
+mod email;
+mod webhooks;
+mod planning;
+mod recurring;
+mod idea_links;
+mod workspaces;
+mod relocation;
+mod backups;
+mod diagnostics;
+pub mod repo;
+mod bulk;
+mod audit;
+#[cfg(test)]
+mod test_support;
 
 use tauri::Manager;
 use crate::database::DbState;
+use crate::ai::cache::CacheCounters;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
@@ -12,33 +34,145 @@ pub fn run() {
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_fs::init())
         .invoke_handler(tauri::generate_handler![
+            // 应用设置命令
+            commands::get_app_settings,
+            commands::save_app_settings,
+            // 工作区命令
+            commands::list_workspaces,
+            commands::create_workspace,
+            commands::rename_workspace,
+            commands::switch_workspace,
+            commands::delete_workspace,
+            commands::get_attachment_dir,
+            commands::relocate_database,
+            commands::confirm_relocation,
+            // 数据库状态命令
+            commands::get_db_status,
+            commands::retry_db_init,
+            // 数据库备份命令
+            commands::create_snapshot,
+            commands::list_snapshots,
+            commands::restore_snapshot,
+            // 数据库诊断命令
+            commands::run_diagnostics,
+            // 审计日志命令
+            commands::list_audit_log,
+            commands::export_audit_log,
             // AI 配置命令
             commands::save_api_config,
             commands::get_api_config,
+            commands::list_local_models,
             // 随手记命令
             commands::add_idea,
             commands::add_done_task,
             commands::get_today_records,
             commands::get_records_by_date_range,
+            commands::get_records_page,
+            // 批量操作命令
+            commands::delete_records,
+            commands::move_records,
+            commands::bulk_tag,
+            commands::bulk_import,
+            // 校验命令
+            commands::validate_done_task,
+            commands::check_day_consistency,
+            // 统计命令
+            commands::time_stats,
             // 删除命令
             commands::delete_idea,
             commands::delete_task,
+            // 计划命令
+            commands::add_planned_task,
+            commands::list_planned_tasks,
+            commands::update_planned_task,
+            commands::set_planned_task_status,
+            commands::delete_planned_task,
+            commands::complete_planned_task,
+            commands::carry_over_planned_tasks,
+            // 想法转化命令
+            commands::promote_idea,
+            commands::set_idea_archived,
+            commands::list_idea_links,
+            // 重复事项命令
+            commands::add_recurring_template,
+            commands::list_recurring_templates,
+            commands::update_recurring_template,
+            commands::delete_recurring_template,
+            commands::materialize_recurring,
+            commands::list_recurring_occurrences,
+            commands::confirm_recurring_occurrence,
+            commands::skip_recurring_occurrence,
             // 提示词管理命令
             commands::add_prompt,
             commands::get_prompts,
             commands::update_prompt,
             commands::delete_prompt,
+            // AI 对话记录命令
+            commands::create_chat_session,
+            commands::list_chat_sessions,
+            commands::rename_chat_session,
+            commands::delete_chat_session,
+            commands::append_chat_message,
+            commands::get_chat_messages,
+            commands::search_chat_messages,
+            // AI 工具调用命令
+            commands::generate_daily_report,
+            commands::run_ai_agent,
+            commands::build_report_context,
+            // AI 响应缓存命令
+            commands::get_ai_cache_stats,
+            commands::clear_ai_cache,
+            // 报告命令
+            commands::list_reports,
+            commands::get_report,
+            commands::delete_report,
+            // 报告推送命令
+            commands::add_publish_target,
+            commands::list_publish_targets,
+            commands::update_publish_target,
+            commands::delete_publish_target,
+            commands::publish_report,
+            commands::list_report_publications,
+            // 邮件发送命令
+            commands::save_smtp_config,
+            commands::get_smtp_config,
+            commands::send_report_email,
+            commands::list_report_emails,
+            // Webhook 命令
+            commands::get_webhook_events,
+            commands::add_webhook,
+            commands::list_webhooks,
+            commands::update_webhook,
+            commands::delete_webhook,
+            commands::test_webhook,
+            commands::list_webhook_deliveries,
+            commands::retry_webhook_delivery,
         ])
         .setup(|app| {
             // 将 DbState 管理为应用状态
             let db_state = DbState::new();
             app.manage(db_state.clone());
+            app.manage(CacheCounters::new());
 
-            // 初始化数据库
+            // 初始化数据库，状态变化通知前端
             let app_handle = app.handle().clone();
+            database::spawn_status_events(app_handle.clone(), &db_state);
+            // 后台任务会等待数据库就绪；初始化失败后重试成功也能继续运行
+            // 启动时备份一次，之后每天备份
+            backups::spawn_backup_worker(app_handle.clone(), db_state.clone());
+            // 继续处理上次未完成的 Webhook 投递
+            webhooks::spawn_retry_worker(db_state.clone());
             tauri::async_runtime::spawn(async move {
-                match database::init_database(&app_handle, db_state).await {
-                    Ok(_) => println!("数据库初始化成功"),
+                match database::init_database(&app_handle, db_state.clone()).await {
+                    Ok(_) => {
+                        println!("数据库初始化成功");
+                        // 顺延过期未完成的计划
+                        if let Ok(pool) = db_state.get_pool().await {
+                            if let Err(e) = planning::auto_carry_over(&app_handle, &pool).await {
+                                eprintln!("顺延计划失败: {}", e);
+                            }
+                        }
+                    }
                     Err(e) => eprintln!("数据库初始化失败: {}", e),
                 }
             });
@@ -8,6 +8,8 @@ pub struct Idea {
     pub attachments: Vec<String>, // 附件路径数组
     pub created_at: i64, // Unix 时间戳
     pub date: String, // YYYY-MM-DD
+    #[serde(default)]
+    pub archived: bool, // 已转为事项后归档
 }
 
 /// 已完成事项表
@@ -20,10 +22,251 @@ pub struct DoneTask {
     pub attachments: Vec<String>, // 附件路径数组
     pub created_at: i64, // Unix 时间戳
     pub date: String, // YYYY-MM-DD
+    #[serde(default)]
+    pub tags: Vec<String>, // 标签数组
 }
 
-/// 提示词表
+/// 新建已完成事项时提交的内容
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NewDoneTask {
+    pub content: String,
+    pub start_time: i64, // Unix 时间戳
+    pub end_time: i64, // Unix 时间戳
+    #[serde(default)]
+    pub attachments: Vec<String>,
+    pub created_at: i64, // Unix 时间戳
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+/// 计划优先级
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PlanPriority {
+    Low,
+    #[default]
+    Normal,
+    High,
+}
+
+impl PlanPriority {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PlanPriority::Low => "low",
+            PlanPriority::Normal => "normal",
+            PlanPriority::High => "high",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "low" => Some(PlanPriority::Low),
+            "normal" => Some(PlanPriority::Normal),
+            "high" => Some(PlanPriority::High),
+            _ => None,
+        }
+    }
+}
+
+/// 计划状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PlanStatus {
+    Pending, // 未完成
+    Done, // 已完成，并已转为已完成事项
+    Cancelled,
+}
+
+impl PlanStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PlanStatus::Pending => "pending",
+            PlanStatus::Done => "done",
+            PlanStatus::Cancelled => "cancelled",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "pending" => Some(PlanStatus::Pending),
+            "done" => Some(PlanStatus::Done),
+            "cancelled" => Some(PlanStatus::Cancelled),
+            _ => None,
+        }
+    }
+}
+
+/// 计划事项表
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlannedTask {
+    pub id: i64,
+    pub content: String,
+    pub target_date: String, // YYYY-MM-DD，顺延后为新的日期
+    pub original_date: String, // 最初计划的日期
+    pub estimate_minutes: Option<i64>, // 预估耗时（分钟）
+    pub priority: PlanPriority,
+    pub status: PlanStatus,
+    pub tags: Vec<String>,
+    pub carry_count: i64, // 已顺延次数
+    pub done_task_id: Option<i64>, // 完成后对应的已完成事项
+    pub created_at: i64, // Unix 时间戳
+    pub updated_at: i64, // Unix 时间戳
+    pub completed_at: Option<i64>, // Unix 时间戳
+}
+
+/// 新建或更新计划时前端提交的内容
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NewPlannedTask {
+    pub content: String,
+    pub target_date: String,
+    #[serde(default)]
+    pub estimate_minutes: Option<i64>,
+    #[serde(default)]
+    pub priority: PlanPriority,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+/// 计划的一次顺延记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlanCarry {
+    pub planned_task_id: i64,
+    pub content: String,
+    pub from_date: String,
+    pub to_date: String,
+    pub created_at: i64, // Unix 时间戳
+}
+
+/// 重复规则
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RecurrenceRule {
+    Daily, // 每天
+    Workdays, // 工作日（见应用设置 plan_workdays）
+    Weekly { weekdays: Vec<u32> }, // 每周的指定几天（1 为周一，7 为周日）
+    Monthly { days: Vec<u32> }, // 每月的指定几号，当月没有该日期时跳过
+}
+
+/// 重复事项模板
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecurringTemplate {
+    pub id: i64,
+    pub content: String,
+    pub rule: RecurrenceRule,
+    pub start_time: String, // 默认开始时间 HH:MM
+    pub end_time: String, // 默认结束时间 HH:MM
+    pub tags: Vec<String>,
+    pub enabled: bool,
+    pub created_at: i64, // Unix 时间戳
+    pub updated_at: i64, // Unix 时间戳
+}
+
+/// 新建或更新重复事项模板时前端提交的内容
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NewRecurringTemplate {
+    pub content: String,
+    pub rule: RecurrenceRule,
+    pub start_time: String,
+    pub end_time: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+/// 重复事项在某一天的状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OccurrenceStatus {
+    Draft, // 待确认
+    Confirmed, // 已确认并生成已完成事项
+    Skipped,
+}
+
+impl OccurrenceStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OccurrenceStatus::Draft => "draft",
+            OccurrenceStatus::Confirmed => "confirmed",
+            OccurrenceStatus::Skipped => "skipped",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "draft" => Some(OccurrenceStatus::Draft),
+            "confirmed" => Some(OccurrenceStatus::Confirmed),
+            "skipped" => Some(OccurrenceStatus::Skipped),
+            _ => None,
+        }
+    }
+}
+
+/// 重复事项在某一天生成的草稿
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecurringOccurrence {
+    pub id: i64,
+    pub template_id: i64,
+    pub date: String, // YYYY-MM-DD
+    pub content: String,
+    pub start_time: i64, // Unix 时间戳
+    pub end_time: i64, // Unix 时间戳
+    pub tags: Vec<String>,
+    pub status: OccurrenceStatus,
+    pub done_task_id: Option<i64>, // 确认后对应的已完成事项
+    pub created_at: i64, // Unix 时间戳
+    pub updated_at: i64, // Unix 时间戳
+}
+
+/// 想法转为事项时的目标
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum PromoteTarget {
+    /// 转为计划
+    Planned {
+        target_date: String,
+        #[serde(default)]
+        estimate_minutes: Option<i64>,
+        #[serde(default)]
+        priority: PlanPriority,
+        #[serde(default)]
+        content: Option<String>, // 为空时使用想法内容
+        #[serde(default)]
+        tags: Vec<String>,
+    },
+    /// 转为已完成事项
+    Done {
+        start_time: i64,
+        end_time: i64,
+        #[serde(default)]
+        content: Option<String>, // 为空时使用想法内容
+        #[serde(default)]
+        tags: Vec<String>,
+    },
+}
+
+/// 想法与由其转化的事项之间的关联
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IdeaLink {
+    pub id: i64,
+    pub idea_id: i64,
+    pub idea_content: String,
+    pub idea_date: String, // 想法的日期 YYYY-MM-DD
+    pub planned_task_id: Option<i64>,
+    pub done_task_id: Option<i64>, // 转为计划的想法在计划完成后也会关联到已完成事项
+    pub created_at: i64, // Unix 时间戳
+}
+
+/// 想法转化的结果
 #[derive(Debug, Serialize, Deserialize)]
+pub struct IdeaPromotion {
+    pub link: IdeaLink,
+    pub planned_task: Option<PlannedTask>,
+    pub done_task: Option<DoneTask>,
+}
+
+/// 提示词表
+#[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
 pub struct Prompt {
     pub id: i64,
     pub name: String,
@@ -32,12 +275,294 @@ pub struct Prompt {
     pub updated_at: i64, // Unix 时间戳
 }
 
+/// AI 服务类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProviderKind {
+    #[default]
+    #[serde(rename = "openai")]
+    OpenAi, // OpenAI 兼容接口
+    Mock, // 内置离线模拟，用于测试和演示
+    Ollama, // 本地 Ollama（/api/chat）
+    LlamaCpp, // 本地 llama.cpp server（OpenAI 兼容接口）
+    Anthropic, // Anthropic Messages API
+    Gemini, // Google Gemini generateContent API
+}
+
+impl ProviderKind {
+    /// 未填写接口地址时使用的默认地址（服务商的官方地址或本地服务的默认端口）
+    pub fn default_url(&self) -> Option<&'static str> {
+        match self {
+            ProviderKind::Ollama => Some("http://localhost:11434"),
+            ProviderKind::LlamaCpp => Some("http://localhost:8080"),
+            ProviderKind::Anthropic => Some("https://api.anthropic.com"),
+            ProviderKind::Gemini => Some("https://generativelanguage.googleapis.com"),
+            _ => None,
+        }
+    }
+}
+
+/// 本地模型服务的参数
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LocalOptions {
+    pub keep_alive: Option<String>, // 模型在内存中保留的时长，如 "5m"、"-1"（仅 Ollama）
+    pub num_ctx: Option<u32>, // 上下文长度，同时作为未配置 context_budget 时的上下文预算
+}
+
 /// AI 配置结构（JSON 文件存储）
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ApiConfig {
     pub api_key: String,
     pub api_url: String,
     pub model: String,
+    #[serde(default)]
+    pub context_budget: Option<usize>, // 上下文 token 预算，为空时按模型默认值
+    #[serde(default)]
+    pub provider: ProviderKind,
+    #[serde(default)]
+    pub mock: Option<crate::ai::mock::MockOptions>, // 仅 mock 类型使用
+    #[serde(default)]
+    pub local: Option<LocalOptions>, // 仅本地模型服务使用
+}
+
+impl ApiConfig {
+    /// 上下文预算：优先使用显式配置，其次使用本地服务的上下文长度
+    pub fn effective_context_budget(&self) -> Option<usize> {
+        self.context_budget
+            .or_else(|| self.local.as_ref().and_then(|l| l.num_ctx).map(|n| n as usize))
+    }
+}
+
+/// 本地模型服务中已安装的模型
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LocalModel {
+    pub name: String,
+    pub size: Option<i64>, // 字节数
+    pub modified_at: Option<String>,
+    pub family: Option<String>,
+    pub parameter_size: Option<String>,
+    pub quantization_level: Option<String>,
+}
+
+/// 工作区（每个工作区有独立的数据库、附件目录和 AI 配置）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Workspace {
+    pub id: String,
+    pub name: String,
+    pub created_at: i64, // Unix 时间戳
+    pub active: bool, // 是否为当前工作区
+    pub data_dir: String, // 工作区目录（AI 配置等）
+    pub db_path: String, // 数据库文件，可通过迁移放到自定义目录
+    pub previous_db_dir: Option<String>, // 迁移后等待确认清理的旧数据库目录
+}
+
+/// 审计日志记录的数据类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditEntity {
+    Idea,
+    Task,
+    Prompt,
+    Report,
+    Plan, // 计划（planned_tasks）
+    Snapshot, // 数据库快照，entity_id 固定为 0
+}
+
+impl AuditEntity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditEntity::Idea => "idea",
+            AuditEntity::Task => "task",
+            AuditEntity::Prompt => "prompt",
+            AuditEntity::Report => "report",
+            AuditEntity::Plan => "plan",
+            AuditEntity::Snapshot => "snapshot",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "idea" => Some(AuditEntity::Idea),
+            "task" => Some(AuditEntity::Task),
+            "prompt" => Some(AuditEntity::Prompt),
+            "report" => Some(AuditEntity::Report),
+            "plan" => Some(AuditEntity::Plan),
+            "snapshot" => Some(AuditEntity::Snapshot),
+            _ => None,
+        }
+    }
+}
+
+/// 审计日志记录的操作
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditAction {
+    Insert,
+    Update,
+    Delete,
+    Restore, // 从快照恢复数据库
+}
+
+impl AuditAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditAction::Insert => "insert",
+            AuditAction::Update => "update",
+            AuditAction::Delete => "delete",
+            AuditAction::Restore => "restore",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "insert" => Some(AuditAction::Insert),
+            "update" => Some(AuditAction::Update),
+            "delete" => Some(AuditAction::Delete),
+            "restore" => Some(AuditAction::Restore),
+            _ => None,
+        }
+    }
+}
+
+/// 修改的来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditOrigin {
+    Ui, // 前端界面调用的命令
+    Cli,
+    HttpApi,
+    Scheduler, // 后台定时任务
+}
+
+impl AuditOrigin {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditOrigin::Ui => "ui",
+            AuditOrigin::Cli => "cli",
+            AuditOrigin::HttpApi => "http_api",
+            AuditOrigin::Scheduler => "scheduler",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "ui" => Some(AuditOrigin::Ui),
+            "cli" => Some(AuditOrigin::Cli),
+            "http_api" => Some(AuditOrigin::HttpApi),
+            "scheduler" => Some(AuditOrigin::Scheduler),
+            _ => None,
+        }
+    }
+}
+
+/// 审计日志（只追加，不可修改或删除）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub id: i64,
+    pub at: i64, // Unix 时间戳
+    pub origin: AuditOrigin,
+    pub entity: AuditEntity,
+    pub entity_id: i64,
+    pub action: AuditAction,
+    pub before: Option<serde_json::Value>, // 修改前的记录，新建时为空
+    pub after: Option<serde_json::Value>, // 修改后的记录，删除时为空
+}
+
+/// 数据库初始化状态
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum DbStatus {
+    Initializing, // 正在初始化，迁移或恢复数据库期间也处于此状态
+    Ready,
+    Failed { error: String },
+}
+
+/// 数据库快照
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub name: String, // 文件名，恢复时使用
+    pub path: String,
+    pub created_at: i64, // Unix 时间戳
+    pub size_bytes: u64,
+    pub pre_restore: bool, // 恢复快照前自动保存的当前数据
+}
+
+/// 诊断发现的问题类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IssueKind {
+    Integrity, // PRAGMA integrity_check 报告的问题
+    ForeignKey, // 引用的父记录不存在
+    InvalidJson, // JSON 列无法解析或类型不对
+    DateMismatch, // date 列与时间戳不一致
+    InvertedRange, // 结束时间早于开始时间
+    MissingAttachment, // 附件文件不存在
+}
+
+/// 诊断发现的单个问题
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiagnosticIssue {
+    pub kind: IssueKind,
+    pub table: Option<String>,
+    pub row_id: Option<i64>,
+    pub column: Option<String>,
+    pub message: String,
+    pub repairable: bool, // 能否安全地自动修复
+    pub repaired: bool,
+}
+
+/// 数据库诊断报告
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiagnosticsReport {
+    pub integrity_ok: bool,
+    pub issues: Vec<DiagnosticIssue>,
+    pub repaired_count: usize,
+    pub snapshot: Option<String>, // 修复前自动生成的快照
+    pub checked_at: i64, // Unix 时间戳
+}
+
+/// 应用设置（JSON 文件存储）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppSettings {
+    pub max_task_duration_minutes: i64, // 单个已完成事项允许的最长时长（分钟）
+    pub ai_cache_enabled: bool, // 是否缓存 AI 响应（默认只用于生成报告，对话需显式开启）
+    pub ai_cache_ttl_hours: i64, // AI 响应缓存有效期（小时）
+    pub ai_cache_max_mb: i64, // AI 响应缓存容量上限（MB），超出后按最近最少使用淘汰
+    pub ai_connect_timeout_secs: u64, // AI 请求连接超时（秒）
+    pub ai_read_timeout_secs: u64, // AI 请求读取超时（秒）
+    pub ai_max_attempts: u32, // AI 请求最多尝试次数（含首次）
+    pub ai_backoff_base_ms: u64, // 重试退避的初始等待（毫秒）
+    pub ai_backoff_max_ms: u64, // 重试单次等待上限（毫秒）
+    pub plan_workdays: Vec<u32>, // 工作日（1 为周一，7 为周日），未完成计划顺延到下一个工作日
+    pub plan_auto_carry_over: bool, // 是否自动顺延过期未完成的计划
+    pub backup_enabled: bool, // 是否自动备份数据库（启动时及每天一次）
+    pub backup_keep_daily: usize, // 保留最近几天的每日快照
+    pub backup_keep_weekly: usize, // 保留最近几周的每周快照
+    pub backup_keep_monthly: usize, // 保留最近几个月的每月快照
+}
+
+impl Default for AppSettings {
+    fn default() -> Self {
+        Self {
+            max_task_duration_minutes: 12 * 60,
+            ai_cache_enabled: true,
+            ai_cache_ttl_hours: 7 * 24,
+            ai_cache_max_mb: 50,
+            ai_connect_timeout_secs: 10,
+            ai_read_timeout_secs: 120,
+            ai_max_attempts: 4,
+            ai_backoff_base_ms: 500,
+            ai_backoff_max_ms: 30_000,
+            plan_workdays: vec![1, 2, 3, 4, 5],
+            plan_auto_carry_over: true,
+            backup_enabled: true,
+            backup_keep_daily: 7,
+            backup_keep_weekly: 4,
+            backup_keep_monthly: 12,
+        }
+    }
 }
 
 /// 今日记录汇总（用于返回给前端）
@@ -46,3 +571,265 @@ pub struct TodayRecords {
     pub ideas: Vec<Idea>,
     pub tasks: Vec<DoneTask>,
 }
+
+/// AI 对话会话表
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ChatSession {
+    pub id: i64,
+    pub title: String,
+    pub range_start: Option<String>, // 会话讨论的日期范围 YYYY-MM-DD
+    pub range_end: Option<String>,
+    pub message_count: i64,
+    pub created_at: i64, // Unix 时间戳
+    pub updated_at: i64, // Unix 时间戳
+}
+
+/// 模型返回的 token 用量
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TokenUsage {
+    pub prompt_tokens: i64,
+    pub completion_tokens: i64,
+    pub total_tokens: i64,
+}
+
+/// AI 对话消息表
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ChatMessage {
+    pub id: i64,
+    pub session_id: i64,
+    pub role: String, // system / user / assistant / tool
+    pub content: String,
+    pub tool_calls: Option<serde_json::Value>, // assistant 发起的工具调用
+    pub tool_call_id: Option<String>, // tool 消息对应的调用 ID
+    pub model: Option<String>,
+    pub usage: Option<TokenUsage>,
+    pub created_at: i64, // Unix 时间戳
+}
+
+/// 追加消息时前端传入的内容
+#[derive(Debug, Deserialize)]
+pub struct NewChatMessage {
+    pub role: String,
+    pub content: String,
+    #[serde(default)]
+    pub tool_calls: Option<serde_json::Value>,
+    #[serde(default)]
+    pub tool_call_id: Option<String>,
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub usage: Option<TokenUsage>,
+}
+
+/// 对话搜索结果
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ChatSearchHit {
+    pub session_id: i64,
+    pub session_title: String,
+    pub message_id: i64,
+    pub role: String,
+    pub snippet: String,
+    pub created_at: i64,
+}
+
+/// 已生成的报告
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Report {
+    pub id: i64,
+    pub title: String,
+    pub content: String, // Markdown
+    pub start_date: String, // YYYY-MM-DD
+    pub end_date: String, // YYYY-MM-DD
+    pub model: Option<String>,
+    pub created_at: i64, // Unix 时间戳
+}
+
+/// 群机器人平台
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PublishPlatform {
+    Feishu, // 飞书自定义机器人
+    Dingtalk, // 钉钉自定义机器人
+    Wecom, // 企业微信群机器人
+}
+
+impl PublishPlatform {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PublishPlatform::Feishu => "feishu",
+            PublishPlatform::Dingtalk => "dingtalk",
+            PublishPlatform::Wecom => "wecom",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "feishu" => Some(PublishPlatform::Feishu),
+            "dingtalk" => Some(PublishPlatform::Dingtalk),
+            "wecom" => Some(PublishPlatform::Wecom),
+            _ => None,
+        }
+    }
+}
+
+/// 报告推送目标（群机器人 Webhook）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PublishTarget {
+    pub id: i64,
+    pub name: String,
+    pub platform: PublishPlatform,
+    pub webhook_url: String,
+    pub secret: Option<String>, // 签名密钥（飞书、钉钉开启签名校验时需要）
+    pub enabled: bool,
+    pub created_at: i64, // Unix 时间戳
+    pub updated_at: i64, // Unix 时间戳
+}
+
+/// 报告推送记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReportPublication {
+    pub id: i64,
+    pub report_id: i64,
+    pub target_id: Option<i64>, // 推送目标删除后为空
+    pub target_name: String,
+    pub platform: PublishPlatform,
+    pub success: bool,
+    pub error: Option<String>,
+    pub response: Option<String>, // 平台返回的原始响应
+    pub created_at: i64, // Unix 时间戳
+}
+
+/// SMTP 连接的加密方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SmtpTls {
+    None, // 不加密（仅用于本地或内网服务器）
+    #[default]
+    StartTls, // 明文连接后升级为 TLS（通常为 587 端口）
+    Tls, // 直接使用 TLS 连接（通常为 465 端口）
+}
+
+/// SMTP 邮件配置（JSON 文件存储）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SmtpConfig {
+    pub host: String,
+    pub port: u16,
+    pub tls: SmtpTls,
+    pub username: String,
+    pub password: String,
+    pub from_address: String,
+    pub from_name: Option<String>,
+    pub recipients: Vec<String>, // 默认收件人
+    pub attach_images: bool, // 是否把报告中引用的本地图片作为附件发送
+}
+
+impl Default for SmtpConfig {
+    fn default() -> Self {
+        Self {
+            host: String::new(),
+            port: 587,
+            tls: SmtpTls::StartTls,
+            username: String::new(),
+            password: String::new(),
+            from_address: String::new(),
+            from_name: None,
+            recipients: Vec::new(),
+            attach_images: true,
+        }
+    }
+}
+
+/// 报告邮件发送记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReportEmail {
+    pub id: i64,
+    pub report_id: i64,
+    pub recipients: Vec<String>,
+    pub subject: String,
+    pub attached_images: i64,
+    pub success: bool,
+    pub error: Option<String>,
+    pub created_at: i64, // Unix 时间戳
+}
+
+/// 自定义 Webhook
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Webhook {
+    pub id: i64,
+    pub name: String,
+    pub url: String,
+    pub events: Vec<String>, // 订阅的事件，"*" 表示全部
+    pub body_template: Option<String>, // JSON 请求体模板，为空时发送默认格式
+    pub headers: std::collections::BTreeMap<String, String>, // 自定义请求头
+    pub secret: Option<String>, // HMAC-SHA256 签名密钥，签名内容为 "{timestamp}.{body}"
+    pub enabled: bool,
+    pub created_at: i64, // Unix 时间戳
+    pub updated_at: i64, // Unix 时间戳
+}
+
+/// 新建或更新 Webhook 时前端提交的内容
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NewWebhook {
+    pub name: String,
+    pub url: String,
+    pub events: Vec<String>,
+    #[serde(default)]
+    pub body_template: Option<String>,
+    #[serde(default)]
+    pub headers: std::collections::BTreeMap<String, String>,
+    #[serde(default)]
+    pub secret: Option<String>,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+/// Webhook 投递状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeliveryStatus {
+    Pending, // 等待发送或等待重试
+    Success,
+    Failed, // 重试次数用尽
+}
+
+impl DeliveryStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DeliveryStatus::Pending => "pending",
+            DeliveryStatus::Success => "success",
+            DeliveryStatus::Failed => "failed",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "pending" => Some(DeliveryStatus::Pending),
+            "success" => Some(DeliveryStatus::Success),
+            "failed" => Some(DeliveryStatus::Failed),
+            _ => None,
+        }
+    }
+}
+
+/// Webhook 投递记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookDelivery {
+    pub id: i64,
+    pub webhook_id: i64,
+    pub event: String,
+    pub payload: String, // 实际发送的请求体
+    pub status: DeliveryStatus,
+    pub attempts: i64,
+    pub next_attempt_at: Option<i64>, // 下次重试时间（Unix 时间戳）
+    pub response_status: Option<i64>,
+    pub response_body: Option<String>,
+    pub error: Option<String>,
+    pub created_at: i64, // Unix 时间戳
+    pub updated_at: i64, // Unix 时间戳
+}
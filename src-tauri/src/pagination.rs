@@ -0,0 +1,352 @@
+use std::cmp::Ordering;
+
+use serde::{Deserialize, Serialize};
+use sqlx::{Pool, Row, Sqlite};
+
+use crate::models::{DoneTask, Idea};
+
+/// 默认每页条数
+pub const DEFAULT_PAGE_SIZE: i64 = 50;
+/// 每页最大条数
+pub const MAX_PAGE_SIZE: i64 = 500;
+
+/// 记录类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordKind {
+    Idea,
+    Task,
+}
+
+impl RecordKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            RecordKind::Idea => "idea",
+            RecordKind::Task => "task",
+        }
+    }
+}
+
+/// 排序方向
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortDirection {
+    Asc,
+    #[default]
+    Desc,
+}
+
+/// 分页中的单条记录
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RecordItem {
+    Idea(Idea),
+    Task(DoneTask),
+}
+
+/// 分页查询结果
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RecordPage {
+    pub items: Vec<RecordItem>,
+    pub total_count: Option<i64>, // 只在第一页（没有游标时）统计，之后的页为空
+    pub next_cursor: Option<String>, // 为空表示没有更多数据
+}
+
+/// 分页查询参数
+pub struct PageQuery {
+    pub start_date: String,
+    pub end_date: String,
+    pub cursor: Option<String>,
+    pub page_size: i64,
+    pub kinds: Vec<RecordKind>,
+    pub direction: SortDirection,
+}
+
+/// 键集游标：(date, created_at, kind, id)
+///
+/// 想法和事项的 id 来自不同的表，可能相同，因此加入 kind 保证游标唯一。
+#[derive(Debug, PartialEq)]
+struct Cursor {
+    date: String,
+    created_at: i64,
+    kind: String,
+    id: i64,
+}
+
+impl Cursor {
+    fn encode(&self) -> String {
+        format!("{}|{}|{}|{}", self.date, self.created_at, self.kind, self.id)
+    }
+
+    fn decode(cursor: &str) -> Result<Self, String> {
+        let parts: Vec<&str> = cursor.split('|').collect();
+        if parts.len() != 4 {
+            return Err(format!("无效的分页游标: {}", cursor));
+        }
+        let created_at = parts[1]
+            .parse::<i64>()
+            .map_err(|_| format!("无效的分页游标: {}", cursor))?;
+        let id = parts[3]
+            .parse::<i64>()
+            .map_err(|_| format!("无效的分页游标: {}", cursor))?;
+        Ok(Self {
+            date: parts[0].to_string(),
+            created_at,
+            kind: parts[2].to_string(),
+            id,
+        })
+    }
+}
+
+/// 构造合并了想法与事项的子查询，参数 ?1/?2 为开始和结束日期
+fn union_sql(kinds: &[RecordKind]) -> String {
+    kinds
+        .iter()
+        .map(|kind| format!("SELECT {} FROM {} WHERE date >= ?1 AND date <= ?2", select_columns(*kind), table(*kind)))
+        .collect::<Vec<_>>()
+        .join(" UNION ALL ")
+}
+
+fn select_columns(kind: RecordKind) -> &'static str {
+    match kind {
+        RecordKind::Idea => {
+            "'idea' AS kind, id, content, attachments, created_at, date, \
+             0 AS start_time, 0 AS end_time, '[]' AS tags, archived"
+        }
+        RecordKind::Task => {
+            "'task' AS kind, id, content, attachments, created_at, date, \
+             start_time, end_time, tags, 0 AS archived"
+        }
+    }
+}
+
+fn table(kind: RecordKind) -> &'static str {
+    match kind {
+        RecordKind::Idea => "ideas",
+        RecordKind::Task => "done_tasks",
+    }
+}
+
+/// 单个表的一页，参数 ?1/?2 为开始和结束日期，?3/?4/?5 为游标的 date、created_at、id
+///
+/// 游标条件和 LIMIT 放在各自的表中，可以直接使用 (date, created_at, id) 索引，
+/// 而不必对整个日期范围排序。同一表中 kind 固定，与游标中 kind 的比较在拼接 SQL 时完成。
+fn branch_sql(kind: RecordKind, cursor: Option<&Cursor>, direction: SortDirection, limit: i64) -> String {
+    let (strict, inclusive, order) = match direction {
+        SortDirection::Desc => ("<", "<=", "DESC"),
+        SortDirection::Asc => (">", ">=", "ASC"),
+    };
+    let cursor_filter = match cursor {
+        None => String::new(),
+        Some(cursor) => match (kind.as_str().cmp(cursor.kind.as_str()), direction) {
+            // 同一时刻的记录按 kind 排序，当前表排在游标之后时同一时刻的记录都还没读过
+            (Ordering::Equal, _) => format!(" AND (date, created_at, id) {} (?3, ?4, ?5)", strict),
+            (Ordering::Less, SortDirection::Desc) | (Ordering::Greater, SortDirection::Asc) => {
+                format!(" AND (date, created_at) {} (?3, ?4)", inclusive)
+            }
+            _ => format!(" AND (date, created_at) {} (?3, ?4)", strict),
+        },
+    };
+    format!(
+        "SELECT * FROM (SELECT {} FROM {} WHERE date >= ?1 AND date <= ?2{} \
+         ORDER BY date {o}, created_at {o}, id {o} LIMIT {})",
+        select_columns(kind),
+        table(kind),
+        cursor_filter,
+        limit,
+        o = order,
+    )
+}
+
+fn parse_string_list(json: &str) -> Vec<String> {
+    serde_json::from_str(json).unwrap_or_else(|_| Vec::new())
+}
+
+/// 按键集游标分页读取日期范围内的记录
+pub async fn fetch_records_page(pool: &Pool<Sqlite>, query: PageQuery) -> Result<RecordPage, String> {
+    let mut kinds = query.kinds;
+    if kinds.is_empty() {
+        kinds = vec![RecordKind::Idea, RecordKind::Task];
+    }
+    kinds.sort_by_key(|kind| kind.as_str());
+    kinds.dedup();
+    let page_size = query.page_size.clamp(1, MAX_PAGE_SIZE);
+    let cursor = query.cursor.as_deref().map(Cursor::decode).transpose()?;
+
+    // 总数只在第一页统计，翻页时不再重复扫描整个日期范围
+    let total_count = match cursor {
+        Some(_) => None,
+        None => Some(
+            sqlx::query_scalar(&format!("SELECT COUNT(*) FROM ({})", union_sql(&kinds)))
+                .bind(&query.start_date)
+                .bind(&query.end_date)
+                .fetch_one(pool)
+                .await
+                .map_err(|e| format!("统计记录数失败: {}", e))?,
+        ),
+    };
+
+    // 每个表各取一页（多取一条用于判断是否还有下一页），再合并排序
+    let order = match query.direction {
+        SortDirection::Desc => "DESC",
+        SortDirection::Asc => "ASC",
+    };
+    let branches: Vec<String> = kinds
+        .iter()
+        .map(|kind| branch_sql(*kind, cursor.as_ref(), query.direction, page_size + 1))
+        .collect();
+    let sql = format!(
+        "SELECT * FROM ({}) ORDER BY date {o}, created_at {o}, kind {o}, id {o} LIMIT {}",
+        branches.join(" UNION ALL "),
+        page_size + 1,
+        o = order,
+    );
+
+    let mut page_query = sqlx::query(&sql)
+        .bind(&query.start_date)
+        .bind(&query.end_date);
+    if let Some(cursor) = &cursor {
+        page_query = page_query
+            .bind(&cursor.date)
+            .bind(cursor.created_at)
+            .bind(cursor.id);
+    }
+
+    let rows = page_query
+        .fetch_all(pool)
+        .await
+        .map_err(|e| format!("分页查询失败: {}", e))?;
+
+    // 多取一条用于判断是否还有下一页
+    let has_more = rows.len() as i64 > page_size;
+    let mut items = Vec::new();
+    let mut last_cursor = None;
+
+    for row in rows.iter().take(page_size as usize) {
+        let kind: String = row.try_get("kind").map_err(|e| format!("读取记录失败: {}", e))?;
+        let id: i64 = row.try_get("id").map_err(|e| format!("读取记录失败: {}", e))?;
+        let content: String = row.try_get("content").map_err(|e| format!("读取记录失败: {}", e))?;
+        let attachments: String = row.try_get("attachments").map_err(|e| format!("读取记录失败: {}", e))?;
+        let created_at: i64 = row.try_get("created_at").map_err(|e| format!("读取记录失败: {}", e))?;
+        let date: String = row.try_get("date").map_err(|e| format!("读取记录失败: {}", e))?;
+
+        last_cursor = Some(Cursor {
+            date: date.clone(),
+            created_at,
+            kind: kind.clone(),
+            id,
+        });
+
+        if kind == RecordKind::Idea.as_str() {
+            let archived: bool = row.try_get("archived").map_err(|e| format!("读取记录失败: {}", e))?;
+            items.push(RecordItem::Idea(Idea {
+                id,
+                content,
+                attachments: parse_string_list(&attachments),
+                created_at,
+                date,
+                archived,
+            }));
+        } else {
+            let start_time: i64 = row.try_get("start_time").map_err(|e| format!("读取记录失败: {}", e))?;
+            let end_time: i64 = row.try_get("end_time").map_err(|e| format!("读取记录失败: {}", e))?;
+            let tags: String = row.try_get("tags").map_err(|e| format!("读取记录失败: {}", e))?;
+            items.push(RecordItem::Task(DoneTask {
+                id,
+                content,
+                start_time,
+                end_time,
+                attachments: parse_string_list(&attachments),
+                created_at,
+                date,
+                tags: parse_string_list(&tags),
+            }));
+        }
+    }
+
+    Ok(RecordPage {
+        items,
+        total_count,
+        next_cursor: if has_more { last_cursor.map(|c| c.encode()) } else { None },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+    use crate::models::{AuditOrigin, NewDoneTask};
+    use crate::repo::{IdeaRepo, TaskRepo};
+    use crate::test_support::temp_pool;
+
+    fn key(item: &RecordItem) -> (&'static str, i64) {
+        match item {
+            RecordItem::Idea(idea) => ("idea", idea.id),
+            RecordItem::Task(task) => ("task", task.id),
+        }
+    }
+
+    async fn all_pages(pool: &Pool<Sqlite>, page_size: i64, direction: SortDirection) -> (Vec<(&'static str, i64)>, Vec<Option<i64>>) {
+        let mut keys = Vec::new();
+        let mut totals = Vec::new();
+        let mut cursor = None;
+        loop {
+            let page = fetch_records_page(
+                pool,
+                PageQuery {
+                    start_date: "2024-05-01".to_string(),
+                    end_date: "2024-05-31".to_string(),
+                    cursor,
+                    page_size,
+                    kinds: Vec::new(),
+                    direction,
+                },
+            )
+            .await
+            .unwrap();
+            keys.extend(page.items.iter().map(key));
+            totals.push(page.total_count);
+            cursor = page.next_cursor;
+            if cursor.is_none() {
+                return (keys, totals);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn pages_cover_every_record_once_in_order() {
+        let (pool, _dir) = temp_pool().await;
+        let at = |day: u32, hour: u32| chrono::Local.with_ymd_and_hms(2024, 5, day, hour, 0, 0).unwrap().timestamp();
+
+        // 同一时刻的想法和事项 id 相同，游标需要靠 kind 区分
+        for (day, hour) in [(6, 9), (6, 9), (6, 10), (7, 9)] {
+            IdeaRepo::new(&pool).insert("想法".to_string(), Vec::new(), at(day, hour), AuditOrigin::Ui).await.unwrap();
+            let task = NewDoneTask {
+                content: "事项".to_string(),
+                start_time: at(day, hour) - 1800,
+                end_time: at(day, hour) - 600,
+                attachments: Vec::new(),
+                created_at: at(day, hour),
+                tags: Vec::new(),
+            };
+            TaskRepo::new(&pool).insert(task, AuditOrigin::Ui).await.unwrap();
+        }
+
+        let (expected, _) = all_pages(&pool, MAX_PAGE_SIZE, SortDirection::Desc).await;
+        assert_eq!(expected.len(), 8);
+        assert_eq!(expected[0], ("task", 4));
+        assert_eq!(expected[1], ("idea", 4));
+
+        for page_size in [1, 2, 3] {
+            let (desc, totals) = all_pages(&pool, page_size, SortDirection::Desc).await;
+            assert_eq!(desc, expected, "page_size = {}", page_size);
+            assert_eq!(totals[0], Some(8));
+            assert!(totals[1..].iter().all(Option::is_none));
+
+            let (mut asc, _) = all_pages(&pool, page_size, SortDirection::Asc).await;
+            asc.reverse();
+            assert_eq!(asc, expected, "page_size = {}", page_size);
+        }
+    }
+}
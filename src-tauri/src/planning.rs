@@ -0,0 +1,516 @@
+use chrono::{Datelike, NaiveDate};
+use sqlx::{sqlite::SqliteRow, Pool, Row, Sqlite, SqliteConnection};
+
+use crate::audit;
+use crate::config::ConfigManager;
+use crate::idea_links;
+use crate::repo::TaskRepo;
+use crate::models::{AuditEntity, AuditOrigin, DoneTask, NewDoneTask, NewPlannedTask, PlanCarry, PlanPriority, PlanStatus, PlannedTask};
+
+const PLAN_COLUMNS: &str = "id, content, target_date, original_date, estimate_minutes, priority, status, tags, carry_count, done_task_id, created_at, updated_at, completed_at";
+
+/// 同一天内的排序：优先级高的在前，其次按创建顺序
+const PLAN_ORDER: &str = "CASE priority WHEN 'high' THEN 0 WHEN 'normal' THEN 1 ELSE 2 END, id";
+
+fn plan_from_row(row: &SqliteRow) -> Result<PlannedTask, String> {
+    let read_err = |e: sqlx::Error| format!("读取计划失败: {}", e);
+    let priority: String = row.try_get("priority").map_err(read_err)?;
+    let status: String = row.try_get("status").map_err(read_err)?;
+    let tags: String = row.try_get("tags").map_err(read_err)?;
+    Ok(PlannedTask {
+        id: row.try_get("id").map_err(read_err)?,
+        content: row.try_get("content").map_err(read_err)?,
+        target_date: row.try_get("target_date").map_err(read_err)?,
+        original_date: row.try_get("original_date").map_err(read_err)?,
+        estimate_minutes: row.try_get("estimate_minutes").map_err(read_err)?,
+        priority: PlanPriority::parse(&priority).unwrap_or_default(),
+        status: PlanStatus::parse(&status).ok_or_else(|| format!("未知的计划状态: {}", status))?,
+        tags: serde_json::from_str(&tags).unwrap_or_default(),
+        carry_count: row.try_get("carry_count").map_err(read_err)?,
+        done_task_id: row.try_get("done_task_id").map_err(read_err)?,
+        created_at: row.try_get("created_at").map_err(read_err)?,
+        updated_at: row.try_get("updated_at").map_err(read_err)?,
+        completed_at: row.try_get("completed_at").map_err(read_err)?,
+    })
+}
+
+fn parse_date(date: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d").map_err(|_| format!("日期格式无效: {}", date))
+}
+
+/// 校验并规范化计划内容
+fn normalize(plan: NewPlannedTask) -> Result<NewPlannedTask, String> {
+    let content = plan.content.trim().to_string();
+    if content.is_empty() {
+        return Err("计划内容不能为空".to_string());
+    }
+    let target_date = parse_date(&plan.target_date)?.format("%Y-%m-%d").to_string();
+    if plan.estimate_minutes.is_some_and(|m| m <= 0) {
+        return Err("预估耗时必须大于 0".to_string());
+    }
+    Ok(NewPlannedTask {
+        content,
+        target_date,
+        ..plan
+    })
+}
+
+/// `date` 当天或之后的第一个工作日；`workdays` 为空时视每天都是工作日
+pub fn workday_on_or_after(date: NaiveDate, workdays: &[u32]) -> NaiveDate {
+    let mut day = date;
+    for _ in 0..7 {
+        if workdays.is_empty() || workdays.contains(&day.weekday().number_from_monday()) {
+            return day;
+        }
+        day = day.succ_opt().unwrap_or(day);
+    }
+    date
+}
+
+/// 新建计划
+pub async fn add_planned_task(pool: &Pool<Sqlite>, plan: NewPlannedTask, origin: AuditOrigin) -> Result<PlannedTask, String> {
+    let mut tx = pool.begin().await.map_err(|e| format!("开启事务失败: {}", e))?;
+    let plan = add_planned_task_with(&mut tx, plan, origin).await?;
+    tx.commit().await.map_err(|e| format!("提交事务失败: {}", e))?;
+    Ok(plan)
+}
+
+/// 在调用方的事务中新建计划
+pub async fn add_planned_task_with(
+    conn: &mut SqliteConnection,
+    plan: NewPlannedTask,
+    origin: AuditOrigin,
+) -> Result<PlannedTask, String> {
+    let plan = normalize(plan)?;
+    let now = chrono::Local::now().timestamp();
+
+    let id = sqlx::query(
+        r#"
+        INSERT INTO planned_tasks (content, target_date, original_date, estimate_minutes, priority, status, tags, created_at, updated_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+        "#,
+    )
+    .bind(&plan.content)
+    .bind(&plan.target_date)
+    .bind(&plan.target_date)
+    .bind(plan.estimate_minutes)
+    .bind(plan.priority.as_str())
+    .bind(PlanStatus::Pending.as_str())
+    .bind(serde_json::to_string(&plan.tags).unwrap_or_else(|_| "[]".to_string()))
+    .bind(now)
+    .bind(now)
+    .execute(&mut *conn)
+    .await
+    .map_err(|e| format!("保存计划失败: {}", e))?
+    .last_insert_rowid();
+
+    let plan = get_planned_task_with(&mut *conn, id).await?;
+    audit::record_insert(conn, origin, AuditEntity::Plan, id, &plan).await?;
+    Ok(plan)
+}
+
+/// 在调用方的连接或事务中读取单个计划
+pub async fn get_planned_task_with(conn: &mut SqliteConnection, id: i64) -> Result<PlannedTask, String> {
+    let row = sqlx::query(&format!("SELECT {} FROM planned_tasks WHERE id = ?", PLAN_COLUMNS))
+        .bind(id)
+        .fetch_optional(conn)
+        .await
+        .map_err(|e| format!("查询计划失败: {}", e))?
+        .ok_or_else(|| format!("计划 {} 不存在", id))?;
+    plan_from_row(&row)
+}
+
+/// 读取日期范围内的计划
+pub async fn list_planned_tasks(
+    pool: &Pool<Sqlite>,
+    start_date: &str,
+    end_date: &str,
+    status: Option<PlanStatus>,
+) -> Result<Vec<PlannedTask>, String> {
+    let rows = sqlx::query(&format!(
+        "SELECT {} FROM planned_tasks WHERE target_date >= ? AND target_date <= ? AND (? IS NULL OR status = ?) ORDER BY target_date, {}",
+        PLAN_COLUMNS, PLAN_ORDER
+    ))
+    .bind(start_date)
+    .bind(end_date)
+    .bind(status.map(|s| s.as_str()))
+    .bind(status.map(|s| s.as_str()))
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("查询计划失败: {}", e))?;
+    rows.iter().map(plan_from_row).collect()
+}
+
+/// `after_date` 之后最近一天的未完成计划（即"下一步计划"）
+pub async fn next_planned_tasks(pool: &Pool<Sqlite>, after_date: &str) -> Result<Vec<PlannedTask>, String> {
+    let rows = sqlx::query(&format!(
+        r#"
+        SELECT {} FROM planned_tasks
+        WHERE status = 'pending'
+          AND target_date = (SELECT MIN(target_date) FROM planned_tasks WHERE status = 'pending' AND target_date > ?)
+        ORDER BY {}
+        "#,
+        PLAN_COLUMNS, PLAN_ORDER
+    ))
+    .bind(after_date)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("查询计划失败: {}", e))?;
+    rows.iter().map(plan_from_row).collect()
+}
+
+/// 读取从日期范围内顺延出去的记录
+pub async fn list_carries(pool: &Pool<Sqlite>, start_date: &str, end_date: &str) -> Result<Vec<PlanCarry>, String> {
+    let rows = sqlx::query(
+        r#"
+        SELECT c.planned_task_id, p.content, c.from_date, c.to_date, c.created_at
+        FROM planned_task_carries c
+        JOIN planned_tasks p ON p.id = c.planned_task_id
+        WHERE c.from_date >= ? AND c.from_date <= ?
+        ORDER BY c.from_date, c.id
+        "#,
+    )
+    .bind(start_date)
+    .bind(end_date)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("查询顺延记录失败: {}", e))?;
+
+    rows.iter()
+        .map(|row| {
+            let read_err = |e: sqlx::Error| format!("读取顺延记录失败: {}", e);
+            Ok(PlanCarry {
+                planned_task_id: row.try_get("planned_task_id").map_err(read_err)?,
+                content: row.try_get("content").map_err(read_err)?,
+                from_date: row.try_get("from_date").map_err(read_err)?,
+                to_date: row.try_get("to_date").map_err(read_err)?,
+                created_at: row.try_get("created_at").map_err(read_err)?,
+            })
+        })
+        .collect()
+}
+
+/// 读取修改后的计划并记录审计日志
+async fn updated(conn: &mut SqliteConnection, before: PlannedTask, origin: AuditOrigin) -> Result<PlannedTask, String> {
+    let after = get_planned_task_with(&mut *conn, before.id).await?;
+    audit::record_update(conn, origin, AuditEntity::Plan, before.id, &before, &after).await?;
+    Ok(after)
+}
+
+/// 更新计划内容（已完成的计划不能修改）
+pub async fn update_planned_task(
+    pool: &Pool<Sqlite>,
+    id: i64,
+    plan: NewPlannedTask,
+    origin: AuditOrigin,
+) -> Result<PlannedTask, String> {
+    let plan = normalize(plan)?;
+    let mut tx = pool.begin().await.map_err(|e| format!("开启事务失败: {}", e))?;
+    let before = get_planned_task_with(&mut tx, id).await?;
+    if before.status == PlanStatus::Done {
+        return Err("计划已完成，不能修改".to_string());
+    }
+
+    sqlx::query(
+        r#"
+        UPDATE planned_tasks
+        SET content = ?, target_date = ?, estimate_minutes = ?, priority = ?, tags = ?, updated_at = ?
+        WHERE id = ?
+        "#,
+    )
+    .bind(&plan.content)
+    .bind(&plan.target_date)
+    .bind(plan.estimate_minutes)
+    .bind(plan.priority.as_str())
+    .bind(serde_json::to_string(&plan.tags).unwrap_or_else(|_| "[]".to_string()))
+    .bind(chrono::Local::now().timestamp())
+    .bind(id)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("更新计划失败: {}", e))?;
+
+    let plan = updated(&mut tx, before, origin).await?;
+    tx.commit().await.map_err(|e| format!("提交事务失败: {}", e))?;
+    Ok(plan)
+}
+
+/// 取消或恢复计划；完成计划请使用 `complete_planned_task`
+pub async fn set_planned_task_status(
+    pool: &Pool<Sqlite>,
+    id: i64,
+    status: PlanStatus,
+    origin: AuditOrigin,
+) -> Result<PlannedTask, String> {
+    if status == PlanStatus::Done {
+        return Err("请通过完成计划生成已完成事项".to_string());
+    }
+    let mut tx = pool.begin().await.map_err(|e| format!("开启事务失败: {}", e))?;
+    let before = get_planned_task_with(&mut tx, id).await?;
+    if before.status == PlanStatus::Done {
+        return Err("计划已完成，不能修改状态".to_string());
+    }
+
+    sqlx::query("UPDATE planned_tasks SET status = ?, updated_at = ? WHERE id = ?")
+        .bind(status.as_str())
+        .bind(chrono::Local::now().timestamp())
+        .bind(id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("更新计划状态失败: {}", e))?;
+
+    let plan = updated(&mut tx, before, origin).await?;
+    tx.commit().await.map_err(|e| format!("提交事务失败: {}", e))?;
+    Ok(plan)
+}
+
+/// 删除计划及其顺延记录并解除想法关联（已生成的已完成事项保留）
+pub async fn delete_planned_task(pool: &Pool<Sqlite>, id: i64, origin: AuditOrigin) -> Result<(), String> {
+    let mut tx = pool.begin().await.map_err(|e| format!("开启事务失败: {}", e))?;
+    let existing = get_planned_task_with(&mut tx, id).await?;
+
+    sqlx::query("DELETE FROM planned_task_carries WHERE planned_task_id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("删除顺延记录失败: {}", e))?;
+
+    sqlx::query("DELETE FROM planned_tasks WHERE id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("删除计划失败: {}", e))?;
+
+    idea_links::on_plan_deleted(&mut tx, id).await?;
+    audit::record_delete(&mut tx, origin, AuditEntity::Plan, id, &existing).await?;
+
+    tx.commit().await.map_err(|e| format!("提交事务失败: {}", e))?;
+    Ok(())
+}
+
+/// 完成计划：按实际起止时间生成已完成事项，并关联到计划及其来源想法
+///
+/// 时间段需由调用方事先校验。
+pub async fn complete_planned_task(
+    pool: &Pool<Sqlite>,
+    id: i64,
+    start_time: i64,
+    end_time: i64,
+    attachments: Vec<String>,
+    origin: AuditOrigin,
+) -> Result<DoneTask, String> {
+    let mut tx = pool.begin().await.map_err(|e| format!("开启事务失败: {}", e))?;
+
+    let plan = get_planned_task_with(&mut tx, id).await?;
+    if plan.status != PlanStatus::Pending {
+        return Err(format!("计划 {} 不是未完成状态", id));
+    }
+
+    let now = chrono::Local::now().timestamp();
+
+    let task = TaskRepo::insert_with(
+        &mut tx,
+        NewDoneTask {
+            content: plan.content.clone(),
+            start_time,
+            end_time,
+            attachments,
+            created_at: now,
+            tags: plan.tags.clone(),
+        },
+        origin,
+    )
+    .await?;
+
+    // 只更新仍未完成的计划，避免并发重复完成
+    let completed = sqlx::query(
+        "UPDATE planned_tasks SET status = ?, done_task_id = ?, completed_at = ?, updated_at = ? WHERE id = ? AND status = ?",
+    )
+    .bind(PlanStatus::Done.as_str())
+    .bind(task.id)
+    .bind(now)
+    .bind(now)
+    .bind(id)
+    .bind(PlanStatus::Pending.as_str())
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("更新计划状态失败: {}", e))?;
+    if completed.rows_affected() == 0 {
+        return Err(format!("计划 {} 不是未完成状态", id));
+    }
+
+    updated(&mut tx, plan, origin).await?;
+    idea_links::on_plan_completed(&mut tx, id, task.id).await?;
+
+    tx.commit().await.map_err(|e| format!("提交事务失败: {}", e))?;
+    Ok(task)
+}
+
+/// 将早于 `today` 的未完成计划顺延到今天或之后的第一个工作日，返回被顺延的计划
+pub async fn carry_over(
+    pool: &Pool<Sqlite>,
+    today: NaiveDate,
+    workdays: &[u32],
+    origin: AuditOrigin,
+) -> Result<Vec<PlannedTask>, String> {
+    let today_str = today.format("%Y-%m-%d").to_string();
+    let to_date = workday_on_or_after(today, workdays).format("%Y-%m-%d").to_string();
+    let now = chrono::Local::now().timestamp();
+
+    let mut tx = pool.begin().await.map_err(|e| format!("开启事务失败: {}", e))?;
+
+    let rows = sqlx::query(&format!("SELECT {} FROM planned_tasks WHERE status = ? AND target_date < ?", PLAN_COLUMNS))
+        .bind(PlanStatus::Pending.as_str())
+        .bind(&today_str)
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(|e| format!("查询过期计划失败: {}", e))?;
+
+    let mut carried = Vec::with_capacity(rows.len());
+    for row in &rows {
+        let before = plan_from_row(row)?;
+        let (id, from_date) = (before.id, before.target_date.clone());
+
+        sqlx::query("INSERT INTO planned_task_carries (planned_task_id, from_date, to_date, created_at) VALUES (?, ?, ?, ?)")
+            .bind(id)
+            .bind(&from_date)
+            .bind(&to_date)
+            .bind(now)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("保存顺延记录失败: {}", e))?;
+
+        sqlx::query("UPDATE planned_tasks SET target_date = ?, carry_count = carry_count + 1, updated_at = ? WHERE id = ?")
+            .bind(&to_date)
+            .bind(now)
+            .bind(id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("顺延计划失败: {}", e))?;
+
+        carried.push(updated(&mut tx, before, origin).await?);
+    }
+
+    tx.commit().await.map_err(|e| format!("提交事务失败: {}", e))?;
+    Ok(carried)
+}
+
+/// 按应用设置自动顺延过期计划；未开启自动顺延时不做任何处理
+///
+/// 由启动、切换工作区等流程自动触发，审计日志中记为后台任务。
+pub async fn auto_carry_over(app: &tauri::AppHandle, pool: &Pool<Sqlite>) -> Result<Vec<PlannedTask>, String> {
+    let settings = ConfigManager::new(app)?.load_settings()?;
+    if !settings.plan_auto_carry_over {
+        return Ok(Vec::new());
+    }
+    carry_over(pool, chrono::Local::now().date_naive(), &settings.plan_workdays, AuditOrigin::Scheduler).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::{self, AuditQuery};
+    use crate::models::{AuditAction, AuditEntity};
+    use crate::test_support::temp_pool;
+
+    fn date(value: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d").unwrap()
+    }
+
+    fn new_plan(content: &str, target_date: &str) -> NewPlannedTask {
+        NewPlannedTask {
+            content: content.to_string(),
+            target_date: target_date.to_string(),
+            estimate_minutes: None,
+            priority: PlanPriority::Normal,
+            tags: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn workday_skips_to_the_next_configured_day() {
+        let weekdays = [1, 2, 3, 4, 5];
+        // 2024-05-10 是周五，2024-05-11 是周六
+        assert_eq!(workday_on_or_after(date("2024-05-10"), &weekdays), date("2024-05-10"));
+        assert_eq!(workday_on_or_after(date("2024-05-11"), &weekdays), date("2024-05-13"));
+        assert_eq!(workday_on_or_after(date("2024-05-11"), &[]), date("2024-05-11"));
+        // 没有有效的工作日时不顺延到更远的日期
+        assert_eq!(workday_on_or_after(date("2024-05-11"), &[9]), date("2024-05-11"));
+    }
+
+    #[tokio::test]
+    async fn carry_over_moves_only_overdue_pending_plans_once() {
+        let (pool, _dir) = temp_pool().await;
+        let overdue = add_planned_task(&pool, new_plan("写周报", "2024-05-09"), AuditOrigin::Ui).await.unwrap();
+        let cancelled = add_planned_task(&pool, new_plan("旧需求", "2024-05-09"), AuditOrigin::Ui).await.unwrap();
+        set_planned_task_status(&pool, cancelled.id, PlanStatus::Cancelled, AuditOrigin::Ui).await.unwrap();
+        let future = add_planned_task(&pool, new_plan("评审", "2024-05-14"), AuditOrigin::Ui).await.unwrap();
+
+        // 周六顺延到下周一
+        let today = date("2024-05-11");
+        let carried = carry_over(&pool, today, &[1, 2, 3, 4, 5], AuditOrigin::Scheduler).await.unwrap();
+        assert_eq!(carried.len(), 1);
+        assert_eq!(carried[0].id, overdue.id);
+        assert_eq!(carried[0].target_date, "2024-05-13");
+        assert_eq!(carried[0].original_date, "2024-05-09");
+        assert_eq!(carried[0].carry_count, 1);
+
+        let carries = list_carries(&pool, "2024-05-01", "2024-05-31").await.unwrap();
+        assert_eq!(carries.len(), 1);
+        assert_eq!((carries[0].from_date.as_str(), carries[0].to_date.as_str()), ("2024-05-09", "2024-05-13"));
+
+        // 同一天再次顺延不会重复处理
+        assert!(carry_over(&pool, today, &[1, 2, 3, 4, 5], AuditOrigin::Scheduler).await.unwrap().is_empty());
+
+        let mut conn = pool.acquire().await.unwrap();
+        assert_eq!(get_planned_task_with(&mut conn, cancelled.id).await.unwrap().target_date, "2024-05-09");
+        assert_eq!(get_planned_task_with(&mut conn, future.id).await.unwrap().carry_count, 0);
+    }
+
+    #[tokio::test]
+    async fn completing_a_plan_creates_the_task_exactly_once() {
+        let (pool, _dir) = temp_pool().await;
+        let plan = NewPlannedTask { tags: vec!["文档".to_string()], ..new_plan("整理接口文档", "2024-05-06") };
+        let plan = add_planned_task(&pool, plan, AuditOrigin::Ui).await.unwrap();
+
+        let task = complete_planned_task(&pool, plan.id, 1_715_000_000, 1_715_001_800, Vec::new(), AuditOrigin::Ui)
+            .await
+            .unwrap();
+        assert_eq!(task.content, "整理接口文档");
+        assert_eq!(task.tags, vec!["文档"]);
+        assert_eq!((task.start_time, task.end_time), (1_715_000_000, 1_715_001_800));
+
+        let mut conn = pool.acquire().await.unwrap();
+        let done = get_planned_task_with(&mut conn, plan.id).await.unwrap();
+        assert_eq!(done.status, PlanStatus::Done);
+        assert_eq!(done.done_task_id, Some(task.id));
+        assert!(done.completed_at.is_some());
+
+        let again = complete_planned_task(&pool, plan.id, 1_715_002_000, 1_715_003_000, Vec::new(), AuditOrigin::Ui).await;
+        assert!(again.is_err());
+        let tasks: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM done_tasks").fetch_one(&pool).await.unwrap();
+        assert_eq!(tasks, 1);
+
+        // 已完成的计划不能再修改或顺延
+        assert!(update_planned_task(&pool, plan.id, new_plan("改名", "2024-05-07"), AuditOrigin::Ui).await.is_err());
+        assert!(carry_over(&pool, date("2024-05-08"), &[], AuditOrigin::Scheduler).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn plan_changes_are_audited_with_their_origin() {
+        let (pool, _dir) = temp_pool().await;
+        let plan = add_planned_task(&pool, new_plan("整理周报", "2024-05-06"), AuditOrigin::Ui).await.unwrap();
+
+        let carried = carry_over(&pool, date("2024-05-08"), &[], AuditOrigin::Scheduler).await.unwrap();
+        assert_eq!(carried.len(), 1);
+
+        let query = AuditQuery { entity: Some(AuditEntity::Plan), entity_id: Some(plan.id), ..Default::default() };
+        let entries = audit::list_entries(&pool, &query).await.unwrap();
+        let actions: Vec<_> = entries.iter().map(|e| (e.action, e.origin)).collect();
+        assert_eq!(
+            actions,
+            vec![(AuditAction::Update, AuditOrigin::Scheduler), (AuditAction::Insert, AuditOrigin::Ui)]
+        );
+        assert_eq!(entries[0].before.as_ref().unwrap()["target_date"], "2024-05-06");
+        assert_eq!(entries[0].after.as_ref().unwrap()["target_date"], "2024-05-08");
+    }
+}
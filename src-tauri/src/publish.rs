@@ -0,0 +1,691 @@
+use std::time::Duration;
+
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use pulldown_cmark::{Event, Options, Parser, Tag, TagEnd};
+use serde_json::{json, Value};
+use sha2::Sha256;
+use sqlx::{sqlite::SqliteRow, Pool, Row, Sqlite};
+
+use crate::models::{PublishPlatform, PublishTarget, Report, ReportPublication};
+use crate::reports;
+
+/// 推送请求超时
+const PUBLISH_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// 各平台消息内容的长度上限（字节），超出部分截断
+const FEISHU_MAX_BYTES: usize = 18_000;
+const DINGTALK_MAX_BYTES: usize = 18_000;
+const WECOM_MAX_BYTES: usize = 4_096;
+
+const TRUNCATED_NOTICE: &str = "\n\n…（内容过长，已截断）";
+
+const TARGET_COLUMNS: &str = "id, name, platform, webhook_url, secret, enabled, created_at, updated_at";
+
+const PUBLICATION_COLUMNS: &str =
+    "id, report_id, target_id, target_name, platform, success, error, response, created_at";
+
+fn parse_platform(value: &str) -> Result<PublishPlatform, String> {
+    PublishPlatform::parse(value).ok_or_else(|| format!("未知的推送平台: {}", value))
+}
+
+fn target_from_row(row: &SqliteRow) -> Result<PublishTarget, String> {
+    let read_err = |e: sqlx::Error| format!("读取推送目标失败: {}", e);
+    let platform: String = row.try_get("platform").map_err(read_err)?;
+    Ok(PublishTarget {
+        id: row.try_get("id").map_err(read_err)?,
+        name: row.try_get("name").map_err(read_err)?,
+        platform: parse_platform(&platform)?,
+        webhook_url: row.try_get("webhook_url").map_err(read_err)?,
+        secret: row.try_get("secret").map_err(read_err)?,
+        enabled: row.try_get("enabled").map_err(read_err)?,
+        created_at: row.try_get("created_at").map_err(read_err)?,
+        updated_at: row.try_get("updated_at").map_err(read_err)?,
+    })
+}
+
+fn publication_from_row(row: &SqliteRow) -> Result<ReportPublication, String> {
+    let read_err = |e: sqlx::Error| format!("读取推送记录失败: {}", e);
+    let platform: String = row.try_get("platform").map_err(read_err)?;
+    Ok(ReportPublication {
+        id: row.try_get("id").map_err(read_err)?,
+        report_id: row.try_get("report_id").map_err(read_err)?,
+        target_id: row.try_get("target_id").map_err(read_err)?,
+        target_name: row.try_get("target_name").map_err(read_err)?,
+        platform: parse_platform(&platform)?,
+        success: row.try_get("success").map_err(read_err)?,
+        error: row.try_get("error").map_err(read_err)?,
+        response: row.try_get("response").map_err(read_err)?,
+        created_at: row.try_get("created_at").map_err(read_err)?,
+    })
+}
+
+fn validate_target(name: &str, webhook_url: &str) -> Result<(), String> {
+    if name.trim().is_empty() {
+        return Err("推送目标名称不能为空".to_string());
+    }
+    let url = webhook_url.trim();
+    if !url.starts_with("https://") && !url.starts_with("http://") {
+        return Err("Webhook 地址必须以 http:// 或 https:// 开头".to_string());
+    }
+    Ok(())
+}
+
+fn normalize_secret(secret: Option<String>) -> Option<String> {
+    secret.map(|s| s.trim().to_string()).filter(|s| !s.is_empty())
+}
+
+// ========== 推送目标 ==========
+
+/// 读取单个推送目标
+pub async fn get_target(pool: &Pool<Sqlite>, id: i64) -> Result<PublishTarget, String> {
+    let row = sqlx::query(&format!("SELECT {} FROM publish_targets WHERE id = ?", TARGET_COLUMNS))
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| format!("查询推送目标失败: {}", e))?
+        .ok_or_else(|| format!("推送目标 {} 不存在", id))?;
+    target_from_row(&row)
+}
+
+/// 列出所有推送目标
+pub async fn list_targets(pool: &Pool<Sqlite>) -> Result<Vec<PublishTarget>, String> {
+    let rows = sqlx::query(&format!("SELECT {} FROM publish_targets ORDER BY id", TARGET_COLUMNS))
+        .fetch_all(pool)
+        .await
+        .map_err(|e| format!("查询推送目标失败: {}", e))?;
+    rows.iter().map(target_from_row).collect()
+}
+
+/// 添加推送目标
+pub async fn add_target(
+    pool: &Pool<Sqlite>,
+    name: &str,
+    platform: PublishPlatform,
+    webhook_url: &str,
+    secret: Option<String>,
+) -> Result<PublishTarget, String> {
+    validate_target(name, webhook_url)?;
+    let now = chrono::Local::now().timestamp();
+
+    let id = sqlx::query(
+        r#"
+        INSERT INTO publish_targets (name, platform, webhook_url, secret, enabled, created_at, updated_at)
+        VALUES (?, ?, ?, ?, 1, ?, ?)
+        "#,
+    )
+    .bind(name.trim())
+    .bind(platform.as_str())
+    .bind(webhook_url.trim())
+    .bind(normalize_secret(secret))
+    .bind(now)
+    .bind(now)
+    .execute(pool)
+    .await
+    .map_err(|e| format!("添加推送目标失败: {}", e))?
+    .last_insert_rowid();
+
+    get_target(pool, id).await
+}
+
+/// 更新推送目标
+pub async fn update_target(
+    pool: &Pool<Sqlite>,
+    id: i64,
+    name: &str,
+    webhook_url: &str,
+    secret: Option<String>,
+    enabled: bool,
+) -> Result<PublishTarget, String> {
+    validate_target(name, webhook_url)?;
+
+    let result = sqlx::query(
+        "UPDATE publish_targets SET name = ?, webhook_url = ?, secret = ?, enabled = ?, updated_at = ? WHERE id = ?",
+    )
+    .bind(name.trim())
+    .bind(webhook_url.trim())
+    .bind(normalize_secret(secret))
+    .bind(enabled)
+    .bind(chrono::Local::now().timestamp())
+    .bind(id)
+    .execute(pool)
+    .await
+    .map_err(|e| format!("更新推送目标失败: {}", e))?;
+
+    if result.rows_affected() == 0 {
+        return Err(format!("推送目标 {} 不存在", id));
+    }
+    get_target(pool, id).await
+}
+
+/// 删除推送目标（保留已有的推送记录）
+pub async fn delete_target(pool: &Pool<Sqlite>, id: i64) -> Result<(), String> {
+    let mut tx = pool.begin().await.map_err(|e| format!("开启事务失败: {}", e))?;
+
+    sqlx::query("UPDATE report_publications SET target_id = NULL WHERE target_id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("更新推送记录失败: {}", e))?;
+
+    sqlx::query("DELETE FROM publish_targets WHERE id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("删除推送目标失败: {}", e))?;
+
+    tx.commit().await.map_err(|e| format!("提交事务失败: {}", e))?;
+    Ok(())
+}
+
+// ========== 推送 ==========
+
+/// 推送报告到指定目标；`target_ids` 为空时推送到所有已启用的目标
+///
+/// 每次推送（无论成功与否）都会记录到 report_publications。
+pub async fn publish_report(
+    pool: &Pool<Sqlite>,
+    report_id: i64,
+    target_ids: Option<Vec<i64>>,
+) -> Result<Vec<ReportPublication>, String> {
+    let report = reports::get_report(pool, report_id).await?;
+
+    let targets = match target_ids {
+        Some(ids) => {
+            let mut targets = Vec::with_capacity(ids.len());
+            for id in ids {
+                targets.push(get_target(pool, id).await?);
+            }
+            targets
+        }
+        None => list_targets(pool).await?.into_iter().filter(|t| t.enabled).collect(),
+    };
+    if targets.is_empty() {
+        return Err("没有可用的推送目标".to_string());
+    }
+
+    let http = reqwest::Client::builder()
+        .timeout(PUBLISH_TIMEOUT)
+        .build()
+        .map_err(|e| format!("创建 HTTP 客户端失败: {}", e))?;
+
+    let mut publications = Vec::with_capacity(targets.len());
+    for target in &targets {
+        let (success, error, response) = match send(&http, target, &report).await {
+            Ok(response) => (true, None, Some(response)),
+            Err((error, response)) => (false, Some(error), response),
+        };
+        publications.push(record(pool, &report, target, success, error, response).await?);
+    }
+    Ok(publications)
+}
+
+/// 读取报告的推送记录
+pub async fn list_publications(pool: &Pool<Sqlite>, report_id: i64) -> Result<Vec<ReportPublication>, String> {
+    let rows = sqlx::query(&format!(
+        "SELECT {} FROM report_publications WHERE report_id = ? ORDER BY id DESC",
+        PUBLICATION_COLUMNS
+    ))
+    .bind(report_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("查询推送记录失败: {}", e))?;
+    rows.iter().map(publication_from_row).collect()
+}
+
+async fn record(
+    pool: &Pool<Sqlite>,
+    report: &Report,
+    target: &PublishTarget,
+    success: bool,
+    error: Option<String>,
+    response: Option<String>,
+) -> Result<ReportPublication, String> {
+    let id = sqlx::query(
+        r#"
+        INSERT INTO report_publications
+            (report_id, target_id, target_name, platform, success, error, response, created_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+        "#,
+    )
+    .bind(report.id)
+    .bind(target.id)
+    .bind(&target.name)
+    .bind(target.platform.as_str())
+    .bind(success)
+    .bind(&error)
+    .bind(&response)
+    .bind(chrono::Local::now().timestamp())
+    .execute(pool)
+    .await
+    .map_err(|e| format!("保存推送记录失败: {}", e))?
+    .last_insert_rowid();
+
+    let row = sqlx::query(&format!("SELECT {} FROM report_publications WHERE id = ?", PUBLICATION_COLUMNS))
+        .bind(id)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| format!("查询推送记录失败: {}", e))?;
+    publication_from_row(&row)
+}
+
+/// 发送一条消息，失败时返回 (错误信息, 平台响应)
+async fn send(
+    http: &reqwest::Client,
+    target: &PublishTarget,
+    report: &Report,
+) -> Result<String, (String, Option<String>)> {
+    let secret = target.secret.as_deref();
+    let mut request = match target.platform {
+        PublishPlatform::Feishu => {
+            let mut body = feishu_message(&report.title, &report.content);
+            if let Some(secret) = secret {
+                let timestamp = chrono::Local::now().timestamp();
+                body["timestamp"] = json!(timestamp.to_string());
+                body["sign"] = json!(feishu_sign(timestamp, secret));
+            }
+            http.post(&target.webhook_url).json(&body)
+        }
+        PublishPlatform::Dingtalk => {
+            let body = dingtalk_message(&report.title, &report.content);
+            http.post(&target.webhook_url).json(&body)
+        }
+        PublishPlatform::Wecom => {
+            let body = wecom_message(&report.content);
+            http.post(&target.webhook_url).json(&body)
+        }
+    };
+    if let (PublishPlatform::Dingtalk, Some(secret)) = (target.platform, secret) {
+        // 钉钉的签名放在 URL 参数中
+        let timestamp = chrono::Local::now().timestamp_millis();
+        request = request.query(&[
+            ("timestamp", timestamp.to_string()),
+            ("sign", dingtalk_sign(timestamp, secret)),
+        ]);
+    }
+
+    let response = request
+        .send()
+        .await
+        .map_err(|e| (format!("请求 Webhook 失败: {}", e), None))?;
+    let status = response.status();
+    let text = response
+        .text()
+        .await
+        .map_err(|e| (format!("读取 Webhook 响应失败: {}", e), None))?;
+    if !status.is_success() {
+        return Err((format!("Webhook 返回 HTTP {}", status.as_u16()), Some(text)));
+    }
+
+    // 各平台都用 HTTP 200 返回业务错误，需要检查响应中的错误码
+    let value: Value = serde_json::from_str(&text).unwrap_or(Value::Null);
+    let (code, message) = match target.platform {
+        PublishPlatform::Feishu => (
+            value["code"].as_i64().or_else(|| value["StatusCode"].as_i64()),
+            value["msg"].as_str().or_else(|| value["StatusMessage"].as_str()),
+        ),
+        PublishPlatform::Dingtalk | PublishPlatform::Wecom => {
+            (value["errcode"].as_i64(), value["errmsg"].as_str())
+        }
+    };
+    match code {
+        Some(0) | None => Ok(text),
+        Some(code) => Err((
+            format!("平台返回错误 {}: {}", code, message.unwrap_or_default()),
+            Some(text),
+        )),
+    }
+}
+
+// ========== 签名 ==========
+
+fn hmac_sha256_base64(key: &[u8], message: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC 可以接受任意长度的密钥");
+    mac.update(message);
+    base64::engine::general_purpose::STANDARD.encode(mac.finalize().into_bytes())
+}
+
+/// 飞书：以 "timestamp\nsecret" 为密钥对空字符串做 HmacSHA256，再 Base64
+fn feishu_sign(timestamp: i64, secret: &str) -> String {
+    hmac_sha256_base64(format!("{}\n{}", timestamp, secret).as_bytes(), b"")
+}
+
+/// 钉钉：以 secret 为密钥对 "timestamp\nsecret" 做 HmacSHA256，再 Base64（毫秒时间戳）
+fn dingtalk_sign(timestamp_ms: i64, secret: &str) -> String {
+    hmac_sha256_base64(secret.as_bytes(), format!("{}\n{}", timestamp_ms, secret).as_bytes())
+}
+
+// ========== 消息格式 ==========
+
+/// 按字节数截断，保证不截断在字符中间
+fn truncate_bytes(text: &str, max_bytes: usize) -> String {
+    if text.len() <= max_bytes {
+        return text.to_string();
+    }
+    let limit = max_bytes.saturating_sub(TRUNCATED_NOTICE.len());
+    let mut end = limit;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}{}", &text[..end], TRUNCATED_NOTICE)
+}
+
+fn is_list_item(line: &str) -> bool {
+    let line = line.trim_start();
+    line.starts_with("- ")
+        || line.starts_with("* ")
+        || line.starts_with("+ ")
+        || line
+            .split_once(". ")
+            .map(|(n, _)| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()))
+            .unwrap_or(false)
+}
+
+/// 去掉代码块标记和空行；钉钉、企业微信不支持代码块
+fn markdown_lines(markdown: &str) -> Vec<&str> {
+    markdown
+        .lines()
+        .map(str::trim_end)
+        .filter(|line| !line.trim().is_empty() && !line.trim_start().starts_with("```"))
+        .collect()
+}
+
+/// 飞书富文本（post）消息
+///
+/// 每个段落、列表项为一行；标题和加粗转为粗体，链接转为 a 标签，图片省略。
+fn feishu_message(title: &str, markdown: &str) -> Value {
+    let markdown = truncate_bytes(markdown, FEISHU_MAX_BYTES);
+    let mut lines: Vec<Vec<Value>> = Vec::new();
+    let mut line: Vec<Value> = Vec::new();
+    let mut bold = 0;
+    let mut italic = 0;
+    let mut strike = 0;
+    let mut link: Option<String> = None;
+    let mut lists: Vec<Option<u64>> = Vec::new();
+    let mut in_image = false;
+
+    fn flush(lines: &mut Vec<Vec<Value>>, line: &mut Vec<Value>) {
+        if !line.is_empty() {
+            lines.push(std::mem::take(line));
+        }
+    }
+
+    let options = Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TABLES;
+    for event in Parser::new_ext(&markdown, options) {
+        match event {
+            Event::Start(Tag::Heading { .. }) => {
+                flush(&mut lines, &mut line);
+                bold += 1;
+            }
+            Event::End(TagEnd::Heading(_)) => {
+                bold -= 1;
+                flush(&mut lines, &mut line);
+            }
+            Event::Start(Tag::Strong) => bold += 1,
+            Event::End(TagEnd::Strong) => bold -= 1,
+            Event::Start(Tag::Emphasis) => italic += 1,
+            Event::End(TagEnd::Emphasis) => italic -= 1,
+            Event::Start(Tag::Strikethrough) => strike += 1,
+            Event::End(TagEnd::Strikethrough) => strike -= 1,
+            Event::Start(Tag::Link { dest_url, .. }) => link = Some(dest_url.to_string()),
+            Event::End(TagEnd::Link) => link = None,
+            Event::Start(Tag::Image { .. }) => in_image = true,
+            Event::End(TagEnd::Image) => in_image = false,
+            Event::Start(Tag::List(start)) => lists.push(start),
+            Event::End(TagEnd::List(_)) => {
+                lists.pop();
+            }
+            Event::Start(Tag::Item) => {
+                flush(&mut lines, &mut line);
+                let indent = "  ".repeat(lists.len().saturating_sub(1));
+                let marker = match lists.last_mut() {
+                    Some(Some(n)) => {
+                        let marker = format!("{}{}. ", indent, n);
+                        *n += 1;
+                        marker
+                    }
+                    _ => format!("{}• ", indent),
+                };
+                line.push(json!({ "tag": "text", "text": marker }));
+            }
+            // 列表项内的段落不另起一行
+            Event::Start(Tag::Paragraph) | Event::End(TagEnd::Paragraph) if lists.is_empty() => {
+                flush(&mut lines, &mut line);
+            }
+            Event::End(TagEnd::Item)
+            | Event::Start(Tag::CodeBlock(_))
+            | Event::End(TagEnd::CodeBlock)
+            | Event::End(TagEnd::TableHead)
+            | Event::End(TagEnd::TableRow)
+            | Event::SoftBreak
+            | Event::HardBreak => flush(&mut lines, &mut line),
+            Event::End(TagEnd::TableCell) => line.push(json!({ "tag": "text", "text": " | " })),
+            Event::Rule => {
+                flush(&mut lines, &mut line);
+                lines.push(vec![json!({ "tag": "text", "text": "────────" })]);
+            }
+            Event::Text(text) | Event::Code(text) if !in_image => {
+                // 代码块中的文本包含换行
+                for (i, piece) in text.split('\n').enumerate() {
+                    if i > 0 {
+                        flush(&mut lines, &mut line);
+                    }
+                    if piece.is_empty() {
+                        continue;
+                    }
+                    let segment = match &link {
+                        Some(href) => json!({ "tag": "a", "text": piece, "href": href }),
+                        None => {
+                            let mut style = Vec::new();
+                            if bold > 0 {
+                                style.push("bold");
+                            }
+                            if italic > 0 {
+                                style.push("italic");
+                            }
+                            if strike > 0 {
+                                style.push("lineThrough");
+                            }
+                            if style.is_empty() {
+                                json!({ "tag": "text", "text": piece })
+                            } else {
+                                json!({ "tag": "text", "text": piece, "style": style })
+                            }
+                        }
+                    };
+                    line.push(segment);
+                }
+            }
+            _ => {}
+        }
+    }
+    flush(&mut lines, &mut line);
+
+    json!({
+        "msg_type": "post",
+        "content": {
+            "post": {
+                "zh_cn": { "title": title, "content": lines }
+            }
+        }
+    })
+}
+
+/// 钉钉 markdown 消息
+///
+/// 钉钉会合并单个换行，非列表行之间使用空行分隔。
+fn dingtalk_message(title: &str, markdown: &str) -> Value {
+    let lines = markdown_lines(markdown);
+    let mut text = String::new();
+    for (i, line) in lines.iter().enumerate() {
+        if i > 0 {
+            let both_items = is_list_item(lines[i - 1]) && is_list_item(line);
+            text.push_str(if both_items { "\n" } else { "\n\n" });
+        }
+        text.push_str(line);
+    }
+
+    json!({
+        "msgtype": "markdown",
+        "markdown": {
+            "title": title,
+            "text": truncate_bytes(&text, DINGTALK_MAX_BYTES),
+        }
+    })
+}
+
+/// 企业微信 markdown 消息
+///
+/// 企业微信不渲染列表，列表标记转为圆点；内容最长 4096 字节。
+fn wecom_message(markdown: &str) -> Value {
+    let text = markdown_lines(markdown)
+        .into_iter()
+        .map(|line| {
+            let trimmed = line.trim_start();
+            let indent = &line[..line.len() - trimmed.len()];
+            match trimmed.get(..2) {
+                Some("- ") | Some("* ") | Some("+ ") => format!("{}• {}", indent, &trimmed[2..]),
+                _ => line.to_string(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    json!({
+        "msgtype": "markdown",
+        "markdown": { "content": truncate_bytes(&text, WECOM_MAX_BYTES) }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::AuditOrigin;
+    use crate::test_support::{temp_pool, Captured, Reply, ScriptedServer, TempDir};
+
+    const REPORT: &str = "# 日报\n\n- 评审接口设计\n- 修复导出问题\n\n**明日**：继续联调";
+
+    async fn report_pool() -> (Pool<Sqlite>, i64, TempDir) {
+        let (pool, dir) = temp_pool().await;
+        let report_id =
+            reports::save_report(&pool, "日报 2024-05-06", REPORT, "2024-05-06", "2024-05-06", None, AuditOrigin::Ui)
+                .await
+                .unwrap();
+        (pool, report_id, dir)
+    }
+
+    /// 只推送到一个目标，返回推送记录和平台收到的请求
+    async fn publish_once(
+        platform: PublishPlatform,
+        secret: Option<&str>,
+        reply: Reply,
+    ) -> (ReportPublication, Captured, Vec<ReportPublication>) {
+        let (pool, report_id, _dir) = report_pool().await;
+        let server = ScriptedServer::start(vec![reply]).await;
+        let webhook_url = format!("{}/hook", server.url);
+        let target = add_target(&pool, "测试群", platform, &webhook_url, secret.map(str::to_string)).await.unwrap();
+
+        let mut publications = publish_report(&pool, report_id, Some(vec![target.id])).await.unwrap();
+        let stored = list_publications(&pool, report_id).await.unwrap();
+        let request = server.requests().pop().expect("平台没有收到请求");
+        (publications.remove(0), request, stored)
+    }
+
+    fn query_param(path: &str, name: &str) -> Option<String> {
+        let url = reqwest::Url::parse(&format!("http://localhost{}", path)).unwrap();
+        url.query_pairs().find(|(key, _)| key == name).map(|(_, value)| value.into_owned())
+    }
+
+    #[tokio::test]
+    async fn feishu_sends_signed_post_message() {
+        let reply = Reply::json(json!({ "code": 0, "msg": "success" }));
+        let (publication, request, stored) = publish_once(PublishPlatform::Feishu, Some("feishu-secret"), reply).await;
+        assert!(publication.success);
+        assert_eq!(stored.len(), 1);
+
+        let body = request.json();
+        assert_eq!(body["msg_type"], "post");
+        let post = &body["content"]["post"]["zh_cn"];
+        assert_eq!(post["title"], "日报 2024-05-06");
+        let lines = post["content"].as_array().unwrap();
+        assert_eq!(lines[0][0], json!({ "tag": "text", "text": "日报", "style": ["bold"] }));
+        assert_eq!(lines[1][0]["text"], "• ");
+        assert_eq!(lines[1][1]["text"], "评审接口设计");
+
+        let timestamp: i64 = body["timestamp"].as_str().unwrap().parse().unwrap();
+        assert_eq!(body["sign"], feishu_sign(timestamp, "feishu-secret"));
+        let expected = hmac_sha256_base64(format!("{}\nfeishu-secret", timestamp).as_bytes(), b"");
+        assert_eq!(body["sign"], expected);
+    }
+
+    #[tokio::test]
+    async fn dingtalk_signs_in_query_string() {
+        let reply = Reply::json(json!({ "errcode": 0, "errmsg": "ok" }));
+        let (publication, request, _) = publish_once(PublishPlatform::Dingtalk, Some("ding-secret"), reply).await;
+        assert!(publication.success);
+
+        let body = request.json();
+        assert_eq!(body["msgtype"], "markdown");
+        assert_eq!(body["markdown"]["title"], "日报 2024-05-06");
+        assert_eq!(
+            body["markdown"]["text"],
+            "# 日报\n\n- 评审接口设计\n- 修复导出问题\n\n**明日**：继续联调"
+        );
+
+        assert!(request.path.starts_with("/hook?"));
+        let timestamp: i64 = query_param(&request.path, "timestamp").unwrap().parse().unwrap();
+        let sign = query_param(&request.path, "sign").unwrap();
+        let expected = hmac_sha256_base64(b"ding-secret", format!("{}\nding-secret", timestamp).as_bytes());
+        assert_eq!(sign, expected);
+        assert_eq!(sign, dingtalk_sign(timestamp, "ding-secret"));
+    }
+
+    #[tokio::test]
+    async fn wecom_sends_markdown_without_signature() {
+        let reply = Reply::json(json!({ "errcode": 0, "errmsg": "ok" }));
+        let (publication, request, _) = publish_once(PublishPlatform::Wecom, None, reply).await;
+        assert!(publication.success);
+
+        assert_eq!(request.path, "/hook");
+        let body = request.json();
+        assert_eq!(body["msgtype"], "markdown");
+        assert_eq!(body["markdown"]["content"], "# 日报\n• 评审接口设计\n• 修复导出问题\n**明日**：继续联调");
+    }
+
+    #[tokio::test]
+    async fn non_success_status_is_recorded_as_failure() {
+        let reply = Reply::status(500, json!({ "msg": "internal error" }));
+        let (publication, _, stored) = publish_once(PublishPlatform::Feishu, None, reply).await;
+        assert!(!publication.success);
+        assert_eq!(publication.error.as_deref(), Some("Webhook 返回 HTTP 500"));
+        assert_eq!(publication.response.as_deref(), Some(r#"{"msg":"internal error"}"#));
+        assert!(!stored[0].success);
+    }
+
+    #[tokio::test]
+    async fn platform_error_code_is_recorded_as_failure() {
+        let (dingtalk, _, stored) = publish_once(
+            PublishPlatform::Dingtalk,
+            Some("ding-secret"),
+            Reply::json(json!({ "errcode": 310000, "errmsg": "sign not match" })),
+        )
+        .await;
+        assert!(!dingtalk.success);
+        assert_eq!(dingtalk.error.as_deref(), Some("平台返回错误 310000: sign not match"));
+        assert!(!stored[0].success);
+
+        let (feishu, _, _) = publish_once(
+            PublishPlatform::Feishu,
+            Some("feishu-secret"),
+            Reply::json(json!({ "code": 19021, "msg": "sign match fail or timestamp is not within one hour" })),
+        )
+        .await;
+        assert!(!feishu.success);
+        assert!(feishu.error.unwrap().starts_with("平台返回错误 19021"));
+
+        let reply = Reply::json(json!({ "errcode": 93000, "errmsg": "invalid webhook url" }));
+        let (wecom, _, _) = publish_once(PublishPlatform::Wecom, None, reply).await;
+        assert!(!wecom.success);
+        assert_eq!(wecom.error.as_deref(), Some("平台返回错误 93000: invalid webhook url"));
+    }
+}
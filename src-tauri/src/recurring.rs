@@ -0,0 +1,559 @@
+use chrono::{Datelike, Duration, LocalResult, NaiveDate, NaiveDateTime, NaiveTime, Offset, TimeZone};
+use sqlx::{sqlite::SqliteRow, Pool, Row, Sqlite};
+
+use crate::models::{
+    AuditOrigin, DoneTask, NewDoneTask, NewRecurringTemplate, OccurrenceStatus, RecurrenceRule, RecurringOccurrence,
+    RecurringTemplate,
+};
+use crate::repo::TaskRepo;
+
+const TEMPLATE_COLUMNS: &str = "id, content, rule, start_time, end_time, tags, enabled, created_at, updated_at";
+
+const OCCURRENCE_COLUMNS: &str =
+    "id, template_id, date, content, start_time, end_time, tags, status, done_task_id, created_at, updated_at";
+
+fn template_from_row(row: &SqliteRow) -> Result<RecurringTemplate, String> {
+    let read_err = |e: sqlx::Error| format!("读取重复事项模板失败: {}", e);
+    let rule: String = row.try_get("rule").map_err(read_err)?;
+    let tags: String = row.try_get("tags").map_err(read_err)?;
+    Ok(RecurringTemplate {
+        id: row.try_get("id").map_err(read_err)?,
+        content: row.try_get("content").map_err(read_err)?,
+        rule: serde_json::from_str(&rule).map_err(|e| format!("重复规则无效: {}", e))?,
+        start_time: row.try_get("start_time").map_err(read_err)?,
+        end_time: row.try_get("end_time").map_err(read_err)?,
+        tags: serde_json::from_str(&tags).unwrap_or_default(),
+        enabled: row.try_get("enabled").map_err(read_err)?,
+        created_at: row.try_get("created_at").map_err(read_err)?,
+        updated_at: row.try_get("updated_at").map_err(read_err)?,
+    })
+}
+
+fn occurrence_from_row(row: &SqliteRow) -> Result<RecurringOccurrence, String> {
+    let read_err = |e: sqlx::Error| format!("读取重复事项失败: {}", e);
+    let status: String = row.try_get("status").map_err(read_err)?;
+    let tags: String = row.try_get("tags").map_err(read_err)?;
+    Ok(RecurringOccurrence {
+        id: row.try_get("id").map_err(read_err)?,
+        template_id: row.try_get("template_id").map_err(read_err)?,
+        date: row.try_get("date").map_err(read_err)?,
+        content: row.try_get("content").map_err(read_err)?,
+        start_time: row.try_get("start_time").map_err(read_err)?,
+        end_time: row.try_get("end_time").map_err(read_err)?,
+        tags: serde_json::from_str(&tags).unwrap_or_default(),
+        status: OccurrenceStatus::parse(&status).ok_or_else(|| format!("未知的重复事项状态: {}", status))?,
+        done_task_id: row.try_get("done_task_id").map_err(read_err)?,
+        created_at: row.try_get("created_at").map_err(read_err)?,
+        updated_at: row.try_get("updated_at").map_err(read_err)?,
+    })
+}
+
+fn parse_time(time: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(time.trim(), "%H:%M").map_err(|_| format!("时间格式无效: {}（应为 HH:MM）", time))
+}
+
+/// 校验并规范化模板
+fn normalize(template: NewRecurringTemplate) -> Result<NewRecurringTemplate, String> {
+    let content = template.content.trim().to_string();
+    if content.is_empty() {
+        return Err("事项内容不能为空".to_string());
+    }
+
+    let start = parse_time(&template.start_time)?;
+    let end = parse_time(&template.end_time)?;
+    if end <= start {
+        return Err("结束时间必须晚于开始时间".to_string());
+    }
+
+    let rule = match template.rule {
+        RecurrenceRule::Weekly { mut weekdays } => {
+            weekdays.sort();
+            weekdays.dedup();
+            if weekdays.is_empty() || weekdays.iter().any(|d| !(1..=7).contains(d)) {
+                return Err("每周重复需要选择 1-7 之间的星期".to_string());
+            }
+            RecurrenceRule::Weekly { weekdays }
+        }
+        RecurrenceRule::Monthly { mut days } => {
+            days.sort();
+            days.dedup();
+            if days.is_empty() || days.iter().any(|d| !(1..=31).contains(d)) {
+                return Err("每月重复需要选择 1-31 之间的日期".to_string());
+            }
+            RecurrenceRule::Monthly { days }
+        }
+        rule => rule,
+    };
+
+    Ok(NewRecurringTemplate {
+        content,
+        rule,
+        start_time: start.format("%H:%M").to_string(),
+        end_time: end.format("%H:%M").to_string(),
+        ..template
+    })
+}
+
+/// 规则是否在指定日期生效
+pub fn matches(rule: &RecurrenceRule, date: NaiveDate, workdays: &[u32]) -> bool {
+    let weekday = date.weekday().number_from_monday();
+    match rule {
+        RecurrenceRule::Daily => true,
+        RecurrenceRule::Workdays => workdays.is_empty() || workdays.contains(&weekday),
+        RecurrenceRule::Weekly { weekdays } => weekdays.contains(&weekday),
+        RecurrenceRule::Monthly { days } => days.contains(&date.day()),
+    }
+}
+
+/// 将日期和 HH:MM 组合为本地时间的 Unix 时间戳
+fn local_timestamp(date: NaiveDate, time: &str) -> Result<i64, String> {
+    let time = parse_time(time)?;
+    resolve_local(&chrono::Local, date.and_time(time))
+        .ok_or_else(|| format!("{} {} 不是有效的本地时间", date, time.format("%H:%M")))
+}
+
+/// 将本地时间换算为 Unix 时间戳
+///
+/// 夏令时回拨出现两次的时间取较早的一次；夏令时跳过的时间按跳变前的偏移换算，
+/// 相当于顺延跳过的时长（如 02:30 变为 03:30），开始和结束时间的间隔保持不变。
+fn resolve_local<Tz: TimeZone>(tz: &Tz, time: NaiveDateTime) -> Option<i64> {
+    match tz.from_local_datetime(&time) {
+        LocalResult::Single(dt) | LocalResult::Ambiguous(dt, _) => Some(dt.timestamp()),
+        LocalResult::None => {
+            let before = tz.from_local_datetime(&(time - Duration::hours(3))).earliest()?;
+            let offset = before.offset().fix().local_minus_utc();
+            Some(time.and_utc().timestamp() - offset as i64)
+        }
+    }
+}
+
+/// 新建模板
+pub async fn add_template(pool: &Pool<Sqlite>, template: NewRecurringTemplate) -> Result<RecurringTemplate, String> {
+    let template = normalize(template)?;
+    let now = chrono::Local::now().timestamp();
+
+    let id = sqlx::query(
+        r#"
+        INSERT INTO recurring_templates (content, rule, start_time, end_time, tags, enabled, created_at, updated_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+        "#,
+    )
+    .bind(&template.content)
+    .bind(serde_json::to_string(&template.rule).map_err(|e| format!("序列化重复规则失败: {}", e))?)
+    .bind(&template.start_time)
+    .bind(&template.end_time)
+    .bind(serde_json::to_string(&template.tags).unwrap_or_else(|_| "[]".to_string()))
+    .bind(template.enabled)
+    .bind(now)
+    .bind(now)
+    .execute(pool)
+    .await
+    .map_err(|e| format!("保存重复事项模板失败: {}", e))?
+    .last_insert_rowid();
+
+    get_template(pool, id).await
+}
+
+/// 读取单个模板
+pub async fn get_template(pool: &Pool<Sqlite>, id: i64) -> Result<RecurringTemplate, String> {
+    let row = sqlx::query(&format!("SELECT {} FROM recurring_templates WHERE id = ?", TEMPLATE_COLUMNS))
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| format!("查询重复事项模板失败: {}", e))?
+        .ok_or_else(|| format!("重复事项模板 {} 不存在", id))?;
+    template_from_row(&row)
+}
+
+/// 按默认开始时间列出所有模板
+pub async fn list_templates(pool: &Pool<Sqlite>) -> Result<Vec<RecurringTemplate>, String> {
+    let rows = sqlx::query(&format!(
+        "SELECT {} FROM recurring_templates ORDER BY start_time, id",
+        TEMPLATE_COLUMNS
+    ))
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("查询重复事项模板失败: {}", e))?;
+    rows.iter().map(template_from_row).collect()
+}
+
+/// 更新模板；已生成的草稿保持不变
+pub async fn update_template(
+    pool: &Pool<Sqlite>,
+    id: i64,
+    template: NewRecurringTemplate,
+) -> Result<RecurringTemplate, String> {
+    let template = normalize(template)?;
+
+    let result = sqlx::query(
+        r#"
+        UPDATE recurring_templates
+        SET content = ?, rule = ?, start_time = ?, end_time = ?, tags = ?, enabled = ?, updated_at = ?
+        WHERE id = ?
+        "#,
+    )
+    .bind(&template.content)
+    .bind(serde_json::to_string(&template.rule).map_err(|e| format!("序列化重复规则失败: {}", e))?)
+    .bind(&template.start_time)
+    .bind(&template.end_time)
+    .bind(serde_json::to_string(&template.tags).unwrap_or_else(|_| "[]".to_string()))
+    .bind(template.enabled)
+    .bind(chrono::Local::now().timestamp())
+    .bind(id)
+    .execute(pool)
+    .await
+    .map_err(|e| format!("更新重复事项模板失败: {}", e))?;
+
+    if result.rows_affected() == 0 {
+        return Err(format!("重复事项模板 {} 不存在", id));
+    }
+    get_template(pool, id).await
+}
+
+/// 删除模板及其生成记录（已确认生成的已完成事项保留）
+pub async fn delete_template(pool: &Pool<Sqlite>, id: i64) -> Result<(), String> {
+    let mut tx = pool.begin().await.map_err(|e| format!("开启事务失败: {}", e))?;
+
+    sqlx::query("DELETE FROM recurring_occurrences WHERE template_id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("删除重复事项记录失败: {}", e))?;
+
+    sqlx::query("DELETE FROM recurring_templates WHERE id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("删除重复事项模板失败: {}", e))?;
+
+    tx.commit().await.map_err(|e| format!("提交事务失败: {}", e))?;
+    Ok(())
+}
+
+/// 读取单条生成记录
+pub async fn get_occurrence(pool: &Pool<Sqlite>, id: i64) -> Result<RecurringOccurrence, String> {
+    let row = sqlx::query(&format!("SELECT {} FROM recurring_occurrences WHERE id = ?", OCCURRENCE_COLUMNS))
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| format!("查询重复事项失败: {}", e))?
+        .ok_or_else(|| format!("重复事项 {} 不存在", id))?;
+    occurrence_from_row(&row)
+}
+
+/// 读取某天的生成记录
+pub async fn list_occurrences(
+    pool: &Pool<Sqlite>,
+    date: &str,
+    status: Option<OccurrenceStatus>,
+) -> Result<Vec<RecurringOccurrence>, String> {
+    let rows = sqlx::query(&format!(
+        "SELECT {} FROM recurring_occurrences WHERE date = ? AND (? IS NULL OR status = ?) ORDER BY start_time, id",
+        OCCURRENCE_COLUMNS
+    ))
+    .bind(date)
+    .bind(status.map(|s| s.as_str()))
+    .bind(status.map(|s| s.as_str()))
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("查询重复事项失败: {}", e))?;
+    rows.iter().map(occurrence_from_row).collect()
+}
+
+/// 为指定日期生成待确认的草稿
+///
+/// 每个模板每天只生成一次：已生成（包括已确认、已跳过）的不会重复生成。
+/// 返回当天全部生成记录。
+pub async fn materialize(pool: &Pool<Sqlite>, date: &str, workdays: &[u32]) -> Result<Vec<RecurringOccurrence>, String> {
+    let day = NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d").map_err(|_| format!("日期格式无效: {}", date))?;
+    let date = day.format("%Y-%m-%d").to_string();
+    let now = chrono::Local::now().timestamp();
+
+    for template in list_templates(pool).await? {
+        if !template.enabled || !matches(&template.rule, day, workdays) {
+            continue;
+        }
+
+        // 个别模板的时间无法换算时只跳过该模板，不影响当天其他事项
+        let (start_time, end_time) =
+            match (local_timestamp(day, &template.start_time), local_timestamp(day, &template.end_time)) {
+                (Ok(start), Ok(end)) => (start, end),
+                (Err(e), _) | (_, Err(e)) => {
+                    eprintln!("生成重复事项「{}」失败: {}", template.content, e);
+                    continue;
+                }
+            };
+
+        sqlx::query(
+            r#"
+            INSERT OR IGNORE INTO recurring_occurrences (template_id, date, content, start_time, end_time, tags, status, created_at, updated_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(template.id)
+        .bind(&date)
+        .bind(&template.content)
+        .bind(start_time)
+        .bind(end_time)
+        .bind(serde_json::to_string(&template.tags).unwrap_or_else(|_| "[]".to_string()))
+        .bind(OccurrenceStatus::Draft.as_str())
+        .bind(now)
+        .bind(now)
+        .execute(pool)
+        .await
+        .map_err(|e| format!("生成重复事项失败: {}", e))?;
+    }
+
+    list_occurrences(pool, &date, None).await
+}
+
+/// 确认草稿：按（可能调整过的）内容和时间生成已完成事项
+///
+/// 时间段需由调用方事先校验。
+pub async fn confirm_occurrence(
+    pool: &Pool<Sqlite>,
+    id: i64,
+    content: &str,
+    start_time: i64,
+    end_time: i64,
+    origin: AuditOrigin,
+) -> Result<DoneTask, String> {
+    let occurrence = get_occurrence(pool, id).await?;
+    if occurrence.status != OccurrenceStatus::Draft {
+        return Err(format!("重复事项 {} 已处理", id));
+    }
+    let content = content.trim();
+    if content.is_empty() {
+        return Err("事项内容不能为空".to_string());
+    }
+
+    let now = chrono::Local::now().timestamp();
+
+    let mut tx = pool.begin().await.map_err(|e| format!("开启事务失败: {}", e))?;
+
+    let task = TaskRepo::insert_with(
+        &mut tx,
+        NewDoneTask {
+            content: content.to_string(),
+            start_time,
+            end_time,
+            attachments: Vec::new(),
+            created_at: now,
+            tags: occurrence.tags,
+        },
+        origin,
+    )
+    .await?;
+
+    // 只更新仍为草稿的记录，避免并发重复确认
+    let updated = sqlx::query(
+        r#"
+        UPDATE recurring_occurrences
+        SET status = ?, content = ?, start_time = ?, end_time = ?, done_task_id = ?, updated_at = ?
+        WHERE id = ? AND status = ?
+        "#,
+    )
+    .bind(OccurrenceStatus::Confirmed.as_str())
+    .bind(content)
+    .bind(start_time)
+    .bind(end_time)
+    .bind(task.id)
+    .bind(now)
+    .bind(id)
+    .bind(OccurrenceStatus::Draft.as_str())
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("更新重复事项失败: {}", e))?;
+    if updated.rows_affected() == 0 {
+        return Err(format!("重复事项 {} 已处理", id));
+    }
+
+    tx.commit().await.map_err(|e| format!("提交事务失败: {}", e))?;
+    Ok(task)
+}
+
+/// 跳过草稿；跳过的记录保留，之后不会再次生成
+pub async fn skip_occurrence(pool: &Pool<Sqlite>, id: i64) -> Result<RecurringOccurrence, String> {
+    let result = sqlx::query("UPDATE recurring_occurrences SET status = ?, updated_at = ? WHERE id = ? AND status = ?")
+        .bind(OccurrenceStatus::Skipped.as_str())
+        .bind(chrono::Local::now().timestamp())
+        .bind(id)
+        .bind(OccurrenceStatus::Draft.as_str())
+        .execute(pool)
+        .await
+        .map_err(|e| format!("跳过重复事项失败: {}", e))?;
+
+    if result.rows_affected() == 0 {
+        // 区分不存在和已处理
+        get_occurrence(pool, id).await?;
+        return Err(format!("重复事项 {} 已处理", id));
+    }
+    get_occurrence(pool, id).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::temp_pool;
+    use chrono::{FixedOffset, MappedLocalTime};
+
+    fn date(value: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d").unwrap()
+    }
+
+    fn template(content: &str, rule: RecurrenceRule) -> NewRecurringTemplate {
+        NewRecurringTemplate {
+            content: content.to_string(),
+            rule,
+            start_time: "09:00".to_string(),
+            end_time: "09:30".to_string(),
+            tags: Vec::new(),
+            enabled: true,
+        }
+    }
+
+    /// 2024-03-31 本地 02:00 从 +01:00 跳到 +02:00 的时区（与欧洲中部夏令时一致）
+    #[derive(Clone, Copy)]
+    struct SpringForward;
+
+    impl SpringForward {
+        fn switch() -> NaiveDateTime {
+            date("2024-03-31").and_hms_opt(1, 0, 0).unwrap() // UTC
+        }
+
+        fn winter() -> FixedOffset {
+            FixedOffset::east_opt(3600).unwrap()
+        }
+
+        fn summer() -> FixedOffset {
+            FixedOffset::east_opt(7200).unwrap()
+        }
+    }
+
+    impl TimeZone for SpringForward {
+        type Offset = FixedOffset;
+
+        fn from_offset(_: &FixedOffset) -> Self {
+            SpringForward
+        }
+
+        fn offset_from_local_date(&self, local: &NaiveDate) -> MappedLocalTime<FixedOffset> {
+            self.offset_from_local_datetime(&local.and_hms_opt(0, 0, 0).unwrap())
+        }
+
+        fn offset_from_local_datetime(&self, local: &NaiveDateTime) -> MappedLocalTime<FixedOffset> {
+            let winter = *local - Duration::seconds(Self::winter().local_minus_utc() as i64) < Self::switch();
+            let summer = *local - Duration::seconds(Self::summer().local_minus_utc() as i64) >= Self::switch();
+            match (winter, summer) {
+                (true, false) => MappedLocalTime::Single(Self::winter()),
+                (false, true) => MappedLocalTime::Single(Self::summer()),
+                (true, true) => MappedLocalTime::Ambiguous(Self::winter(), Self::summer()),
+                (false, false) => MappedLocalTime::None,
+            }
+        }
+
+        fn offset_from_utc_date(&self, utc: &NaiveDate) -> FixedOffset {
+            self.offset_from_utc_datetime(&utc.and_hms_opt(0, 0, 0).unwrap())
+        }
+
+        fn offset_from_utc_datetime(&self, utc: &NaiveDateTime) -> FixedOffset {
+            if *utc < Self::switch() {
+                Self::winter()
+            } else {
+                Self::summer()
+            }
+        }
+    }
+
+    #[test]
+    fn rules_match_their_days() {
+        let workdays = [1, 2, 3, 4, 5];
+        // 2024-05-10 是周五，2024-05-11 是周六
+        assert!(matches(&RecurrenceRule::Daily, date("2024-05-11"), &workdays));
+        assert!(matches(&RecurrenceRule::Workdays, date("2024-05-10"), &workdays));
+        assert!(!matches(&RecurrenceRule::Workdays, date("2024-05-11"), &workdays));
+        // 未配置工作日时每天都生效
+        assert!(matches(&RecurrenceRule::Workdays, date("2024-05-11"), &[]));
+
+        let weekly = RecurrenceRule::Weekly { weekdays: vec![1, 6] };
+        assert!(matches(&weekly, date("2024-05-11"), &workdays));
+        assert!(matches(&weekly, date("2024-05-13"), &workdays));
+        assert!(!matches(&weekly, date("2024-05-10"), &workdays));
+
+        // 当月没有 31 号时跳过
+        let monthly = RecurrenceRule::Monthly { days: vec![1, 31] };
+        assert!(matches(&monthly, date("2024-05-31"), &workdays));
+        assert!(matches(&monthly, date("2024-06-01"), &workdays));
+        assert!(!matches(&monthly, date("2024-06-30"), &workdays));
+    }
+
+    #[test]
+    fn times_in_a_dst_gap_are_shifted_forward() {
+        let at = |h, m| date("2024-03-31").and_hms_opt(h, m, 0).unwrap();
+        // 01:30 仍在冬令时
+        assert_eq!(resolve_local(&SpringForward, at(1, 30)), Some(at(0, 30).and_utc().timestamp()));
+        // 02:30 不存在，顺延为 03:30（UTC 01:30）
+        assert_eq!(resolve_local(&SpringForward, at(2, 30)), Some(at(1, 30).and_utc().timestamp()));
+        assert_eq!(resolve_local(&SpringForward, at(3, 30)), Some(at(1, 30).and_utc().timestamp()));
+    }
+
+    #[tokio::test]
+    async fn materialize_only_generates_matching_enabled_templates() {
+        let (pool, _dir) = temp_pool().await;
+        let daily = add_template(&pool, template("站会", RecurrenceRule::Daily)).await.unwrap();
+        add_template(&pool, template("周会", RecurrenceRule::Weekly { weekdays: vec![1] })).await.unwrap();
+        add_template(&pool, NewRecurringTemplate { enabled: false, ..template("停用", RecurrenceRule::Daily) })
+            .await
+            .unwrap();
+
+        // 2024-05-10 是周五
+        let drafts = materialize(&pool, "2024-05-10", &[]).await.unwrap();
+        assert_eq!(drafts.len(), 1);
+        assert_eq!(drafts[0].template_id, daily.id);
+        assert_eq!(drafts[0].status, OccurrenceStatus::Draft);
+        assert_eq!(drafts[0].end_time - drafts[0].start_time, 30 * 60);
+    }
+
+    #[tokio::test]
+    async fn confirmed_and_skipped_occurrences_are_never_regenerated() {
+        let (pool, _dir) = temp_pool().await;
+        add_template(&pool, template("站会", RecurrenceRule::Daily)).await.unwrap();
+        add_template(&pool, template("日报", RecurrenceRule::Daily)).await.unwrap();
+
+        let drafts = materialize(&pool, "2024-05-10", &[]).await.unwrap();
+        assert_eq!(drafts.len(), 2);
+        // 同一天重复生成不会新增记录
+        assert_eq!(materialize(&pool, "2024-05-10", &[]).await.unwrap().len(), 2);
+
+        let (confirm, skip) = (&drafts[0], &drafts[1]);
+        let end_time = confirm.end_time + 600;
+        let task = confirm_occurrence(&pool, confirm.id, "站会（延长）", confirm.start_time, end_time, AuditOrigin::Ui)
+            .await
+            .unwrap();
+        assert_eq!(task.content, "站会（延长）");
+        skip_occurrence(&pool, skip.id).await.unwrap();
+
+        let again = materialize(&pool, "2024-05-10", &[]).await.unwrap();
+        assert_eq!(again.len(), 2);
+        let confirmed = again.iter().find(|o| o.id == confirm.id).unwrap();
+        assert_eq!(confirmed.status, OccurrenceStatus::Confirmed);
+        assert_eq!(confirmed.done_task_id, Some(task.id));
+        assert_eq!(again.iter().find(|o| o.id == skip.id).unwrap().status, OccurrenceStatus::Skipped);
+
+        // 已处理的记录不能再次确认或跳过
+        assert!(confirm_occurrence(&pool, confirm.id, "站会", confirm.start_time, confirm.end_time, AuditOrigin::Ui)
+            .await
+            .is_err());
+        assert!(confirm_occurrence(&pool, skip.id, "日报", skip.start_time, skip.end_time, AuditOrigin::Ui)
+            .await
+            .is_err());
+        assert!(skip_occurrence(&pool, confirm.id).await.is_err());
+        let tasks: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM done_tasks").fetch_one(&pool).await.unwrap();
+        assert_eq!(tasks, 1);
+
+        // 第二天正常生成新的草稿
+        let next = materialize(&pool, "2024-05-11", &[]).await.unwrap();
+        assert_eq!(next.len(), 2);
+        assert!(next.iter().all(|o| o.status == OccurrenceStatus::Draft));
+    }
+}
@@ -0,0 +1,159 @@
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use sha2::{Digest, Sha256};
+use tauri::AppHandle;
+
+use crate::database::{self, DbState};
+use crate::models::Workspace;
+use crate::workspaces::{self, DB_FILE};
+
+/// 计算文件的 SHA-256
+fn file_checksum(path: &Path) -> Result<String, String> {
+    let mut file = std::fs::File::open(path).map_err(|e| format!("打开 {} 失败: {}", path.display(), e))?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; 64 * 1024];
+    loop {
+        let read = file.read(&mut buffer).map_err(|e| format!("读取 {} 失败: {}", path.display(), e))?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
+
+fn same_dir(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+/// 复制数据库文件并校验，返回新数据库文件路径
+fn copy_verified(source: &Path, target_dir: &Path) -> Result<PathBuf, String> {
+    // 关闭连接池后 WAL 应已合并到主文件，仍有内容说明还有其他进程在使用数据库
+    let wal = source.with_file_name(format!("{}-wal", DB_FILE));
+    if std::fs::metadata(&wal).map(|m| m.len() > 0).unwrap_or(false) {
+        return Err("数据库仍有未写回的日志，请关闭其他使用该数据库的程序后重试".to_string());
+    }
+
+    let target = target_dir.join(DB_FILE);
+    let source_checksum = file_checksum(source)?;
+    std::fs::copy(source, &target).map_err(|e| format!("复制数据库失败: {}", e))?;
+
+    let target_checksum = file_checksum(&target)?;
+    if target_checksum != source_checksum {
+        let _ = std::fs::remove_file(&target);
+        return Err(format!(
+            "数据库校验失败：源文件 {} 与副本 {} 不一致",
+            source_checksum, target_checksum
+        ));
+    }
+    Ok(target)
+}
+
+async fn quick_check(pool: &sqlx::Pool<sqlx::Sqlite>) -> Result<(), String> {
+    let result: String = sqlx::query_scalar("PRAGMA quick_check")
+        .fetch_one(pool)
+        .await
+        .map_err(|e| format!("检查新数据库失败: {}", e))?;
+    if result != "ok" {
+        return Err(format!("新数据库完整性检查未通过: {}", result));
+    }
+    Ok(())
+}
+
+/// 将当前工作区的数据库迁移到新目录
+///
+/// 迁移期间新的数据库请求会等待：先写回 WAL 并关闭旧连接池，复制文件并校验 SHA-256，
+/// 打开新数据库检查后再切换。旧文件保留，调用 `confirm_relocation` 后才删除；
+/// 任一步骤失败都会重新打开旧数据库。
+pub async fn relocate_database(app: &AppHandle, db_state: &DbState, new_dir: &str) -> Result<Workspace, String> {
+    let _switching = workspaces::SWITCH_LOCK.lock().await;
+
+    let id = workspaces::active_id(app)?;
+    let paths = workspaces::active_paths(app)?;
+    if paths.previous_db_dir.is_some() {
+        return Err("上一次迁移的旧数据库尚未清理，请先确认迁移".to_string());
+    }
+    // 当前数据库不可用（例如卷未挂载）时没有可迁移的数据，失败后也无法恢复
+    workspaces::check_db_dir(&paths)?;
+    if !paths.db_path.is_file() {
+        return Err(format!("数据库文件 {} 不存在，无法迁移", paths.db_path.display()));
+    }
+
+    let new_dir = PathBuf::from(new_dir.trim());
+    if !new_dir.is_absolute() {
+        return Err("请选择绝对路径".to_string());
+    }
+    std::fs::create_dir_all(&new_dir).map_err(|e| format!("无法创建目录 {}: {}", new_dir.display(), e))?;
+    if same_dir(&new_dir, &paths.db_dir) {
+        return Err("新目录与当前数据库目录相同".to_string());
+    }
+    if new_dir.join(DB_FILE).exists() {
+        return Err(format!("目录 {} 中已存在数据库文件", new_dir.display()));
+    }
+
+    // 取走连接池后新的请求会等待，旧连接池关闭后不会再有写入
+    if let Some(old) = db_state.take_pool() {
+        if let Err(e) = sqlx::query("PRAGMA wal_checkpoint(TRUNCATE)").execute(&old).await {
+            db_state.replace_pool(old);
+            return Err(format!("写回数据库日志失败: {}", e));
+        }
+        old.close().await;
+    }
+
+    let result = async {
+        let target = copy_verified(&paths.db_path, &new_dir)?;
+        let pool = database::open_existing(&target).await?;
+        if let Err(e) = quick_check(&pool).await {
+            pool.close().await;
+            let _ = workspaces::remove_db_files(&new_dir);
+            return Err(e);
+        }
+        Ok(pool)
+    }
+    .await;
+
+    let pool = match result {
+        Ok(pool) => pool,
+        Err(e) => {
+            // 恢复旧数据库
+            db_state.reopen(&paths).await?;
+            return Err(e);
+        }
+    };
+
+    // 迁回工作区目录时不再记录自定义目录
+    let db_dir = if same_dir(&new_dir, &paths.data_dir) {
+        None
+    } else {
+        Some(new_dir.to_string_lossy().to_string())
+    };
+    if let Err(e) = workspaces::set_db_dir(app, &id, db_dir, Some(paths.db_dir.to_string_lossy().to_string())) {
+        pool.close().await;
+        let _ = workspaces::remove_db_files(&new_dir);
+        db_state.reopen(&paths).await?;
+        return Err(e);
+    }
+
+    db_state.replace_pool(pool);
+
+    workspaces::get_workspace(app, &id)
+}
+
+/// 确认迁移成功，删除旧位置的数据库文件
+pub async fn confirm_relocation(app: &AppHandle) -> Result<Workspace, String> {
+    let _switching = workspaces::SWITCH_LOCK.lock().await;
+
+    let id = workspaces::active_id(app)?;
+    let paths = workspaces::active_paths(app)?;
+    let previous = paths.previous_db_dir.ok_or_else(|| "没有等待确认的迁移".to_string())?;
+
+    workspaces::remove_db_files(&previous)?;
+    let db_dir = paths.custom_db_dir.then(|| paths.db_dir.to_string_lossy().to_string());
+    workspaces::set_db_dir(app, &id, db_dir, None)?;
+
+    workspaces::get_workspace(app, &id)
+}
@@ -0,0 +1,195 @@
+use sqlx::{Pool, Sqlite, SqliteConnection};
+
+use super::{date_of, order, string_list, to_json, SortDirection};
+use crate::audit;
+use crate::idea_links;
+use crate::models::{AuditEntity, AuditOrigin, Idea};
+
+const IDEA_COLUMNS: &str = "id, content, attachments, created_at, date, archived";
+
+#[derive(sqlx::FromRow)]
+struct IdeaRow {
+    id: i64,
+    content: String,
+    attachments: String,
+    created_at: i64,
+    date: String,
+    archived: bool,
+}
+
+impl From<IdeaRow> for Idea {
+    fn from(row: IdeaRow) -> Self {
+        Idea {
+            id: row.id,
+            content: row.content,
+            attachments: string_list(&row.attachments),
+            created_at: row.created_at,
+            date: row.date,
+            archived: row.archived,
+        }
+    }
+}
+
+/// 随手记的数据访问
+pub struct IdeaRepo<'a> {
+    pool: &'a Pool<Sqlite>,
+}
+
+impl<'a> IdeaRepo<'a> {
+    pub fn new(pool: &'a Pool<Sqlite>) -> Self {
+        Self { pool }
+    }
+
+    pub async fn get(&self, id: i64) -> Result<Option<Idea>, String> {
+        let mut conn = self.pool.acquire().await.map_err(|e| format!("获取数据库连接失败: {}", e))?;
+        Self::get_with(&mut conn, id).await
+    }
+
+    pub async fn get_with(conn: &mut SqliteConnection, id: i64) -> Result<Option<Idea>, String> {
+        let row = sqlx::query_as::<_, IdeaRow>(&format!("SELECT {} FROM ideas WHERE id = ?", IDEA_COLUMNS))
+            .bind(id)
+            .fetch_optional(conn)
+            .await
+            .map_err(|e| format!("查询想法失败: {}", e))?;
+        Ok(row.map(Idea::from))
+    }
+
+    /// 某一天的想法，最新的在前
+    pub async fn list_by_date(&self, date: &str) -> Result<Vec<Idea>, String> {
+        let rows = sqlx::query_as::<_, IdeaRow>(&format!(
+            "SELECT {} FROM ideas WHERE date = ? ORDER BY created_at DESC",
+            IDEA_COLUMNS
+        ))
+        .bind(date)
+        .fetch_all(self.pool)
+        .await
+        .map_err(|e| format!("查询想法失败: {}", e))?;
+        Ok(rows.into_iter().map(Idea::from).collect())
+    }
+
+    /// 日期范围内（含两端）的想法，按日期和创建时间排序
+    pub async fn list_in_range(&self, start_date: &str, end_date: &str, direction: SortDirection) -> Result<Vec<Idea>, String> {
+        let rows = sqlx::query_as::<_, IdeaRow>(&format!(
+            "SELECT {} FROM ideas WHERE date >= ? AND date <= ? ORDER BY date {dir}, created_at {dir}",
+            IDEA_COLUMNS,
+            dir = order(direction)
+        ))
+        .bind(start_date)
+        .bind(end_date)
+        .fetch_all(self.pool)
+        .await
+        .map_err(|e| format!("查询想法失败: {}", e))?;
+        Ok(rows.into_iter().map(Idea::from).collect())
+    }
+
+    /// 新建想法，日期由创建时间得出
+    pub async fn insert(
+        &self,
+        content: String,
+        attachments: Vec<String>,
+        created_at: i64,
+        origin: AuditOrigin,
+    ) -> Result<Idea, String> {
+        let mut tx = self.pool.begin().await.map_err(|e| format!("开启事务失败: {}", e))?;
+        let idea = Self::insert_with(&mut tx, content, attachments, created_at, origin).await?;
+        tx.commit().await.map_err(|e| format!("提交事务失败: {}", e))?;
+        Ok(idea)
+    }
+
+    /// 在调用方的事务中新建想法
+    pub async fn insert_with(
+        conn: &mut SqliteConnection,
+        content: String,
+        attachments: Vec<String>,
+        created_at: i64,
+        origin: AuditOrigin,
+    ) -> Result<Idea, String> {
+        let date = date_of(created_at)?;
+        let id = sqlx::query("INSERT INTO ideas (content, attachments, created_at, date) VALUES (?, ?, ?, ?)")
+            .bind(&content)
+            .bind(to_json(&attachments))
+            .bind(created_at)
+            .bind(&date)
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| format!("保存想法失败: {}", e))?
+            .last_insert_rowid();
+
+        let idea = Idea { id, content, attachments, created_at, date, archived: false };
+        audit::record_insert(conn, origin, AuditEntity::Idea, id, &idea).await?;
+        Ok(idea)
+    }
+
+    /// 修改想法的创建时间，日期随之更新；不存在时返回 None
+    pub async fn reschedule_with(
+        conn: &mut SqliteConnection,
+        id: i64,
+        created_at: i64,
+        origin: AuditOrigin,
+    ) -> Result<Option<Idea>, String> {
+        let Some(before) = Self::get_with(&mut *conn, id).await? else {
+            return Ok(None);
+        };
+        let date = date_of(created_at)?;
+        sqlx::query("UPDATE ideas SET created_at = ?, date = ? WHERE id = ?")
+            .bind(created_at)
+            .bind(&date)
+            .bind(id)
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| format!("更新想法失败: {}", e))?;
+        Self::updated(conn, before, origin).await
+    }
+
+    /// 设置想法的归档状态；不存在时返回 None
+    pub async fn set_archived_with(
+        conn: &mut SqliteConnection,
+        id: i64,
+        archived: bool,
+        origin: AuditOrigin,
+    ) -> Result<Option<Idea>, String> {
+        let Some(before) = Self::get_with(&mut *conn, id).await? else {
+            return Ok(None);
+        };
+        sqlx::query("UPDATE ideas SET archived = ? WHERE id = ?")
+            .bind(archived)
+            .bind(id)
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| format!("更新想法失败: {}", e))?;
+        Self::updated(conn, before, origin).await
+    }
+
+    /// 读取修改后的想法并记录审计日志
+    async fn updated(conn: &mut SqliteConnection, before: Idea, origin: AuditOrigin) -> Result<Option<Idea>, String> {
+        let after = Self::get_with(&mut *conn, before.id).await?;
+        if let Some(after) = &after {
+            audit::record_update(conn, origin, AuditEntity::Idea, before.id, &before, after).await?;
+        }
+        Ok(after)
+    }
+
+    /// 删除想法及其关联，返回被删除的想法；不存在时返回 None
+    pub async fn delete(&self, id: i64, origin: AuditOrigin) -> Result<Option<Idea>, String> {
+        let mut tx = self.pool.begin().await.map_err(|e| format!("开启事务失败: {}", e))?;
+        let deleted = Self::delete_with(&mut tx, id, origin).await?;
+        tx.commit().await.map_err(|e| format!("提交事务失败: {}", e))?;
+        Ok(deleted)
+    }
+
+    /// 在调用方的事务中删除想法
+    pub async fn delete_with(conn: &mut SqliteConnection, id: i64, origin: AuditOrigin) -> Result<Option<Idea>, String> {
+        let Some(existing) = Self::get_with(&mut *conn, id).await? else {
+            return Ok(None);
+        };
+
+        sqlx::query("DELETE FROM ideas WHERE id = ?")
+            .bind(id)
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| format!("删除想法失败: {}", e))?;
+        idea_links::on_idea_deleted(&mut *conn, id).await?;
+        audit::record_delete(conn, origin, AuditEntity::Idea, id, &existing).await?;
+        Ok(Some(existing))
+    }
+}
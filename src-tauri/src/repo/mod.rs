@@ -0,0 +1,37 @@
+//! 类型化的数据访问层：随手记、已完成事项与提示词的查询和写入
+//!
+//! 查询结果通过 `sqlx::FromRow` 直接映射为结构体，参数按实际类型绑定；
+//! 涉及多条语句的写操作在事务中执行，并提供 `*_with` 版本供调用方在自己的事务中使用。
+
+mod ideas;
+mod prompts;
+mod tasks;
+
+pub use crate::pagination::SortDirection;
+pub use ideas::IdeaRepo;
+pub use prompts::PromptRepo;
+pub use tasks::TaskRepo;
+
+/// 解析 JSON 字符串数组列，格式错误时返回空列表（可通过 run_diagnostics 检查）
+fn string_list(json: &str) -> Vec<String> {
+    serde_json::from_str(json).unwrap_or_default()
+}
+
+fn to_json(list: &[String]) -> String {
+    serde_json::to_string(list).unwrap_or_else(|_| "[]".to_string())
+}
+
+/// 将时间戳转换为日期字符串
+fn date_of(timestamp: i64) -> Result<String, String> {
+    Ok(chrono::DateTime::from_timestamp(timestamp, 0)
+        .ok_or("无效的时间戳")?
+        .format("%Y-%m-%d")
+        .to_string())
+}
+
+fn order(direction: SortDirection) -> &'static str {
+    match direction {
+        SortDirection::Asc => "ASC",
+        SortDirection::Desc => "DESC",
+    }
+}
@@ -0,0 +1,94 @@
+use sqlx::{Pool, Sqlite, SqliteConnection};
+
+use crate::audit;
+use crate::models::{AuditEntity, AuditOrigin, Prompt};
+
+const PROMPT_COLUMNS: &str = "id, name, content, created_at, updated_at";
+
+/// 提示词的数据访问
+pub struct PromptRepo<'a> {
+    pool: &'a Pool<Sqlite>,
+}
+
+impl<'a> PromptRepo<'a> {
+    pub fn new(pool: &'a Pool<Sqlite>) -> Self {
+        Self { pool }
+    }
+
+    /// 全部提示词，最近更新的在前
+    pub async fn list(&self) -> Result<Vec<Prompt>, String> {
+        sqlx::query_as::<_, Prompt>(&format!("SELECT {} FROM prompts ORDER BY updated_at DESC", PROMPT_COLUMNS))
+            .fetch_all(self.pool)
+            .await
+            .map_err(|e| format!("查询提示词失败: {}", e))
+    }
+
+    async fn get_with(conn: &mut SqliteConnection, id: i64) -> Result<Option<Prompt>, String> {
+        sqlx::query_as::<_, Prompt>(&format!("SELECT {} FROM prompts WHERE id = ?", PROMPT_COLUMNS))
+            .bind(id)
+            .fetch_optional(conn)
+            .await
+            .map_err(|e| format!("查询提示词失败: {}", e))
+    }
+
+    pub async fn insert(&self, name: String, content: String, origin: AuditOrigin) -> Result<Prompt, String> {
+        let mut tx = self.pool.begin().await.map_err(|e| format!("开启事务失败: {}", e))?;
+        let now = chrono::Local::now().timestamp();
+        let id = sqlx::query("INSERT INTO prompts (name, content, created_at, updated_at) VALUES (?, ?, ?, ?)")
+            .bind(&name)
+            .bind(&content)
+            .bind(now)
+            .bind(now)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("保存提示词失败: {}", e))?
+            .last_insert_rowid();
+
+        let prompt = Prompt { id, name, content, created_at: now, updated_at: now };
+        audit::record_insert(&mut tx, origin, AuditEntity::Prompt, id, &prompt).await?;
+        tx.commit().await.map_err(|e| format!("提交事务失败: {}", e))?;
+        Ok(prompt)
+    }
+
+    pub async fn update(&self, id: i64, name: &str, content: &str, origin: AuditOrigin) -> Result<(), String> {
+        let mut tx = self.pool.begin().await.map_err(|e| format!("开启事务失败: {}", e))?;
+        let before = Self::get_with(&mut tx, id)
+            .await?
+            .ok_or_else(|| format!("提示词 {} 不存在", id))?;
+        let after = Prompt {
+            id,
+            name: name.to_string(),
+            content: content.to_string(),
+            created_at: before.created_at,
+            updated_at: chrono::Local::now().timestamp(),
+        };
+
+        sqlx::query("UPDATE prompts SET name = ?, content = ?, updated_at = ? WHERE id = ?")
+            .bind(&after.name)
+            .bind(&after.content)
+            .bind(after.updated_at)
+            .bind(id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("更新提示词失败: {}", e))?;
+        audit::record_update(&mut tx, origin, AuditEntity::Prompt, id, &before, &after).await?;
+        tx.commit().await.map_err(|e| format!("提交事务失败: {}", e))?;
+        Ok(())
+    }
+
+    pub async fn delete(&self, id: i64, origin: AuditOrigin) -> Result<(), String> {
+        let mut tx = self.pool.begin().await.map_err(|e| format!("开启事务失败: {}", e))?;
+        let Some(existing) = Self::get_with(&mut tx, id).await? else {
+            return Ok(());
+        };
+
+        sqlx::query("DELETE FROM prompts WHERE id = ?")
+            .bind(id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("删除提示词失败: {}", e))?;
+        audit::record_delete(&mut tx, origin, AuditEntity::Prompt, id, &existing).await?;
+        tx.commit().await.map_err(|e| format!("提交事务失败: {}", e))?;
+        Ok(())
+    }
+}
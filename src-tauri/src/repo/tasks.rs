@@ -0,0 +1,223 @@
+use sqlx::{Pool, Sqlite, SqliteConnection};
+
+use super::{date_of, order, string_list, to_json, SortDirection};
+use crate::audit;
+use crate::idea_links;
+use crate::models::{AuditEntity, AuditOrigin, DoneTask, NewDoneTask};
+
+const TASK_COLUMNS: &str = "id, content, start_time, end_time, attachments, created_at, date, tags";
+
+#[derive(sqlx::FromRow)]
+struct TaskRow {
+    id: i64,
+    content: String,
+    start_time: i64,
+    end_time: i64,
+    attachments: String,
+    created_at: i64,
+    date: String,
+    tags: String,
+}
+
+impl From<TaskRow> for DoneTask {
+    fn from(row: TaskRow) -> Self {
+        DoneTask {
+            id: row.id,
+            content: row.content,
+            start_time: row.start_time,
+            end_time: row.end_time,
+            attachments: string_list(&row.attachments),
+            created_at: row.created_at,
+            date: row.date,
+            tags: string_list(&row.tags),
+        }
+    }
+}
+
+/// 已完成事项的数据访问
+pub struct TaskRepo<'a> {
+    pool: &'a Pool<Sqlite>,
+}
+
+impl<'a> TaskRepo<'a> {
+    pub fn new(pool: &'a Pool<Sqlite>) -> Self {
+        Self { pool }
+    }
+
+    pub async fn get(&self, id: i64) -> Result<Option<DoneTask>, String> {
+        let mut conn = self.pool.acquire().await.map_err(|e| format!("获取数据库连接失败: {}", e))?;
+        Self::get_with(&mut conn, id).await
+    }
+
+    pub async fn get_with(conn: &mut SqliteConnection, id: i64) -> Result<Option<DoneTask>, String> {
+        let row = sqlx::query_as::<_, TaskRow>(&format!("SELECT {} FROM done_tasks WHERE id = ?", TASK_COLUMNS))
+            .bind(id)
+            .fetch_optional(conn)
+            .await
+            .map_err(|e| format!("查询事项失败: {}", e))?;
+        Ok(row.map(DoneTask::from))
+    }
+
+    /// 某一天的事项，按开始时间升序
+    pub async fn list_by_date(&self, date: &str) -> Result<Vec<DoneTask>, String> {
+        let rows = sqlx::query_as::<_, TaskRow>(&format!(
+            "SELECT {} FROM done_tasks WHERE date = ? ORDER BY start_time ASC",
+            TASK_COLUMNS
+        ))
+        .bind(date)
+        .fetch_all(self.pool)
+        .await
+        .map_err(|e| format!("查询事项失败: {}", e))?;
+        Ok(rows.into_iter().map(DoneTask::from).collect())
+    }
+
+    /// 日期范围内（含两端）的事项，按日期和开始时间排序
+    pub async fn list_in_range(&self, start_date: &str, end_date: &str, direction: SortDirection) -> Result<Vec<DoneTask>, String> {
+        let rows = sqlx::query_as::<_, TaskRow>(&format!(
+            "SELECT {} FROM done_tasks WHERE date >= ? AND date <= ? ORDER BY date {dir}, start_time {dir}",
+            TASK_COLUMNS,
+            dir = order(direction)
+        ))
+        .bind(start_date)
+        .bind(end_date)
+        .fetch_all(self.pool)
+        .await
+        .map_err(|e| format!("查询事项失败: {}", e))?;
+        Ok(rows.into_iter().map(DoneTask::from).collect())
+    }
+
+    /// 与 [range_start, range_end) 有交集的事项，跨天事项也会取出
+    pub async fn overlapping(&self, range_start: i64, range_end: i64) -> Result<Vec<DoneTask>, String> {
+        let mut conn = self.pool.acquire().await.map_err(|e| format!("获取数据库连接失败: {}", e))?;
+        Self::overlapping_with(&mut conn, range_start, range_end).await
+    }
+
+    /// 在调用方的事务中查询，能看到事务内尚未提交的修改
+    pub async fn overlapping_with(conn: &mut SqliteConnection, range_start: i64, range_end: i64) -> Result<Vec<DoneTask>, String> {
+        let rows = sqlx::query_as::<_, TaskRow>(&format!(
+            "SELECT {} FROM done_tasks WHERE start_time < ? AND end_time > ?",
+            TASK_COLUMNS
+        ))
+        .bind(range_end)
+        .bind(range_start)
+        .fetch_all(conn)
+        .await
+        .map_err(|e| format!("查询事项失败: {}", e))?;
+        Ok(rows.into_iter().map(DoneTask::from).collect())
+    }
+
+    /// 新建事项，日期由开始时间得出；时间段需由调用方事先校验
+    pub async fn insert(&self, task: NewDoneTask, origin: AuditOrigin) -> Result<DoneTask, String> {
+        let mut tx = self.pool.begin().await.map_err(|e| format!("开启事务失败: {}", e))?;
+        let task = Self::insert_with(&mut tx, task, origin).await?;
+        tx.commit().await.map_err(|e| format!("提交事务失败: {}", e))?;
+        Ok(task)
+    }
+
+    /// 在调用方的事务中新建事项
+    pub async fn insert_with(conn: &mut SqliteConnection, task: NewDoneTask, origin: AuditOrigin) -> Result<DoneTask, String> {
+        let date = date_of(task.start_time)?;
+        let id = sqlx::query(
+            "INSERT INTO done_tasks (content, start_time, end_time, attachments, created_at, date, tags) VALUES (?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(&task.content)
+        .bind(task.start_time)
+        .bind(task.end_time)
+        .bind(to_json(&task.attachments))
+        .bind(task.created_at)
+        .bind(&date)
+        .bind(to_json(&task.tags))
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| format!("保存事项失败: {}", e))?
+        .last_insert_rowid();
+
+        let task = DoneTask {
+            id,
+            content: task.content,
+            start_time: task.start_time,
+            end_time: task.end_time,
+            attachments: task.attachments,
+            created_at: task.created_at,
+            date,
+            tags: task.tags,
+        };
+        audit::record_insert(conn, origin, AuditEntity::Task, id, &task).await?;
+        Ok(task)
+    }
+
+    /// 修改事项的时间段，日期随开始时间更新；不存在时返回 None
+    pub async fn reschedule_with(
+        conn: &mut SqliteConnection,
+        id: i64,
+        start_time: i64,
+        end_time: i64,
+        origin: AuditOrigin,
+    ) -> Result<Option<DoneTask>, String> {
+        let Some(before) = Self::get_with(&mut *conn, id).await? else {
+            return Ok(None);
+        };
+        let date = date_of(start_time)?;
+        sqlx::query("UPDATE done_tasks SET start_time = ?, end_time = ?, date = ? WHERE id = ?")
+            .bind(start_time)
+            .bind(end_time)
+            .bind(&date)
+            .bind(id)
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| format!("更新事项失败: {}", e))?;
+        Self::updated(conn, before, origin).await
+    }
+
+    /// 替换事项的标签；不存在时返回 None
+    pub async fn set_tags_with(
+        conn: &mut SqliteConnection,
+        id: i64,
+        tags: &[String],
+        origin: AuditOrigin,
+    ) -> Result<Option<DoneTask>, String> {
+        let Some(before) = Self::get_with(&mut *conn, id).await? else {
+            return Ok(None);
+        };
+        sqlx::query("UPDATE done_tasks SET tags = ? WHERE id = ?")
+            .bind(to_json(tags))
+            .bind(id)
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| format!("更新事项失败: {}", e))?;
+        Self::updated(conn, before, origin).await
+    }
+
+    /// 读取修改后的事项并记录审计日志
+    async fn updated(conn: &mut SqliteConnection, before: DoneTask, origin: AuditOrigin) -> Result<Option<DoneTask>, String> {
+        let after = Self::get_with(&mut *conn, before.id).await?;
+        if let Some(after) = &after {
+            audit::record_update(conn, origin, AuditEntity::Task, before.id, &before, after).await?;
+        }
+        Ok(after)
+    }
+
+    /// 删除事项并解除想法关联，返回被删除的事项；不存在时返回 None
+    pub async fn delete(&self, id: i64, origin: AuditOrigin) -> Result<Option<DoneTask>, String> {
+        let mut tx = self.pool.begin().await.map_err(|e| format!("开启事务失败: {}", e))?;
+        let deleted = Self::delete_with(&mut tx, id, origin).await?;
+        tx.commit().await.map_err(|e| format!("提交事务失败: {}", e))?;
+        Ok(deleted)
+    }
+
+    /// 在调用方的事务中删除事项
+    pub async fn delete_with(conn: &mut SqliteConnection, id: i64, origin: AuditOrigin) -> Result<Option<DoneTask>, String> {
+        let Some(existing) = Self::get_with(&mut *conn, id).await? else {
+            return Ok(None);
+        };
+
+        sqlx::query("DELETE FROM done_tasks WHERE id = ?")
+            .bind(id)
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| format!("删除事项失败: {}", e))?;
+        idea_links::on_task_deleted(&mut *conn, id).await?;
+        audit::record_delete(conn, origin, AuditEntity::Task, id, &existing).await?;
+        Ok(Some(existing))
+    }
+}
@@ -0,0 +1,132 @@
+use sqlx::{sqlite::SqliteRow, Pool, Row, Sqlite};
+
+use crate::audit;
+use crate::models::{AuditEntity, AuditOrigin, Report};
+
+const REPORT_COLUMNS: &str = "id, title, content, start_date, end_date, model, created_at";
+
+fn report_from_row(row: &SqliteRow) -> Result<Report, String> {
+    let read_err = |e: sqlx::Error| format!("读取报告失败: {}", e);
+    Ok(Report {
+        id: row.try_get("id").map_err(read_err)?,
+        title: row.try_get("title").map_err(read_err)?,
+        content: row.try_get("content").map_err(read_err)?,
+        start_date: row.try_get("start_date").map_err(read_err)?,
+        end_date: row.try_get("end_date").map_err(read_err)?,
+        model: row.try_get("model").map_err(read_err)?,
+        created_at: row.try_get("created_at").map_err(read_err)?,
+    })
+}
+
+/// 报告的默认标题
+pub fn default_title(start_date: &str, end_date: &str) -> String {
+    if start_date == end_date {
+        format!("日报 {}", start_date)
+    } else {
+        format!("日报 {} 至 {}", start_date, end_date)
+    }
+}
+
+/// 保存报告，返回报告 id
+pub async fn save_report(
+    pool: &Pool<Sqlite>,
+    title: &str,
+    content: &str,
+    start_date: &str,
+    end_date: &str,
+    model: Option<&str>,
+    origin: AuditOrigin,
+) -> Result<i64, String> {
+    let mut tx = pool.begin().await.map_err(|e| format!("开启事务失败: {}", e))?;
+    let created_at = chrono::Local::now().timestamp();
+    let id = sqlx::query(
+        "INSERT INTO reports (title, content, start_date, end_date, model, created_at) VALUES (?, ?, ?, ?, ?, ?)",
+    )
+    .bind(title)
+    .bind(content)
+    .bind(start_date)
+    .bind(end_date)
+    .bind(model)
+    .bind(created_at)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("保存报告失败: {}", e))?
+    .last_insert_rowid();
+
+    let report = Report {
+        id,
+        title: title.to_string(),
+        content: content.to_string(),
+        start_date: start_date.to_string(),
+        end_date: end_date.to_string(),
+        model: model.map(str::to_string),
+        created_at,
+    };
+    audit::record_insert(&mut tx, origin, AuditEntity::Report, id, &report).await?;
+    tx.commit().await.map_err(|e| format!("提交事务失败: {}", e))?;
+    Ok(id)
+}
+
+/// 读取单个报告
+pub async fn get_report(pool: &Pool<Sqlite>, id: i64) -> Result<Report, String> {
+    let row = sqlx::query(&format!("SELECT {} FROM reports WHERE id = ?", REPORT_COLUMNS))
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| format!("查询报告失败: {}", e))?
+        .ok_or_else(|| format!("报告 {} 不存在", id))?;
+    report_from_row(&row)
+}
+
+/// 按生成时间倒序列出报告
+pub async fn list_reports(pool: &Pool<Sqlite>, limit: i64, offset: i64) -> Result<Vec<Report>, String> {
+    let rows = sqlx::query(&format!(
+        "SELECT {} FROM reports ORDER BY created_at DESC, id DESC LIMIT ? OFFSET ?",
+        REPORT_COLUMNS
+    ))
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("查询报告失败: {}", e))?;
+
+    rows.iter().map(report_from_row).collect()
+}
+
+/// 删除报告及其推送、邮件发送记录
+pub async fn delete_report(pool: &Pool<Sqlite>, id: i64, origin: AuditOrigin) -> Result<(), String> {
+    let mut tx = pool.begin().await.map_err(|e| format!("开启事务失败: {}", e))?;
+
+    let existing = sqlx::query(&format!("SELECT {} FROM reports WHERE id = ?", REPORT_COLUMNS))
+        .bind(id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| format!("查询报告失败: {}", e))?
+        .map(|row| report_from_row(&row))
+        .transpose()?;
+
+    sqlx::query("DELETE FROM report_emails WHERE report_id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("删除邮件发送记录失败: {}", e))?;
+
+    sqlx::query("DELETE FROM report_publications WHERE report_id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("删除推送记录失败: {}", e))?;
+
+    sqlx::query("DELETE FROM reports WHERE id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("删除报告失败: {}", e))?;
+
+    if let Some(existing) = &existing {
+        audit::record_delete(&mut tx, origin, AuditEntity::Report, id, existing).await?;
+    }
+
+    tx.commit().await.map_err(|e| format!("提交事务失败: {}", e))?;
+    Ok(())
+}
@@ -0,0 +1,368 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use chrono::{Datelike, Duration, Local, NaiveDate, NaiveDateTime, TimeZone, Timelike};
+use serde::{Deserialize, Serialize};
+
+use crate::models::DoneTask;
+
+/// 未设置标签的事项归入此分组
+pub const UNTAGGED: &str = "未分类";
+
+/// 统计分组方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GroupBy {
+    Day,
+    Week, // ISO 周
+    Weekday,
+    Hour, // 一天中的小时
+    Tag,
+}
+
+/// 单个分组的统计值
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StatsBucket {
+    pub key: String, // 排序用的键，例如 2024-01-01、2024-W01、1、09、标签名
+    pub label: String, // 展示用的名称
+    pub seconds: i64,
+    pub hours: f64,
+    pub task_count: usize,
+    pub daily_average_seconds: f64, // 按该分组覆盖的天数计算的日均时长
+}
+
+/// 时间统计结果（可直接用于绘制图表）
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TimeStats {
+    pub start_date: String,
+    pub end_date: String,
+    pub group_by: GroupBy,
+    pub total_seconds: i64,
+    pub total_hours: f64,
+    pub task_count: usize,
+    pub day_count: i64,
+    pub active_day_count: usize,
+    pub daily_average_seconds: f64,
+    pub active_daily_average_seconds: f64,
+    pub series: Vec<StatsBucket>,
+}
+
+/// 本地日期零点对应的时间戳（夏令时跳过零点时取之后最早的时刻）
+pub fn local_midnight(date: NaiveDate) -> i64 {
+    local_timestamp(date.and_hms_opt(0, 0, 0).unwrap_or_default())
+}
+
+fn local_timestamp(naive: NaiveDateTime) -> i64 {
+    let mut naive = naive;
+    // 本地时间不存在（夏令时跳变）时向后推移
+    for _ in 0..4 {
+        if let Some(dt) = Local.from_local_datetime(&naive).earliest() {
+            return dt.timestamp();
+        }
+        naive += Duration::hours(1);
+    }
+    naive.and_utc().timestamp()
+}
+
+fn local_datetime(timestamp: i64) -> NaiveDateTime {
+    Local
+        .timestamp_opt(timestamp, 0)
+        .single()
+        .map(|dt| dt.naive_local())
+        .unwrap_or_default()
+}
+
+/// 合并重叠的时间段，返回按开始时间排序、互不重叠的时间段
+pub fn merge_intervals(mut intervals: Vec<(i64, i64)>) -> Vec<(i64, i64)> {
+    intervals.retain(|(start, end)| end > start);
+    intervals.sort();
+
+    let mut merged: Vec<(i64, i64)> = Vec::new();
+    for (start, end) in intervals {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+}
+
+/// 下一个分组边界（不含当前时刻）
+fn next_boundary(timestamp: i64, group_by: GroupBy) -> i64 {
+    let local = local_datetime(timestamp);
+    let next = match group_by {
+        GroupBy::Hour => local
+            .with_minute(0)
+            .and_then(|dt| dt.with_second(0))
+            .unwrap_or(local)
+            + Duration::hours(1),
+        _ => (local.date() + Duration::days(1))
+            .and_hms_opt(0, 0, 0)
+            .unwrap_or(local),
+    };
+    let boundary = local_timestamp(next);
+    // 防止时区异常导致死循环
+    if boundary > timestamp { boundary } else { timestamp + 3600 }
+}
+
+fn weekday_label(number: u32) -> &'static str {
+    match number {
+        1 => "周一",
+        2 => "周二",
+        3 => "周三",
+        4 => "周四",
+        5 => "周五",
+        6 => "周六",
+        _ => "周日",
+    }
+}
+
+/// 时刻所属分组的键与展示名称（不适用于标签分组）
+fn bucket_of(timestamp: i64, group_by: GroupBy) -> (String, String) {
+    let local = local_datetime(timestamp);
+    date_bucket(local.date(), local.hour(), group_by)
+}
+
+fn date_bucket(date: NaiveDate, hour: u32, group_by: GroupBy) -> (String, String) {
+    match group_by {
+        GroupBy::Day => {
+            let key = date.format("%Y-%m-%d").to_string();
+            (key.clone(), key)
+        }
+        GroupBy::Week => {
+            let week = date.iso_week();
+            let key = format!("{}-W{:02}", week.year(), week.week());
+            let label = format!("{}年第{}周", week.year(), week.week());
+            (key, label)
+        }
+        GroupBy::Weekday => {
+            let number = date.weekday().number_from_monday();
+            (number.to_string(), weekday_label(number).to_string())
+        }
+        GroupBy::Hour => (format!("{:02}", hour), format!("{:02}:00", hour)),
+        GroupBy::Tag => (String::new(), String::new()),
+    }
+}
+
+#[derive(Default)]
+struct Accumulator {
+    label: String,
+    seconds: i64,
+    task_ids: BTreeSet<i64>,
+    days: i64,
+}
+
+fn round_hours(seconds: i64) -> f64 {
+    (seconds as f64 / 3600.0 * 100.0).round() / 100.0
+}
+
+/// 计算指定日期范围（含首尾两天）内的时间统计
+pub fn compute_time_stats(
+    tasks: &[DoneTask],
+    start_date: NaiveDate,
+    end_date: NaiveDate,
+    group_by: GroupBy,
+) -> TimeStats {
+    let range_start = local_midnight(start_date);
+    let range_end = local_midnight(end_date + Duration::days(1));
+
+    // 裁剪到统计范围内
+    let clipped: Vec<(&DoneTask, i64, i64)> = tasks
+        .iter()
+        .map(|t| (t, t.start_time.max(range_start), t.end_time.min(range_end)))
+        .filter(|(_, start, end)| end > start)
+        .collect();
+
+    // 预先填充范围内的所有分组，保证图表横轴连续
+    let mut buckets: BTreeMap<String, Accumulator> = BTreeMap::new();
+    let mut date = start_date;
+    while date <= end_date {
+        if group_by != GroupBy::Tag {
+            let hours: Vec<u32> = if group_by == GroupBy::Hour { (0..24).collect() } else { vec![0] };
+            for hour in hours {
+                let (key, label) = date_bucket(date, hour, group_by);
+                let bucket = buckets.entry(key).or_default();
+                bucket.label = label;
+                bucket.days += 1;
+            }
+        }
+        date += Duration::days(1);
+    }
+    let day_count = (end_date - start_date).num_days() + 1;
+
+    if group_by == GroupBy::Tag {
+        // 每个标签内单独合并；一个事项的时间会计入它的每个标签
+        let mut per_tag: BTreeMap<String, Vec<(i64, i64)>> = BTreeMap::new();
+        for (task, start, end) in &clipped {
+            let tags: Vec<String> = if task.tags.is_empty() {
+                vec![UNTAGGED.to_string()]
+            } else {
+                task.tags.clone()
+            };
+            for tag in tags {
+                per_tag.entry(tag.clone()).or_default().push((*start, *end));
+                let bucket = buckets.entry(tag.clone()).or_default();
+                bucket.label = tag;
+                bucket.days = day_count;
+                bucket.task_ids.insert(task.id);
+            }
+        }
+        for (tag, intervals) in per_tag {
+            let seconds: i64 = merge_intervals(intervals).iter().map(|(s, e)| e - s).sum();
+            if let Some(bucket) = buckets.get_mut(&tag) {
+                bucket.seconds = seconds;
+            }
+        }
+    } else {
+        for (task, start, end) in &clipped {
+            let mut cursor = *start;
+            while cursor < *end {
+                let (key, _) = bucket_of(cursor, group_by);
+                if let Some(bucket) = buckets.get_mut(&key) {
+                    bucket.task_ids.insert(task.id);
+                }
+                cursor = next_boundary(cursor, group_by).min(*end);
+            }
+        }
+
+        let merged = merge_intervals(clipped.iter().map(|(_, s, e)| (*s, *e)).collect());
+        for (start, end) in merged {
+            let mut cursor = start;
+            while cursor < end {
+                let segment_end = next_boundary(cursor, group_by).min(end);
+                let (key, _) = bucket_of(cursor, group_by);
+                if let Some(bucket) = buckets.get_mut(&key) {
+                    bucket.seconds += segment_end - cursor;
+                }
+                cursor = segment_end;
+            }
+        }
+    }
+
+    // 所有事项合并后的总时长（标签分组时避免重复计算）
+    let merged_all = merge_intervals(clipped.iter().map(|(_, s, e)| (*s, *e)).collect());
+    let total_seconds: i64 = merged_all.iter().map(|(s, e)| e - s).sum();
+
+    let mut active_days: BTreeSet<String> = BTreeSet::new();
+    for (start, end) in &merged_all {
+        let mut cursor = *start;
+        while cursor < *end {
+            active_days.insert(bucket_of(cursor, GroupBy::Day).0);
+            cursor = next_boundary(cursor, GroupBy::Day).min(*end);
+        }
+    }
+
+    let series = buckets
+        .into_iter()
+        .map(|(key, bucket)| StatsBucket {
+            key,
+            label: bucket.label,
+            seconds: bucket.seconds,
+            hours: round_hours(bucket.seconds),
+            task_count: bucket.task_ids.len(),
+            daily_average_seconds: if bucket.days > 0 {
+                bucket.seconds as f64 / bucket.days as f64
+            } else {
+                0.0
+            },
+        })
+        .collect();
+
+    let task_count = clipped.iter().map(|(t, _, _)| t.id).collect::<BTreeSet<_>>().len();
+
+    TimeStats {
+        start_date: start_date.format("%Y-%m-%d").to_string(),
+        end_date: end_date.format("%Y-%m-%d").to_string(),
+        group_by,
+        total_seconds,
+        total_hours: round_hours(total_seconds),
+        task_count,
+        day_count,
+        active_day_count: active_days.len(),
+        daily_average_seconds: total_seconds as f64 / day_count.max(1) as f64,
+        active_daily_average_seconds: if active_days.is_empty() {
+            0.0
+        } else {
+            total_seconds as f64 / active_days.len() as f64
+        },
+        series,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(year: i32, month: u32, day: u32, hour: u32) -> i64 {
+        Local.with_ymd_and_hms(year, month, day, hour, 0, 0).unwrap().timestamp()
+    }
+
+    fn date(value: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d").unwrap()
+    }
+
+    fn task(id: i64, start_time: i64, end_time: i64, tags: &[&str]) -> DoneTask {
+        DoneTask {
+            id,
+            content: format!("事项 {}", id),
+            start_time,
+            end_time,
+            attachments: Vec::new(),
+            created_at: start_time,
+            date: String::new(),
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn merged_intervals_never_overlap() {
+        let cases = [
+            (vec![], vec![]),
+            (vec![(30, 40), (0, 10)], vec![(0, 10), (30, 40)]),
+            (vec![(0, 10), (5, 20)], vec![(0, 20)]),
+            (vec![(0, 10), (10, 20)], vec![(0, 20)]), // 首尾相接
+            (vec![(0, 30), (5, 10), (12, 20)], vec![(0, 30)]), // 包含
+            (vec![(5, 5), (9, 3), (0, 1)], vec![(0, 1)]), // 丢弃零长度和颠倒的时间段
+        ];
+        for (input, expected) in cases {
+            assert_eq!(merge_intervals(input.clone()), expected, "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn weeks_follow_iso_numbering_without_double_counting() {
+        let tasks = [
+            // 2024-12-29 是周日，跨午夜进入 2025 年第 1 周
+            task(1, at(2024, 12, 29, 23), at(2024, 12, 30, 1), &[]),
+            task(2, at(2025, 1, 2, 9), at(2025, 1, 2, 11), &[]),
+            task(3, at(2025, 1, 2, 10), at(2025, 1, 2, 12), &[]),
+            // 超出统计范围的部分被裁剪
+            task(4, at(2025, 1, 5, 22), at(2025, 1, 6, 3), &[]),
+        ];
+        let stats = compute_time_stats(&tasks, date("2024-12-28"), date("2025-01-05"), GroupBy::Week);
+
+        let series: Vec<_> = stats.series.iter().map(|b| (b.key.as_str(), b.seconds, b.task_count)).collect();
+        assert_eq!(series, vec![("2024-W52", 3600, 1), ("2025-W01", 3600 + 3 * 3600 + 2 * 3600, 4)]);
+        assert_eq!(stats.series[1].label, "2025年第1周");
+        assert_eq!(stats.total_seconds, 7 * 3600);
+        assert_eq!(stats.task_count, 4);
+        assert_eq!(stats.day_count, 9);
+        assert_eq!(stats.active_day_count, 4);
+        // 日均按各分组覆盖的天数计算
+        assert_eq!(stats.series[0].daily_average_seconds, 3600.0 / 2.0);
+        assert_eq!(stats.series[1].daily_average_seconds, 6.0 * 3600.0 / 7.0);
+    }
+
+    #[test]
+    fn tags_count_shared_time_once_in_the_total() {
+        let tasks = [
+            task(1, at(2024, 5, 10, 9), at(2024, 5, 10, 11), &["开发", "评审"]),
+            task(2, at(2024, 5, 10, 10), at(2024, 5, 10, 12), &["开发"]),
+            task(3, at(2024, 5, 10, 14), at(2024, 5, 10, 15), &[]),
+        ];
+        let stats = compute_time_stats(&tasks, date("2024-05-10"), date("2024-05-10"), GroupBy::Tag);
+
+        let series: Vec<_> = stats.series.iter().map(|b| (b.key.as_str(), b.seconds, b.task_count)).collect();
+        assert_eq!(series, vec![("开发", 3 * 3600, 2), ("未分类", 3600, 1), ("评审", 2 * 3600, 1)]);
+        assert_eq!(stats.total_seconds, 4 * 3600);
+    }
+}
@@ -0,0 +1,322 @@
+//! 测试辅助：按脚本回复的本地 HTTP 服务、SMTP 收件服务、临时数据库
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serde_json::Value;
+use sqlx::{Pool, Sqlite};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+
+use crate::ai::client::AiClient;
+use crate::ai::retry::RetryPolicy;
+use crate::database;
+use crate::models::{ApiConfig, ProviderKind};
+
+/// 本地服务收到的一次请求
+#[derive(Debug, Clone)]
+pub(crate) struct Captured {
+    pub path: String, // 含查询参数
+    pub headers: HashMap<String, String>, // 键为小写
+    pub body: String,
+}
+
+impl Captured {
+    pub fn json(&self) -> Value {
+        serde_json::from_str(&self.body).unwrap_or(Value::Null)
+    }
+}
+
+/// 脚本中的一次回复
+pub(crate) struct Reply {
+    status: u16,
+    content_type: &'static str,
+    body: String,
+}
+
+impl Reply {
+    pub fn json(body: Value) -> Self {
+        Self::status(200, body)
+    }
+
+    pub fn status(status: u16, body: Value) -> Self {
+        Self { status, content_type: "application/json", body: body.to_string() }
+    }
+
+    /// 每行一个 JSON 对象
+    pub fn ndjson(lines: &[Value]) -> Self {
+        let body: String = lines.iter().map(|line| format!("{}\n", line)).collect();
+        Self { status: 200, content_type: "application/x-ndjson", body }
+    }
+
+    /// 原样返回的 SSE 流
+    pub fn sse(body: impl Into<String>) -> Self {
+        Self { status: 200, content_type: "text/event-stream", body: body.into() }
+    }
+}
+
+/// 按顺序为每个连接返回脚本中的下一条回复，脚本用完后不再接受连接
+pub(crate) struct ScriptedServer {
+    pub url: String,
+    requests: Arc<Mutex<Vec<Captured>>>,
+}
+
+impl ScriptedServer {
+    pub async fn start(replies: Vec<Reply>) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.expect("绑定本地端口失败");
+        let url = format!("http://{}", listener.local_addr().expect("读取本地地址失败"));
+        let requests = Arc::new(Mutex::new(Vec::new()));
+
+        let captured = requests.clone();
+        tokio::spawn(async move {
+            for reply in replies {
+                let Ok((mut stream, _)) = listener.accept().await else {
+                    return;
+                };
+                if let Some(request) = read_request(&mut stream).await {
+                    captured.lock().unwrap().push(request);
+                }
+                let head = format!(
+                    "HTTP/1.1 {} Scripted\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                    reply.status,
+                    reply.content_type,
+                    reply.body.len()
+                );
+                let _ = stream.write_all(head.as_bytes()).await;
+                let _ = stream.write_all(reply.body.as_bytes()).await;
+                let _ = stream.shutdown().await;
+            }
+        });
+
+        Self { url, requests }
+    }
+
+    /// 目前收到的全部请求
+    pub fn requests(&self) -> Vec<Captured> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+async fn read_request(stream: &mut TcpStream) -> Option<Captured> {
+    let mut buffer = Vec::new();
+    let mut chunk = [0u8; 4096];
+    let header_end = loop {
+        if let Some(pos) = buffer.windows(4).position(|w| w == b"\r\n\r\n") {
+            break pos + 4;
+        }
+        let read = stream.read(&mut chunk).await.ok()?;
+        if read == 0 {
+            return None;
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+    };
+
+    let head = String::from_utf8_lossy(&buffer[..header_end]).into_owned();
+    let mut lines = head.lines();
+    let mut request_line = lines.next()?.split_whitespace();
+    let _method = request_line.next()?;
+    let path = request_line.next()?.to_string();
+    let headers: HashMap<String, String> = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_ascii_lowercase(), value.trim().to_string()))
+        .collect();
+
+    let length: usize = headers.get("content-length").and_then(|v| v.parse().ok()).unwrap_or(0);
+    while buffer.len() < header_end + length {
+        let read = stream.read(&mut chunk).await.ok()?;
+        if read == 0 {
+            break;
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+    }
+    let body = String::from_utf8_lossy(&buffer[header_end..]).into_owned();
+
+    Some(Captured { path, headers, body })
+}
+
+/// SMTP 收件服务收到的一封邮件
+#[derive(Debug, Clone)]
+pub(crate) struct ReceivedMail {
+    pub auth: Option<String>, // AUTH 命令的参数
+    pub from: String,
+    pub recipients: Vec<String>,
+    pub data: String,
+}
+
+/// 只收不发的本地 SMTP 服务，不支持 TLS
+pub(crate) struct SmtpSink {
+    pub port: u16,
+    mails: Arc<Mutex<Vec<ReceivedMail>>>,
+}
+
+impl SmtpSink {
+    pub async fn start() -> Self {
+        Self::start_with(false).await
+    }
+
+    /// 拒绝所有收件人
+    pub async fn rejecting() -> Self {
+        Self::start_with(true).await
+    }
+
+    async fn start_with(reject_recipients: bool) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.expect("绑定本地端口失败");
+        let port = listener.local_addr().expect("读取本地地址失败").port();
+        let mails = Arc::new(Mutex::new(Vec::new()));
+
+        let received = mails.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let received = received.clone();
+                tokio::spawn(async move {
+                    let _ = smtp_session(stream, reject_recipients, received).await;
+                });
+            }
+        });
+
+        Self { port, mails }
+    }
+
+    /// 目前收到的全部邮件
+    pub fn mails(&self) -> Vec<ReceivedMail> {
+        self.mails.lock().unwrap().clone()
+    }
+}
+
+async fn smtp_session(
+    stream: TcpStream,
+    reject_recipients: bool,
+    received: Arc<Mutex<Vec<ReceivedMail>>>,
+) -> std::io::Result<()> {
+    let (reader, mut writer) = stream.into_split();
+    let mut reader = BufReader::new(reader);
+    let mut mail = ReceivedMail { auth: None, from: String::new(), recipients: Vec::new(), data: String::new() };
+
+    writer.write_all(b"220 sink ESMTP\r\n").await?;
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line).await? == 0 {
+            return Ok(());
+        }
+        let command = line.trim_end();
+        let upper = command.to_ascii_uppercase();
+        let reply: &[u8] = if upper.starts_with("EHLO") {
+            b"250-sink\r\n250-AUTH PLAIN LOGIN\r\n250 8BITMIME\r\n"
+        } else if upper.starts_with("AUTH") {
+            mail.auth = Some(command[4..].trim().to_string());
+            b"235 2.7.0 Authentication successful\r\n"
+        } else if upper.starts_with("MAIL FROM:") {
+            mail.from = smtp_address(command);
+            b"250 OK\r\n"
+        } else if upper.starts_with("RCPT TO:") {
+            if reject_recipients {
+                b"550 5.1.1 Mailbox unavailable\r\n"
+            } else {
+                mail.recipients.push(smtp_address(command));
+                b"250 OK\r\n"
+            }
+        } else if upper == "DATA" {
+            writer.write_all(b"354 End data with <CR><LF>.<CR><LF>\r\n").await?;
+            loop {
+                line.clear();
+                if reader.read_line(&mut line).await? == 0 {
+                    return Ok(());
+                }
+                if line == ".\r\n" {
+                    break;
+                }
+                mail.data.push_str(&line);
+            }
+            received.lock().unwrap().push(mail.clone());
+            b"250 OK\r\n"
+        } else if upper == "QUIT" {
+            writer.write_all(b"221 Bye\r\n").await?;
+            return Ok(());
+        } else {
+            b"250 OK\r\n"
+        };
+        writer.write_all(reply).await?;
+    }
+}
+
+fn smtp_address(command: &str) -> String {
+    let start = command.find('<').map(|i| i + 1).unwrap_or(0);
+    let end = command.find('>').unwrap_or(command.len());
+    command[start..end].to_string()
+}
+
+/// 测试结束时删除的临时目录
+pub(crate) struct TempDir(PathBuf);
+
+impl TempDir {
+    pub fn new() -> Self {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let dir = std::env::temp_dir().join(format!(
+            "daily-report-test-{}-{}",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::SeqCst)
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).expect("创建临时目录失败");
+        Self(dir)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+/// 在临时目录中创建并初始化数据库
+pub(crate) async fn temp_pool() -> (Pool<Sqlite>, TempDir) {
+    let dir = TempDir::new();
+    let pool = database::open_pool(&dir.path().join("data.db")).await.expect("初始化测试数据库失败");
+    (pool, dir)
+}
+
+/// 读取录制的 SSE 流中每个 `data:` 行的 JSON
+pub(crate) fn sse_events(fixture: &str) -> Vec<Value> {
+    fixture
+        .lines()
+        .filter_map(|line| line.strip_prefix("data:"))
+        .map(|data| serde_json::from_str(data.trim()).expect("录制数据不是合法的 JSON"))
+        .collect()
+}
+
+/// 指向本地服务的 AI 配置
+pub(crate) fn config_for(provider: ProviderKind, api_url: &str) -> ApiConfig {
+    ApiConfig {
+        api_key: "test-key".to_string(),
+        api_url: api_url.to_string(),
+        model: "test-model".to_string(),
+        context_budget: None,
+        provider,
+        mock: None,
+        local: None,
+    }
+}
+
+/// 测试用的策略：不重试，超时较短
+pub(crate) fn test_policy() -> RetryPolicy {
+    RetryPolicy {
+        connect_timeout: Duration::from_secs(2),
+        read_timeout: Duration::from_secs(5),
+        max_attempts: 1,
+        base_delay: Duration::ZERO,
+        max_delay: Duration::ZERO,
+    }
+}
+
+/// 指向本地服务的 AI 客户端
+pub(crate) fn client_for(provider: ProviderKind, api_url: &str) -> AiClient {
+    AiClient::new(config_for(provider, api_url)).with_policy(test_policy())
+}
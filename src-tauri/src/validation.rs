@@ -0,0 +1,339 @@
+use chrono::{Local, TimeZone};
+use serde::{Deserialize, Serialize};
+
+use crate::models::DoneTask;
+
+/// 允许的时钟误差（秒），结束时间在此范围内超出当前时间不视为未来时间
+const FUTURE_TOLERANCE_SECS: i64 = 5 * 60;
+
+/// 问题级别
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IssueLevel {
+    Warning,
+    Error,
+}
+
+/// 单条校验问题
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ValidationIssue {
+    pub level: IssueLevel,
+    pub code: String, // inverted_range / zero_length / future_time / spans_midnight / overlap / too_long
+    pub message: String,
+    pub task_id: Option<i64>, // 新建事项时为空
+    pub conflicting_ids: Vec<i64>, // 与之重叠的事项 ID
+}
+
+/// 校验结果（用于返回给前端）
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ValidationReport {
+    pub errors: Vec<ValidationIssue>,
+    pub warnings: Vec<ValidationIssue>,
+}
+
+impl ValidationReport {
+    fn push(&mut self, issue: ValidationIssue) {
+        match issue.level {
+            IssueLevel::Error => self.errors.push(issue),
+            IssueLevel::Warning => self.warnings.push(issue),
+        }
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// 将所有错误拼接为一条消息
+    pub fn error_message(&self) -> String {
+        self.errors
+            .iter()
+            .map(|issue| issue.message.as_str())
+            .collect::<Vec<_>>()
+            .join("; ")
+    }
+}
+
+/// 某一天的一致性检查结果
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DayConsistencyReport {
+    pub date: String,
+    pub task_count: usize,
+    pub issues: Vec<ValidationIssue>,
+}
+
+/// 校验所需的参数
+pub struct ValidationRules {
+    pub now: i64,
+    pub max_duration_secs: i64,
+}
+
+/// 规范化标签（去除首尾空白与 # 前缀、去重、丢弃空标签）
+pub fn normalize_tags(tags: Vec<String>) -> Vec<String> {
+    let mut result: Vec<String> = Vec::new();
+    for tag in tags {
+        let tag = tag.trim().trim_start_matches('#').trim().to_string();
+        if !tag.is_empty() && !result.contains(&tag) {
+            result.push(tag);
+        }
+    }
+    result
+}
+
+fn issue(level: IssueLevel, code: &str, message: String, task_id: Option<i64>) -> ValidationIssue {
+    ValidationIssue {
+        level,
+        code: code.to_string(),
+        message,
+        task_id,
+        conflicting_ids: Vec::new(),
+    }
+}
+
+/// 判断时间段是否跨越本地时间的午夜
+fn spans_midnight(start_time: i64, end_time: i64) -> bool {
+    let start = Local.timestamp_opt(start_time, 0).single();
+    let end = Local.timestamp_opt(end_time, 0).single();
+    match (start, end) {
+        // 恰好结束于午夜不算跨天
+        (Some(start), Some(end)) => start.date_naive() != (end - chrono::Duration::seconds(1)).date_naive(),
+        _ => false,
+    }
+}
+
+/// 校验单个时间段本身（不涉及与其他事项的关系）
+fn check_range(task_id: Option<i64>, start_time: i64, end_time: i64, rules: &ValidationRules) -> Vec<ValidationIssue> {
+    let mut issues = Vec::new();
+
+    if end_time < start_time {
+        issues.push(issue(
+            IssueLevel::Error,
+            "inverted_range",
+            "结束时间早于开始时间".to_string(),
+            task_id,
+        ));
+        // 时间段颠倒时其余检查没有意义
+        return issues;
+    }
+
+    if end_time == start_time {
+        issues.push(issue(
+            IssueLevel::Error,
+            "zero_length",
+            "开始时间与结束时间相同".to_string(),
+            task_id,
+        ));
+    }
+
+    if end_time > rules.now + FUTURE_TOLERANCE_SECS {
+        issues.push(issue(
+            IssueLevel::Error,
+            "future_time",
+            "结束时间晚于当前时间".to_string(),
+            task_id,
+        ));
+    }
+
+    let duration = end_time - start_time;
+    if rules.max_duration_secs > 0 && duration > rules.max_duration_secs {
+        issues.push(issue(
+            IssueLevel::Error,
+            "too_long",
+            format!(
+                "时长 {} 分钟超过上限 {} 分钟",
+                duration / 60,
+                rules.max_duration_secs / 60
+            ),
+            task_id,
+        ));
+    }
+
+    if spans_midnight(start_time, end_time) {
+        issues.push(issue(
+            IssueLevel::Warning,
+            "spans_midnight",
+            "事项跨越午夜".to_string(),
+            task_id,
+        ));
+    }
+
+    issues
+}
+
+/// 找出与给定时间段重叠的事项 ID（首尾相接不算重叠）
+fn overlapping_ids(start_time: i64, end_time: i64, others: &[DoneTask], exclude_id: Option<i64>) -> Vec<i64> {
+    others
+        .iter()
+        .filter(|t| Some(t.id) != exclude_id)
+        .filter(|t| t.end_time > t.start_time)
+        .filter(|t| t.start_time < end_time && start_time < t.end_time)
+        .map(|t| t.id)
+        .collect()
+}
+
+/// 校验一个待保存的已完成事项
+///
+/// `existing` 为可能与之重叠的已有事项，`exclude_id` 用于编辑时排除自身。
+pub fn validate_task(
+    start_time: i64,
+    end_time: i64,
+    existing: &[DoneTask],
+    exclude_id: Option<i64>,
+    rules: &ValidationRules,
+) -> ValidationReport {
+    let mut report = ValidationReport::default();
+
+    let range_issues = check_range(exclude_id, start_time, end_time, rules);
+    let inverted = range_issues.iter().any(|i| i.code == "inverted_range");
+    for i in range_issues {
+        report.push(i);
+    }
+
+    if !inverted {
+        let conflicts = overlapping_ids(start_time, end_time, existing, exclude_id);
+        if !conflicts.is_empty() {
+            let mut overlap = issue(
+                IssueLevel::Warning,
+                "overlap",
+                format!("与 {} 个已有事项时间重叠", conflicts.len()),
+                exclude_id,
+            );
+            overlap.conflicting_ids = conflicts;
+            report.push(overlap);
+        }
+    }
+
+    report
+}
+
+/// 检查一组事项（通常为同一天）的全部问题
+pub fn check_tasks(tasks: &[DoneTask], rules: &ValidationRules) -> Vec<ValidationIssue> {
+    let mut issues = Vec::new();
+
+    for task in tasks {
+        issues.extend(check_range(Some(task.id), task.start_time, task.end_time, rules));
+
+        if task.end_time <= task.start_time {
+            continue;
+        }
+
+        // 每对重叠只报告一次：只记录 ID 更大的冲突方
+        let conflicts: Vec<i64> = overlapping_ids(task.start_time, task.end_time, tasks, Some(task.id))
+            .into_iter()
+            .filter(|id| *id > task.id)
+            .collect();
+        if !conflicts.is_empty() {
+            let mut overlap = issue(
+                IssueLevel::Warning,
+                "overlap",
+                format!("事项 {} 与 {:?} 时间重叠", task.id, conflicts),
+                Some(task.id),
+            );
+            overlap.conflicting_ids = conflicts;
+            issues.push(overlap);
+        }
+    }
+
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(day: u32, hour: u32, minute: u32) -> i64 {
+        Local.with_ymd_and_hms(2024, 5, day, hour, minute, 0).unwrap().timestamp()
+    }
+
+    fn task(id: i64, start_time: i64, end_time: i64) -> DoneTask {
+        DoneTask {
+            id,
+            content: format!("事项 {}", id),
+            start_time,
+            end_time,
+            attachments: Vec::new(),
+            created_at: start_time,
+            date: "2024-05-10".to_string(),
+            tags: Vec::new(),
+        }
+    }
+
+    fn rules() -> ValidationRules {
+        ValidationRules { now: at(20, 0, 0), max_duration_secs: 12 * 3600 }
+    }
+
+    fn codes(issues: &[ValidationIssue]) -> Vec<&str> {
+        issues.iter().map(|i| i.code.as_str()).collect()
+    }
+
+    #[test]
+    fn midnight_is_detected_in_local_time() {
+        let cases = [
+            (at(10, 9, 0), at(10, 10, 0), false),
+            (at(10, 23, 0), at(11, 1, 0), true),
+            (at(10, 22, 0), at(11, 0, 0), false), // 恰好结束于午夜
+            (at(11, 0, 0), at(11, 1, 0), false),
+            (at(10, 12, 0), at(12, 12, 0), true),
+        ];
+        for (start, end, expected) in cases {
+            assert_eq!(spans_midnight(start, end), expected, "{} - {}", start, end);
+        }
+    }
+
+    #[test]
+    fn overlaps_list_the_conflicting_ids() {
+        let existing = [
+            task(1, at(10, 9, 0), at(10, 10, 0)),
+            task(2, at(10, 10, 0), at(10, 11, 0)), // 首尾相接
+            task(3, at(10, 9, 30), at(10, 9, 45)),
+            task(4, at(10, 9, 30), at(10, 9, 30)), // 零时长事项不参与重叠判断
+        ];
+
+        let report = validate_task(at(10, 9, 15), at(10, 10, 0), &existing, None, &rules());
+        assert!(report.is_valid());
+        assert_eq!(codes(&report.warnings), vec!["overlap"]);
+        assert_eq!(report.warnings[0].conflicting_ids, vec![1, 3]);
+        assert_eq!(report.warnings[0].task_id, None);
+
+        // 编辑时排除自身
+        let report = validate_task(at(10, 9, 15), at(10, 10, 0), &existing, Some(1), &rules());
+        assert_eq!(report.warnings[0].conflicting_ids, vec![3]);
+        assert_eq!(report.warnings[0].task_id, Some(1));
+
+        let report = validate_task(at(10, 11, 0), at(10, 12, 0), &existing, None, &rules());
+        assert!(report.warnings.is_empty());
+    }
+
+    #[test]
+    fn invalid_ranges_are_errors() {
+        let existing = [task(1, at(10, 9, 0), at(10, 10, 0))];
+        let cases = [
+            (at(10, 10, 0), at(10, 9, 0), vec!["inverted_range"]), // 颠倒时不再检查重叠
+            (at(10, 9, 30), at(10, 9, 30), vec!["zero_length"]),
+            (at(20, 9, 0), at(20, 10, 0), vec!["future_time"]),
+            (at(10, 9, 0), at(11, 9, 0), vec!["too_long"]),
+        ];
+        for (start, end, expected) in cases {
+            let report = validate_task(start, end, &existing, None, &rules());
+            assert!(!report.is_valid());
+            assert_eq!(codes(&report.errors), expected);
+        }
+        // 在时钟误差范围内不算未来时间
+        let report = validate_task(at(19, 23, 0), at(20, 0, 4), &[], None, &rules());
+        assert!(report.is_valid());
+    }
+
+    #[test]
+    fn check_tasks_reports_each_overlap_once() {
+        let tasks = [
+            task(1, at(10, 9, 0), at(10, 10, 0)),
+            task(2, at(10, 9, 30), at(10, 10, 30)),
+            task(3, at(10, 10, 15), at(10, 11, 0)),
+        ];
+        let overlaps: Vec<_> = check_tasks(&tasks, &rules())
+            .into_iter()
+            .filter(|i| i.code == "overlap")
+            .map(|i| (i.task_id, i.conflicting_ids))
+            .collect();
+        assert_eq!(overlaps, vec![(Some(1), vec![2]), (Some(2), vec![3])]);
+    }
+}
@@ -0,0 +1,650 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use hmac::{Hmac, Mac};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, CONTENT_TYPE};
+use serde_json::{json, Value};
+use sha2::Sha256;
+use sqlx::{sqlite::SqliteRow, Pool, Row, Sqlite};
+
+use crate::database::DbState;
+use crate::models::{DeliveryStatus, NewWebhook, Webhook, WebhookDelivery};
+
+/// 可订阅的事件
+pub const EVENTS: &[&str] = &[
+    "idea.created",
+    "idea.updated",
+    "idea.deleted",
+    "task.created",
+    "task.updated",
+    "task.deleted",
+    "report.generated",
+    "report.published",
+    "report.emailed",
+    "report.deleted",
+];
+
+/// 测试发送使用的事件
+pub const TEST_EVENT: &str = "webhook.test";
+
+/// 单次请求超时
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// 最多尝试次数（含首次），用尽后标记为失败
+const MAX_ATTEMPTS: i64 = 8;
+
+/// 重试间隔：首次 30 秒，之后翻倍，最长 6 小时
+const RETRY_BASE_SECS: i64 = 30;
+const RETRY_MAX_SECS: i64 = 6 * 60 * 60;
+
+/// 发送中的记录在该时间内不会被重复领取
+const CLAIM_LEASE_SECS: i64 = 120;
+
+/// 重试队列的检查间隔
+const WORKER_INTERVAL: Duration = Duration::from_secs(30);
+
+/// 每个 Webhook 保留的投递记录数（不含待重试的记录）
+const HISTORY_LIMIT: i64 = 500;
+
+/// 保存的响应内容最大字符数
+const RESPONSE_BODY_CHARS: usize = 2000;
+
+const SIGNATURE_HEADER: &str = "x-webhook-signature";
+
+const WEBHOOK_COLUMNS: &str =
+    "id, name, url, events, body_template, headers, secret, enabled, created_at, updated_at";
+
+const DELIVERY_COLUMNS: &str = r#"
+    id, webhook_id, event, payload, status, attempts, next_attempt_at,
+    response_status, response_body, error, created_at, updated_at
+"#;
+
+fn webhook_from_row(row: &SqliteRow) -> Result<Webhook, String> {
+    let read_err = |e: sqlx::Error| format!("读取 Webhook 失败: {}", e);
+    let events: String = row.try_get("events").map_err(read_err)?;
+    let headers: String = row.try_get("headers").map_err(read_err)?;
+    Ok(Webhook {
+        id: row.try_get("id").map_err(read_err)?,
+        name: row.try_get("name").map_err(read_err)?,
+        url: row.try_get("url").map_err(read_err)?,
+        events: serde_json::from_str(&events).unwrap_or_default(),
+        body_template: row.try_get("body_template").map_err(read_err)?,
+        headers: serde_json::from_str(&headers).unwrap_or_default(),
+        secret: row.try_get("secret").map_err(read_err)?,
+        enabled: row.try_get("enabled").map_err(read_err)?,
+        created_at: row.try_get("created_at").map_err(read_err)?,
+        updated_at: row.try_get("updated_at").map_err(read_err)?,
+    })
+}
+
+fn delivery_from_row(row: &SqliteRow) -> Result<WebhookDelivery, String> {
+    let read_err = |e: sqlx::Error| format!("读取投递记录失败: {}", e);
+    let status: String = row.try_get("status").map_err(read_err)?;
+    Ok(WebhookDelivery {
+        id: row.try_get("id").map_err(read_err)?,
+        webhook_id: row.try_get("webhook_id").map_err(read_err)?,
+        event: row.try_get("event").map_err(read_err)?,
+        payload: row.try_get("payload").map_err(read_err)?,
+        status: DeliveryStatus::parse(&status).ok_or_else(|| format!("未知的投递状态: {}", status))?,
+        attempts: row.try_get("attempts").map_err(read_err)?,
+        next_attempt_at: row.try_get("next_attempt_at").map_err(read_err)?,
+        response_status: row.try_get("response_status").map_err(read_err)?,
+        response_body: row.try_get("response_body").map_err(read_err)?,
+        error: row.try_get("error").map_err(read_err)?,
+        created_at: row.try_get("created_at").map_err(read_err)?,
+        updated_at: row.try_get("updated_at").map_err(read_err)?,
+    })
+}
+
+/// 校验并规范化 Webhook 配置
+fn normalize(webhook: NewWebhook) -> Result<NewWebhook, String> {
+    let name = webhook.name.trim().to_string();
+    if name.is_empty() {
+        return Err("Webhook 名称不能为空".to_string());
+    }
+    let url = webhook.url.trim().to_string();
+    if !url.starts_with("https://") && !url.starts_with("http://") {
+        return Err("Webhook 地址必须以 http:// 或 https:// 开头".to_string());
+    }
+
+    let mut events: Vec<String> = webhook.events.iter().map(|e| e.trim().to_string()).collect();
+    events.sort();
+    events.dedup();
+    if events.is_empty() {
+        return Err("请至少订阅一个事件".to_string());
+    }
+    if let Some(unknown) = events.iter().find(|e| e.as_str() != "*" && !EVENTS.contains(&e.as_str())) {
+        return Err(format!("未知的事件: {}", unknown));
+    }
+
+    let body_template = webhook.body_template.filter(|t| !t.trim().is_empty());
+    if let Some(template) = &body_template {
+        serde_json::from_str::<Value>(template).map_err(|e| format!("请求体模板不是合法的 JSON: {}", e))?;
+    }
+
+    for (name, value) in &webhook.headers {
+        HeaderName::from_bytes(name.as_bytes()).map_err(|_| format!("请求头名称无效: {}", name))?;
+        HeaderValue::from_str(value).map_err(|_| format!("请求头 {} 的值无效", name))?;
+    }
+
+    Ok(NewWebhook {
+        name,
+        url,
+        events,
+        body_template,
+        headers: webhook.headers,
+        secret: webhook.secret.map(|s| s.trim().to_string()).filter(|s| !s.is_empty()),
+        enabled: webhook.enabled,
+    })
+}
+
+// ========== Webhook 配置 ==========
+
+/// 读取单个 Webhook
+pub async fn get_webhook(pool: &Pool<Sqlite>, id: i64) -> Result<Webhook, String> {
+    let row = sqlx::query(&format!("SELECT {} FROM webhooks WHERE id = ?", WEBHOOK_COLUMNS))
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| format!("查询 Webhook 失败: {}", e))?
+        .ok_or_else(|| format!("Webhook {} 不存在", id))?;
+    webhook_from_row(&row)
+}
+
+/// 列出所有 Webhook
+pub async fn list_webhooks(pool: &Pool<Sqlite>) -> Result<Vec<Webhook>, String> {
+    let rows = sqlx::query(&format!("SELECT {} FROM webhooks ORDER BY id", WEBHOOK_COLUMNS))
+        .fetch_all(pool)
+        .await
+        .map_err(|e| format!("查询 Webhook 失败: {}", e))?;
+    rows.iter().map(webhook_from_row).collect()
+}
+
+/// 添加 Webhook
+pub async fn add_webhook(pool: &Pool<Sqlite>, webhook: NewWebhook) -> Result<Webhook, String> {
+    let webhook = normalize(webhook)?;
+    let now = chrono::Local::now().timestamp();
+
+    let id = sqlx::query(
+        r#"
+        INSERT INTO webhooks (name, url, events, body_template, headers, secret, enabled, created_at, updated_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+        "#,
+    )
+    .bind(&webhook.name)
+    .bind(&webhook.url)
+    .bind(serde_json::to_string(&webhook.events).unwrap_or_else(|_| "[]".to_string()))
+    .bind(&webhook.body_template)
+    .bind(serde_json::to_string(&webhook.headers).unwrap_or_else(|_| "{}".to_string()))
+    .bind(&webhook.secret)
+    .bind(webhook.enabled)
+    .bind(now)
+    .bind(now)
+    .execute(pool)
+    .await
+    .map_err(|e| format!("添加 Webhook 失败: {}", e))?
+    .last_insert_rowid();
+
+    get_webhook(pool, id).await
+}
+
+/// 更新 Webhook
+pub async fn update_webhook(pool: &Pool<Sqlite>, id: i64, webhook: NewWebhook) -> Result<Webhook, String> {
+    let webhook = normalize(webhook)?;
+
+    let result = sqlx::query(
+        r#"
+        UPDATE webhooks
+        SET name = ?, url = ?, events = ?, body_template = ?, headers = ?, secret = ?, enabled = ?, updated_at = ?
+        WHERE id = ?
+        "#,
+    )
+    .bind(&webhook.name)
+    .bind(&webhook.url)
+    .bind(serde_json::to_string(&webhook.events).unwrap_or_else(|_| "[]".to_string()))
+    .bind(&webhook.body_template)
+    .bind(serde_json::to_string(&webhook.headers).unwrap_or_else(|_| "{}".to_string()))
+    .bind(&webhook.secret)
+    .bind(webhook.enabled)
+    .bind(chrono::Local::now().timestamp())
+    .bind(id)
+    .execute(pool)
+    .await
+    .map_err(|e| format!("更新 Webhook 失败: {}", e))?;
+
+    if result.rows_affected() == 0 {
+        return Err(format!("Webhook {} 不存在", id));
+    }
+    get_webhook(pool, id).await
+}
+
+/// 删除 Webhook 及其投递记录
+pub async fn delete_webhook(pool: &Pool<Sqlite>, id: i64) -> Result<(), String> {
+    let mut tx = pool.begin().await.map_err(|e| format!("开启事务失败: {}", e))?;
+
+    sqlx::query("DELETE FROM webhook_deliveries WHERE webhook_id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("删除投递记录失败: {}", e))?;
+
+    sqlx::query("DELETE FROM webhooks WHERE id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("删除 Webhook 失败: {}", e))?;
+
+    tx.commit().await.map_err(|e| format!("提交事务失败: {}", e))?;
+    Ok(())
+}
+
+/// 读取 Webhook 的投递记录
+pub async fn list_deliveries(pool: &Pool<Sqlite>, webhook_id: i64, limit: i64) -> Result<Vec<WebhookDelivery>, String> {
+    let rows = sqlx::query(&format!(
+        "SELECT {} FROM webhook_deliveries WHERE webhook_id = ? ORDER BY id DESC LIMIT ?",
+        DELIVERY_COLUMNS
+    ))
+    .bind(webhook_id)
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("查询投递记录失败: {}", e))?;
+    rows.iter().map(delivery_from_row).collect()
+}
+
+async fn get_delivery(pool: &Pool<Sqlite>, id: i64) -> Result<WebhookDelivery, String> {
+    let row = sqlx::query(&format!("SELECT {} FROM webhook_deliveries WHERE id = ?", DELIVERY_COLUMNS))
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| format!("查询投递记录失败: {}", e))?
+        .ok_or_else(|| format!("投递记录 {} 不存在", id))?;
+    delivery_from_row(&row)
+}
+
+// ========== 请求体模板 ==========
+
+/// 按 `a.b.0.c` 形式的路径读取值
+fn lookup<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.').try_fold(value, |current, key| match current {
+        Value::Object(map) => map.get(key),
+        Value::Array(items) => key.parse::<usize>().ok().and_then(|i| items.get(i)),
+        _ => None,
+    })
+}
+
+fn value_text(value: Option<&Value>) -> String {
+    match value {
+        Some(Value::String(text)) => text.clone(),
+        Some(Value::Null) | None => String::new(),
+        Some(other) => other.to_string(),
+    }
+}
+
+/// 替换字符串中的 `{{path}}` 占位符
+///
+/// 整个字符串只有一个占位符时保留原始 JSON 类型（对象、数字等），否则按文本拼接。
+fn render_string(text: &str, envelope: &Value) -> Value {
+    let trimmed = text.trim();
+    if let Some(path) = trimmed.strip_prefix("{{").and_then(|t| t.strip_suffix("}}")) {
+        if !path.contains("{{") && !path.contains("}}") {
+            return lookup(envelope, path.trim()).cloned().unwrap_or(Value::Null);
+        }
+    }
+
+    let mut output = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start..].find("}}") else {
+            break;
+        };
+        output.push_str(&rest[..start]);
+        let path = rest[start + 2..start + end].trim();
+        output.push_str(&value_text(lookup(envelope, path)));
+        rest = &rest[start + end + 2..];
+    }
+    output.push_str(rest);
+    Value::String(output)
+}
+
+fn render_value(template: &Value, envelope: &Value) -> Value {
+    match template {
+        Value::String(text) => render_string(text, envelope),
+        Value::Array(items) => Value::Array(items.iter().map(|item| render_value(item, envelope)).collect()),
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(key, value)| (key.clone(), render_value(value, envelope)))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+/// 生成请求体；模板中可以使用 `{{event}}`、`{{timestamp}}`、`{{data}}`、`{{data.content}}` 等占位符
+fn render_body(template: Option<&str>, event: &str, data: &Value) -> Result<String, String> {
+    let envelope = json!({
+        "event": event,
+        "timestamp": chrono::Local::now().timestamp(),
+        "data": data,
+    });
+    match template {
+        Some(template) => {
+            let template: Value =
+                serde_json::from_str(template).map_err(|e| format!("请求体模板不是合法的 JSON: {}", e))?;
+            Ok(render_value(&template, &envelope).to_string())
+        }
+        None => Ok(envelope.to_string()),
+    }
+}
+
+// ========== 投递 ==========
+
+/// 触发事件：为每个订阅了该事件的 Webhook 创建投递记录并在后台发送
+///
+/// 发送失败不会影响触发事件的操作，失败的投递进入重试队列。
+pub async fn emit(pool: &Pool<Sqlite>, event: &str, data: Value) {
+    let webhooks = match list_webhooks(pool).await {
+        Ok(webhooks) => webhooks,
+        Err(e) => {
+            eprintln!("{}", e);
+            return;
+        }
+    };
+
+    for webhook in webhooks
+        .iter()
+        .filter(|w| w.enabled && w.events.iter().any(|e| e == "*" || e == event))
+    {
+        match enqueue(pool, webhook, event, &data).await {
+            Ok(delivery_id) => {
+                let pool = pool.clone();
+                tauri::async_runtime::spawn(async move {
+                    if let Err(e) = attempt(&pool, delivery_id).await {
+                        eprintln!("Webhook 投递失败: {}", e);
+                    }
+                });
+            }
+            Err(e) => eprintln!("创建 Webhook 投递记录失败: {}", e),
+        }
+    }
+}
+
+/// 向指定 Webhook 发送测试事件，等待发送完成后返回投递记录
+pub async fn send_test(pool: &Pool<Sqlite>, webhook_id: i64) -> Result<WebhookDelivery, String> {
+    let webhook = get_webhook(pool, webhook_id).await?;
+    let data = json!({ "message": "这是一条测试消息", "webhook": webhook.name });
+    let delivery_id = enqueue(pool, &webhook, TEST_EVENT, &data).await?;
+    attempt(pool, delivery_id).await?;
+    get_delivery(pool, delivery_id).await
+}
+
+/// 手动重试一条投递记录
+pub async fn retry_delivery(pool: &Pool<Sqlite>, id: i64) -> Result<WebhookDelivery, String> {
+    let now = chrono::Local::now().timestamp();
+    let result = sqlx::query(
+        "UPDATE webhook_deliveries SET status = ?, next_attempt_at = ?, updated_at = ? WHERE id = ? AND status != ?",
+    )
+    .bind(DeliveryStatus::Pending.as_str())
+    .bind(now)
+    .bind(now)
+    .bind(id)
+    .bind(DeliveryStatus::Success.as_str())
+    .execute(pool)
+    .await
+    .map_err(|e| format!("更新投递记录失败: {}", e))?;
+
+    if result.rows_affected() == 0 {
+        return Err("投递记录不存在或已发送成功".to_string());
+    }
+    attempt(pool, id).await?;
+    get_delivery(pool, id).await
+}
+
+async fn enqueue(pool: &Pool<Sqlite>, webhook: &Webhook, event: &str, data: &Value) -> Result<i64, String> {
+    let payload = render_body(webhook.body_template.as_deref(), event, data)?;
+    let now = chrono::Local::now().timestamp();
+
+    let id = sqlx::query(
+        r#"
+        INSERT INTO webhook_deliveries (webhook_id, event, payload, status, attempts, next_attempt_at, created_at, updated_at)
+        VALUES (?, ?, ?, ?, 0, ?, ?, ?)
+        "#,
+    )
+    .bind(webhook.id)
+    .bind(event)
+    .bind(&payload)
+    .bind(DeliveryStatus::Pending.as_str())
+    .bind(now)
+    .bind(now)
+    .bind(now)
+    .execute(pool)
+    .await
+    .map_err(|e| format!("保存投递记录失败: {}", e))?
+    .last_insert_rowid();
+
+    // 只保留最近的投递记录
+    sqlx::query(
+        r#"
+        DELETE FROM webhook_deliveries
+        WHERE webhook_id = ? AND status != ? AND id NOT IN (
+            SELECT id FROM webhook_deliveries WHERE webhook_id = ? ORDER BY id DESC LIMIT ?
+        )
+        "#,
+    )
+    .bind(webhook.id)
+    .bind(DeliveryStatus::Pending.as_str())
+    .bind(webhook.id)
+    .bind(HISTORY_LIMIT)
+    .execute(pool)
+    .await
+    .map_err(|e| format!("清理投递记录失败: {}", e))?;
+
+    Ok(id)
+}
+
+/// 领取一条到期的投递记录，避免后台任务和重试队列重复发送
+async fn claim(pool: &Pool<Sqlite>, id: i64, now: i64) -> Result<bool, String> {
+    let result = sqlx::query(
+        "UPDATE webhook_deliveries SET next_attempt_at = ? WHERE id = ? AND status = ? AND next_attempt_at <= ?",
+    )
+    .bind(now + CLAIM_LEASE_SECS)
+    .bind(id)
+    .bind(DeliveryStatus::Pending.as_str())
+    .bind(now)
+    .execute(pool)
+    .await
+    .map_err(|e| format!("领取投递记录失败: {}", e))?;
+    Ok(result.rows_affected() == 1)
+}
+
+/// 请求签名：以密钥对 `"{timestamp}.{body}"` 做 HmacSHA256，结果为 `sha256=<十六进制>`
+///
+/// timestamp 与 x-webhook-timestamp 请求头相同（Unix 秒）。签名包含时间戳，
+/// 接收方校验签名后再拒绝时间偏差过大的请求，即可防止截获的请求被重放。
+fn signature(secret: &str, timestamp: i64, body: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC 可以接受任意长度的密钥");
+    mac.update(format!("{}.{}", timestamp, body).as_bytes());
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+fn request_headers(webhook: &Webhook, delivery: &WebhookDelivery) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    for (name, value) in &webhook.headers {
+        if let (Ok(name), Ok(value)) = (HeaderName::from_bytes(name.as_bytes()), HeaderValue::from_str(value)) {
+            headers.insert(name, value);
+        }
+    }
+
+    // 以下请求头由系统生成，覆盖同名的自定义请求头
+    let timestamp = chrono::Local::now().timestamp();
+    let mut system: BTreeMap<&str, String> = BTreeMap::new();
+    system.insert("x-webhook-event", delivery.event.clone());
+    system.insert("x-webhook-delivery", delivery.id.to_string());
+    system.insert("x-webhook-timestamp", timestamp.to_string());
+    if let Some(secret) = &webhook.secret {
+        system.insert(SIGNATURE_HEADER, signature(secret, timestamp, &delivery.payload));
+    }
+    for (name, value) in system {
+        if let Ok(value) = HeaderValue::from_str(&value) {
+            headers.insert(name, value);
+        }
+    }
+    if !headers.contains_key(CONTENT_TYPE) {
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    }
+    headers
+}
+
+/// 发送一条投递记录并更新状态；未领取到（已被其他任务处理）时直接返回
+async fn attempt(pool: &Pool<Sqlite>, id: i64) -> Result<(), String> {
+    let now = chrono::Local::now().timestamp();
+    if !claim(pool, id, now).await? {
+        return Ok(());
+    }
+    let delivery = get_delivery(pool, id).await?;
+    let webhook = get_webhook(pool, delivery.webhook_id).await?;
+
+    let http = reqwest::Client::builder()
+        .timeout(DELIVERY_TIMEOUT)
+        .build()
+        .map_err(|e| format!("创建 HTTP 客户端失败: {}", e))?;
+    let result = http
+        .post(&webhook.url)
+        .headers(request_headers(&webhook, &delivery))
+        .body(delivery.payload.clone())
+        .send()
+        .await;
+
+    let (response_status, response_body, error) = match result {
+        Ok(response) => {
+            let status = response.status();
+            let body: String = response
+                .text()
+                .await
+                .unwrap_or_default()
+                .chars()
+                .take(RESPONSE_BODY_CHARS)
+                .collect();
+            let error = if status.is_success() {
+                None
+            } else {
+                Some(format!("HTTP {}", status.as_u16()))
+            };
+            (Some(status.as_u16() as i64), Some(body), error)
+        }
+        Err(e) => (None, None, Some(format!("请求失败: {}", e))),
+    };
+
+    let attempts = delivery.attempts + 1;
+    let now = chrono::Local::now().timestamp();
+    let (status, next_attempt_at) = match &error {
+        None => (DeliveryStatus::Success, None),
+        Some(_) if attempts >= MAX_ATTEMPTS => (DeliveryStatus::Failed, None),
+        Some(_) => {
+            let delay = (RETRY_BASE_SECS << (attempts - 1).min(20)).min(RETRY_MAX_SECS);
+            (DeliveryStatus::Pending, Some(now + delay))
+        }
+    };
+
+    sqlx::query(
+        r#"
+        UPDATE webhook_deliveries
+        SET status = ?, attempts = ?, next_attempt_at = ?, response_status = ?, response_body = ?, error = ?, updated_at = ?
+        WHERE id = ?
+        "#,
+    )
+    .bind(status.as_str())
+    .bind(attempts)
+    .bind(next_attempt_at)
+    .bind(response_status)
+    .bind(response_body)
+    .bind(&error)
+    .bind(now)
+    .bind(id)
+    .execute(pool)
+    .await
+    .map_err(|e| format!("更新投递记录失败: {}", e))?;
+
+    Ok(())
+}
+
+/// 发送所有到期的待重试记录
+async fn process_due(pool: &Pool<Sqlite>) -> Result<(), String> {
+    let now = chrono::Local::now().timestamp();
+    let ids: Vec<i64> = sqlx::query_scalar(
+        "SELECT id FROM webhook_deliveries WHERE status = ? AND next_attempt_at <= ? ORDER BY next_attempt_at, id",
+    )
+    .bind(DeliveryStatus::Pending.as_str())
+    .bind(now)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("查询重试队列失败: {}", e))?;
+
+    for id in ids {
+        if let Err(e) = attempt(pool, id).await {
+            eprintln!("Webhook 重试失败: {}", e);
+        }
+    }
+    Ok(())
+}
+
+/// 启动重试队列的后台任务（数据库未就绪时跳过本轮），应用重启后继续处理未完成的投递
+pub fn spawn_retry_worker(db_state: DbState) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            if let Ok(pool) = db_state.get_pool().await {
+                if let Err(e) = process_due(&pool).await {
+                    eprintln!("{}", e);
+                }
+            }
+            tokio::time::sleep(WORKER_INTERVAL).await;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{temp_pool, Reply, ScriptedServer};
+
+    fn hmac_hex(secret: &str, message: &str) -> String {
+        let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).unwrap();
+        mac.update(message.as_bytes());
+        hex::encode(mac.finalize().into_bytes())
+    }
+
+    #[tokio::test]
+    async fn signature_covers_timestamp_and_body() {
+        let (pool, _dir) = temp_pool().await;
+        let server = ScriptedServer::start(vec![Reply::json(json!({ "ok": true }))]).await;
+        let webhook = add_webhook(
+            &pool,
+            NewWebhook {
+                name: "接收端".to_string(),
+                url: format!("{}/events", server.url),
+                events: vec!["*".to_string()],
+                body_template: None,
+                headers: BTreeMap::from([("x-webhook-event".to_string(), "伪造".to_string())]),
+                secret: Some("hook-secret".to_string()),
+                enabled: true,
+            },
+        )
+        .await
+        .unwrap();
+
+        let delivery = send_test(&pool, webhook.id).await.unwrap();
+        assert_eq!(delivery.status, DeliveryStatus::Success);
+
+        let request = server.requests().remove(0);
+        assert_eq!(request.path, "/events");
+        assert_eq!(request.headers["x-webhook-event"], TEST_EVENT);
+        assert_eq!(request.headers["x-webhook-delivery"], delivery.id.to_string());
+        assert_eq!(request.body, delivery.payload);
+
+        let timestamp = &request.headers["x-webhook-timestamp"];
+        let expected = format!("sha256={}", hmac_hex("hook-secret", &format!("{}.{}", timestamp, request.body)));
+        assert_eq!(request.headers[SIGNATURE_HEADER], expected);
+        // 只对请求体签名的旧格式不再成立
+        assert_ne!(request.headers[SIGNATURE_HEADER], format!("sha256={}", hmac_hex("hook-secret", &request.body)));
+    }
+}
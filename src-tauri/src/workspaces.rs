@@ -0,0 +1,369 @@
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::database::{self, DbState};
+use crate::models::Workspace;
+
+/// 默认工作区沿用旧版本的数据位置，升级后无需迁移
+pub const DEFAULT_WORKSPACE: &str = "default";
+
+/// 切换工作区后通知前端重新加载数据的事件
+pub const WORKSPACE_CHANGED_EVENT: &str = "workspace-changed";
+
+const REGISTRY_FILE: &str = "workspaces.json";
+
+/// 数据库文件名
+pub const DB_FILE: &str = "data.db";
+
+/// 工作区名称的最大字符数
+const MAX_NAME_CHARS: usize = 50;
+
+/// 保护工作区列表文件的读-改-写
+static REGISTRY_LOCK: Mutex<()> = Mutex::new(());
+
+/// 串行化工作区切换和数据库迁移，避免交错替换连接池
+pub(crate) static SWITCH_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct WorkspaceEntry {
+    id: String,
+    name: String,
+    created_at: i64,
+    #[serde(default)]
+    db_dir: Option<String>, // 自定义数据库目录，为空时使用工作区目录
+    #[serde(default)]
+    previous_db_dir: Option<String>, // 迁移后保留的旧数据库目录，确认后清理
+}
+
+/// 工作区列表（JSON 文件存储）
+#[derive(Debug, Serialize, Deserialize)]
+struct Registry {
+    active: String,
+    workspaces: Vec<WorkspaceEntry>,
+}
+
+impl Default for Registry {
+    fn default() -> Self {
+        Self {
+            active: DEFAULT_WORKSPACE.to_string(),
+            workspaces: vec![WorkspaceEntry {
+                id: DEFAULT_WORKSPACE.to_string(),
+                name: "默认".to_string(),
+                created_at: 0,
+                db_dir: None,
+                previous_db_dir: None,
+            }],
+        }
+    }
+}
+
+/// 工作区的文件位置
+pub struct WorkspacePaths {
+    pub data_dir: PathBuf,
+    pub db_dir: PathBuf,
+    pub custom_db_dir: bool,
+    pub previous_db_dir: Option<PathBuf>,
+    pub db_path: PathBuf,
+    pub ai_config_path: PathBuf,
+    pub attachment_dir: PathBuf,
+}
+
+fn config_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app.path()
+        .app_config_dir()
+        .map_err(|e| format!("无法获取应用配置目录: {}", e))?;
+    std::fs::create_dir_all(&dir).map_err(|e| format!("无法创建目录: {}", e))?;
+    Ok(dir)
+}
+
+fn load_registry(app: &AppHandle) -> Result<Registry, String> {
+    let path = config_dir(app)?.join(REGISTRY_FILE);
+    if !path.exists() {
+        return Ok(Registry::default());
+    }
+
+    let content = std::fs::read_to_string(&path)
+        .map_err(|e| format!("读取工作区列表失败: {}", e))?;
+    let mut registry: Registry = serde_json::from_str(&content)
+        .map_err(|e| format!("解析工作区列表失败: {}", e))?;
+
+    // 默认工作区始终存在
+    if !registry.workspaces.iter().any(|w| w.id == DEFAULT_WORKSPACE) {
+        registry.workspaces.insert(0, Registry::default().workspaces.remove(0));
+    }
+    if !registry.workspaces.iter().any(|w| w.id == registry.active) {
+        registry.active = DEFAULT_WORKSPACE.to_string();
+    }
+    Ok(registry)
+}
+
+fn save_registry(app: &AppHandle, registry: &Registry) -> Result<(), String> {
+    let path = config_dir(app)?.join(REGISTRY_FILE);
+    let json = serde_json::to_string_pretty(registry)
+        .map_err(|e| format!("序列化工作区列表失败: {}", e))?;
+    std::fs::write(&path, json).map_err(|e| format!("写入工作区列表失败: {}", e))
+}
+
+fn paths_for(app: &AppHandle, entry: &WorkspaceEntry) -> Result<WorkspacePaths, String> {
+    let id = entry.id.as_str();
+    let config_dir = config_dir(app)?;
+    let (data_dir, attachment_dir) = if id == DEFAULT_WORKSPACE {
+        // 旧版本附件保存在应用缓存目录
+        let cache_dir = app.path()
+            .app_cache_dir()
+            .map_err(|e| format!("无法获取应用缓存目录: {}", e))?;
+        (config_dir, cache_dir)
+    } else {
+        let data_dir = config_dir.join("workspaces").join(id);
+        let attachment_dir = data_dir.join("attachments");
+        (data_dir, attachment_dir)
+    };
+
+    let db_dir = entry.db_dir.as_ref().map(PathBuf::from).unwrap_or_else(|| data_dir.clone());
+    Ok(WorkspacePaths {
+        db_path: db_dir.join(DB_FILE),
+        custom_db_dir: entry.db_dir.is_some(),
+        previous_db_dir: entry.previous_db_dir.as_ref().map(PathBuf::from),
+        db_dir,
+        ai_config_path: data_dir.join("ai_config.json"),
+        attachment_dir,
+        data_dir,
+    })
+}
+
+/// 当前工作区的文件位置
+pub fn active_paths(app: &AppHandle) -> Result<WorkspacePaths, String> {
+    let entry = {
+        let _guard = REGISTRY_LOCK.lock().map_err(|_| "工作区列表被锁定".to_string())?;
+        let registry = load_registry(app)?;
+        registry
+            .workspaces
+            .into_iter()
+            .find(|w| w.id == registry.active)
+            .ok_or_else(|| "当前工作区不存在".to_string())?
+    };
+    paths_for(app, &entry)
+}
+
+/// 当前工作区的 id
+pub fn active_id(app: &AppHandle) -> Result<String, String> {
+    let _guard = REGISTRY_LOCK.lock().map_err(|_| "工作区列表被锁定".to_string())?;
+    Ok(load_registry(app)?.active)
+}
+
+/// 检查数据库目录是否可用
+///
+/// 自定义目录（例如加密卷）不存在或其中没有数据库文件时不自动创建，
+/// 避免卷未挂载时在挂载点下写入新数据库。
+pub fn check_db_dir(paths: &WorkspacePaths) -> Result<(), String> {
+    if paths.custom_db_dir && !paths.db_path.is_file() {
+        return Err(format!(
+            "数据库目录 {} 不可用，请确认所在磁盘或加密卷已挂载，或重新选择数据库位置",
+            paths.db_dir.display()
+        ));
+    }
+    Ok(())
+}
+
+/// 记录工作区新的数据库目录，旧目录保留到确认迁移为止
+pub(crate) fn set_db_dir(app: &AppHandle, id: &str, db_dir: Option<String>, previous_db_dir: Option<String>) -> Result<(), String> {
+    let _guard = REGISTRY_LOCK.lock().map_err(|_| "工作区列表被锁定".to_string())?;
+    let mut registry = load_registry(app)?;
+    let entry = registry
+        .workspaces
+        .iter_mut()
+        .find(|w| w.id == id)
+        .ok_or_else(|| format!("工作区 {} 不存在", id))?;
+    entry.db_dir = db_dir;
+    entry.previous_db_dir = previous_db_dir;
+    save_registry(app, &registry)
+}
+
+fn to_workspace(app: &AppHandle, entry: &WorkspaceEntry, active: &str) -> Result<Workspace, String> {
+    let paths = paths_for(app, entry)?;
+    Ok(Workspace {
+        id: entry.id.clone(),
+        name: entry.name.clone(),
+        created_at: entry.created_at,
+        active: entry.id == active,
+        data_dir: paths.data_dir.to_string_lossy().to_string(),
+        db_path: paths.db_path.to_string_lossy().to_string(),
+        previous_db_dir: entry.previous_db_dir.clone(),
+    })
+}
+
+/// 读取工作区信息
+pub fn get_workspace(app: &AppHandle, id: &str) -> Result<Workspace, String> {
+    let _guard = REGISTRY_LOCK.lock().map_err(|_| "工作区列表被锁定".to_string())?;
+    let registry = load_registry(app)?;
+    let entry = registry
+        .workspaces
+        .iter()
+        .find(|w| w.id == id)
+        .ok_or_else(|| format!("工作区 {} 不存在", id))?;
+    to_workspace(app, entry, &registry.active)
+}
+
+fn normalize_name(registry: &Registry, name: &str, exclude_id: Option<&str>) -> Result<String, String> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err("工作区名称不能为空".to_string());
+    }
+    if name.chars().count() > MAX_NAME_CHARS {
+        return Err(format!("工作区名称不能超过 {} 个字符", MAX_NAME_CHARS));
+    }
+    if registry.workspaces.iter().any(|w| w.name == name && Some(w.id.as_str()) != exclude_id) {
+        return Err(format!("工作区 {} 已存在", name));
+    }
+    Ok(name)
+}
+
+/// 列出所有工作区
+pub fn list_workspaces(app: &AppHandle) -> Result<Vec<Workspace>, String> {
+    let _guard = REGISTRY_LOCK.lock().map_err(|_| "工作区列表被锁定".to_string())?;
+    let registry = load_registry(app)?;
+    registry
+        .workspaces
+        .iter()
+        .map(|entry| to_workspace(app, entry, &registry.active))
+        .collect()
+}
+
+/// 新建工作区（不会自动切换）
+pub fn create_workspace(app: &AppHandle, name: &str) -> Result<Workspace, String> {
+    let _guard = REGISTRY_LOCK.lock().map_err(|_| "工作区列表被锁定".to_string())?;
+    let mut registry = load_registry(app)?;
+    let name = normalize_name(&registry, name, None)?;
+
+    let now = chrono::Local::now();
+    let entry = WorkspaceEntry {
+        id: format!("ws-{}", now.timestamp_millis()),
+        name,
+        created_at: now.timestamp(),
+        db_dir: None,
+        previous_db_dir: None,
+    };
+    if registry.workspaces.iter().any(|w| w.id == entry.id) {
+        return Err("创建工作区过于频繁，请稍后重试".to_string());
+    }
+
+    let paths = paths_for(app, &entry)?;
+    std::fs::create_dir_all(&paths.attachment_dir).map_err(|e| format!("创建工作区目录失败: {}", e))?;
+
+    registry.workspaces.push(entry.clone());
+    save_registry(app, &registry)?;
+    to_workspace(app, &entry, &registry.active)
+}
+
+/// 重命名工作区
+pub fn rename_workspace(app: &AppHandle, id: &str, name: &str) -> Result<Workspace, String> {
+    let _guard = REGISTRY_LOCK.lock().map_err(|_| "工作区列表被锁定".to_string())?;
+    let mut registry = load_registry(app)?;
+    let name = normalize_name(&registry, name, Some(id))?;
+
+    let entry = registry
+        .workspaces
+        .iter_mut()
+        .find(|w| w.id == id)
+        .ok_or_else(|| format!("工作区 {} 不存在", id))?;
+    entry.name = name;
+    let entry = entry.clone();
+
+    save_registry(app, &registry)?;
+    to_workspace(app, &entry, &registry.active)
+}
+
+/// 切换到指定工作区，无需重启应用
+///
+/// 先打开目标工作区的数据库，成功后才替换连接池并记录为当前工作区；
+/// 旧连接池在正在执行的操作结束后关闭。
+pub async fn switch_workspace(app: &AppHandle, db_state: &DbState, id: &str) -> Result<Workspace, String> {
+    let _switching = SWITCH_LOCK.lock().await;
+
+    let entry = {
+        let _guard = REGISTRY_LOCK.lock().map_err(|_| "工作区列表被锁定".to_string())?;
+        load_registry(app)?
+            .workspaces
+            .into_iter()
+            .find(|w| w.id == id)
+            .ok_or_else(|| format!("工作区 {} 不存在", id))?
+    };
+
+    let paths = paths_for(app, &entry)?;
+    check_db_dir(&paths)?;
+    std::fs::create_dir_all(&paths.attachment_dir).map_err(|e| format!("创建工作区目录失败: {}", e))?;
+    let pool = database::open_workspace(&paths).await?;
+
+    {
+        let _guard = REGISTRY_LOCK.lock().map_err(|_| "工作区列表被锁定".to_string())?;
+        let mut registry = load_registry(app)?;
+        registry.active = id.to_string();
+        save_registry(app, &registry)?;
+    }
+
+    if let Some(old) = db_state.replace_pool(pool) {
+        old.close().await;
+    }
+
+    let workspace = to_workspace(app, &entry, id)?;
+    let _ = app.emit(WORKSPACE_CHANGED_EVENT, &workspace);
+    Ok(workspace)
+}
+
+/// 删除工作区及其全部数据；不能删除默认工作区和当前工作区
+///
+/// 持有切换锁直到删除完成，避免删除期间切换到该工作区。
+pub async fn delete_workspace(app: &AppHandle, id: &str) -> Result<(), String> {
+    if id == DEFAULT_WORKSPACE {
+        return Err("不能删除默认工作区".to_string());
+    }
+
+    let _switching = SWITCH_LOCK.lock().await;
+    let _guard = REGISTRY_LOCK.lock().map_err(|_| "工作区列表被锁定".to_string())?;
+    let mut registry = load_registry(app)?;
+    if registry.active == id {
+        return Err("不能删除当前工作区，请先切换到其他工作区".to_string());
+    }
+    let index = registry
+        .workspaces
+        .iter()
+        .position(|w| w.id == id)
+        .ok_or_else(|| format!("工作区 {} 不存在", id))?;
+
+    let paths = paths_for(app, &registry.workspaces[index])?;
+    registry.workspaces.remove(index);
+    save_registry(app, &registry)?;
+
+    if paths.data_dir.exists() {
+        std::fs::remove_dir_all(&paths.data_dir).map_err(|e| format!("删除工作区数据失败: {}", e))?;
+    }
+    // 自定义目录中只删除数据库文件，目录本身可能还有其他数据
+    if paths.custom_db_dir {
+        remove_db_files(&paths.db_dir)?;
+    }
+    if let Some(previous) = &paths.previous_db_dir {
+        remove_db_files(previous)?;
+    }
+    Ok(())
+}
+
+/// 当前工作区的附件目录（不存在时创建）
+pub fn attachment_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = active_paths(app)?.attachment_dir;
+    std::fs::create_dir_all(&dir).map_err(|e| format!("创建附件目录失败: {}", e))?;
+    Ok(dir)
+}
+
+/// 删除目录中的数据库文件（包括 WAL 和共享内存文件）
+pub(crate) fn remove_db_files(dir: &std::path::Path) -> Result<(), String> {
+    for suffix in ["", "-wal", "-shm"] {
+        let path = dir.join(format!("{}{}", DB_FILE, suffix));
+        if path.exists() {
+            std::fs::remove_file(&path).map_err(|e| format!("删除 {} 失败: {}", path.display(), e))?;
+        }
+    }
+    Ok(())
+}
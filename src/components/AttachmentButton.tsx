@@ -1,5 +1,6 @@
 import { Paperclip } from 'lucide-react';
-import { appCacheDir } from '@tauri-apps/api/path';
+import { invoke } from '@tauri-apps/api/core';
+import { join } from '@tauri-apps/api/path';
 import { writeFile } from '@tauri-apps/plugin-fs';
 
 interface AttachmentButtonProps {
@@ -14,14 +15,15 @@ export function AttachmentButton({ onAdd }: AttachmentButtonProps) {
     const paths: string[] = [];
 
     try {
-      const cacheDir = await appCacheDir();
+      // 附件保存到当前工作区的附件目录
+      const attachmentDir = await invoke<string>('get_attachment_dir');
 
       for (const file of files) {
         // 生成唯一文件名
         const fileName = `${Date.now()}_${file.name.replace(/[^a-zA-Z0-9.]/g, '_')}`;
-        const filePath = `${cacheDir}${fileName}`;
+        const filePath = await join(attachmentDir, fileName);
 
-        // 读取文件并保存到缓存目录
+        // 读取文件并保存到附件目录
         const buffer = await file.arrayBuffer();
         await writeFile(filePath, new Uint8Array(buffer));
 
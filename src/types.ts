@@ -4,12 +4,24 @@ export interface ApiConfig {
   model: string;
 }
 
+export type ProviderKind = 'openai' | 'mock' | 'ollama' | 'llama_cpp' | 'anthropic' | 'gemini';
+
+export interface LocalModel {
+  name: string;
+  size?: number; // 字节数
+  modified_at?: string;
+  family?: string;
+  parameter_size?: string;
+  quantization_level?: string;
+}
+
 export interface Idea {
   id: number;
   content: string;
   attachments: string[];
   created_at: number; // Unix 时间戳
   date: string;
+  archived?: boolean; // 已转为事项后归档
 }
 
 export interface DoneTask {
@@ -20,8 +32,226 @@ export interface DoneTask {
   attachments: string[];
   created_at: number; // Unix 时间戳
   date: string;
+  tags?: string[];
+}
+
+export type RecordKind = 'idea' | 'task';
+
+export interface RecordRef {
+  kind: RecordKind;
+  id: number;
+}
+
+// 批量导入的一条记录
+export type ImportRecord =
+  | { kind: 'idea'; content: string; attachments?: string[]; created_at: number }
+  | {
+      kind: 'task';
+      content: string;
+      start_time: number;
+      end_time: number;
+      attachments?: string[];
+      created_at: number;
+      tags?: string[];
+    };
+
+// rolled_back：本条成功，但因其他记录失败而整批回滚
+export type BulkItemStatus = 'ok' | 'failed' | 'rolled_back';
+
+export interface BulkItemResult {
+  index: number; // 在请求中的位置
+  kind: RecordKind;
+  id?: number; // 导入时为新记录的 ID
+  status: BulkItemStatus;
+  error?: string;
+}
+
+export interface BulkResult {
+  items: BulkItemResult[];
+  succeeded: number;
+  failed: number;
+  rolled_back: boolean; // atomic 模式下有失败项时整批回滚
+}
+
+export type PlanPriority = 'low' | 'normal' | 'high';
+
+export type PlanStatus = 'pending' | 'done' | 'cancelled';
+
+export interface PlannedTask {
+  id: number;
+  content: string;
+  target_date: string; // 顺延后为新的日期
+  original_date: string; // 最初计划的日期
+  estimate_minutes?: number;
+  priority: PlanPriority;
+  status: PlanStatus;
+  tags: string[];
+  carry_count: number;
+  done_task_id?: number; // 完成后对应的已完成事项
+  created_at: number;
+  updated_at: number;
+  completed_at?: number;
+}
+
+export interface NewPlannedTask {
+  content: string;
+  target_date: string;
+  estimate_minutes?: number;
+  priority?: PlanPriority;
+  tags?: string[];
+}
+
+export type RecurrenceRule =
+  | { kind: 'daily' }
+  | { kind: 'workdays' }
+  | { kind: 'weekly'; weekdays: number[] } // 1 为周一，7 为周日
+  | { kind: 'monthly'; days: number[] };
+
+export interface RecurringTemplate {
+  id: number;
+  content: string;
+  rule: RecurrenceRule;
+  start_time: string; // HH:MM
+  end_time: string; // HH:MM
+  tags: string[];
+  enabled: boolean;
+  created_at: number;
+  updated_at: number;
+}
+
+export type NewRecurringTemplate = Omit<RecurringTemplate, 'id' | 'created_at' | 'updated_at'>;
+
+export type OccurrenceStatus = 'draft' | 'confirmed' | 'skipped';
+
+export interface RecurringOccurrence {
+  id: number;
+  template_id: number;
+  date: string;
+  content: string;
+  start_time: number; // Unix 时间戳
+  end_time: number; // Unix 时间戳
+  tags: string[];
+  status: OccurrenceStatus;
+  done_task_id?: number; // 确认后对应的已完成事项
+  created_at: number;
+  updated_at: number;
+}
+
+export type PromoteTarget =
+  | {
+      kind: 'planned';
+      target_date: string;
+      estimate_minutes?: number;
+      priority?: PlanPriority;
+      content?: string; // 为空时使用想法内容
+      tags?: string[];
+    }
+  | {
+      kind: 'done';
+      start_time: number;
+      end_time: number;
+      content?: string; // 为空时使用想法内容
+      tags?: string[];
+    };
+
+export interface IdeaLink {
+  id: number;
+  idea_id: number;
+  idea_content: string;
+  idea_date: string;
+  planned_task_id?: number;
+  done_task_id?: number; // 转为计划的想法在计划完成后也会关联到已完成事项
+  created_at: number;
+}
+
+export interface IdeaPromotion {
+  link: IdeaLink;
+  planned_task?: PlannedTask;
+  done_task?: DoneTask;
+}
+
+export interface Workspace {
+  id: string;
+  name: string;
+  created_at: number;
+  active: boolean; // 是否为当前工作区
+  data_dir: string;
+  db_path: string; // 数据库文件，可迁移到自定义目录
+  previous_db_dir?: string; // 迁移后等待确认清理的旧数据库目录
+}
+
+// 数据库初始化状态，变化时触发 db-status-changed 事件
+export type DbStatus =
+  | { state: 'initializing' } // 迁移或恢复数据库期间也处于此状态
+  | { state: 'ready' }
+  | { state: 'failed'; error: string }; // 可调用 retry_db_init 重试
+
+export interface Snapshot {
+  name: string; // 文件名，恢复时使用
+  path: string;
+  created_at: number;
+  size_bytes: number;
+  pre_restore: boolean; // 恢复快照前自动保存的当前数据
 }
 
+export type IssueKind =
+  | 'integrity'
+  | 'foreign_key'
+  | 'invalid_json'
+  | 'date_mismatch'
+  | 'inverted_range'
+  | 'missing_attachment';
+
+export interface DiagnosticIssue {
+  kind: IssueKind;
+  table?: string;
+  row_id?: number;
+  column?: string;
+  message: string;
+  repairable: boolean; // 可在修复模式下自动修复
+  repaired: boolean;
+}
+
+export interface DiagnosticsReport {
+  integrity_ok: boolean; // 完整性检查未通过时不会执行修复
+  issues: DiagnosticIssue[];
+  repaired_count: number;
+  snapshot?: string; // 修复前生成的快照名称
+  checked_at: number;
+}
+
+export type AuditEntity = 'idea' | 'task' | 'prompt' | 'report' | 'plan' | 'snapshot';
+
+export type AuditAction = 'insert' | 'update' | 'delete' | 'restore';
+
+export type AuditOrigin = 'ui' | 'cli' | 'http_api' | 'scheduler';
+
+// 审计日志只追加，不可修改或删除
+export interface AuditEntry {
+  id: number;
+  at: number; // Unix 时间戳
+  origin: AuditOrigin;
+  entity: AuditEntity;
+  entity_id: number;
+  action: AuditAction;
+  before?: unknown; // 修改前的快照，新建时为空
+  after?: unknown; // 修改后的快照，删除时为空
+}
+
+export interface AuditQuery {
+  entity?: AuditEntity;
+  entity_id?: number;
+  action?: AuditAction;
+  origin?: AuditOrigin;
+  from?: number; // 起始时间戳（含）
+  to?: number; // 结束时间戳（不含）
+  report_id?: number; // 只看该报告生成后，其日期范围内的想法和事项的修改
+  limit?: number; // 默认 200，最多 1000
+  offset?: number;
+}
+
+export type ExportFormat = 'json' | 'csv';
+
 export interface Prompt {
   id: number;
   name: string;
@@ -35,6 +265,66 @@ export interface TodayRecords {
   tasks: DoneTask[];
 }
 
+export interface ToolCallEvent {
+  iteration: number;
+  tool_call_id: string;
+  name: string;
+  arguments: unknown;
+  result: unknown | null;
+  error: string | null;
+  duration_ms: number;
+}
+
+export interface AiError {
+  kind: 'auth' | 'quota' | 'invalid_model' | 'network' | 'server' | 'bad_request' | 'invalid_response' | 'not_configured' | 'other';
+  message: string;
+  status: number | null;
+  attempts: number;
+}
+
+export interface AgentOutcome {
+  content: string;
+  model: string;
+  iterations: number;
+  tool_calls: ToolCallEvent[];
+  report_id?: number; // 生成日报时保存的报告 id
+}
+
+export interface Report {
+  id: number;
+  title: string;
+  content: string; // Markdown
+  start_date: string;
+  end_date: string;
+  model?: string;
+  created_at: number; // Unix 时间戳
+}
+
+export type PublishPlatform = 'feishu' | 'dingtalk' | 'wecom';
+
+export interface PublishTarget {
+  id: number;
+  name: string;
+  platform: PublishPlatform;
+  webhook_url: string;
+  secret?: string;
+  enabled: boolean;
+  created_at: number;
+  updated_at: number;
+}
+
+export interface ReportPublication {
+  id: number;
+  report_id: number;
+  target_id?: number;
+  target_name: string;
+  platform: PublishPlatform;
+  success: boolean;
+  error?: string;
+  response?: string;
+  created_at: number;
+}
+
 export interface AIMessage {
   role: 'user' | 'assistant';
   content: string;
@@ -42,3 +332,58 @@ export interface AIMessage {
 }
 
 export type CurrentView = 'ideas' | 'tasks' | 'settings' | 'prompts' | 'history';
+
+export type SmtpTls = 'none' | 'start_tls' | 'tls';
+
+export interface SmtpConfig {
+  host: string;
+  port: number;
+  tls: SmtpTls;
+  username: string;
+  password: string; // 读取时已保存的密码为 "********"，原样回传表示不修改
+  from_address: string;
+  from_name?: string;
+  recipients: string[]; // 默认收件人
+  attach_images: boolean;
+}
+
+export interface ReportEmail {
+  id: number;
+  report_id: number;
+  recipients: string[];
+  subject: string;
+  attached_images: number;
+  success: boolean;
+  error?: string;
+  created_at: number;
+}
+
+export interface Webhook {
+  id: number;
+  name: string;
+  url: string;
+  events: string[]; // "*" 表示全部事件
+  body_template?: string; // JSON 模板，支持 {{event}}、{{timestamp}}、{{data.xxx}} 占位符
+  headers: Record<string, string>;
+  secret?: string; // 签名为 HMAC-SHA256("{timestamp}.{body}")，见 x-webhook-signature 请求头
+  enabled: boolean;
+  created_at: number;
+  updated_at: number;
+}
+
+export type NewWebhook = Omit<Webhook, 'id' | 'created_at' | 'updated_at'>;
+
+export interface WebhookDelivery {
+  id: number;
+  webhook_id: number;
+  event: string;
+  payload: string;
+  status: 'pending' | 'success' | 'failed';
+  attempts: number;
+  next_attempt_at?: number;
+  response_status?: number;
+  response_body?: string;
+  error?: string;
+  created_at: number;
+  updated_at: number;
+}